@@ -1,80 +1,93 @@
 use vpi::{
-    printf, register_cb, startup_routines, CbData, CbReason, Handle, ObjectType, Property, Value,
-    ValueType,
+    printf, register_cb, startup_routines, CbData, CbReason, Handle, ObjectType, Property,
+    SimContext, Value, ValueType,
 };
 
 startup_routines!(sim_info_startup);
 
 #[unsafe(no_mangle)]
 pub extern "C" fn sim_info_startup() {
-    register_cb(CbReason::StartOfSimulation, start_of_simulation);
-    register_cb(CbReason::EndOfSimulation, end_of_simulation);
+    let Some(ctx) = SimContext::current() else {
+        return;
+    };
+    register_cb(&ctx, CbReason::StartOfSimulation, start_of_simulation);
+    register_cb(&ctx, CbReason::EndOfSimulation, end_of_simulation);
 }
 
-fn start_of_simulation(_cb_data: &CbData) {
-    printf("=== Simulation Started ===\n");
-    walk_hierarchy(&Handle::default(), 0);
+fn start_of_simulation(cb_data: &CbData) {
+    let ctx = cb_data.context();
+    printf(ctx, "=== Simulation Started ===\n");
+    walk_hierarchy(ctx, &Handle::default(), 0);
 }
 
-fn end_of_simulation(_cb_data: &CbData) {
-    printf("=== Simulation Ended ===\n");
+fn end_of_simulation(cb_data: &CbData) {
+    printf(cb_data.context(), "=== Simulation Ended ===\n");
 }
 
 fn value_change_cb(cb_data: &CbData) {
+    let ctx = cb_data.context();
     let sig = &cb_data.obj;
-    let name = sig.get_name().unwrap_or("<unnamed>".to_string());
+    let name = sig.get_name(ctx).unwrap_or("<unnamed>".to_string());
     let value = sig
-        .get_value(ValueType::ObjType)
+        .get_value(ctx, ValueType::ObjType)
         .unwrap_or(Value::String("<unknown>".to_string()));
-    printf!("Value change on signal {name}: {value}");
+    printf!(ctx, "Value change on signal {name}: {value}");
 }
 
-fn walk_hierarchy(handle: &Handle, indent: usize) {
+fn walk_hierarchy(ctx: &SimContext, handle: &Handle, indent: usize) {
     if !handle.is_null() {
-        let name = handle.get_name().unwrap_or("<unnamed>".to_string());
-        printf!("{}Module: {name}", " ".repeat(indent));
+        let name = handle.get_name(ctx).unwrap_or("<unnamed>".to_string());
+        printf!(ctx, "{}Module: {name}", " ".repeat(indent));
     }
-    if handle.get_bool(Property::TopModule).unwrap_or(false) {
-        printf!("{}(Top-level module)", " ".repeat(indent + 1));
+    if handle.get_bool(ctx, Property::TopModule).unwrap_or(false) {
+        printf!(ctx, "{}(Top-level module)", " ".repeat(indent + 1));
     }
 
-    let children = handle.iterator(ObjectType::Module);
+    let children = handle.iterator(ctx, ObjectType::Module);
     for child in children {
-        walk_hierarchy(&child, indent + 1);
+        walk_hierarchy(ctx, &child, indent + 1);
     }
-    printf!("\n{}Ports", " ".repeat(indent + 1));
-    printf!("{}=======", " ".repeat(indent + 1));
-    for signal in handle.iterators(&[ObjectType::Port]) {
-        let name = signal.get_name().unwrap_or("<unnamed>".to_string());
-        let signal_type = signal.get_type_name().unwrap_or("<unknown>".to_string());
+    printf!(ctx, "\n{}Ports", " ".repeat(indent + 1));
+    printf!(ctx, "{}=======", " ".repeat(indent + 1));
+    for signal in handle.iterators(ctx, &[ObjectType::Port]) {
+        let name = signal.get_name(ctx).unwrap_or("<unnamed>".to_string());
+        let signal_type = signal.get_type_name(ctx).unwrap_or("<unknown>".to_string());
         let direction = signal
-            .get_direction()
+            .get_direction(ctx)
             .unwrap_or(vpi::Direction::NoDirection);
         printf!(
+            ctx,
             "{}Signal: {name} ({signal_type}, {direction})",
             " ".repeat(indent + 1)
         );
     }
 
-    printf!("\n{}Signals", " ".repeat(indent + 1));
-    printf!("{}=======", " ".repeat(indent + 1));
-    for signal in handle.iterators(&[
-        ObjectType::Net,
-        ObjectType::Reg,
-        ObjectType::Variables,
-        ObjectType::Parameter,
-    ]) {
-        let name = signal.get_name().unwrap_or("<unnamed>".to_string());
-        let signal_type = signal.get_type_name().unwrap_or("<unknown>".to_string());
-        printf!("{}Signal: {name} ({signal_type})", " ".repeat(indent + 1));
-        signal.register_cb(CbReason::ValueChange, value_change_cb);
+    printf!(ctx, "\n{}Signals", " ".repeat(indent + 1));
+    printf!(ctx, "{}=======", " ".repeat(indent + 1));
+    for signal in handle.iterators(
+        ctx,
+        &[
+            ObjectType::Net,
+            ObjectType::Reg,
+            ObjectType::Variables,
+            ObjectType::Parameter,
+        ],
+    ) {
+        let name = signal.get_name(ctx).unwrap_or("<unnamed>".to_string());
+        let signal_type = signal.get_type_name(ctx).unwrap_or("<unknown>".to_string());
+        printf!(
+            ctx,
+            "{}Signal: {name} ({signal_type})",
+            " ".repeat(indent + 1)
+        );
+        signal.register_cb(ctx, CbReason::ValueChange, value_change_cb);
     }
 
-    for memory in handle.iterator(ObjectType::Memory) {
-        let name = memory.get_name().unwrap_or("<unnamed>".to_string());
-        printf!("{}Memory: {name}", " ".repeat(indent + 1));
-        for word in memory.iterator(ObjectType::MemoryWord) {
-            word.register_cb(CbReason::ValueChange, value_change_cb);
+    for memory in handle.iterator(ctx, ObjectType::Memory) {
+        let name = memory.get_name(ctx).unwrap_or("<unnamed>".to_string());
+        printf!(ctx, "{}Memory: {name}", " ".repeat(indent + 1));
+        for word in memory.iterator(ctx, ObjectType::MemoryWord) {
+            word.register_cb(ctx, CbReason::ValueChange, value_change_cb);
         }
     }
 }
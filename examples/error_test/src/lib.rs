@@ -1,6 +1,6 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
-use vpi::{register_cb, startup_routines, CbData, CbReason};
+use vpi::{register_cb, startup_routines, CbData, CbReason, SimContext};
 
 startup_routines!(error_test_startup);
 
@@ -46,21 +46,24 @@ fn end_of_simulation_callback(_data: &CbData) {
 
 pub extern "C" fn error_test_startup() {
     eprintln!("[ERROR_TEST VPI] Registering error callbacks");
+    let Some(ctx) = SimContext::current() else {
+        return;
+    };
 
     // Register error callback
-    let _ = register_cb(CbReason::Error, error_callback);
-    if let Some(data) = vpi::check_error() {
+    let _ = register_cb(&ctx, CbReason::Error, error_callback);
+    if let Some(data) = vpi::check_error(&ctx) {
         dbg!("[ERROR_TEST VPI] check_error returned data: {:?}", data);
     }
 
     // Register PLI error callback
-    let _ = register_cb(CbReason::PLIError, pli_error_callback);
-    if let Some(data) = vpi::check_error() {
+    let _ = register_cb(&ctx, CbReason::PLIError, pli_error_callback);
+    if let Some(data) = vpi::check_error(&ctx) {
         dbg!("[ERROR_TEST VPI] check_error returned data: {:?}", data);
     }
 
     // Register end of simulation callback to report results
-    let _ = register_cb(CbReason::EndOfSimulation, end_of_simulation_callback);
+    let _ = register_cb(&ctx, CbReason::EndOfSimulation, end_of_simulation_callback);
 
     eprintln!("[ERROR_TEST VPI] Callbacks registered successfully");
 }
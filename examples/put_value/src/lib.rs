@@ -1,7 +1,7 @@
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use vpi::{
     control, register_cb, register_cb_with_time, startup_routines, value_array_to_int_array,
-    CbData, CbReason, Control, Handle, LogicVal, LogicVec, Time, Value, ValueType,
+    CbData, CbReason, Control, Handle, LogicVal, LogicVec, SimContext, Time, Value, ValueType,
 };
 
 startup_routines!(put_value_startup);
@@ -104,32 +104,40 @@ static HAD_FAILURE: AtomicBool = AtomicBool::new(false);
 
 #[unsafe(no_mangle)]
 pub extern "C" fn put_value_startup() {
-    let _ = register_cb(CbReason::StartOfSimulation, start_of_simulation);
+    let Some(ctx) = SimContext::current() else {
+        return;
+    };
+    let _ = register_cb(&ctx, CbReason::StartOfSimulation, start_of_simulation);
 }
 
-fn start_of_simulation(_cb_data: &CbData) {
+fn start_of_simulation(cb_data: &CbData) {
+    let ctx = cb_data.context();
     CURRENT_TEST_INDEX.store(0, Ordering::SeqCst);
     HAD_FAILURE.store(false, Ordering::SeqCst);
 
-    vpi::printf!("=== put_value test suite start ({} cases) ===", TESTS.len());
+    vpi::printf!(
+        ctx,
+        "=== put_value test suite start ({} cases) ===",
+        TESTS.len()
+    );
 
-    let _ = register_cb_with_time(CbReason::AfterDelay, Time::Sim(1), run_next_test);
+    let _ = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(1), run_next_test);
 }
 
-fn resolve_dut_handles() -> Option<DutHandles> {
+fn resolve_dut_handles(ctx: &SimContext) -> Option<DutHandles> {
     let handles = DutHandles {
-        bit_in: Handle::handle_by_name(BIT_IN),
-        vec_in: Handle::handle_by_name(VEC_IN),
-        int_in: Handle::handle_by_name(INT_IN),
-        int_arr_in: Handle::handle_by_name(INT_ARR_IN),
-        bit_out: Handle::handle_by_name(BIT_OUT),
-        vec_out: Handle::handle_by_name(VEC_OUT),
-        int_out: Handle::handle_by_name(INT_OUT),
-        int_arr_out: Handle::handle_by_name(INT_ARR_OUT),
-        arr_in_0: Handle::handle_by_name(ARR_IN_0),
-        arr_in_1: Handle::handle_by_name(ARR_IN_1),
-        arr_out_0: Handle::handle_by_name(ARR_OUT_0),
-        arr_out_1: Handle::handle_by_name(ARR_OUT_1),
+        bit_in: Handle::handle_by_name(ctx, BIT_IN),
+        vec_in: Handle::handle_by_name(ctx, VEC_IN),
+        int_in: Handle::handle_by_name(ctx, INT_IN),
+        int_arr_in: Handle::handle_by_name(ctx, INT_ARR_IN),
+        bit_out: Handle::handle_by_name(ctx, BIT_OUT),
+        vec_out: Handle::handle_by_name(ctx, VEC_OUT),
+        int_out: Handle::handle_by_name(ctx, INT_OUT),
+        int_arr_out: Handle::handle_by_name(ctx, INT_ARR_OUT),
+        arr_in_0: Handle::handle_by_name(ctx, ARR_IN_0),
+        arr_in_1: Handle::handle_by_name(ctx, ARR_IN_1),
+        arr_out_0: Handle::handle_by_name(ctx, ARR_OUT_0),
+        arr_out_1: Handle::handle_by_name(ctx, ARR_OUT_1),
     };
 
     if [
@@ -193,107 +201,120 @@ fn inverted_scalar_string(bits: &str) -> Option<String> {
     Some(LogicVec::from(values).to_string())
 }
 
-fn run_next_test(_cb_data: &CbData) {
+fn run_next_test(cb_data: &CbData) {
+    let ctx = cb_data.context();
     let index = CURRENT_TEST_INDEX.load(Ordering::SeqCst);
 
     if index >= TESTS.len() {
         if HAD_FAILURE.load(Ordering::SeqCst) {
-            vpi::printf("FAIL: put_value test suite failed");
+            vpi::printf(ctx, "FAIL: put_value test suite failed");
         } else {
-            vpi::printf("PASS: put_value test suite passed");
+            vpi::printf(ctx, "PASS: put_value test suite passed");
         }
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     }
 
-    let Some(handles) = resolve_dut_handles() else {
-        vpi::printf("ERROR: could not resolve one or more DUT handles");
+    let Some(handles) = resolve_dut_handles(ctx) else {
+        vpi::printf(ctx, "ERROR: could not resolve one or more DUT handles");
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     };
 
     let test = TESTS[index];
-    vpi::printf!("Running test {}: {}", index + 1, test.name);
+    vpi::printf!(ctx, "Running test {}: {}", index + 1, test.name);
 
     let Some(vec_in_values) = LogicVec::try_from_str(test.vec_in) else {
         vpi::printf!(
+            ctx,
             "ERROR [{}]: invalid vec_in scalar string '{}'",
             test.name,
             test.vec_in
         );
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     };
     let Some(arr_in_0_values) = LogicVec::try_from_str(test.arr_in[0]) else {
         vpi::printf!(
+            ctx,
             "ERROR [{}]: invalid arr_in[0] scalar string '{}'",
             test.name,
             test.arr_in[0]
         );
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     };
     let Some(arr_in_1_values) = LogicVec::try_from_str(test.arr_in[1]) else {
         vpi::printf!(
+            ctx,
             "ERROR [{}]: invalid arr_in[1] scalar string '{}'",
             test.name,
             test.arr_in[1]
         );
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     };
 
-    let _ = handles.bit_in.put_value(&Value::Scalar(test.bit_in));
-    let _ = handles.vec_in.put_value(&vec_in_values.as_vector_value());
-    let _ = handles.int_in.put_value(&Value::Int(test.int_in));
+    let _ = handles.bit_in.put_value(ctx, &Value::Scalar(test.bit_in));
+    let _ = handles
+        .vec_in
+        .put_value(ctx, &vec_in_values.as_vector_value());
+    let _ = handles.int_in.put_value(ctx, &Value::Int(test.int_in));
     let int_arr_in_values = vpi::int_array_to_value_array(test.int_arr_in);
-    if !handles.int_arr_in.put_value_array(&int_arr_in_values) {
-        vpi::printf!("ERROR [{}]: int_arr_in put_value_array failed", test.name);
+    if !handles.int_arr_in.put_value_array(ctx, &int_arr_in_values) {
+        vpi::printf!(
+            ctx,
+            "ERROR [{}]: int_arr_in put_value_array failed",
+            test.name
+        );
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     }
     let _ = handles
         .arr_in_0
-        .put_value(&arr_in_0_values.as_vector_value());
+        .put_value(ctx, &arr_in_0_values.as_vector_value());
     let _ = handles
         .arr_in_1
-        .put_value(&arr_in_1_values.as_vector_value());
+        .put_value(ctx, &arr_in_1_values.as_vector_value());
 
     let _ = register_cb_with_time(
+        ctx,
         CbReason::AfterDelay,
         Time::Sim(test.verify_delay),
         verify_current_test,
     );
 }
 
-fn verify_current_test(_cb_data: &CbData) {
+fn verify_current_test(cb_data: &CbData) {
+    let ctx = cb_data.context();
     let index = CURRENT_TEST_INDEX.load(Ordering::SeqCst);
     if index >= TESTS.len() {
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     }
 
     let test = TESTS[index];
     let mut ok = true;
 
-    let Some(handles) = resolve_dut_handles() else {
-        vpi::printf("ERROR: could not resolve one or more DUT handles");
+    let Some(handles) = resolve_dut_handles(ctx) else {
+        vpi::printf(ctx, "ERROR: could not resolve one or more DUT handles");
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     };
 
-    match handles.bit_in.get_value(ValueType::Scalar) {
+    match handles.bit_in.get_value(ctx, ValueType::Scalar) {
         Some(Value::Scalar(v)) if v == test.bit_in => {}
         other => {
             ok = false;
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: bit_in expected {}, got {:?}",
                 test.name,
                 char::from(test.bit_in),
@@ -304,20 +325,22 @@ fn verify_current_test(_cb_data: &CbData) {
 
     let Some(expected_in_vec) = normalized_scalar_string(test.vec_in) else {
         vpi::printf!(
+            ctx,
             "ERROR [{}]: invalid vec_in scalar string '{}'",
             test.name,
             test.vec_in
         );
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     };
-    match handles.vec_in.get_value(ValueType::Vector) {
+    match handles.vec_in.get_value(ctx, ValueType::Vector) {
         Some(Value::Vector(v)) => {
             let s = v.to_string();
             if s != expected_in_vec {
                 ok = false;
                 vpi::printf!(
+                    ctx,
                     "ERROR [{}]: vec_in expected {}, got {}",
                     test.name,
                     expected_in_vec,
@@ -328,6 +351,7 @@ fn verify_current_test(_cb_data: &CbData) {
         other => {
             ok = false;
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: vec_in expected vector, got {:?}",
                 test.name,
                 other
@@ -341,22 +365,24 @@ fn verify_current_test(_cb_data: &CbData) {
     ] {
         let Some(expected) = normalized_scalar_string(expected_src) else {
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: invalid {} scalar string '{}'",
                 test.name,
                 label,
                 expected_src
             );
             HAD_FAILURE.store(true, Ordering::SeqCst);
-            control(Control::Finish);
+            control(ctx, Control::Finish);
             return;
         };
 
-        match handle.get_value(ValueType::Vector) {
+        match handle.get_value(ctx, ValueType::Vector) {
             Some(Value::Vector(v)) => {
                 let s = v.to_string();
                 if s != expected {
                     ok = false;
                     vpi::printf!(
+                        ctx,
                         "ERROR [{}]: {} expected {}, got {}",
                         test.name,
                         label,
@@ -368,6 +394,7 @@ fn verify_current_test(_cb_data: &CbData) {
             other => {
                 ok = false;
                 vpi::printf!(
+                    ctx,
                     "ERROR [{}]: {} expected vector, got {:?}",
                     test.name,
                     label,
@@ -377,11 +404,12 @@ fn verify_current_test(_cb_data: &CbData) {
         }
     }
 
-    match handles.int_in.get_value(ValueType::Int) {
+    match handles.int_in.get_value(ctx, ValueType::Int) {
         Some(Value::Int(v)) if v == test.int_in => {}
         other => {
             ok = false;
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: int_in expected {}, got {:?}",
                 test.name,
                 test.int_in,
@@ -390,12 +418,13 @@ fn verify_current_test(_cb_data: &CbData) {
         }
     }
 
-    if let Some(values) = handles.int_arr_in.get_value_array(ValueType::Int) {
+    if let Some(values) = handles.int_arr_in.get_value_array(ctx, ValueType::Int) {
         match value_array_to_int_array(&values) {
             Some(values) if values == test.int_arr_in => {}
             Some(values) => {
                 ok = false;
                 vpi::printf!(
+                    ctx,
                     "ERROR [{}]: int_arr_in expected {:?}, got {:?}",
                     test.name,
                     test.int_arr_in,
@@ -404,20 +433,29 @@ fn verify_current_test(_cb_data: &CbData) {
             }
             None => {
                 ok = false;
-                vpi::printf!("ERROR [{}]: int_arr_in returned non-int values", test.name);
+                vpi::printf!(
+                    ctx,
+                    "ERROR [{}]: int_arr_in returned non-int values",
+                    test.name
+                );
             }
         }
     } else {
         ok = false;
-        vpi::printf!("ERROR [{}]: int_arr_in get_value_array failed", test.name);
+        vpi::printf!(
+            ctx,
+            "ERROR [{}]: int_arr_in get_value_array failed",
+            test.name
+        );
     }
 
     let expected_bit_out = invert_binary_scalar(test.bit_in);
-    match handles.bit_out.get_value(ValueType::Scalar) {
+    match handles.bit_out.get_value(ctx, ValueType::Scalar) {
         Some(Value::Scalar(v)) if v == expected_bit_out => {}
         other => {
             ok = false;
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: bit_out expected {}, got {:?}",
                 test.name,
                 char::from(expected_bit_out),
@@ -428,20 +466,22 @@ fn verify_current_test(_cb_data: &CbData) {
 
     let Some(expected_out_vec) = inverted_scalar_string(test.vec_in) else {
         vpi::printf!(
+            ctx,
             "ERROR [{}]: invalid vec_in scalar string '{}'",
             test.name,
             test.vec_in
         );
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        control(Control::Finish);
+        control(ctx, Control::Finish);
         return;
     };
-    match handles.vec_out.get_value(ValueType::Vector) {
+    match handles.vec_out.get_value(ctx, ValueType::Vector) {
         Some(Value::Vector(v)) => {
             let s = v.to_string();
             if s != expected_out_vec {
                 ok = false;
                 vpi::printf!(
+                    ctx,
                     "ERROR [{}]: vec_out expected {}, got {}",
                     test.name,
                     expected_out_vec,
@@ -452,6 +492,7 @@ fn verify_current_test(_cb_data: &CbData) {
         other => {
             ok = false;
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: vec_out expected vector, got {:?}",
                 test.name,
                 other
@@ -465,22 +506,24 @@ fn verify_current_test(_cb_data: &CbData) {
     ] {
         let Some(expected) = inverted_scalar_string(expected_src) else {
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: invalid source for {} scalar string '{}'",
                 test.name,
                 label,
                 expected_src
             );
             HAD_FAILURE.store(true, Ordering::SeqCst);
-            control(Control::Finish);
+            control(ctx, Control::Finish);
             return;
         };
 
-        match handle.get_value(ValueType::Vector) {
+        match handle.get_value(ctx, ValueType::Vector) {
             Some(Value::Vector(v)) => {
                 let s = v.to_string();
                 if s != expected {
                     ok = false;
                     vpi::printf!(
+                        ctx,
                         "ERROR [{}]: {} expected {}, got {}",
                         test.name,
                         label,
@@ -492,6 +535,7 @@ fn verify_current_test(_cb_data: &CbData) {
             other => {
                 ok = false;
                 vpi::printf!(
+                    ctx,
                     "ERROR [{}]: {} expected vector, got {:?}",
                     test.name,
                     label,
@@ -502,11 +546,12 @@ fn verify_current_test(_cb_data: &CbData) {
     }
 
     let expected_int_out = test.int_in + 1;
-    match handles.int_out.get_value(ValueType::Int) {
+    match handles.int_out.get_value(ctx, ValueType::Int) {
         Some(Value::Int(v)) if v == expected_int_out => {}
         other => {
             ok = false;
             vpi::printf!(
+                ctx,
                 "ERROR [{}]: int_out expected {}, got {:?}",
                 test.name,
                 expected_int_out,
@@ -516,12 +561,13 @@ fn verify_current_test(_cb_data: &CbData) {
     }
 
     let expected_int_arr_out = test.int_arr_in.map(|value| value + 1);
-    if let Some(values) = handles.int_arr_out.get_value_array(ValueType::Int) {
+    if let Some(values) = handles.int_arr_out.get_value_array(ctx, ValueType::Int) {
         match value_array_to_int_array(&values) {
             Some(values) if values == expected_int_arr_out => {}
             Some(values) => {
                 ok = false;
                 vpi::printf!(
+                    ctx,
                     "ERROR [{}]: int_arr_out expected {:?}, got {:?}",
                     test.name,
                     expected_int_arr_out,
@@ -530,23 +576,32 @@ fn verify_current_test(_cb_data: &CbData) {
             }
             None => {
                 ok = false;
-                vpi::printf!("ERROR [{}]: int_arr_out returned non-int values", test.name);
+                vpi::printf!(
+                    ctx,
+                    "ERROR [{}]: int_arr_out returned non-int values",
+                    test.name
+                );
             }
         }
     } else {
         ok = false;
-        vpi::printf!("ERROR [{}]: int_arr_out get_value_array failed", test.name);
+        vpi::printf!(
+            ctx,
+            "ERROR [{}]: int_arr_out get_value_array failed",
+            test.name
+        );
     }
 
     if ok {
-        vpi::printf!("PASS [{}]", test.name);
+        vpi::printf!(ctx, "PASS [{}]", test.name);
     } else {
         HAD_FAILURE.store(true, Ordering::SeqCst);
-        vpi::printf!("FAIL [{}]", test.name);
+        vpi::printf!(ctx, "FAIL [{}]", test.name);
     }
 
     CURRENT_TEST_INDEX.fetch_add(1, Ordering::SeqCst);
     let _ = register_cb_with_time(
+        ctx,
         CbReason::AfterDelay,
         Time::Sim(test.inter_test_delay),
         run_next_test,
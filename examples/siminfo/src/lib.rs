@@ -2,27 +2,38 @@ vpi::startup_routines!(sim_info_startup);
 
 #[unsafe(no_mangle)]
 pub extern "C" fn sim_info_startup() {
-    vpi::register_cb(vpi::CbReason::EndOfSimulation, end_of_simulation);
+    let Some(ctx) = vpi::SimContext::current() else {
+        return;
+    };
+    let ctx = &ctx;
+    vpi::register_cb(ctx, vpi::CbReason::EndOfSimulation, end_of_simulation);
 
-    let sim_info = vpi::simulator_info();
-    vpi::printf("=== Simulator Information ===\n");
-    vpi::printf!("Simulator: {} {}\n", sim_info.product, sim_info.version);
-    vpi::printf!("Command-line arguments:\n");
+    let sim_info = vpi::simulator_info(ctx);
+    vpi::printf(ctx, "=== Simulator Information ===\n");
+    vpi::printf!(
+        ctx,
+        "Simulator: {} {}\n",
+        sim_info.product,
+        sim_info.version
+    );
+    vpi::printf!(ctx, "Command-line arguments:\n");
     for (i, arg) in sim_info.arguments.iter().enumerate() {
-        vpi::printf!("  [{}] {}\n", i, arg);
+        vpi::printf!(ctx, "  [{}] {}\n", i, arg);
     }
-    vpi::printf!("Simulator name: {}\n", vpi::simulator_name());
-    vpi::printf!("Simulator version: {}\n", vpi::simulator_version());
+    vpi::printf!(ctx, "Simulator name: {}\n", vpi::simulator_name(ctx));
+    vpi::printf!(ctx, "Simulator version: {}\n", vpi::simulator_version(ctx));
 }
 
-fn end_of_simulation(_cb_data: &vpi::CbData) {
+fn end_of_simulation(cb_data: &vpi::CbData) {
+    let ctx = cb_data.context();
     vpi::printf!(
+        ctx,
         "End of simulation time: {}\n",
-        vpi::current_simulation_time()
+        vpi::current_simulation_time(ctx)
     );
-    if let Some(error) = vpi::check_error() {
-        vpi::printf!("Last error: {}\n", error);
+    if let Some(error) = vpi::check_error(ctx) {
+        vpi::printf!(ctx, "Last error: {}\n", error);
     } else {
-        vpi::printf!("No errors reported.\n");
+        vpi::printf!(ctx, "No errors reported.\n");
     }
 }
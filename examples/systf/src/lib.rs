@@ -1,105 +1,110 @@
+use std::cell::Cell;
 use std::ffi::CStr;
 use std::os::raw::c_char;
 
 use vpi::{
-    current_systf_call, get_systf_arg, register_systf, startup_routines, ObjectType, SysFuncType,
-    SystfKind, Value, ValueType,
+    current_systf_call, get_systf_arg, register_systf, startup_routines, ObjectType, SimContext,
+    SysFuncType, SystfKind, Value, ValueType,
 };
 
-startup_routines!(systf_startup);
+startup_routines!(
+    rust_log_plus_one::register,
+    rust_add_one::register,
+    systf_startup
+);
 
-static TASK_NAME: &CStr = c"$rust_log_plus_one";
-static FUNC_NAME: &CStr = c"$rust_add_one";
 static REVERSE_FUNC_NAME: &CStr = c"$rust_reverse_bits";
 
-#[unsafe(no_mangle)]
-pub extern "C" fn systf_startup() {
-    let _ = register_systf(
-        SystfKind::Task,
-        TASK_NAME,
-        Some(calltf_log_plus_one),
-        Some(compiletf_one_int_arg),
-        None,
-        std::ptr::null_mut(),
-        None,
+#[vpi::systf]
+fn rust_log_plus_one(ctx: &SimContext, arg: i32) {
+    // Each call site in the HDL counts its own invocations.
+    let call = current_systf_call(ctx);
+    let calls = match call.user_data::<Cell<u32>>(ctx) {
+        Some(calls) => {
+            calls.set(calls.get() + 1);
+            calls.get()
+        }
+        None => {
+            call.set_user_data(ctx, Cell::new(1_u32));
+            1
+        }
+    };
+    let result = arg + 1;
+    vpi::printf!(
+        ctx,
+        "$rust_log_plus_one arg={} result={} call={}",
+        arg,
+        result,
+        calls
     );
+}
 
-    let _ = register_systf(
-        SystfKind::Func,
-        FUNC_NAME,
-        Some(calltf_add_one),
-        Some(compiletf_one_int_arg),
-        None,
-        std::ptr::null_mut(),
-        Some(SysFuncType::Sized),
-    );
+#[vpi::systf]
+fn rust_add_one(ctx: &SimContext, arg: i32) -> i32 {
+    let result = arg + 1;
+    vpi::printf!(ctx, "$rust_add_one arg={} result={}", arg, result);
+    result
+}
 
+// The result width of `$rust_reverse_bits` follows its argument, so it is
+// registered by hand with a `sizetf` routine that inspects the call.
+#[unsafe(no_mangle)]
+pub extern "C" fn systf_startup() {
+    let Some(ctx) = SimContext::current() else {
+        return;
+    };
     let _ = register_systf(
+        &ctx,
         SystfKind::Func,
         REVERSE_FUNC_NAME,
         Some(calltf_reverse_bits),
-        Some(compiletf_one_int_arg),
+        Some(compiletf_one_arg),
         Some(sizetf_reverse_bits),
         std::ptr::null_mut(),
         Some(SysFuncType::Sized),
     );
 
-    vpi::printf("Registered $rust_log_plus_one, $rust_add_one, and $rust_reverse_bits");
+    ctx.printf("Registered $rust_reverse_bits");
 }
 
-unsafe extern "C" fn compiletf_one_int_arg(_user_data: *mut c_char) -> i32 {
+unsafe extern "C" fn compiletf_one_arg(_user_data: *mut c_char) -> i32 {
     // Keep compile-time checks minimal for simulator compatibility in this example.
     0
 }
 
-unsafe extern "C" fn calltf_log_plus_one(_user_data: *mut c_char) -> i32 {
-    let arg = match get_systf_arg(0, ValueType::Int) {
-        Some(Value::Int(v)) => v,
-        _ => 0,
-    };
-    let result = arg + 1;
-    vpi::printf!("$rust_log_plus_one arg={} result={}", arg, result);
-    0
-}
-
-unsafe extern "C" fn calltf_add_one(_user_data: *mut c_char) -> i32 {
-    let arg = match get_systf_arg(0, ValueType::Int) {
-        Some(Value::Int(v)) => v,
-        _ => 0,
-    };
-    let result = arg + 1;
-
-    let call = current_systf_call();
-    let _ = call.put_value(&Value::Int(result));
-
-    vpi::printf!("$rust_add_one arg={} result={}", arg, result);
-    0
-}
-
 unsafe extern "C" fn sizetf_reverse_bits(_user_data: *mut c_char) -> i32 {
-    let call = current_systf_call();
+    // Called by the simulator on its own thread.
+    let ctx = unsafe { SimContext::new_unchecked() };
+    let call = current_systf_call(&ctx);
     if call.is_null() {
         return 0;
     }
 
-    let mut args = call.iterator(ObjectType::Argument);
+    let mut args = call.iterator(&ctx, ObjectType::Argument);
     let Some(first_arg) = args.next() else {
         return 0;
     };
 
-    i32::try_from(first_arg.get_u32(vpi::Property::Size).unwrap_or(0)).unwrap_or(0)
+    i32::try_from(first_arg.get_u32(&ctx, vpi::Property::Size).unwrap_or(0)).unwrap_or(0)
 }
 
 unsafe extern "C" fn calltf_reverse_bits(_user_data: *mut c_char) -> i32 {
-    let Some(Value::Vector(vec)) = get_systf_arg(0, ValueType::Vector) else {
+    // Called by the simulator on its own thread.
+    let ctx = unsafe { SimContext::new_unchecked() };
+    let Some(Value::Vector(vec)) = get_systf_arg(&ctx, 0, ValueType::Vector) else {
         return 0;
     };
 
     let reversed_vec = vec.reverse();
 
-    let call = current_systf_call();
-    let _ = call.put_value(&reversed_vec.as_vector_value());
+    let call = current_systf_call(&ctx);
+    let _ = call.put_value(&ctx, &reversed_vec.as_vector_value());
 
-    vpi::printf!("$rust_reverse_bits arg={} result={}", vec, reversed_vec);
+    vpi::printf!(
+        &ctx,
+        "$rust_reverse_bits arg={} result={}",
+        vec,
+        reversed_vec
+    );
     0
 }
@@ -2,19 +2,23 @@ vpi::startup_routines!(timescale_startup);
 
 #[unsafe(no_mangle)]
 pub extern "C" fn timescale_startup() {
-    vpi::register_cb(vpi::CbReason::StartOfSimulation, timescale_callback);
+    let Some(ctx) = vpi::SimContext::current() else {
+        return;
+    };
+    vpi::register_cb(&ctx, vpi::CbReason::StartOfSimulation, timescale_callback);
 }
 
-fn timescale_callback(_cb_data: &vpi::CbData) {
+fn timescale_callback(cb_data: &vpi::CbData) {
+    let ctx = cb_data.context();
     println!("=== Timescale Information ===");
 
     // Get simulator info
-    let sim_info = vpi::simulator_info();
+    let sim_info = vpi::simulator_info(ctx);
     println!("Simulator: {} {}", sim_info.product, sim_info.version);
     println!();
 
     // Get timescale for all top-level modules
-    let timescales = vpi::get_top_module_timescales();
+    let timescales = vpi::get_top_module_timescales(ctx);
 
     if timescales.is_empty() {
         println!("No modules found");
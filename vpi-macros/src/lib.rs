@@ -0,0 +1,404 @@
+//! Procedural macros for the [`vpi`](https://docs.rs/vpi) crate.
+//!
+//! Use these through the `vpi` crate's `macros` feature, as `#[vpi::systf]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{FnArg, ItemFn, LitStr, ReturnType, Token, Type};
+
+/// Declares a typed VPI system task or function.
+///
+/// The annotated function keeps its signature. Next to it, a module of the
+/// same name is generated that holds the `calltf`, `compiletf` and `sizetf`
+/// routines and a `register` routine to list in `startup_routines!`:
+///
+/// - `compiletf` checks the argument count and, through
+///   `SystfArg::accepts`, the type and width of each argument. On a
+///   mismatch it reports an error and finishes the simulation.
+/// - `calltf` converts each argument with `SystfArg::from_arg`, calls the
+///   function and writes the result back with `put_value`.
+/// - `sizetf` reports `SystfReturn::SIZE` for sized return types.
+///
+/// A first parameter of type `&SimContext` is not a task argument; it
+/// receives the simulator context of the call.
+///
+/// Panics in `compiletf` and `calltf` are caught with `catch_panic` and
+/// handled according to the crate's `PanicPolicy`.
+///
+/// Functions returning `()` are registered as system tasks; other return
+/// types select the system function kind through `SystfReturn::FUNC_TYPE`.
+///
+/// The registered name defaults to the function name prefixed with `$`, and
+/// can be set with `#[vpi::systf(name = "$other_name")]`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[vpi::systf]
+/// fn rust_add_one(x: i32) -> i32 {
+///     x + 1
+/// }
+///
+/// #[vpi::systf(name = "$rust_log")]
+/// fn log(ctx: &vpi::SimContext, message: String) {
+///     ctx.printf(message);
+/// }
+///
+/// vpi::startup_routines!(rust_add_one::register, log::register);
+/// ```
+#[proc_macro_attribute]
+pub fn systf(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Options accepted by `#[systf(...)]`.
+#[derive(Default)]
+struct SystfArgs {
+    /// Registered name including the leading `$`.
+    name: Option<LitStr>,
+}
+
+impl Parse for SystfArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = SystfArgs::default();
+        let options = Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated(input)?;
+        for option in options {
+            if !option.path.is_ident("name") {
+                return Err(syn::Error::new_spanned(
+                    option.path,
+                    "unknown systf option, expected `name`",
+                ));
+            }
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(name),
+                ..
+            }) = option.value
+            else {
+                return Err(syn::Error::new_spanned(
+                    option.value,
+                    "expected a string literal such as `\"$my_task\"`",
+                ));
+            };
+            if args.name.replace(name).is_some() {
+                return Err(syn::Error::new_spanned(
+                    option.path,
+                    "duplicate `name` option",
+                ));
+            }
+        }
+        Ok(args)
+    }
+}
+
+fn expand(attr: TokenStream2, item: TokenStream2) -> syn::Result<TokenStream2> {
+    let args: SystfArgs = syn::parse2(attr)?;
+    let function: ItemFn = syn::parse2(item)?;
+    let sig = &function.sig;
+
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "systf functions cannot be generic",
+        ));
+    }
+    if let Some(token) = sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            token,
+            "systf functions cannot be async",
+        ));
+    }
+    if let Some(token) = sig.unsafety {
+        return Err(syn::Error::new_spanned(
+            token,
+            "systf functions cannot be unsafe",
+        ));
+    }
+    if let Some(abi) = &sig.abi {
+        return Err(syn::Error::new_spanned(
+            abi,
+            "systf functions must use the Rust ABI; the extern \"C\" routines are generated",
+        ));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new_spanned(
+            variadic,
+            "systf functions cannot be variadic",
+        ));
+    }
+
+    let mut arg_types = Vec::with_capacity(sig.inputs.len());
+    let mut takes_context = false;
+    for (index, input) in sig.inputs.iter().enumerate() {
+        match input {
+            FnArg::Typed(pat) if index == 0 && is_context(&pat.ty) => takes_context = true,
+            FnArg::Typed(pat) => arg_types.push(pat.ty.as_ref().clone()),
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "systf functions cannot take `self`",
+                ));
+            }
+        }
+    }
+
+    let ident = &sig.ident;
+    let name = match &args.name {
+        Some(name) => {
+            let value = name.value();
+            if !value.starts_with('$') || value.len() < 2 {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "system task and function names must start with `$`",
+                ));
+            }
+            if value.contains('\0') {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "system task and function names cannot contain NUL bytes",
+                ));
+            }
+            value
+        }
+        None => format!("${}", ident.to_string().trim_start_matches("r#")),
+    };
+    let Ok(c_name) = std::ffi::CString::new(name.clone()) else {
+        unreachable!("NUL bytes are rejected above");
+    };
+    let mut name_lit = Literal::c_string(&c_name);
+    name_lit.set_span(Span::call_site());
+
+    let return_type: Type = match &sig.output {
+        ReturnType::Default => syn::parse_quote!(()),
+        ReturnType::Type(_, ty) => ty.as_ref().clone(),
+    };
+    let vis = &function.vis;
+    let arg_count = arg_types.len();
+    let arg_idents: Vec<_> = (0..arg_count).map(|i| format_ident!("arg{}", i)).collect();
+    let positions: Vec<_> = (1..=arg_count).collect();
+    let module_doc = format!("VPI routines generated for the `{name}` system task or function.");
+    let context_arg = takes_context.then(|| quote!(&ctx,));
+    let compiletf_name = format!("{name} compiletf");
+    let calltf_name = format!("{name} calltf");
+
+    Ok(quote! {
+        #function
+
+        #[doc = #module_doc]
+        #[allow(clippy::all, unused_imports, unused_mut, unused_variables)]
+        #vis mod #ident {
+            use super::*;
+
+            /// Name registered with the simulator.
+            pub const NAME: &::core::ffi::CStr = #name_lit;
+
+            /// Registers the system task or function with the simulator.
+            ///
+            /// List this routine in the `startup_routines!` table.
+            pub extern "C" fn register() {
+                // Called by the simulator on its own thread.
+                let ctx = unsafe { ::vpi::SimContext::new_unchecked() };
+                let func_type =
+                    <#return_type as ::vpi::SystfReturn>::FUNC_TYPE;
+                let kind = if func_type.is_some() {
+                    ::vpi::SystfKind::Func
+                } else {
+                    ::vpi::SystfKind::Task
+                };
+                let sizetf: ::core::option::Option<::vpi::SystfCallback> =
+                    if <#return_type as ::vpi::SystfReturn>::SIZE.is_some() {
+                        ::core::option::Option::Some(sizetf)
+                    } else {
+                        ::core::option::Option::None
+                    };
+                let _ = ::vpi::register_systf(
+                    &ctx,
+                    kind,
+                    NAME,
+                    ::core::option::Option::Some(calltf),
+                    ::core::option::Option::Some(compiletf),
+                    sizetf,
+                    ::core::ptr::null_mut(),
+                    func_type,
+                );
+            }
+
+            unsafe extern "C" fn compiletf(_user_data: *mut ::core::ffi::c_char) -> i32 {
+                ::vpi::enter_simulator_thread();
+                // Called by the simulator on its own thread.
+                let ctx = unsafe { ::vpi::SimContext::new_unchecked() };
+                let _ = ::vpi::catch_panic(&ctx, #compiletf_name, || {
+                    let checks: [fn(&::vpi::SimContext, &::vpi::Handle) -> bool; #arg_count] = [
+                        #(<#arg_types as ::vpi::SystfArg>::accepts,)*
+                    ];
+                    let _ = ::vpi::check_systf_args(&ctx, NAME, &checks);
+                });
+                0
+            }
+
+            unsafe extern "C" fn sizetf(_user_data: *mut ::core::ffi::c_char) -> i32 {
+                <#return_type as ::vpi::SystfReturn>::SIZE
+                    .and_then(|size| i32::try_from(size).ok())
+                    .unwrap_or(32)
+            }
+
+            unsafe extern "C" fn calltf(_user_data: *mut ::core::ffi::c_char) -> i32 {
+                ::vpi::enter_simulator_thread();
+                // Called by the simulator on its own thread.
+                let ctx = unsafe { ::vpi::SimContext::new_unchecked() };
+                let _ = ::vpi::catch_panic(&ctx, #calltf_name, || {
+                    let call = ctx.current_systf_call();
+                    let mut args = call.iterator(&ctx, ::vpi::ObjectType::Argument);
+                    #(
+                        let ::core::option::Option::Some(#arg_idents) = args
+                            .next()
+                            .and_then(|arg| <#arg_types as ::vpi::SystfArg>::from_arg(&ctx, &arg))
+                        else {
+                            ::vpi::report_systf_error(
+                                &ctx,
+                                NAME,
+                                &::std::format!("cannot convert argument {}", #positions),
+                            );
+                            return;
+                        };
+                    )*
+                    let result: #return_type = super::#ident(#context_arg #(#arg_idents),*);
+                    if let ::core::option::Option::Some(value) =
+                        ::vpi::SystfReturn::into_value(result)
+                    {
+                        let _ = call.put_value(&ctx, &value);
+                    }
+                });
+                0
+            }
+        }
+    })
+}
+
+/// Returns `true` for a `&SimContext` parameter, which receives the
+/// simulator context instead of a task argument.
+fn is_context(ty: &Type) -> bool {
+    let Type::Reference(reference) = ty else {
+        return false;
+    };
+    if reference.mutability.is_some() {
+        return false;
+    }
+    let Type::Path(path) = reference.elem.as_ref() else {
+        return false;
+    };
+    path.qself.is_none()
+        && path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "SimContext" && segment.arguments.is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::expand;
+
+    fn error(attr: proc_macro2::TokenStream, item: proc_macro2::TokenStream) -> String {
+        expand(attr, item).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn derives_name_from_function() {
+        let tokens = expand(
+            quote!(),
+            quote!(
+                fn add_one(x: i32) -> i32 {
+                    x + 1
+                }
+            ),
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("c\"$add_one\""));
+        assert!(tokens.contains("mod add_one"));
+    }
+
+    #[test]
+    fn accepts_explicit_name() {
+        let tokens = expand(
+            quote!(name = "$inc"),
+            quote!(
+                fn add_one(x: i32) -> i32 {
+                    x + 1
+                }
+            ),
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("c\"$inc\""));
+    }
+
+    #[test]
+    fn passes_context_to_leading_parameter() {
+        let tokens = expand(
+            quote!(),
+            quote!(
+                fn log(ctx: &vpi::SimContext, message: String) {}
+            ),
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("super :: log (& ctx , arg0)"));
+        assert!(
+            tokens.contains("[fn (& :: vpi :: SimContext , & :: vpi :: Handle) -> bool ; 1usize]")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_declarations() {
+        assert!(error(
+            quote!(name = "inc"),
+            quote!(
+                fn f() {}
+            )
+        )
+        .contains("must start with `$`"));
+        assert!(error(
+            quote!(size = 3),
+            quote!(
+                fn f() {}
+            )
+        )
+        .contains("unknown systf option"));
+        assert!(error(
+            quote!(),
+            quote!(
+                fn f<T>(x: T) {}
+            )
+        )
+        .contains("cannot be generic"));
+        assert!(error(
+            quote!(),
+            quote!(
+                async fn f() {}
+            )
+        )
+        .contains("cannot be async"));
+        assert!(error(
+            quote!(),
+            quote!(
+                unsafe fn f() {}
+            )
+        )
+        .contains("cannot be unsafe"));
+        assert!(error(
+            quote!(),
+            quote!(
+                extern "C" fn f() {}
+            )
+        )
+        .contains("Rust ABI"));
+    }
+}
@@ -139,7 +139,12 @@ forward_fn! {
     fn vpi_compare_objects(object1: vpi_sys::vpiHandle, object2: vpi_sys::vpiHandle) -> vpi_sys::PLI_INT32;
     fn vpi_chk_error(error_info_p: vpi_sys::p_vpi_error_info) -> vpi_sys::PLI_INT32;
     fn vpi_release_handle(object: vpi_sys::vpiHandle) -> vpi_sys::PLI_INT32;
+    fn vpi_free_object(object: vpi_sys::vpiHandle) -> vpi_sys::PLI_INT32;
     fn vpi_flush() -> vpi_sys::PLI_INT32;
+    fn vpi_get_data(id: vpi_sys::PLI_INT32, dataLoc: *mut vpi_sys::PLI_BYTE8, numOfBytes: vpi_sys::PLI_INT32) -> vpi_sys::PLI_INT32;
+    fn vpi_put_data(id: vpi_sys::PLI_INT32, dataLoc: *mut vpi_sys::PLI_BYTE8, numOfBytes: vpi_sys::PLI_INT32) -> vpi_sys::PLI_INT32;
+    fn vpi_get_userdata(obj: vpi_sys::vpiHandle) -> *mut c_void;
+    fn vpi_put_userdata(obj: vpi_sys::vpiHandle, userdata: *mut c_void) -> vpi_sys::PLI_INT32;
     fn vpi_put_value(
         object: vpi_sys::vpiHandle,
         value_p: vpi_sys::p_vpi_value,
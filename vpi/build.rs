@@ -0,0 +1,17 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(mock_backend)");
+
+    // The mock simulator provides the `vpi_*` symbols itself, which clashes
+    // with the runtime lookup of `vpi-shim`. `mock_backend` is set when the
+    // mock is actually compiled in, so code can be gated on a single cfg.
+    let mock = env::var_os("CARGO_FEATURE_MOCK").is_some();
+    let dynamic = env::var_os("CARGO_FEATURE_DYNAMIC").is_some();
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let shim = dynamic && matches!(target_os.as_str(), "windows" | "macos");
+    if mock && !shim {
+        println!("cargo:rustc-cfg=mock_backend");
+    }
+}
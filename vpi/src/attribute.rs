@@ -0,0 +1,161 @@
+use crate::query::child_scopes;
+use crate::{Handle, ObjectType, Property, SimContext, Value, ValueType};
+
+/// A Verilog `(* name = value *)` attribute, as returned by
+/// [`Handle::attributes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    /// Attribute name.
+    pub name: String,
+    /// Attribute value, in the simulator's native format. Attributes written
+    /// without a value have the value 1.
+    pub value: Value,
+    /// `true` for attributes of the module definition (`vpiDefAttribute`)
+    /// rather than of the instance.
+    pub def_attr: bool,
+}
+
+impl Attribute {
+    fn from_handle(ctx: &SimContext, handle: &Handle) -> Option<Self> {
+        Some(Self {
+            name: handle.get_name(ctx)?,
+            value: handle.get_value(ctx, ValueType::ObjType)?,
+            def_attr: handle
+                .get_bool(ctx, Property::DefAttribute)
+                .unwrap_or(false),
+        })
+    }
+}
+
+/// Objects searched by [`objects_with_attribute`] in every module.
+const ATTRIBUTED: &[ObjectType] = &[
+    ObjectType::Port,
+    ObjectType::Net,
+    ObjectType::Reg,
+    ObjectType::Variables,
+    ObjectType::Memory,
+    ObjectType::Parameter,
+    ObjectType::Primitive,
+    ObjectType::ContAssign,
+    ObjectType::Process,
+    ObjectType::Function,
+    ObjectType::Task,
+];
+
+impl Handle {
+    /// Returns the attributes attached to this object with `vpiAttribute`.
+    ///
+    /// Returns an empty list for null handles and for simulators without
+    /// attribute support.
+    #[must_use]
+    pub fn attributes(&self, ctx: &SimContext) -> Vec<Attribute> {
+        if self.is_null() {
+            return Vec::new();
+        }
+        self.iterator(ctx, ObjectType::Attribute)
+            .filter_map(|attribute| Attribute::from_handle(ctx, &attribute))
+            .collect()
+    }
+
+    /// Returns the attribute `name` of this object, if present.
+    #[must_use]
+    pub fn attribute(&self, ctx: &SimContext, name: &str) -> Option<Attribute> {
+        self.attributes(ctx)
+            .into_iter()
+            .find(|attribute| attribute.name == name)
+    }
+
+    /// Returns `true` if this object carries the attribute `name`.
+    #[must_use]
+    pub fn has_attribute(&self, ctx: &SimContext, name: &str) -> bool {
+        self.attribute(ctx, name).is_some()
+    }
+}
+
+/// Returns all objects of the design carrying the attribute `name`, in
+/// hierarchy order.
+///
+/// Modules, their generate scopes and named blocks, and the ports, nets, variables, memories, parameters, primitives,
+/// continuous assignments, processes, functions and tasks declared in them
+/// are searched.
+#[must_use]
+pub fn objects_with_attribute(ctx: &SimContext, name: &str) -> Vec<Handle> {
+    let mut found = Vec::new();
+    for top in Handle::null().iterator(ctx, ObjectType::Module) {
+        collect_attributed(ctx, &top, name, &mut found);
+    }
+    found
+}
+
+/// Like [`objects_with_attribute`], but searches only `scope` and the
+/// modules below it.
+#[must_use]
+pub fn objects_with_attribute_in(ctx: &SimContext, scope: &Handle, name: &str) -> Vec<Handle> {
+    let mut found = Vec::new();
+    collect_attributed(ctx, scope, name, &mut found);
+    found
+}
+
+fn collect_attributed(ctx: &SimContext, scope: &Handle, name: &str, found: &mut Vec<Handle>) {
+    if scope.is_null() {
+        return;
+    }
+    if scope.has_attribute(ctx, name) {
+        found.push(scope.clone());
+    }
+    found.extend(
+        scope
+            .iterators(ctx, ATTRIBUTED)
+            .filter(|object| object.has_attribute(ctx, name)),
+    );
+    for child in child_scopes(ctx, scope) {
+        collect_attributed(ctx, &child, name, found);
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::{objects_with_attribute, objects_with_attribute_in, Attribute};
+    use crate::mock::MockSimulator;
+    use crate::{Handle, LogicVal, Value};
+
+    #[test]
+    fn attributes_are_read_and_searched() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        let dut = sim.add_module(&tb, "dut", "dut");
+        let probe = sim.add_net(&tb, "probe", 1);
+        let scan = sim.add_reg(&dut, "scan_en", 1);
+        let _ = sim.add_attribute(&probe, "debug_probe", &Value::Scalar(LogicVal::One), false);
+        let _ = sim.add_attribute(&probe, "group", &Value::String("bus".into()), false);
+        let _ = sim.add_attribute(&scan, "debug_probe", &Value::Int(2), false);
+        let _ = sim.add_attribute(&dut, "dft", &Value::Int(1), true);
+        let tap = sim.add_net(&sim.add_gen_scope(&tb, "gen[0]"), "tap", 1);
+        let _ = sim.add_attribute(&tap, "debug_probe", &Value::Int(1), false);
+
+        assert_eq!(probe.attributes(ctx).len(), 2);
+        assert_eq!(
+            probe.attribute(ctx, "group"),
+            Some(Attribute {
+                name: "group".into(),
+                value: Value::String("bus".into()),
+                def_attr: false,
+            })
+        );
+        assert!(dut.attribute(ctx, "dft").unwrap().def_attr);
+        assert!(!tb.has_attribute(ctx, "dft"));
+        assert!(Handle::null().attributes(ctx).is_empty());
+
+        let probes: Vec<_> = objects_with_attribute(ctx, "debug_probe")
+            .iter()
+            .filter_map(|handle| handle.get_full_name(ctx))
+            .collect();
+        assert_eq!(probes, ["tb.probe", "tb.dut.scan_en", "tb.gen[0].tap"]);
+        assert_eq!(
+            objects_with_attribute(ctx, "dft"),
+            std::slice::from_ref(&dut)
+        );
+        assert_eq!(objects_with_attribute_in(ctx, &dut, "debug_probe"), [scan]);
+    }
+}
@@ -1,220 +1,297 @@
-use crate::{value::decode_vpi_value, Handle, Time, Value, ValueType};
+use crate::{
+    value::decode_vpi_value, Error, Handle, HandleRef, ObjectType, Property, SimContext, Time,
+    TimeFormat, VPIError, Value, ValueType,
+};
 use num_traits::FromPrimitive;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 #[cfg(any(not(feature = "cb_info"), feature = "sv"))]
 use std::{
     collections::HashMap,
     sync::{Mutex, OnceLock},
 };
 
-/// VPI callback reasons used when registering simulator callbacks.
-///
-/// These values map directly to `vpi_sys::cb*` constants.
-#[repr(u32)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, num_derive::FromPrimitive)]
-pub enum CbReason {
+/// Declares [`CbReason`] together with its conversions from and to the raw
+/// `vpi_sys::cb*` constants.
+macro_rules! cb_reasons {
+    ($($(#[cfg($cfg:meta)])? $(#[doc = $doc:literal])* $name:ident => $value:path,)*) => {
+        /// VPI callback reasons used when registering simulator callbacks.
+        ///
+        /// These values map directly to `vpi_sys::cb*` constants; see
+        /// [`CbReason::from_raw`] and [`CbReason::as_raw`].
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum CbReason {
+            $($(#[cfg($cfg)])? $(#[doc = $doc])* $name,)*
+            /// A reason without a variant in this crate, holding the raw value
+            /// reported by the simulator.
+            Unknown(i32),
+        }
+
+        impl CbReason {
+            /// Converts a raw `cb*` value, mapping values without a variant
+            /// to [`CbReason::Unknown`].
+            #[must_use]
+            pub fn from_raw(raw: i32) -> Self {
+                match u32::try_from(raw) {
+                    $($(#[cfg($cfg)])? Ok($value) => Self::$name,)*
+                    _ => Self::Unknown(raw),
+                }
+            }
+
+            /// Returns the raw `cb*` value passed to the simulator.
+            #[must_use]
+            pub fn as_raw(self) -> i32 {
+                match self {
+                    $($(#[cfg($cfg)])? Self::$name => $value as i32,)*
+                    Self::Unknown(raw) => raw,
+                }
+            }
+        }
+    };
+}
+
+cb_reasons! {
     /// Callback on value change.
-    ValueChange = vpi_sys::cbValueChange,
+    ValueChange => vpi_sys::cbValueChange,
     /// Callback on statement execution.
-    Stmt = vpi_sys::cbStmt,
+    Stmt => vpi_sys::cbStmt,
     /// Callback on force.
-    Force = vpi_sys::cbForce,
+    Force => vpi_sys::cbForce,
     /// Callback on release.
-    Release = vpi_sys::cbRelease,
+    Release => vpi_sys::cbRelease,
     /// Callback at the start of the current simulation time.
-    AtStartOfSimTime = vpi_sys::cbAtStartOfSimTime,
+    AtStartOfSimTime => vpi_sys::cbAtStartOfSimTime,
     /// Callback during the read-write synchronization phase.
-    ReadWriteSynch = vpi_sys::cbReadWriteSynch,
+    ReadWriteSynch => vpi_sys::cbReadWriteSynch,
     /// Callback during the read-only synchronization phase.
-    ReadOnlySynch = vpi_sys::cbReadOnlySynch,
+    ReadOnlySynch => vpi_sys::cbReadOnlySynch,
     /// Callback at the next simulation time.
-    NextSimTime = vpi_sys::cbNextSimTime,
+    NextSimTime => vpi_sys::cbNextSimTime,
     /// Callback after a delay.
-    AfterDelay = vpi_sys::cbAfterDelay,
+    AfterDelay => vpi_sys::cbAfterDelay,
     /// Callback at the end of compilation.
-    EndOfCompile = vpi_sys::cbEndOfCompile,
+    EndOfCompile => vpi_sys::cbEndOfCompile,
     /// Callback at the start of simulation.
-    StartOfSimulation = vpi_sys::cbStartOfSimulation,
+    StartOfSimulation => vpi_sys::cbStartOfSimulation,
     /// Callback at the end of simulation.
-    EndOfSimulation = vpi_sys::cbEndOfSimulation,
+    EndOfSimulation => vpi_sys::cbEndOfSimulation,
     /// Callback on error.
-    Error = vpi_sys::cbError,
+    Error => vpi_sys::cbError,
     /// Callback on timing-check violation.
-    TchkViolation = vpi_sys::cbTchkViolation,
+    TchkViolation => vpi_sys::cbTchkViolation,
     /// Callback at the start of save.
-    StartOfSave = vpi_sys::cbStartOfSave,
+    StartOfSave => vpi_sys::cbStartOfSave,
     /// Callback at the end of save.
-    EndOfSave = vpi_sys::cbEndOfSave,
+    EndOfSave => vpi_sys::cbEndOfSave,
     /// Callback at the start of restart.
-    StartOfRestart = vpi_sys::cbStartOfRestart,
+    StartOfRestart => vpi_sys::cbStartOfRestart,
     /// Callback at the end of restart.
-    EndOfRestart = vpi_sys::cbEndOfRestart,
+    EndOfRestart => vpi_sys::cbEndOfRestart,
     /// Callback at the start of reset.
-    StartOfReset = vpi_sys::cbStartOfReset,
+    StartOfReset => vpi_sys::cbStartOfReset,
     /// Callback at the end of reset.
-    EndOfReset = vpi_sys::cbEndOfReset,
+    EndOfReset => vpi_sys::cbEndOfReset,
     /// Callback on entry to interactive mode.
-    EnterInteractive = vpi_sys::cbEnterInteractive,
+    EnterInteractive => vpi_sys::cbEnterInteractive,
     /// Callback on exit from interactive mode.
-    ExitInteractive = vpi_sys::cbExitInteractive,
+    ExitInteractive => vpi_sys::cbExitInteractive,
     /// Callback when the interactive scope changes.
-    InteractiveScopeChange = vpi_sys::cbInteractiveScopeChange,
+    InteractiveScopeChange => vpi_sys::cbInteractiveScopeChange,
     /// Callback on unresolved system task or function lookup.
-    UnresolvedSystf = vpi_sys::cbUnresolvedSystf,
+    UnresolvedSystf => vpi_sys::cbUnresolvedSystf,
     /// Callback on PLI error.
-    PLIError = vpi_sys::cbPLIError,
+    PLIError => vpi_sys::cbPLIError,
     /// Callback on assignment.
-    Assign = vpi_sys::cbAssign,
+    Assign => vpi_sys::cbAssign,
     /// Callback on deassignment.
-    Deassign = vpi_sys::cbDeassign,
+    Deassign => vpi_sys::cbDeassign,
     /// Callback on disable.
-    Disable = vpi_sys::cbDisable,
+    Disable => vpi_sys::cbDisable,
     /// Callback on signal delivery.
-    Signal = vpi_sys::cbSignal,
+    Signal => vpi_sys::cbSignal,
     /// Callback during the NBA synchronization phase.
-    NBASynch = vpi_sys::cbNBASynch,
+    NBASynch => vpi_sys::cbNBASynch,
     /// Callback at the end of the current simulation time.
-    AtEndOfSimTime = vpi_sys::cbAtEndOfSimTime,
+    AtEndOfSimTime => vpi_sys::cbAtEndOfSimTime,
 
     #[cfg(feature = "sv")]
     // SystemVerilog thread callbacks (600-605)
     /// Callback on thread creation.
-    StartOfThread = vpi_sys::cbStartOfThread,
+    StartOfThread => vpi_sys::cbStartOfThread,
     #[cfg(feature = "sv")]
     /// Callback on thread termination.
-    EndOfThread = vpi_sys::cbEndOfThread,
+    EndOfThread => vpi_sys::cbEndOfThread,
     #[cfg(feature = "sv")]
     /// Callback on thread reentry.
-    EnterThread = vpi_sys::cbEnterThread,
+    EnterThread => vpi_sys::cbEnterThread,
     #[cfg(feature = "sv")]
     /// Callback on frame creation.
-    StartOfFrame = vpi_sys::cbStartOfFrame,
+    StartOfFrame => vpi_sys::cbStartOfFrame,
     #[cfg(feature = "sv")]
     /// Callback on frame exit.
-    EndOfFrame = vpi_sys::cbEndOfFrame,
+    EndOfFrame => vpi_sys::cbEndOfFrame,
     #[cfg(feature = "sv")]
     /// Callback on array variable size change.
-    SizeChange = vpi_sys::cbSizeChange,
+    SizeChange => vpi_sys::cbSizeChange,
 
     #[cfg(feature = "sv")]
     // SystemVerilog assertion callbacks (606-662)
     /// Assertion start.
-    AssertionStart = vpi_sys::cbAssertionStart,
+    AssertionStart => vpi_sys::cbAssertionStart,
     #[cfg(feature = "sv")]
     /// Assertion success.
-    AssertionSuccess = vpi_sys::cbAssertionSuccess,
+    AssertionSuccess => vpi_sys::cbAssertionSuccess,
     #[cfg(feature = "sv")]
     /// Assertion failure.
-    AssertionFailure = vpi_sys::cbAssertionFailure,
+    AssertionFailure => vpi_sys::cbAssertionFailure,
     #[cfg(feature = "sv")]
     /// Assertion step success.
-    AssertionStepSuccess = vpi_sys::cbAssertionStepSuccess,
+    AssertionStepSuccess => vpi_sys::cbAssertionStepSuccess,
     #[cfg(feature = "sv")]
     /// Assertion step failure.
-    AssertionStepFailure = vpi_sys::cbAssertionStepFailure,
+    AssertionStepFailure => vpi_sys::cbAssertionStepFailure,
     #[cfg(feature = "sv")]
     /// Assertion disable.
-    AssertionDisable = vpi_sys::cbAssertionDisable,
+    AssertionDisable => vpi_sys::cbAssertionDisable,
     #[cfg(feature = "sv")]
     /// Assertion enable.
-    AssertionEnable = vpi_sys::cbAssertionEnable,
+    AssertionEnable => vpi_sys::cbAssertionEnable,
     #[cfg(feature = "sv")]
     /// Assertion reset.
-    AssertionReset = vpi_sys::cbAssertionReset,
+    AssertionReset => vpi_sys::cbAssertionReset,
     #[cfg(feature = "sv")]
     /// Assertion kill.
-    AssertionKill = vpi_sys::cbAssertionKill,
+    AssertionKill => vpi_sys::cbAssertionKill,
     #[cfg(feature = "sv")]
     /// Assertion system initialization.
-    AssertionSysInitialized = vpi_sys::cbAssertionSysInitialized,
+    AssertionSysInitialized => vpi_sys::cbAssertionSysInitialized,
     #[cfg(feature = "sv")]
     /// Assertion system on.
-    AssertionSysOn = vpi_sys::cbAssertionSysOn,
+    AssertionSysOn => vpi_sys::cbAssertionSysOn,
     #[cfg(feature = "sv")]
     /// Assertion system off.
-    AssertionSysOff = vpi_sys::cbAssertionSysOff,
+    AssertionSysOff => vpi_sys::cbAssertionSysOff,
     #[cfg(feature = "sv")]
     /// Assertion system kill.
-    AssertionSysKill = vpi_sys::cbAssertionSysKill,
+    AssertionSysKill => vpi_sys::cbAssertionSysKill,
     #[cfg(feature = "sv")]
     /// Assertion system end.
-    AssertionSysEnd = vpi_sys::cbAssertionSysEnd,
+    AssertionSysEnd => vpi_sys::cbAssertionSysEnd,
     #[cfg(feature = "sv")]
     /// Assertion system reset.
-    AssertionSysReset = vpi_sys::cbAssertionSysReset,
+    AssertionSysReset => vpi_sys::cbAssertionSysReset,
     #[cfg(feature = "sv")]
     /// Assertion vacuous success.
-    AssertionVacuousSuccess = vpi_sys::cbAssertionVacuousSuccess,
+    AssertionVacuousSuccess => vpi_sys::cbAssertionVacuousSuccess,
     #[cfg(feature = "sv")]
     /// Assertion disabled evaluation.
-    AssertionDisabledEvaluation = vpi_sys::cbAssertionDisabledEvaluation,
+    AssertionDisabledEvaluation => vpi_sys::cbAssertionDisabledEvaluation,
     #[cfg(feature = "sv")]
     /// Assertion system lock.
-    AssertionSysLock = vpi_sys::cbAssertionSysLock,
+    AssertionSysLock => vpi_sys::cbAssertionSysLock,
     #[cfg(feature = "sv")]
     /// Assertion system unlock.
-    AssertionSysUnlock = vpi_sys::cbAssertionSysUnlock,
+    AssertionSysUnlock => vpi_sys::cbAssertionSysUnlock,
     #[cfg(feature = "sv")]
     /// Assertion lock.
-    AssertionLock = vpi_sys::cbAssertionLock,
+    AssertionLock => vpi_sys::cbAssertionLock,
     #[cfg(feature = "sv")]
     /// Assertion unlock.
-    AssertionUnlock = vpi_sys::cbAssertionUnlock,
+    AssertionUnlock => vpi_sys::cbAssertionUnlock,
     #[cfg(feature = "sv")]
     /// Assertion enable pass action.
-    AssertionEnablePassAction = vpi_sys::cbAssertionEnablePassAction,
+    AssertionEnablePassAction => vpi_sys::cbAssertionEnablePassAction,
     #[cfg(feature = "sv")]
     /// Assertion enable fail action.
-    AssertionEnableFailAction = vpi_sys::cbAssertionEnableFailAction,
+    AssertionEnableFailAction => vpi_sys::cbAssertionEnableFailAction,
     #[cfg(feature = "sv")]
     /// Assertion disable pass action.
-    AssertionDisablePassAction = vpi_sys::cbAssertionDisablePassAction,
+    AssertionDisablePassAction => vpi_sys::cbAssertionDisablePassAction,
     #[cfg(feature = "sv")]
     /// Assertion disable fail action.
-    AssertionDisableFailAction = vpi_sys::cbAssertionDisableFailAction,
+    AssertionDisableFailAction => vpi_sys::cbAssertionDisableFailAction,
     #[cfg(feature = "sv")]
     /// Assertion enable non-vacuous action.
-    AssertionEnableNonvacuousAction = vpi_sys::cbAssertionEnableNonvacuousAction,
+    AssertionEnableNonvacuousAction => vpi_sys::cbAssertionEnableNonvacuousAction,
     #[cfg(feature = "sv")]
     /// Assertion disable vacuous action.
-    AssertionDisableVacuousAction = vpi_sys::cbAssertionDisableVacuousAction,
+    AssertionDisableVacuousAction => vpi_sys::cbAssertionDisableVacuousAction,
     #[cfg(feature = "sv")]
     /// Assertion system enable pass action.
-    AssertionSysEnablePassAction = vpi_sys::cbAssertionSysEnablePassAction,
+    AssertionSysEnablePassAction => vpi_sys::cbAssertionSysEnablePassAction,
     #[cfg(feature = "sv")]
     /// Assertion system enable fail action.
-    AssertionSysEnableFailAction = vpi_sys::cbAssertionSysEnableFailAction,
+    AssertionSysEnableFailAction => vpi_sys::cbAssertionSysEnableFailAction,
     #[cfg(feature = "sv")]
     /// Assertion system disable pass action.
-    AssertionSysDisablePassAction = vpi_sys::cbAssertionSysDisablePassAction,
+    AssertionSysDisablePassAction => vpi_sys::cbAssertionSysDisablePassAction,
     #[cfg(feature = "sv")]
     /// Assertion system disable fail action.
-    AssertionSysDisableFailAction = vpi_sys::cbAssertionSysDisableFailAction,
+    AssertionSysDisableFailAction => vpi_sys::cbAssertionSysDisableFailAction,
     #[cfg(feature = "sv")]
     /// Assertion system enable non-vacuous action.
-    AssertionSysEnableNonvacuousAction = vpi_sys::cbAssertionSysEnableNonvacuousAction,
+    AssertionSysEnableNonvacuousAction => vpi_sys::cbAssertionSysEnableNonvacuousAction,
     #[cfg(feature = "sv")]
     /// Assertion system disable vacuous action.
-    AssertionSysDisableVacuousAction = vpi_sys::cbAssertionSysDisableVacuousAction,
+    AssertionSysDisableVacuousAction => vpi_sys::cbAssertionSysDisableVacuousAction,
 
     #[cfg(feature = "sv")]
     // SystemVerilog object callbacks (700-702)
     /// Callback on class object creation.
-    CreateObj = vpi_sys::cbCreateObj,
+    CreateObj => vpi_sys::cbCreateObj,
     #[cfg(feature = "sv")]
     /// Callback on class object reclamation.
-    ReclaimObj = vpi_sys::cbReclaimObj,
+    ReclaimObj => vpi_sys::cbReclaimObj,
     #[cfg(feature = "sv")]
     /// Callback on transient object deletion.
-    EndOfObject = vpi_sys::cbEndOfObject,
+    EndOfObject => vpi_sys::cbEndOfObject,
+}
+
+/// Converts only values with a named variant; use [`CbReason::from_raw`] to
+/// keep unknown values.
+impl num_traits::FromPrimitive for CbReason {
+    fn from_i64(n: i64) -> Option<Self> {
+        let reason = Self::from_raw(i32::try_from(n).ok()?);
+        (!matches!(reason, Self::Unknown(_))).then_some(reason)
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        Self::from_i64(i64::try_from(n).ok()?)
+    }
+}
+
+impl CbReason {
+    /// Returns `true` for reasons whose callbacks fire at most once.
+    ///
+    /// These are the simulation time callbacks, which the simulator retires
+    /// after calling them, so their handles must not be removed afterwards.
+    /// Action callbacks such as `cbEndOfCompile` or `cbEndOfSimulation` stay
+    /// registered even though the action happens only once.
+    #[must_use]
+    pub fn is_one_shot(self) -> bool {
+        matches!(
+            self,
+            CbReason::AtStartOfSimTime
+                | CbReason::ReadWriteSynch
+                | CbReason::ReadOnlySynch
+                | CbReason::NextSimTime
+                | CbReason::AfterDelay
+                | CbReason::NBASynch
+                | CbReason::AtEndOfSimTime
+        )
+    }
 }
 
 /// Safe callback data passed to Rust closures.
 #[derive(Debug)]
-pub struct CbData {
+pub struct CbData<'a> {
     /// Callback reason.
     pub reason: CbReason,
     /// Object handle associated with the callback invocation.
-    pub obj: Handle,
+    ///
+    /// The object is owned by the simulator and only borrowed for the
+    /// duration of the callback.
+    pub obj: HandleRef<'a>,
     /// Optional callback time payload.
     pub time: Option<Time>,
     /// Optional callback value payload.
@@ -223,6 +300,146 @@ pub struct CbData {
     pub value_type: Option<ValueType>,
     /// Callback index payload. Meaning depends on callback reason; may be unused for some callbacks.
     pub index: i32,
+    /// Error record of error callbacks, read before any other VPI call.
+    error: Option<VPIError>,
+}
+
+/// Callback payload decoded according to the callback reason, as returned
+/// by [`CbData::event`].
+///
+/// Reasons without a dedicated variant, or whose payload the simulator did
+/// not provide, are reported as [`CbEvent::Other`]; the raw fields remain
+/// available in [`CbData`].
+#[derive(Debug)]
+pub enum CbEvent<'a> {
+    /// [`CbReason::ValueChange`].
+    ValueChange {
+        /// The object whose value changed.
+        signal: HandleRef<'a>,
+        /// The new value, in the requested format.
+        value: Option<Value>,
+        /// Time of the change.
+        time: Option<Time>,
+        /// Index of the changed element when `signal` is an array.
+        array_index: Option<i32>,
+    },
+    /// [`CbReason::Stmt`].
+    Stmt {
+        /// The statement being executed.
+        stmt: HandleRef<'a>,
+        /// Source file of the statement.
+        file: Option<String>,
+        /// Source line of the statement.
+        line: Option<u32>,
+    },
+    /// [`CbReason::Force`].
+    Force {
+        /// The forced object.
+        target: HandleRef<'a>,
+        /// The forced value.
+        value: Option<Value>,
+    },
+    /// [`CbReason::Release`].
+    Release {
+        /// The released object.
+        target: HandleRef<'a>,
+        /// The value after the release.
+        value: Option<Value>,
+    },
+    /// [`CbReason::TchkViolation`].
+    TchkViolation {
+        /// The violated timing check.
+        tchk: HandleRef<'a>,
+        /// Time of the violation.
+        time: Option<Time>,
+    },
+    /// [`CbReason::Error`] and [`CbReason::PLIError`], with the error record
+    /// read through [`crate::chk_error`] before the callback runs.
+    Error(VPIError),
+    /// [`CbReason::UnresolvedSystf`].
+    UnresolvedSystf {
+        /// Name of the unresolved system task or function.
+        name: String,
+    },
+    /// Simulation time callbacks, such as [`CbReason::AfterDelay`] or
+    /// [`CbReason::ReadOnlySynch`].
+    SimTime {
+        /// Current simulation time.
+        time: Option<Time>,
+    },
+    /// Any other reason.
+    Other,
+}
+
+impl<'a> CbData<'a> {
+    /// Decodes the payload according to the callback reason.
+    ///
+    /// Decoding may query the simulator, e.g. for the type of the changed
+    /// object or the source location of a statement, so it is only done when
+    /// asked for rather than for every callback.
+    #[must_use]
+    pub fn event(&self) -> CbEvent<'a> {
+        let ctx = self.context();
+        let object = || unsafe { HandleRef::from_raw(self.obj.as_raw()) };
+        let (time, value, index) = (self.time.clone(), self.value.clone(), self.index);
+        match self.reason {
+            CbReason::ValueChange => {
+                let signal = object();
+                let array_index = matches!(
+                    signal.get_type(ctx),
+                    Some(ObjectType::Memory | ObjectType::NetArray | ObjectType::RegArray)
+                )
+                .then_some(index);
+                CbEvent::ValueChange {
+                    signal,
+                    value,
+                    time,
+                    array_index,
+                }
+            }
+            CbReason::Stmt => {
+                let stmt = object();
+                CbEvent::Stmt {
+                    file: stmt.get_str(ctx, Property::File),
+                    line: stmt.get_u32(ctx, Property::LineNo),
+                    stmt,
+                }
+            }
+            CbReason::Force => CbEvent::Force {
+                target: object(),
+                value,
+            },
+            CbReason::Release => CbEvent::Release {
+                target: object(),
+                value,
+            },
+            CbReason::TchkViolation => CbEvent::TchkViolation {
+                tchk: object(),
+                time,
+            },
+            CbReason::Error | CbReason::PLIError => {
+                self.error.clone().map_or(CbEvent::Other, CbEvent::Error)
+            }
+            CbReason::UnresolvedSystf => match value {
+                Some(Value::String(name)) => CbEvent::UnresolvedSystf { name },
+                _ => CbEvent::Other,
+            },
+            CbReason::AtStartOfSimTime
+            | CbReason::ReadWriteSynch
+            | CbReason::ReadOnlySynch
+            | CbReason::NextSimTime
+            | CbReason::AfterDelay
+            | CbReason::NBASynch
+            | CbReason::AtEndOfSimTime => CbEvent::SimTime { time },
+            _ => CbEvent::Other,
+        }
+    }
+
+    /// Returns the simulator context of the running callback.
+    #[must_use]
+    pub fn context(&self) -> &SimContext {
+        SimContext::borrowed()
+    }
 }
 
 fn time_from_cb_data(raw_time: vpi_sys::s_vpi_time) -> Option<Time> {
@@ -236,10 +453,144 @@ fn time_from_cb_data(raw_time: vpi_sys::s_vpi_time) -> Option<Time> {
     }
 }
 
+type MutCallback = Box<dyn FnMut(&CbData)>;
+type OnceCallback = Box<dyn FnOnce(&CbData)>;
+
+/// The closure invoked by the trampoline.
+enum CallbackFn {
+    Fn(Box<dyn Fn(&CbData)>),
+    Mut(RefCell<MutCallback>),
+    Once(Cell<Option<OnceCallback>>),
+}
+
+impl CallbackFn {
+    fn shared(callback: impl Fn(&CbData) + 'static) -> Self {
+        CallbackFn::Fn(Box::new(callback))
+    }
+
+    fn mutable(callback: impl FnMut(&CbData) + 'static) -> Self {
+        CallbackFn::Mut(RefCell::new(Box::new(callback)))
+    }
+
+    fn once(callback: impl FnOnce(&CbData) + 'static) -> Self {
+        CallbackFn::Once(Cell::new(Some(Box::new(callback))))
+    }
+
+    /// Calls the closure.
+    ///
+    /// An `FnMut` or `FnOnce` closure that is already running, for example
+    /// because it wrote a signal it watches, is not entered again; the
+    /// nested call is reported through [`crate::printf`] and skipped.
+    fn call(&self, data: &CbData) {
+        match self {
+            CallbackFn::Fn(callback) => callback(data),
+            CallbackFn::Mut(callback) => match callback.try_borrow_mut() {
+                Ok(mut callback) => callback(data),
+                Err(_) => report_reentry(data),
+            },
+            CallbackFn::Once(callback) => match callback.take() {
+                Some(callback) => callback(data),
+                None => report_reentry(data),
+            },
+        }
+    }
+}
+
+fn report_reentry(data: &CbData) {
+    let reason = data.reason;
+    crate::printf(
+        data.context(),
+        format!("ERROR: {reason:?} callback fired while it was still running; call skipped"),
+    );
+}
+
 struct CallbackState {
-    callback: Box<dyn Fn(&CbData)>,
+    callback: CallbackFn,
     time: Option<Box<vpi_sys::t_vpi_time>>,
     value: Option<Box<vpi_sys::t_vpi_value>>,
+    /// Passed as `t_cb_data.index`.
+    index: i32,
+    /// Set for callbacks owned by a [`CallbackGuard`].
+    life: Option<Rc<CallbackLife>>,
+}
+
+impl CallbackState {
+    fn new(
+        callback: CallbackFn,
+        time: Option<vpi_sys::t_vpi_time>,
+        value: Option<vpi_sys::t_vpi_value>,
+    ) -> Box<Self> {
+        Box::new(Self {
+            callback,
+            time: time.map(Box::new),
+            value: value.map(Box::new),
+            index: 0,
+            life: None,
+        })
+    }
+}
+
+/// Shared between a [`CallbackGuard`] and its trampoline so the callback
+/// state is freed exactly once.
+struct CallbackLife {
+    /// The simulator retires the callback after it fires.
+    one_shot: bool,
+    /// The closure is an `FnOnce`, so the callback is retired after its
+    /// first call even if the simulator would keep it.
+    once: bool,
+    /// Callback handle, used to remove a persistent `FnOnce` callback.
+    handle: Cell<vpi_sys::vpiHandle>,
+    /// Number of invocations currently executing.
+    running: Cell<u32>,
+    /// Set once the callback has been retired or removed.
+    finished: Cell<bool>,
+    /// Set once the callback state has been freed.
+    freed: Cell<bool>,
+}
+
+impl CallbackLife {
+    fn new(one_shot: bool, once: bool) -> Self {
+        Self {
+            one_shot,
+            once,
+            handle: Cell::new(std::ptr::null_mut()),
+            running: Cell::new(0),
+            finished: Cell::new(false),
+            freed: Cell::new(false),
+        }
+    }
+
+    /// Removes the callback from the simulator unless it was already retired
+    /// or removed. The state is not freed here.
+    fn retire(&self) {
+        if self.finished.replace(true) {
+            return;
+        }
+        // A one-shot callback that is executing is retired when it returns.
+        if !(self.running.get() > 0 && self.one_shot) {
+            unsafe {
+                vpi_sys::vpi_remove_cb(self.handle.get());
+            }
+        }
+    }
+
+    /// Runs one invocation and returns `true` if the state must be freed
+    /// afterwards.
+    fn run(&self, invoke: impl FnOnce()) -> bool {
+        self.running.set(self.running.get() + 1);
+        invoke();
+        self.running.set(self.running.get() - 1);
+        if self.one_shot {
+            self.finished.set(true);
+        } else if self.once && !self.finished.replace(true) {
+            unsafe {
+                vpi_sys::vpi_remove_cb(self.handle.get());
+            }
+        }
+        let free = self.finished.get() && self.running.get() == 0 && !self.freed.get();
+        self.freed.set(self.freed.get() || free);
+        free
+    }
 }
 
 #[cfg(not(feature = "cb_info"))]
@@ -267,19 +618,15 @@ fn take_callback_state(handle: vpi_sys::vpiHandle) -> Option<*mut CallbackState>
         .map(|state_ptr| state_ptr as *mut CallbackState)
 }
 
-fn default_cb_time() -> vpi_sys::t_vpi_time {
-    vpi_sys::t_vpi_time {
-        type_: vpi_sys::vpiSimTime as i32,
-        high: 0,
-        low: 0,
-        real: 0.0,
-    }
-}
-
-fn default_cb_value() -> vpi_sys::t_vpi_value {
-    vpi_sys::t_vpi_value {
-        format: vpi_sys::vpiObjTypeVal as i32,
-        value: vpi_sys::t_vpi_value__bindgen_ty_1 { integer: 0 },
+/// Drops the registry entry of `handle` if it still refers to `state_ptr`;
+/// the simulator may have reused a retired handle for another callback.
+#[cfg(not(feature = "cb_info"))]
+fn forget_callback_state(handle: vpi_sys::vpiHandle, state_ptr: *mut CallbackState) {
+    let mut registry = callback_state_registry()
+        .lock()
+        .expect("callback state registry poisoned");
+    if registry.get(&(handle as usize)) == Some(&(state_ptr as usize)) {
+        registry.remove(&(handle as usize));
     }
 }
 
@@ -323,22 +670,34 @@ fn take_assertion_callback_state(
 #[cfg(feature = "sv")]
 struct AssertionCallbackState {
     callback: Box<dyn Fn(&AssertionCbData)>,
+    /// Set for callbacks owned by a [`CallbackGuard`].
+    life: Option<Rc<CallbackLife>>,
 }
 
 /// Safe callback data passed to SystemVerilog assertion callbacks.
 #[cfg(feature = "sv")]
 #[derive(Debug)]
-pub struct AssertionCbData {
+pub struct AssertionCbData<'a> {
     /// Callback reason.
     pub reason: CbReason,
-    /// Assertion object associated with the callback.
-    pub assertion: Handle,
+    /// Assertion object associated with the callback, borrowed from the
+    /// simulator for the duration of the callback.
+    pub assertion: HandleRef<'a>,
     /// Optional callback time payload.
     pub time: Option<Time>,
     /// Optional assertion-attempt metadata from `p_vpi_attempt_info`.
     pub attempt_info: Option<AssertionAttemptInfo>,
 }
 
+#[cfg(feature = "sv")]
+impl AssertionCbData<'_> {
+    /// Returns the simulator context of the running callback.
+    #[must_use]
+    pub fn context(&self) -> &SimContext {
+        SimContext::borrowed()
+    }
+}
+
 /// Decoded step-transition details for assertion step callbacks.
 #[cfg(feature = "sv")]
 #[derive(Debug)]
@@ -438,110 +797,491 @@ unsafe extern "C" fn assertion_trampoline(
     if state_ptr.is_null() {
         return 0;
     }
+    let reason = CbReason::from_raw(reason);
+    let ctx = crate::context::enter_callback();
 
-    let Some(reason) = CbReason::from_u32(reason as u32) else {
-        return 0;
-    };
-
-    let mut data = AssertionCbData {
-        reason,
-        assertion: Handle::from_raw(assertion),
-        time: if cb_time.is_null() {
-            None
-        } else {
-            time_from_cb_data(unsafe { *cb_time })
-        },
-        attempt_info: decode_assertion_attempt_info(reason, info),
-    };
-
+    let assertion = unsafe { HandleRef::from_raw(assertion) };
     let state = unsafe { &*state_ptr };
-    (state.callback)(&data);
-    data.assertion.clear();
+    // Panics are caught inside the run, so the guard state stays consistent.
+    let call = || {
+        let _ = crate::panic::guard_callback(ctx, reason, &assertion, || {
+            let data = AssertionCbData {
+                reason,
+                assertion: unsafe { HandleRef::from_raw(assertion.as_raw()) },
+                time: if cb_time.is_null() {
+                    None
+                } else {
+                    time_from_cb_data(unsafe { *cb_time })
+                },
+                attempt_info: decode_assertion_attempt_info(reason, info),
+            };
+            (state.callback)(&data);
+        });
+    };
+    match state.life.clone() {
+        Some(life) => {
+            if life.run(call) {
+                drop(unsafe { Box::from_raw(state_ptr) });
+            }
+        }
+        None => call(),
+    }
     0
 }
 
+/// Registers `state` with the trampoline and returns the callback handle,
+/// freeing the state again if registration fails.
+///
+/// Missing time or value storage is passed to the simulator as null.
 fn register_with_state(
+    ctx: &SimContext,
     reason: CbReason,
     obj: vpi_sys::vpiHandle,
     state: Box<CallbackState>,
-) -> Handle {
+) -> Result<(Handle, *mut CallbackState), Error> {
     let state_ptr = Box::into_raw(state);
     let state_ref = unsafe { &mut *state_ptr };
 
     let handle = unsafe {
         let mut cb_data = vpi_sys::s_cb_data {
-            reason: reason as i32,
+            reason: reason.as_raw(),
             cb_rtn: Some(trampoline),
             obj,
-            time: std::ptr::from_mut(
-                state_ref
-                    .time
-                    .as_mut()
-                    .expect("register_with_state requires time storage")
-                    .as_mut(),
-            ),
-            value: std::ptr::from_mut(
-                state_ref
-                    .value
-                    .as_mut()
-                    .expect("register_with_state requires value storage")
-                    .as_mut(),
-            ),
-            index: 0,
+            time: state_ref
+                .time
+                .as_deref_mut()
+                .map_or(std::ptr::null_mut(), std::ptr::from_mut),
+            value: state_ref
+                .value
+                .as_deref_mut()
+                .map_or(std::ptr::null_mut(), std::ptr::from_mut),
+            index: state_ref.index,
             user_data: state_ptr.cast::<vpi_sys::PLI_BYTE8>(),
         };
         vpi_sys::vpi_register_cb(&raw mut cb_data)
     };
 
     if handle.is_null() {
+        // Collect the simulator error first; dropping the closure may call into VPI.
+        let error = Error::last(
+            ctx,
+            "vpi_register_cb",
+            format!("cannot register {reason:?} callback"),
+        );
         unsafe {
             let _ = Box::from_raw(state_ptr);
         }
-    } else {
-        #[cfg(not(feature = "cb_info"))]
-        register_callback_state(handle, state_ptr);
+        return Err(error);
     }
+    #[cfg(not(feature = "cb_info"))]
+    register_callback_state(handle, state_ptr);
 
-    Handle::from_raw(handle)
+    Ok((Handle::from_raw(handle), state_ptr))
 }
 
-impl Handle {
-    /// Registers a callback associated with this handle.
+/// Registers `state` and returns the bare callback handle.
+fn register_handle(
+    ctx: &SimContext,
+    reason: CbReason,
+    obj: vpi_sys::vpiHandle,
+    state: Box<CallbackState>,
+) -> Result<Handle, Error> {
+    register_with_state(ctx, reason, obj, state).map(|(handle, _)| handle)
+}
+
+/// Registers `state` owned by the returned guard.
+fn try_register_guarded(
+    ctx: &SimContext,
+    reason: CbReason,
+    obj: vpi_sys::vpiHandle,
+    mut state: Box<CallbackState>,
+) -> Result<CallbackGuard, Error> {
+    let once = matches!(state.callback, CallbackFn::Once(_));
+    let life = Rc::new(CallbackLife::new(reason.is_one_shot(), once));
+    state.life = Some(Rc::clone(&life));
+    let (handle, state_ptr) = register_with_state(ctx, reason, obj, state)?;
+    life.handle.set(handle.as_raw());
+    Ok(CallbackGuard::new(
+        handle,
+        life,
+        GuardedState::Callback(state_ptr),
+    ))
+}
+
+/// Owns a callback registration and removes it when dropped.
+///
+/// Returned by the `*_guarded` registration functions, such as
+/// [`register_cb_guarded`] and [`Handle::register_value_change_cb_guarded`].
+/// Unlike with a bare callback [`Handle`], the boxed closure is always freed:
+/// by the guard when it is dropped, or right after the callback fires for
+/// one-shot reasons (see [`CbReason::is_one_shot`]), which the simulator
+/// retires on its own. Dropping the guard after that does not touch the
+/// retired handle.
+///
+/// The guard may be dropped from inside its own callback; the closure is
+/// then freed once it returns. Use [`CallbackGuard::detach`] to keep a
+/// callback registered for the rest of the simulation.
+#[must_use = "dropping a CallbackGuard removes the callback"]
+pub struct CallbackGuard {
+    handle: Handle,
+    life: Rc<CallbackLife>,
+    state: GuardedState,
+}
+
+enum GuardedState {
+    Callback(*mut CallbackState),
+    #[cfg(feature = "sv")]
+    Assertion(*mut AssertionCallbackState),
+}
+
+impl CallbackGuard {
+    fn new(handle: Handle, life: Rc<CallbackLife>, state: GuardedState) -> Self {
+        if handle.is_null() {
+            life.finished.set(true);
+            life.freed.set(true);
+        }
+        Self {
+            handle,
+            life,
+            state,
+        }
+    }
+
+    /// Returns a guard for a failed registration.
+    fn inactive() -> Self {
+        Self::new(
+            Handle::null(),
+            Rc::new(CallbackLife::new(false, false)),
+            GuardedState::Callback(std::ptr::null_mut()),
+        )
+    }
+
+    /// Returns the callback handle, which is null if registration failed.
     ///
-    /// Returns a callback handle that can be removed with [`remove_cb`].
-    pub fn register_cb<F>(&self, reason: CbReason, callback: F) -> Handle
+    /// Passing the handle to [`remove_cb`] removes the callback like
+    /// [`CallbackGuard::remove`], but the closure is only freed once the
+    /// guard is dropped.
+    #[must_use]
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Returns `true` while the callback is registered.
+    ///
+    /// This is `false` if registration failed, once a one-shot or `FnOnce`
+    /// callback has fired and its closure has been freed, and after removal.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        !self.life.finished.get()
+    }
+
+    /// Removes the callback now, like dropping the guard.
+    pub fn remove(self) {
+        drop(self);
+    }
+
+    /// Leaves the callback registered for the rest of the simulation.
+    ///
+    /// The closure of a persistent callback is intentionally leaked. One-shot
+    /// and `FnOnce` callbacks are still freed after they fire.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl std::fmt::Debug for CallbackGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackGuard")
+            .field("handle", &self.handle)
+            .field("active", &self.is_active())
+            .finish()
+    }
+}
+
+/// Builds a callback registration with full control over `t_cb_data`.
+///
+/// The registration helpers such as [`register_cb`] and
+/// [`Handle::register_value_change_cb`] are shorthands for common settings
+/// of this builder. Without [`CallbackBuilder::time_format`] or
+/// [`CallbackBuilder::at`] the simulator receives a null time pointer, and
+/// without [`CallbackBuilder::value_format`] a null value pointer.
+///
+/// A builder can register any number of callbacks with the same settings.
+///
+/// ```ignore
+/// // A cheap value-change callback that needs neither time nor value.
+/// let guard = CallbackBuilder::new(CbReason::ValueChange)
+///     .object(&clk)
+///     .time_format(TimeFormat::Suppress)
+///     .value_format(ValueType::Suppress)
+///     .register_guarded(ctx, |_| edges.set(edges.get() + 1))?;
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct CallbackBuilder {
+    reason: CbReason,
+    object: vpi_sys::vpiHandle,
+    time_format: Option<TimeFormat>,
+    time: Option<Time>,
+    value_format: Option<ValueType>,
+    index: i32,
+}
+
+impl CallbackBuilder {
+    /// Starts a registration for `reason`, not tied to an object.
+    pub fn new(reason: CbReason) -> Self {
+        Self {
+            reason,
+            object: std::ptr::null_mut(),
+            time_format: None,
+            time: None,
+            value_format: None,
+            index: 0,
+        }
+    }
+
+    /// Sets the callback reason (`t_cb_data.reason`).
+    pub fn reason(mut self, reason: CbReason) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    /// Sets the object the callback is tied to (`t_cb_data.obj`).
+    ///
+    /// The handle must stay valid while the callback is registered.
+    pub fn object(mut self, object: &Handle) -> Self {
+        self.object = object.as_raw();
+        self
+    }
+
+    /// Sets the format of the time passed to the callback
+    /// (`t_cb_data.time->type`).
+    ///
+    /// Use [`TimeFormat::Suppress`] for callbacks that do not need the time.
+    pub fn time_format(mut self, format: TimeFormat) -> Self {
+        self.time_format = Some(format);
+        self
+    }
+
+    /// Sets the time or delay of time callbacks, such as
+    /// [`CbReason::AfterDelay`].
+    ///
+    /// The variant of `time` also selects the format of the time passed to
+    /// the callback, and takes precedence over
+    /// [`CallbackBuilder::time_format`].
+    pub fn at(mut self, time: Time) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Sets the format of the value passed to the callback
+    /// (`t_cb_data.value->format`).
+    ///
+    /// Use [`ValueType::Suppress`] for callbacks that do not need the value.
+    pub fn value_format(mut self, format: ValueType) -> Self {
+        self.value_format = Some(format);
+        self
+    }
+
+    /// Sets `t_cb_data.index`, whose meaning depends on the reason.
+    pub fn index(mut self, index: i32) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Registers `callback` and returns the callback handle, which can be
+    /// removed with [`remove_cb`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator rejects the registration.
+    pub fn register<F>(&self, ctx: &SimContext, callback: F) -> Result<Handle, Error>
     where
         F: Fn(&CbData) + 'static,
     {
-        let user_data = Box::into_raw(Box::new(CallbackState {
-            callback: Box::new(callback),
-            time: None,
-            value: None,
-        }));
+        register_handle(
+            ctx,
+            self.reason,
+            self.object,
+            self.state(CallbackFn::shared(callback)),
+        )
+    }
 
-        let handle = unsafe {
-            let mut cb_data = vpi_sys::s_cb_data {
-                reason: reason as i32,
-                cb_rtn: Some(trampoline),
-                obj: self.as_raw(),
-                time: std::ptr::null_mut(),
-                value: std::ptr::null_mut(),
-                index: 0,
-                user_data: user_data.cast::<vpi_sys::PLI_BYTE8>(),
-            };
-            vpi_sys::vpi_register_cb(&raw mut cb_data)
+    /// `FnMut` variant of [`CallbackBuilder::register`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator rejects the registration.
+    pub fn register_mut<F>(&self, ctx: &SimContext, callback: F) -> Result<Handle, Error>
+    where
+        F: FnMut(&CbData) + 'static,
+    {
+        register_handle(
+            ctx,
+            self.reason,
+            self.object,
+            self.state(CallbackFn::mutable(callback)),
+        )
+    }
+
+    /// Registers `callback` owned by the returned guard; see
+    /// [`CallbackGuard`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator rejects the registration.
+    pub fn register_guarded<F>(&self, ctx: &SimContext, callback: F) -> Result<CallbackGuard, Error>
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        try_register_guarded(
+            ctx,
+            self.reason,
+            self.object,
+            self.state(CallbackFn::shared(callback)),
+        )
+    }
+
+    /// Registers `callback` to run at most once, owned by the returned
+    /// guard; see [`register_once`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator rejects the registration.
+    pub fn register_once<F>(&self, ctx: &SimContext, callback: F) -> Result<CallbackGuard, Error>
+    where
+        F: FnOnce(&CbData) + 'static,
+    {
+        try_register_guarded(
+            ctx,
+            self.reason,
+            self.object,
+            self.state(CallbackFn::once(callback)),
+        )
+    }
+
+    fn state(&self, callback: CallbackFn) -> Box<CallbackState> {
+        let time = match (&self.time, self.time_format) {
+            (Some(time), _) => Some(time.into()),
+            (None, Some(format)) => Some(vpi_sys::t_vpi_time {
+                type_: format.as_raw(),
+                high: 0,
+                low: 0,
+                real: 0.0,
+            }),
+            (None, None) => None,
         };
+        let value = self.value_format.map(cb_value_with_format);
+        let mut state = CallbackState::new(callback, time, value);
+        state.index = self.index;
+        state
+    }
+}
 
-        if handle.is_null() {
-            unsafe {
-                let _ = Box::from_raw(user_data);
+impl Drop for CallbackGuard {
+    fn drop(&mut self) {
+        self.life.retire();
+        #[cfg(not(feature = "cb_info"))]
+        match self.state {
+            GuardedState::Callback(state) => forget_callback_state(self.handle.as_raw(), state),
+            #[cfg(feature = "sv")]
+            GuardedState::Assertion(_) => {}
+        }
+        // Removed or retired by the simulator, which may have freed the handle.
+        self.handle.clear();
+        // If running, the trampoline frees the state once the callback returns.
+        if self.life.running.get() == 0 && !self.life.freed.replace(true) {
+            match self.state {
+                GuardedState::Callback(state) => drop(unsafe { Box::from_raw(state) }),
+                #[cfg(feature = "sv")]
+                GuardedState::Assertion(state) => drop(unsafe { Box::from_raw(state) }),
             }
-        } else {
-            #[cfg(not(feature = "cb_info"))]
-            register_callback_state(handle, user_data.cast::<CallbackState>());
         }
+    }
+}
+
+impl Handle {
+    /// Registers a callback associated with this handle.
+    ///
+    /// Returns a callback handle that can be removed with [`remove_cb`].
+    pub fn register_cb<F>(&self, ctx: &SimContext, reason: CbReason, callback: F) -> Handle
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        CallbackBuilder::new(reason)
+            .object(self)
+            .register(ctx, callback)
+            .unwrap_or_default()
+    }
+
+    /// Fallible variant of [`Handle::register_cb`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator rejects the registration.
+    pub fn try_register_cb<F>(
+        &self,
+        ctx: &SimContext,
+        reason: CbReason,
+        callback: F,
+    ) -> Result<Handle, Error>
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        CallbackBuilder::new(reason)
+            .object(self)
+            .register(ctx, callback)
+    }
 
-        Handle::from_raw(handle)
+    /// Registers a callback associated with this handle, owned by the
+    /// returned guard.
+    ///
+    /// See [`CallbackGuard`] for how the callback is removed and freed.
+    pub fn register_cb_guarded<F>(
+        &self,
+        ctx: &SimContext,
+        reason: CbReason,
+        callback: F,
+    ) -> CallbackGuard
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        CallbackBuilder::new(reason)
+            .object(self)
+            .register_guarded(ctx, callback)
+            .unwrap_or_else(|_| CallbackGuard::inactive())
+    }
+
+    /// Registers a callback associated with this handle that may mutate its
+    /// captured state.
+    ///
+    /// Returns a callback handle that can be removed with [`remove_cb`]. If
+    /// the callback fires again while it is still running, the nested call is
+    /// reported and skipped.
+    pub fn register_cb_mut<F>(&self, ctx: &SimContext, reason: CbReason, callback: F) -> Handle
+    where
+        F: FnMut(&CbData) + 'static,
+    {
+        CallbackBuilder::new(reason)
+            .object(self)
+            .register_mut(ctx, callback)
+            .unwrap_or_default()
+    }
+
+    /// Registers a callback associated with this handle that runs at most
+    /// once, owned by the returned guard.
+    ///
+    /// The callback is retired after its first call, also for persistent
+    /// reasons such as [`CbReason::ValueChange`], and the closure is freed.
+    /// See [`register_once`].
+    pub fn register_once<F>(&self, ctx: &SimContext, reason: CbReason, callback: F) -> CallbackGuard
+    where
+        F: FnOnce(&CbData) + 'static,
+    {
+        CallbackBuilder::new(reason)
+            .object(self)
+            .register_once(ctx, callback)
+            .unwrap_or_else(|_| CallbackGuard::inactive())
     }
 
     /// Registers a callback with persistent time/value registration buffers.
@@ -549,16 +1289,34 @@ impl Handle {
     /// This variant populates `t_cb_data.time` and `t_cb_data.value` at
     /// registration time so simulators can write callback payloads through
     /// those pointers.
-    pub fn register_full_cb<F>(&self, reason: CbReason, callback: F) -> Handle
+    pub fn register_full_cb<F>(&self, ctx: &SimContext, reason: CbReason, callback: F) -> Handle
     where
         F: Fn(&CbData) + 'static,
     {
-        let state = Box::new(CallbackState {
-            callback: Box::new(callback),
-            time: Some(Box::new(default_cb_time())),
-            value: Some(Box::new(default_cb_value())),
-        });
-        register_with_state(reason, self.as_raw(), state)
+        CallbackBuilder::new(reason)
+            .object(self)
+            .time_format(TimeFormat::Sim)
+            .value_format(ValueType::ObjType)
+            .register(ctx, callback)
+            .unwrap_or_default()
+    }
+
+    /// Guarded variant of [`Handle::register_full_cb`].
+    pub fn register_full_cb_guarded<F>(
+        &self,
+        ctx: &SimContext,
+        reason: CbReason,
+        callback: F,
+    ) -> CallbackGuard
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        CallbackBuilder::new(reason)
+            .object(self)
+            .time_format(TimeFormat::Sim)
+            .value_format(ValueType::ObjType)
+            .register_guarded(ctx, callback)
+            .unwrap_or_else(|_| CallbackGuard::inactive())
     }
 
     /// Registers a value-change callback with an explicit value format.
@@ -566,16 +1324,79 @@ impl Handle {
     /// Some simulators require `t_cb_data.value.format` to match the expected
     /// callback value encoding for `cbValueChange` callbacks. This helper sets
     /// that format during registration.
-    pub fn register_value_change_cb<F>(&self, value_type: ValueType, callback: F) -> Handle
+    pub fn register_value_change_cb<F>(
+        &self,
+        ctx: &SimContext,
+        value_type: ValueType,
+        callback: F,
+    ) -> Handle
     where
         F: Fn(&CbData) + 'static,
     {
-        let state = Box::new(CallbackState {
-            callback: Box::new(callback),
-            time: Some(Box::new(default_cb_time())),
-            value: Some(Box::new(cb_value_with_format(value_type))),
-        });
-        register_with_state(CbReason::ValueChange, self.as_raw(), state)
+        CallbackBuilder::new(CbReason::ValueChange)
+            .object(self)
+            .time_format(TimeFormat::Sim)
+            .value_format(value_type)
+            .register(ctx, callback)
+            .unwrap_or_default()
+    }
+
+    /// Fallible variant of [`Handle::register_value_change_cb`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator rejects the registration, for example
+    /// because this object has no value.
+    pub fn try_register_value_change_cb<F>(
+        &self,
+        ctx: &SimContext,
+        value_type: ValueType,
+        callback: F,
+    ) -> Result<Handle, Error>
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        CallbackBuilder::new(CbReason::ValueChange)
+            .object(self)
+            .time_format(TimeFormat::Sim)
+            .value_format(value_type)
+            .register(ctx, callback)
+    }
+
+    /// `FnMut` variant of [`Handle::register_value_change_cb`].
+    pub fn register_value_change_cb_mut<F>(
+        &self,
+        ctx: &SimContext,
+        value_type: ValueType,
+        callback: F,
+    ) -> Handle
+    where
+        F: FnMut(&CbData) + 'static,
+    {
+        CallbackBuilder::new(CbReason::ValueChange)
+            .object(self)
+            .time_format(TimeFormat::Sim)
+            .value_format(value_type)
+            .register_mut(ctx, callback)
+            .unwrap_or_default()
+    }
+
+    /// Guarded variant of [`Handle::register_value_change_cb`].
+    pub fn register_value_change_cb_guarded<F>(
+        &self,
+        ctx: &SimContext,
+        value_type: ValueType,
+        callback: F,
+    ) -> CallbackGuard
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        CallbackBuilder::new(CbReason::ValueChange)
+            .object(self)
+            .time_format(TimeFormat::Sim)
+            .value_format(value_type)
+            .register_guarded(ctx, callback)
+            .unwrap_or_else(|_| CallbackGuard::inactive())
     }
 }
 
@@ -588,71 +1409,124 @@ unsafe extern "C" fn trampoline(cb_data: *mut vpi_sys::t_cb_data) -> i32 {
     if user_data.is_null() {
         return 0; // No user data, just return
     }
-
     let cb_data_ref = unsafe { &*cb_data };
-    let value = if cb_data_ref.value.is_null() {
-        None
+    let reason = CbReason::from_raw(cb_data_ref.reason);
+    let ctx = crate::context::enter_callback();
+    // Read before any other VPI call resets the error record.
+    let error = if matches!(reason, CbReason::Error | CbReason::PLIError) {
+        crate::chk_error(ctx)
     } else {
-        Some(unsafe { *cb_data_ref.value })
-    };
-
-    let mut data = CbData {
-        reason: CbReason::from_u32(cb_data_ref.reason as u32)
-            .expect("received unknown callback reason from simulator"),
-        obj: Handle::from_raw(cb_data_ref.obj),
-        time: if cb_data_ref.time.is_null() {
-            None
-        } else {
-            time_from_cb_data(unsafe { *cb_data_ref.time })
-        },
-        value: value.and_then(|raw| decode_vpi_value(raw, cb_data_ref.obj)),
-        value_type: value.and_then(|raw| ValueType::from_u32(raw.format as u32)),
-        index: cb_data_ref.index,
+        None
     };
 
+    let obj = unsafe { HandleRef::from_raw(cb_data_ref.obj) };
     let state = unsafe { &*user_data };
-    (state.callback)(&data);
+    // Panics are caught inside the run, so the guard state stays consistent,
+    // and cover decoding the callback data as well as the closure.
+    let call = || {
+        let _ = crate::panic::guard_callback(ctx, reason, &obj, || {
+            let value = if cb_data_ref.value.is_null() {
+                None
+            } else {
+                Some(unsafe { *cb_data_ref.value })
+            };
 
-    data.obj.clear(); // We do not own this handle
+            let time = if cb_data_ref.time.is_null() {
+                None
+            } else {
+                time_from_cb_data(unsafe { *cb_data_ref.time })
+            };
+            let decoded = value.and_then(|raw| decode_vpi_value(raw, cb_data_ref.obj));
+            let data = CbData {
+                reason,
+                obj: unsafe { HandleRef::from_raw(cb_data_ref.obj) },
+                time,
+                value: decoded,
+                value_type: value.and_then(|raw| ValueType::from_u32(raw.format as u32)),
+                index: cb_data_ref.index,
+                error,
+            };
+            state.callback.call(&data);
+        });
+    };
+    match state.life.clone() {
+        Some(life) => {
+            // Guarded state is freed here once retired or removed while running.
+            if life.run(call) {
+                drop(unsafe { Box::from_raw(user_data) });
+            }
+        }
+        None => call(),
+    }
     0 // Return 0 to indicate success
 }
 
 /// Registers a global callback not tied to a specific object handle.
 ///
 /// Returns a callback handle that can be removed with [`remove_cb`].
-pub fn register_cb<F>(reason: CbReason, callback: F) -> Handle
+pub fn register_cb<F>(ctx: &SimContext, reason: CbReason, callback: F) -> Handle
 where
     F: Fn(&CbData) + 'static,
 {
-    let user_data = Box::into_raw(Box::new(CallbackState {
-        callback: Box::new(callback),
-        time: None,
-        value: None,
-    }));
+    CallbackBuilder::new(reason)
+        .register(ctx, callback)
+        .unwrap_or_default()
+}
 
-    let handle = unsafe {
-        let mut cb_data = vpi_sys::s_cb_data {
-            reason: reason as i32,
-            cb_rtn: Some(trampoline),
-            obj: std::ptr::null_mut(),
-            time: std::ptr::null_mut(),
-            value: std::ptr::null_mut(),
-            index: 0,
-            user_data: user_data.cast::<vpi_sys::PLI_BYTE8>(),
-        };
-        vpi_sys::vpi_register_cb(&raw mut cb_data)
-    };
+/// Fallible variant of [`register_cb`].
+///
+/// # Errors
+///
+/// Fails when the simulator rejects the registration.
+pub fn try_register_cb<F>(ctx: &SimContext, reason: CbReason, callback: F) -> Result<Handle, Error>
+where
+    F: Fn(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason).register(ctx, callback)
+}
 
-    if handle.is_null() {
-        unsafe {
-            let _ = Box::from_raw(user_data);
-        }
-    } else {
-        #[cfg(not(feature = "cb_info"))]
-        register_callback_state(handle, user_data.cast::<CallbackState>());
-    }
+/// Registers a global callback owned by the returned guard.
+///
+/// See [`CallbackGuard`] for how the callback is removed and freed.
+pub fn register_cb_guarded<F>(ctx: &SimContext, reason: CbReason, callback: F) -> CallbackGuard
+where
+    F: Fn(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason)
+        .register_guarded(ctx, callback)
+        .unwrap_or_else(|_| CallbackGuard::inactive())
+}
+
+/// Registers a global callback that may mutate its captured state.
+///
+/// Returns a callback handle that can be removed with [`remove_cb`]. If the
+/// callback fires again while it is still running, the nested call is
+/// reported through [`crate::printf`] and skipped instead of re-entering the
+/// closure.
+pub fn register_cb_mut<F>(ctx: &SimContext, reason: CbReason, callback: F) -> Handle
+where
+    F: FnMut(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason)
+        .register_mut(ctx, callback)
+        .unwrap_or_default()
+}
 
-    Handle::from_raw(handle)
+/// Registers a global callback that runs at most once, owned by the returned
+/// guard.
+///
+/// Intended for one-shot reasons such as [`CbReason::StartOfSimulation`] or
+/// [`CbReason::EndOfSimulation`]; other reasons are removed after the first
+/// call. The closure is freed right after it runs. Dropping the guard before
+/// that removes the callback, so call [`CallbackGuard::detach`] to let it
+/// fire on its own.
+pub fn register_once<F>(ctx: &SimContext, reason: CbReason, callback: F) -> CallbackGuard
+where
+    F: FnOnce(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason)
+        .register_once(ctx, callback)
+        .unwrap_or_else(|_| CallbackGuard::inactive())
 }
 
 /// Registers a global callback with persistent time/value registration buffers.
@@ -660,50 +1534,141 @@ where
 /// This variant populates `t_cb_data.time` and `t_cb_data.value` at
 /// registration time so simulators can write callback payloads through
 /// those pointers.
-pub fn register_full_cb<F>(reason: CbReason, callback: F) -> Handle
+pub fn register_full_cb<F>(ctx: &SimContext, reason: CbReason, callback: F) -> Handle
 where
     F: Fn(&CbData) + 'static,
 {
-    let state = Box::new(CallbackState {
-        callback: Box::new(callback),
-        time: Some(Box::new(default_cb_time())),
-        value: Some(Box::new(default_cb_value())),
-    });
-    register_with_state(reason, std::ptr::null_mut(), state)
+    CallbackBuilder::new(reason)
+        .time_format(TimeFormat::Sim)
+        .value_format(ValueType::ObjType)
+        .register(ctx, callback)
+        .unwrap_or_default()
+}
+
+/// Guarded variant of [`register_full_cb`].
+pub fn register_full_cb_guarded<F>(ctx: &SimContext, reason: CbReason, callback: F) -> CallbackGuard
+where
+    F: Fn(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason)
+        .time_format(TimeFormat::Sim)
+        .value_format(ValueType::ObjType)
+        .register_guarded(ctx, callback)
+        .unwrap_or_else(|_| CallbackGuard::inactive())
 }
 
 /// Registers a time-based callback.
 ///
 /// The callback is scheduled according to `reason` and `time` as interpreted
 /// by the simulator.
-pub fn register_cb_with_time<F>(reason: CbReason, time: Time, callback: F) -> Handle
+pub fn register_cb_with_time<F>(
+    ctx: &SimContext,
+    reason: CbReason,
+    time: Time,
+    callback: F,
+) -> Handle
 where
     F: Fn(&CbData) + 'static,
 {
-    let state = Box::new(CallbackState {
-        callback: Box::new(callback),
-        time: Some(Box::new(time.into())),
-        value: Some(Box::new(default_cb_value())),
-    });
-    register_with_state(reason, std::ptr::null_mut(), state)
+    CallbackBuilder::new(reason)
+        .at(time)
+        .value_format(ValueType::ObjType)
+        .register(ctx, callback)
+        .unwrap_or_default()
 }
 
-/// Registers a SystemVerilog assertion callback.
+/// Fallible variant of [`register_cb_with_time`].
 ///
-/// Available only with the `sv` feature.
-#[cfg(feature = "sv")]
-pub fn register_assertion_cb<F>(assertion: &Handle, reason: CbReason, callback: F) -> Handle
+/// # Errors
+///
+/// Fails when the simulator rejects the registration, for example because
+/// `time` is not valid for `reason`.
+pub fn try_register_cb_with_time<F>(
+    ctx: &SimContext,
+    reason: CbReason,
+    time: Time,
+    callback: F,
+) -> Result<Handle, Error>
 where
-    F: Fn(&AssertionCbData) + 'static,
+    F: Fn(&CbData) + 'static,
 {
-    let state_ptr = Box::into_raw(Box::new(AssertionCallbackState {
-        callback: Box::new(callback),
-    }));
+    CallbackBuilder::new(reason)
+        .at(time)
+        .value_format(ValueType::ObjType)
+        .register(ctx, callback)
+}
+
+/// `FnMut` variant of [`register_cb_with_time`].
+pub fn register_cb_with_time_mut<F>(
+    ctx: &SimContext,
+    reason: CbReason,
+    time: Time,
+    callback: F,
+) -> Handle
+where
+    F: FnMut(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason)
+        .at(time)
+        .value_format(ValueType::ObjType)
+        .register_mut(ctx, callback)
+        .unwrap_or_default()
+}
+
+/// Registers a time-based callback that runs at most once, owned by the
+/// returned guard.
+///
+/// Typically used with [`CbReason::AfterDelay`] or
+/// [`CbReason::ReadOnlySynch`]. See [`register_once`].
+pub fn register_once_with_time<F>(
+    ctx: &SimContext,
+    reason: CbReason,
+    time: Time,
+    callback: F,
+) -> CallbackGuard
+where
+    F: FnOnce(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason)
+        .at(time)
+        .value_format(ValueType::ObjType)
+        .register_once(ctx, callback)
+        .unwrap_or_else(|_| CallbackGuard::inactive())
+}
+
+/// Guarded variant of [`register_cb_with_time`].
+///
+/// Time callbacks are one-shot: the closure is freed right after it fires
+/// and [`CallbackGuard::is_active`] turns `false`.
+pub fn register_cb_with_time_guarded<F>(
+    ctx: &SimContext,
+    reason: CbReason,
+    time: Time,
+    callback: F,
+) -> CallbackGuard
+where
+    F: Fn(&CbData) + 'static,
+{
+    CallbackBuilder::new(reason)
+        .at(time)
+        .value_format(ValueType::ObjType)
+        .register_guarded(ctx, callback)
+        .unwrap_or_else(|_| CallbackGuard::inactive())
+}
+
+#[cfg(feature = "sv")]
+fn register_assertion_with_state(
+    assertion: &Handle,
+    reason: CbReason,
+    state: Box<AssertionCallbackState>,
+) -> (Handle, *mut AssertionCallbackState) {
+    let guarded = state.life.is_some();
+    let state_ptr = Box::into_raw(state);
 
     let handle = unsafe {
         vpi_sys::vpi_register_assertion_cb(
             assertion.as_raw(),
-            reason as i32,
+            reason.as_raw(),
             Some(assertion_trampoline),
             state_ptr.cast::<vpi_sys::PLI_BYTE8>(),
         )
@@ -713,18 +1678,60 @@ where
         unsafe {
             let _ = Box::from_raw(state_ptr);
         }
-    } else {
+    } else if !guarded {
         register_assertion_callback_state(handle, state_ptr);
     }
 
-    Handle::from_raw(handle)
+    (Handle::from_raw(handle), state_ptr)
+}
+
+/// Registers a SystemVerilog assertion callback.
+///
+/// Available only with the `sv` feature.
+#[cfg(feature = "sv")]
+pub fn register_assertion_cb<F>(
+    _ctx: &SimContext,
+    assertion: &Handle,
+    reason: CbReason,
+    callback: F,
+) -> Handle
+where
+    F: Fn(&AssertionCbData) + 'static,
+{
+    let state = Box::new(AssertionCallbackState {
+        callback: Box::new(callback),
+        life: None,
+    });
+    register_assertion_with_state(assertion, reason, state).0
+}
+
+/// Registers a SystemVerilog assertion callback owned by the returned guard.
+///
+/// Available only with the `sv` feature.
+#[cfg(feature = "sv")]
+pub fn register_assertion_cb_guarded<F>(
+    _ctx: &SimContext,
+    assertion: &Handle,
+    reason: CbReason,
+    callback: F,
+) -> CallbackGuard
+where
+    F: Fn(&AssertionCbData) + 'static,
+{
+    let life = Rc::new(CallbackLife::new(false, false));
+    let state = Box::new(AssertionCallbackState {
+        callback: Box::new(callback),
+        life: Some(Rc::clone(&life)),
+    });
+    let (handle, state_ptr) = register_assertion_with_state(assertion, reason, state);
+    CallbackGuard::new(handle, life, GuardedState::Assertion(state_ptr))
 }
 
 /// Removes a previously registered SystemVerilog assertion callback.
 ///
 /// Available only with the `sv` feature.
 #[cfg(feature = "sv")]
-pub fn remove_assertion_cb(handle: &Handle) {
+pub fn remove_assertion_cb(_ctx: &SimContext, handle: &Handle) {
     if handle.is_null() {
         return;
     }
@@ -742,7 +1749,7 @@ pub fn remove_assertion_cb(handle: &Handle) {
 ///
 /// If `handle` is null, this is a no-op.
 #[cfg(feature = "cb_info")]
-pub fn remove_cb(handle: &Handle) {
+pub fn remove_cb(_ctx: &SimContext, handle: &Handle) {
     if !handle.is_null() {
         unsafe {
             let mut cb_data = vpi_sys::s_cb_data {
@@ -755,13 +1762,22 @@ pub fn remove_cb(handle: &Handle) {
                 user_data: std::ptr::null_mut(),
             };
             vpi_sys::vpi_get_cb_info(handle.as_raw(), &raw mut cb_data);
-            vpi_sys::vpi_remove_cb(handle.as_raw());
             let trampoline_ptr = trampoline as unsafe extern "C" fn(*mut vpi_sys::t_cb_data) -> i32;
             let is_internal = cb_data
                 .cb_rtn
                 .is_some_and(|cb| (cb as usize) == (trampoline_ptr as usize));
-            if is_internal && !cb_data.user_data.is_null() {
-                let _ = Box::from_raw(cb_data.user_data.cast::<CallbackState>());
+            if !is_internal || cb_data.user_data.is_null() {
+                vpi_sys::vpi_remove_cb(handle.as_raw());
+                return;
+            }
+            let state_ptr = cb_data.user_data.cast::<CallbackState>();
+            match (*state_ptr).life.clone() {
+                // Owned by a guard, which frees the state when dropped.
+                Some(life) => life.retire(),
+                None => {
+                    vpi_sys::vpi_remove_cb(handle.as_raw());
+                    let _ = Box::from_raw(state_ptr);
+                }
             }
         }
     }
@@ -772,16 +1788,360 @@ pub fn remove_cb(handle: &Handle) {
 /// This version does not call `vpi_get_cb_info` and instead uses the
 /// callback state captured at registration time.
 #[cfg(not(feature = "cb_info"))]
-pub fn remove_cb(handle: &Handle) {
+pub fn remove_cb(_ctx: &SimContext, handle: &Handle) {
     if handle.is_null() {
         return;
     }
 
-    let state_ptr = take_callback_state(handle.as_raw());
-    unsafe {
-        vpi_sys::vpi_remove_cb(handle.as_raw());
-        if let Some(state_ptr) = state_ptr {
-            let _ = Box::from_raw(state_ptr);
+    let Some(state_ptr) = take_callback_state(handle.as_raw()) else {
+        unsafe {
+            vpi_sys::vpi_remove_cb(handle.as_raw());
         }
+        return;
+    };
+    match unsafe { (*state_ptr).life.clone() } {
+        // Owned by a guard, which frees the state when dropped.
+        Some(life) => life.retire(),
+        None => unsafe {
+            vpi_sys::vpi_remove_cb(handle.as_raw());
+            let _ = Box::from_raw(state_ptr);
+        },
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use crate::mock::MockSimulator;
+    use crate::{
+        register_cb_guarded, register_cb_with_time_guarded, register_once_with_time,
+        CallbackBuilder, CallbackGuard, CbEvent, CbReason, Handle, Severity, Time, TimeFormat,
+        Value, ValueType,
+    };
+
+    #[test]
+    fn builder_sets_time_value_formats_and_index() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 4);
+        sim.start();
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&seen);
+        let _delay = CallbackBuilder::new(CbReason::AfterDelay)
+            .at(Time::Sim(2))
+            .index(7)
+            .register_once(ctx, move |data| {
+                log.borrow_mut()
+                    .push(format!("{:?} {:?} {}", data.time, data.value, data.index));
+            })
+            .unwrap();
+        let log = Rc::clone(&seen);
+        let _change = CallbackBuilder::new(CbReason::ValueChange)
+            .object(&q)
+            .time_format(TimeFormat::ScaledReal)
+            .value_format(ValueType::Suppress)
+            .register_guarded(ctx, move |data| {
+                log.borrow_mut()
+                    .push(format!("{:?} {:?}", data.time, data.value));
+            })
+            .unwrap();
+
+        sim.run_for(2);
+        let _ = sim.set_value(&q, &Value::Int(3));
+        assert_eq!(
+            *seen.borrow(),
+            ["Some(Sim(2)) None 7", "Some(ScaledReal(2.0)) None"]
+        );
+    }
+
+    #[test]
+    fn events_are_decoded_by_reason() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let mem = sim.add_memory(&top, "mem", 8, 4);
+        sim.start();
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&events);
+        let _change = mem.register_value_change_cb_guarded(ctx, ValueType::Int, move |data| {
+            if let CbEvent::ValueChange {
+                signal,
+                value,
+                array_index,
+                ..
+            } = data.event()
+            {
+                log.borrow_mut().push(format!(
+                    "{:?} {value:?} {array_index:?}",
+                    signal.get_name(ctx)
+                ));
+            }
+        });
+        let log = Rc::clone(&events);
+        let _error = register_cb_guarded(ctx, CbReason::Error, move |data| {
+            if let CbEvent::Error(error) = data.event() {
+                log.borrow_mut().push(error.message.clone());
+            }
+        });
+        let log = Rc::clone(&events);
+        let _delay =
+            register_once_with_time(ctx, CbReason::AfterDelay, Time::Sim(1), move |data| {
+                if let CbEvent::SimTime { time } = data.event() {
+                    log.borrow_mut().push(format!("{time:?}"));
+                }
+            });
+
+        let word = mem.handle_by_index(ctx, 2);
+        let _ = sim.set_value(&word, &Value::Int(9));
+        sim.report_error(Severity::Error, "bad thing");
+        sim.run_for(1);
+        assert_eq!(
+            *events.borrow(),
+            [
+                "Some(\"mem\") Some(Int(9)) Some(2)",
+                "bad thing",
+                "Some(Sim(1))"
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_reasons_keep_their_raw_value() {
+        use num_traits::FromPrimitive;
+
+        let raw = CbReason::EndOfSimulation.as_raw();
+        assert_eq!(raw, vpi_sys::cbEndOfSimulation as i32);
+        assert_eq!(CbReason::from_raw(raw), CbReason::EndOfSimulation);
+        assert_eq!(CbReason::from_raw(9999), CbReason::Unknown(9999));
+        assert_eq!(CbReason::Unknown(9999).as_raw(), 9999);
+        assert_eq!(CbReason::from_i32(9999), None);
+    }
+
+    #[test]
+    fn one_shot_state_is_freed_after_firing() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        sim.start();
+        let token = Rc::new(());
+        let held = Rc::clone(&token);
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let guard =
+            register_cb_with_time_guarded(ctx, CbReason::AfterDelay, Time::Sim(3), move |_| {
+                let _ = &held;
+                counter.set(counter.get() + 1);
+            });
+        assert!(guard.is_active());
+        assert_eq!(Rc::strong_count(&token), 2);
+
+        sim.run();
+        assert_eq!(calls.get(), 1);
+        assert!(!guard.is_active());
+        assert_eq!(Rc::strong_count(&token), 1);
+        drop(guard);
+        assert_eq!(sim.active_callbacks(), 0);
+    }
+
+    #[test]
+    fn action_callback_guard_removes_registration() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let guard = register_cb_guarded(ctx, CbReason::StartOfSimulation, move |_| {
+            counter.set(counter.get() + 1);
+        });
+        sim.start();
+        assert_eq!(calls.get(), 1);
+        assert!(guard.is_active());
+        assert_eq!(sim.active_callbacks(), 1);
+        drop(guard);
+        assert_eq!(sim.active_callbacks(), 0);
+    }
+
+    #[test]
+    fn dropping_guard_removes_pending_callback() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        sim.start();
+        let fired = Rc::new(Cell::new(false));
+        let flag = Rc::clone(&fired);
+        let guard =
+            register_cb_with_time_guarded(ctx, CbReason::AfterDelay, Time::Sim(3), move |_| {
+                flag.set(true);
+            });
+        assert_eq!(sim.active_callbacks(), 1);
+        guard.remove();
+        assert_eq!(sim.active_callbacks(), 0);
+        sim.run();
+        assert!(!fired.get());
+        assert_eq!(sim.time(), 0);
+    }
+
+    #[test]
+    fn remove_cb_on_guarded_handle_leaves_freeing_to_guard() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let token = Rc::new(());
+        let held = Rc::clone(&token);
+        let guard = q.register_value_change_cb_guarded(ctx, ValueType::Scalar, move |_| {
+            let _ = &held;
+        });
+        crate::remove_cb(ctx, guard.handle());
+        assert!(!guard.is_active());
+        assert_eq!(sim.active_callbacks(), 0);
+        assert_eq!(Rc::strong_count(&token), 2);
+        drop(guard);
+        assert_eq!(Rc::strong_count(&token), 1);
+    }
+
+    #[test]
+    fn guard_can_be_dropped_inside_its_callback() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let token = Rc::new(());
+        let held = Rc::clone(&token);
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let slot: Rc<RefCell<Option<CallbackGuard>>> = Rc::default();
+        let own = Rc::clone(&slot);
+        let guard = q.register_value_change_cb_guarded(ctx, ValueType::Scalar, move |_| {
+            let _ = &held;
+            counter.set(counter.get() + 1);
+            drop(own.borrow_mut().take());
+        });
+        *slot.borrow_mut() = Some(guard);
+
+        let _ = q.put_value(ctx, &Value::Int(1));
+        assert_eq!(calls.get(), 1);
+        assert_eq!(sim.active_callbacks(), 0);
+        assert_eq!(Rc::strong_count(&token), 1);
+        let _ = q.put_value(ctx, &Value::Int(0));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn detached_callback_stays_registered() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        q.register_value_change_cb_guarded(ctx, ValueType::Scalar, move |_| {
+            counter.set(counter.get() + 1);
+        })
+        .detach();
+        let _ = q.put_value(ctx, &Value::Int(1));
+        let _ = q.put_value(ctx, &Value::Int(0));
+        assert_eq!(calls.get(), 2);
+        assert_eq!(sim.active_callbacks(), 1);
+    }
+
+    #[test]
+    fn mut_callback_keeps_its_own_state() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let seen = Rc::new(Cell::new(0));
+        let report = Rc::clone(&seen);
+        let mut count = 0;
+        let handle = q.register_value_change_cb_mut(ctx, ValueType::Scalar, move |_| {
+            count += 1;
+            report.set(count);
+        });
+        let _ = q.put_value(ctx, &Value::Int(1));
+        let _ = q.put_value(ctx, &Value::Int(0));
+        assert_eq!(seen.get(), 2);
+        crate::remove_cb(ctx, &handle);
+        assert_eq!(sim.active_callbacks(), 0);
+    }
+
+    #[test]
+    fn once_callback_is_removed_after_first_call() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let token = Rc::new(());
+        let held = Rc::clone(&token);
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let guard = q.register_once(ctx, CbReason::ValueChange, move |_| {
+            drop(held);
+            counter.set(counter.get() + 1);
+        });
+        let _ = q.put_value(ctx, &Value::Int(1));
+        let _ = q.put_value(ctx, &Value::Int(0));
+        assert_eq!(calls.get(), 1);
+        assert!(!guard.is_active());
+        assert_eq!(sim.active_callbacks(), 0);
+        assert_eq!(Rc::strong_count(&token), 1);
+    }
+
+    #[test]
+    fn detached_once_callback_fires_and_is_freed() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        sim.start();
+        let token = Rc::new(());
+        let held = Rc::clone(&token);
+        let fired = Rc::new(Cell::new(None));
+        let at = Rc::clone(&fired);
+        register_once_with_time(ctx, CbReason::AfterDelay, Time::Sim(4), move |data| {
+            let _ = &held;
+            at.set(data.time.clone());
+        })
+        .detach();
+        assert_eq!(Rc::strong_count(&token), 2);
+
+        sim.run();
+        assert_eq!(fired.take(), Some(Time::Sim(4)));
+        assert_eq!(Rc::strong_count(&token), 1);
+        assert_eq!(sim.active_callbacks(), 0);
+    }
+
+    #[test]
+    fn reentrant_mut_call_is_reported_and_skipped() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let target = q.clone();
+        let handle = q.register_value_change_cb_mut(ctx, ValueType::Scalar, move |_| {
+            counter.set(counter.get() + 1);
+            let _ = target.put_value(ctx, &Value::Int(0));
+        });
+        sim.take_output();
+        let _ = q.put_value(ctx, &Value::Int(1));
+        assert_eq!(calls.get(), 1);
+        assert!(sim
+            .output()
+            .contains("ERROR: ValueChange callback fired while it was still running"));
+        crate::remove_cb(ctx, &handle);
     }
 }
@@ -0,0 +1,405 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{register_cb_guarded, remove_cb, CallbackGuard, CbReason, Error, Handle, SimContext};
+
+/// Size of the chunks passed to `vpi_put_data` and `vpi_get_data`.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Plugin state that is carried through a simulator save and restart.
+///
+/// Register an implementation with [`register_checkpoint`]; the crate then
+/// writes the bytes returned by [`Checkpoint::save`] into the simulator's
+/// save image and hands them back to [`Checkpoint::restore`] on restart.
+pub trait Checkpoint {
+    /// Serializes the state at the start of a save.
+    fn save(&self) -> Vec<u8>;
+
+    /// Restores the state from bytes returned by [`Checkpoint::save`].
+    fn restore(&mut self, data: &[u8]);
+}
+
+/// Returns the save/restart ID of the current callback.
+///
+/// Wraps `vpi_get(vpiSaveRestartID, NULL)`, which is valid inside
+/// [`CbReason::StartOfSave`] and [`CbReason::EndOfSave`] callbacks. Returns
+/// `None` if the simulator reports no ID.
+#[must_use]
+pub fn save_restart_id(_ctx: &SimContext) -> Option<i32> {
+    let id = unsafe {
+        vpi_sys::vpi_get(
+            vpi_sys::vpiSaveRestartID as vpi_sys::PLI_INT32,
+            std::ptr::null_mut(),
+        )
+    };
+    (id > 0).then_some(id)
+}
+
+/// Returns the location of the save image being written or read.
+///
+/// Wraps `vpi_get_str(vpiSaveRestartLocation, NULL)`.
+#[must_use]
+pub fn save_restart_location(_ctx: &SimContext) -> Option<String> {
+    let ptr = unsafe {
+        vpi_sys::vpi_get_str(
+            vpi_sys::vpiSaveRestartLocation as vpi_sys::PLI_INT32,
+            std::ptr::null_mut(),
+        )
+    };
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { std::ffi::CStr::from_ptr(ptr) }
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+/// Appends `data` to the save image for `id` using `vpi_put_data`.
+///
+/// Large buffers are written in chunks.
+///
+/// # Errors
+///
+/// Fails when the simulator accepts fewer bytes than offered, for example
+/// because no save is in progress for `id`.
+pub fn put_data(ctx: &SimContext, id: i32, data: &[u8]) -> Result<(), Error> {
+    for chunk in data.chunks(CHUNK_SIZE) {
+        let len = vpi_sys::PLI_INT32::try_from(chunk.len()).expect("chunk fits in PLI_INT32");
+        let written = unsafe { vpi_sys::vpi_put_data(id, chunk.as_ptr().cast_mut().cast(), len) };
+        if written != len {
+            return Err(Error::last(
+                ctx,
+                "vpi_put_data",
+                format!("wrote {written} of {len} bytes for ID {id}"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the next bytes of the save image for `id` using `vpi_get_data`.
+///
+/// Fills `buf` in chunks and returns the number of bytes read, which is less
+/// than `buf.len()` once the data for `id` is exhausted.
+pub fn get_data(_ctx: &SimContext, id: i32, buf: &mut [u8]) -> usize {
+    let mut read = 0;
+    for chunk in buf.chunks_mut(CHUNK_SIZE) {
+        let len = vpi_sys::PLI_INT32::try_from(chunk.len()).expect("chunk fits in PLI_INT32");
+        let count = unsafe { vpi_sys::vpi_get_data(id, chunk.as_mut_ptr().cast(), len) };
+        let count = usize::try_from(count).unwrap_or(0).min(chunk.len());
+        read += count;
+        if count < chunk.len() {
+            break;
+        }
+    }
+    read
+}
+
+/// A component registered with [`register_checkpoint`].
+struct Registered {
+    component: Rc<RefCell<dyn Checkpoint>>,
+    /// The `cbStartOfRestart` callback registered by the last save.
+    restart: Option<Handle>,
+}
+
+thread_local! {
+    /// Registered components, by slot. Slots are handed out lowest first,
+    /// so the same registration order yields the same slots after restart.
+    static COMPONENTS: RefCell<Vec<Option<Registered>>> = RefCell::default();
+}
+
+/// Registers `component` to be saved and restored with the simulation.
+///
+/// At [`CbReason::StartOfSave`] the bytes from [`Checkpoint::save`] are
+/// written with a length prefix under the callback's save/restart ID, and a
+/// [`CbReason::StartOfRestart`] callback carrying that ID in its `user_data`
+/// is registered, so that it becomes part of the save image. On restart that
+/// callback reads the bytes back and passes them to [`Checkpoint::restore`].
+///
+/// Saved data is matched to components by registration order, so a
+/// restarted simulation must register its components in the same order as
+/// the one that saved.
+///
+/// Failures are reported through [`crate::printf`]; a component whose data
+/// cannot be read back is left untouched.
+pub fn register_checkpoint<C>(ctx: &SimContext, component: &Rc<RefCell<C>>) -> CheckpointGuard
+where
+    C: Checkpoint + 'static,
+{
+    let component: Rc<RefCell<dyn Checkpoint>> = component.clone();
+    let slot = COMPONENTS.with_borrow_mut(|components| {
+        let registered = Some(Registered {
+            component,
+            restart: None,
+        });
+        match components.iter().position(Option::is_none) {
+            Some(slot) => {
+                components[slot] = registered;
+                slot
+            }
+            None => {
+                components.push(registered);
+                components.len() - 1
+            }
+        }
+    });
+    let save = register_cb_guarded(ctx, CbReason::StartOfSave, move |data| {
+        save_component(data.context(), slot);
+    });
+    CheckpointGuard { save, slot }
+}
+
+/// Writes the record of the component in `slot` and registers its restart.
+fn save_component(ctx: &SimContext, slot: usize) {
+    let Some(id) = save_restart_id(ctx) else {
+        report(ctx, "no save/restart ID during save");
+        return;
+    };
+    let Some(component) = component_in(slot) else {
+        return;
+    };
+    let Ok(state) = component.try_borrow() else {
+        report(ctx, "component is mutably borrowed during save");
+        return;
+    };
+    let data = state.save();
+    drop(state);
+    let header = [
+        (slot as u64).to_le_bytes(),
+        (data.len() as u64).to_le_bytes(),
+    ]
+    .concat();
+    if let Err(error) = put_data(ctx, id, &header).and_then(|()| put_data(ctx, id, &data)) {
+        report(ctx, &error.to_string());
+        return;
+    }
+
+    let restart = register_restart(ctx, id);
+    if restart.is_null() {
+        report(
+            ctx,
+            &format!("cannot register restart callback for ID {id}"),
+        );
+        return;
+    }
+    let previous = COMPONENTS.with_borrow_mut(|components| {
+        let registered = components.get_mut(slot)?.as_mut()?;
+        registered.restart.replace(restart)
+    });
+    if let Some(previous) = previous {
+        remove_cb(ctx, &previous);
+    }
+}
+
+/// Registers the `cbStartOfRestart` callback for the record saved under `id`.
+fn register_restart(_ctx: &SimContext, id: i32) -> Handle {
+    let mut cb_data = vpi_sys::t_cb_data {
+        reason: CbReason::StartOfRestart.as_raw(),
+        cb_rtn: Some(restart_trampoline),
+        obj: std::ptr::null_mut(),
+        time: std::ptr::null_mut(),
+        value: std::ptr::null_mut(),
+        index: 0,
+        user_data: id as usize as *mut vpi_sys::PLI_BYTE8,
+    };
+    Handle::from_raw(unsafe { vpi_sys::vpi_register_cb(&raw mut cb_data) })
+}
+
+/// Restores the component whose record was saved under the ID in `user_data`.
+///
+/// `vpiSaveRestartID` is only valid while saving, so the ID registered by
+/// [`save_component`] is the only way to find the record.
+unsafe extern "C" fn restart_trampoline(cb_data: *mut vpi_sys::t_cb_data) -> i32 {
+    if cb_data.is_null() {
+        return 0;
+    }
+    let id = unsafe { (*cb_data).user_data } as usize as i32;
+    let ctx = crate::context::enter_callback();
+    let _ = crate::panic::guard_callback(ctx, CbReason::StartOfRestart, &Handle::null(), || {
+        restore_component(ctx, id);
+    });
+    0
+}
+
+fn restore_component(ctx: &SimContext, id: i32) {
+    let mut slot = [0; 8];
+    if get_data(ctx, id, &mut slot) != slot.len() {
+        report(ctx, &format!("incomplete data for ID {id}"));
+        return;
+    }
+    let Some(data) = read_record(ctx, id) else {
+        report(ctx, &format!("incomplete data for ID {id}"));
+        return;
+    };
+    let slot = u64::from_le_bytes(slot);
+    let Some(component) = usize::try_from(slot).ok().and_then(component_in) else {
+        report(
+            ctx,
+            &format!("no component registered in slot {slot} for ID {id}"),
+        );
+        return;
+    };
+    match component.try_borrow_mut() {
+        Ok(mut state) => state.restore(&data),
+        Err(_) => report(ctx, "component is borrowed during restart"),
+    };
+}
+
+fn component_in(slot: usize) -> Option<Rc<RefCell<dyn Checkpoint>>> {
+    COMPONENTS.with_borrow(|components| {
+        let registered = components.get(slot)?.as_ref()?;
+        Some(Rc::clone(&registered.component))
+    })
+}
+
+/// Reads one length-prefixed record written by [`register_checkpoint`].
+fn read_record(ctx: &SimContext, id: i32) -> Option<Vec<u8>> {
+    let mut len = [0; 8];
+    if get_data(ctx, id, &mut len) != len.len() {
+        return None;
+    }
+    let len = usize::try_from(u64::from_le_bytes(len)).ok()?;
+    let mut data = vec![0; len];
+    (get_data(ctx, id, &mut data) == len).then_some(data)
+}
+
+fn report(ctx: &SimContext, message: &str) {
+    crate::printf(ctx, format!("ERROR: checkpoint: {message}"));
+}
+
+/// Keeps a component registered with [`register_checkpoint`].
+///
+/// Dropping the guard removes the save callback and the restart callback of
+/// the last save, and frees the component's slot.
+#[must_use = "dropping a CheckpointGuard unregisters the component"]
+#[derive(Debug)]
+pub struct CheckpointGuard {
+    save: CallbackGuard,
+    slot: usize,
+}
+
+impl CheckpointGuard {
+    /// Returns `true` while the save callback is registered.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.save.is_active()
+    }
+
+    /// Keeps the component registered for the rest of the simulation.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for CheckpointGuard {
+    fn drop(&mut self) {
+        let registered = COMPONENTS.with_borrow_mut(|components| {
+            let registered = components.get_mut(self.slot)?.take();
+            while components.last().is_some_and(Option::is_none) {
+                components.pop();
+            }
+            registered
+        });
+        // Removed outside the borrow, since destructors may use the VPI.
+        if let Some(restart) = registered.and_then(|registered| registered.restart) {
+            // The guard holds a handle, so it is dropped on the simulator thread.
+            remove_cb(SimContext::borrowed(), &restart);
+        }
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::{register_checkpoint, Checkpoint, CHUNK_SIZE};
+    use crate::mock::MockSimulator;
+
+    #[derive(Default)]
+    struct Counter {
+        events: u32,
+        log: Vec<u8>,
+    }
+
+    impl Checkpoint for Counter {
+        fn save(&self) -> Vec<u8> {
+            let mut data = self.events.to_le_bytes().to_vec();
+            data.extend_from_slice(&self.log);
+            data
+        }
+
+        fn restore(&mut self, data: &[u8]) {
+            let (events, log) = data.split_at(4);
+            self.events = u32::from_le_bytes(events.try_into().unwrap());
+            self.log = log.to_vec();
+        }
+    }
+
+    #[test]
+    fn component_state_survives_restart() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        sim.start();
+        let first = Rc::new(RefCell::new(Counter {
+            events: 3,
+            log: b"abc".to_vec(),
+        }));
+        // Larger than one chunk, to exercise the chunked transfer.
+        let second = Rc::new(RefCell::new(Counter {
+            events: 7,
+            log: vec![0x5a; CHUNK_SIZE + 10],
+        }));
+        let _first_guard = register_checkpoint(ctx, &first);
+        let second_guard = register_checkpoint(ctx, &second);
+        assert!(second_guard.is_active());
+
+        let checkpoint = sim.save();
+        assert_eq!(checkpoint.data(1).map(<[u8]>::len), Some(16 + 7));
+        *first.borrow_mut() = Counter::default();
+        second.borrow_mut().events = 0;
+
+        sim.restart(&checkpoint);
+        assert_eq!(first.borrow().events, 3);
+        assert_eq!(first.borrow().log, b"abc");
+        assert_eq!(second.borrow().events, 7);
+        assert_eq!(second.borrow().log.len(), CHUNK_SIZE + 10);
+        assert!(!sim.output().contains("ERROR"));
+    }
+
+    #[test]
+    fn restart_reaches_components_registered_afresh() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        sim.start();
+        let saved = Rc::new(RefCell::new(Counter {
+            events: 9,
+            log: b"xyz".to_vec(),
+        }));
+        let guard = register_checkpoint(ctx, &saved);
+        let checkpoint = sim.save();
+        // A restarted simulator runs a new plugin instance.
+        drop(guard);
+        drop(saved);
+        assert_eq!(sim.active_callbacks(), 0);
+
+        let fresh = Rc::new(RefCell::new(Counter::default()));
+        let _guard = register_checkpoint(ctx, &fresh);
+        sim.restart(&checkpoint);
+        assert_eq!(fresh.borrow().events, 9);
+        assert_eq!(fresh.borrow().log, b"xyz");
+        assert!(!sim.output().contains("ERROR"));
+    }
+
+    #[test]
+    fn dropped_guard_stops_checkpointing() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        sim.start();
+        let state = Rc::new(RefCell::new(Counter::default()));
+        drop(register_checkpoint(ctx, &state));
+        assert_eq!(sim.active_callbacks(), 0);
+        assert_eq!(sim.save().data(1), None);
+    }
+}
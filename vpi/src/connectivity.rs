@@ -0,0 +1,527 @@
+//! Connectivity graphs of nets, ports, continuous assignments and primitives.
+//!
+//! A [`ConnectivityGraph`] is traced from one net or variable, either
+//! upstream to everything that drives it ([`ConnectivityGraph::fan_in`]) or
+//! downstream to everything it drives ([`ConnectivityGraph::fan_out`]). The
+//! trace follows `vpiDriver`, `vpiLocalDriver`, `vpiLoad` and `vpiLocalLoad`
+//! of signals, `vpiHighConn` and `vpiLowConn` of ports, found through
+//! `vpiPortInst`, the sides of continuous assignments and the terminals of
+//! primitives, across module boundaries. Expressions are reduced to the
+//! signals they read, so bit and part selects lead to their parent signal.
+//!
+//! Regs and variables are assigned by procedural code, which the trace does
+//! not follow: in a fan-in they are the sources, listed by
+//! [`ConnectivityGraph::source_registers`].
+//!
+//! Drivers and loads are read with `vpiLocalDriver` and `vpiLocalLoad`,
+//! which stop at ports, so that every port crossing appears in the graph.
+//! `vpiDriver` and `vpiLoad` span the whole net collapsed across ports, and
+//! would add edges skipping the ports and count a driver once per module it
+//! reaches; they are only used by simulators without the local relations.
+//!
+//! Simulators differ in the relations they support. Ports without
+//! `vpiLowConn` are connected to the signal of the same name in their module,
+//! and signals without `vpiPortInst` to the ports of their module whose
+//! internal connection they are.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::connectivity::ConnectivityGraph;
+//! use vpi::{Handle, SimContext};
+//!
+//! fn report(ctx: &SimContext) {
+//!     let ready = Handle::handle_by_name(ctx, "tb.dut.ready");
+//!     let graph = ConnectivityGraph::fan_in(ctx, &ready);
+//!     for id in graph.source_registers() {
+//!         vpi::printf!(ctx, "source: {:?}", graph.node(id).name);
+//!     }
+//!
+//!     let reset = ConnectivityGraph::fan_out(ctx, &Handle::handle_by_name(ctx, "tb.rst_n"));
+//!     for id in reset.loads(reset.root()) {
+//!         vpi::printf!(ctx, "load: {:?}", reset.node(id).name);
+//!     }
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem::Discriminant;
+
+use crate::model::ObjectKind;
+use crate::{Direction, Handle, ObjectType, SimContext};
+
+/// Direction in which a [`ConnectivityGraph`] is traced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trace {
+    /// From a signal to everything driving it.
+    FanIn,
+    /// From a signal to everything it drives.
+    FanOut,
+}
+
+/// Identifies a node of a [`ConnectivityGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// A signal, port, continuous assignment or primitive of a
+/// [`ConnectivityGraph`].
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// The object, classified by type.
+    pub object: ObjectKind,
+    /// Full hierarchical name of the object, if it has one.
+    pub name: Option<String>,
+}
+
+/// A connection in which `from` drives `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    /// The driving node.
+    pub from: NodeId,
+    /// The driven node.
+    pub to: NodeId,
+}
+
+/// Connectivity traced from one signal; see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct ConnectivityGraph {
+    trace: Trace,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    named: HashMap<(Discriminant<ObjectKind>, String), NodeId>,
+    edge_set: HashSet<Edge>,
+}
+
+impl ConnectivityGraph {
+    /// Traces everything driving `signal`, back to registers, undriven nets
+    /// and top-level ports.
+    #[must_use]
+    pub fn fan_in(ctx: &SimContext, signal: &Handle) -> Self {
+        Self::trace(ctx, signal, Trace::FanIn)
+    }
+
+    /// Traces everything driven by `signal`, across module boundaries.
+    #[must_use]
+    pub fn fan_out(ctx: &SimContext, signal: &Handle) -> Self {
+        Self::trace(ctx, signal, Trace::FanOut)
+    }
+
+    /// Traces from `signal` in the direction of `trace`.
+    ///
+    /// The graph always contains `signal` itself as its
+    /// [`root`](Self::root).
+    #[must_use]
+    pub fn trace(ctx: &SimContext, signal: &Handle, trace: Trace) -> Self {
+        let mut graph = Self {
+            trace,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            named: HashMap::new(),
+            edge_set: HashSet::new(),
+        };
+        let (root, _) = graph.insert(ctx, signal.clone());
+        let mut pending = VecDeque::from([root]);
+        while let Some(id) = pending.pop_front() {
+            for next in graph.expand(ctx, id) {
+                let (next, added) = graph.insert(ctx, next);
+                match trace {
+                    Trace::FanIn => graph.connect(next, id),
+                    Trace::FanOut => graph.connect(id, next),
+                }
+                if added {
+                    pending.push_back(next);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Returns the direction the graph was traced in.
+    #[must_use]
+    pub fn direction(&self) -> Trace {
+        self.trace
+    }
+
+    /// Returns the signal the graph was traced from.
+    #[must_use]
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns a node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` belongs to another graph.
+    #[must_use]
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// Iterates over all nodes, in the order they were reached.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (NodeId(i), node))
+    }
+
+    /// Returns all connections.
+    #[must_use]
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Returns the node of `object`, if it was reached.
+    #[must_use]
+    pub fn find(&self, ctx: &SimContext, object: &Handle) -> Option<NodeId> {
+        let kind = object.kind(ctx);
+        match object.get_full_name(ctx) {
+            Some(name) => self
+                .named
+                .get(&(std::mem::discriminant(&kind), name))
+                .copied(),
+            None => self
+                .nodes
+                .iter()
+                .position(|node| node.object.handle() == object)
+                .map(NodeId),
+        }
+    }
+
+    /// Iterates over the nodes directly driving `id`.
+    pub fn drivers(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.to == id)
+            .map(|edge| edge.from)
+    }
+
+    /// Iterates over the nodes directly driven by `id`.
+    pub fn loads(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.edges
+            .iter()
+            .filter(move |edge| edge.from == id)
+            .map(|edge| edge.to)
+    }
+
+    /// Returns the nodes where the trace ended: the sources of a fan-in or
+    /// the final loads of a fan-out.
+    #[must_use]
+    pub fn endpoints(&self) -> Vec<NodeId> {
+        self.nodes()
+            .map(|(id, _)| id)
+            .filter(|&id| id != self.root())
+            .filter(|&id| match self.trace {
+                Trace::FanIn => self.drivers(id).next().is_none(),
+                Trace::FanOut => self.loads(id).next().is_none(),
+            })
+            .collect()
+    }
+
+    /// Returns the regs and variables reached, other than the root.
+    ///
+    /// In a fan-in these are the registers the root is computed from.
+    #[must_use]
+    pub fn source_registers(&self) -> Vec<NodeId> {
+        self.nodes()
+            .filter(|(id, node)| {
+                *id != self.root()
+                    && matches!(node.object, ObjectKind::Reg(_) | ObjectKind::Variable(_))
+            })
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    fn insert(&mut self, ctx: &SimContext, handle: Handle) -> (NodeId, bool) {
+        let object = handle.kind(ctx);
+        let name = handle.get_full_name(ctx);
+        let existing = match &name {
+            Some(name) => self
+                .named
+                .get(&(std::mem::discriminant(&object), name.clone()))
+                .copied(),
+            None => self
+                .nodes
+                .iter()
+                .position(|node| node.name.is_none() && *node.object.handle() == handle)
+                .map(NodeId),
+        };
+        if let Some(id) = existing {
+            return (id, false);
+        }
+        let id = NodeId(self.nodes.len());
+        if let Some(name) = &name {
+            self.named
+                .insert((std::mem::discriminant(&object), name.clone()), id);
+        }
+        self.nodes.push(Node { object, name });
+        (id, true)
+    }
+
+    fn connect(&mut self, from: NodeId, to: NodeId) {
+        let edge = Edge { from, to };
+        if from != to && self.edge_set.insert(edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    /// Returns the objects one step further in the trace direction.
+    fn expand(&self, ctx: &SimContext, id: NodeId) -> Vec<Handle> {
+        let fan_in = self.trace == Trace::FanIn;
+        match &self.nodes[id.0].object {
+            ObjectKind::Reg(_) | ObjectKind::Variable(_) if fan_in && id != self.root() => {
+                Vec::new()
+            }
+            ObjectKind::Net(_) | ObjectKind::Reg(_) | ObjectKind::Variable(_) => {
+                let signal = self.nodes[id.0].object.handle();
+                neighbours(ctx, signal, self.trace)
+            }
+            ObjectKind::Port(port) => {
+                let port = port.as_ref();
+                let direction = port.get_direction(ctx);
+                let inner = low_conn(ctx, port);
+                let outer = port.get(ctx, ObjectType::HighConn);
+                let (from_outer, from_inner) = match direction {
+                    Some(Direction::Input) => (true, false),
+                    Some(Direction::Output) => (false, true),
+                    _ => (true, true),
+                };
+                let mut next = Vec::new();
+                // A fan-in crosses input ports inwards to outwards, and a
+                // fan-out the other way around.
+                if (fan_in && from_outer) || (!fan_in && from_inner) {
+                    next.extend(signals(ctx, &outer));
+                }
+                if (fan_in && from_inner) || (!fan_in && from_outer) {
+                    next.extend(signals(ctx, &inner));
+                }
+                next
+            }
+            ObjectKind::ContAssign(assign) => {
+                let side = if fan_in {
+                    ObjectType::Rhs
+                } else {
+                    ObjectType::Lhs
+                };
+                signals(ctx, &assign.as_ref().get(ctx, side))
+            }
+            ObjectKind::Primitive(primitive) => primitive
+                .as_ref()
+                .iterator(ctx, ObjectType::PrimTerm)
+                .filter(|term| match term.get_direction(ctx) {
+                    Some(Direction::Input) => fan_in,
+                    Some(Direction::Output) => !fan_in,
+                    _ => true,
+                })
+                .flat_map(|term| signals(ctx, &term.get(ctx, ObjectType::Expr)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Returns the objects directly driving a signal for [`Trace::FanIn`], or
+/// driven by it for [`Trace::FanOut`]: local drivers or loads, falling back
+/// to `vpiDriver` and `vpiLoad` when the simulator rejects the local
+/// relations, with primitive terminals resolved to their primitive, and the
+/// connected ports.
+pub(crate) fn neighbours(ctx: &SimContext, signal: &Handle, trace: Trace) -> Vec<Handle> {
+    let fan_in = trace == Trace::FanIn;
+    let (local, global) = if fan_in {
+        (ObjectType::LocalDriver, ObjectType::Driver)
+    } else {
+        (ObjectType::LocalLoad, ObjectType::Load)
+    };
+    // Local relations stop at ports, which are followed explicitly below.
+    let mut objects: Vec<Handle> = signal.iterator(ctx, local).collect();
+    if objects.is_empty() && crate::check_last_call(ctx, "vpi_iterate").is_err() {
+        objects = signal.iterator(ctx, global).collect();
+    }
+    let mut next: Vec<Handle> = Vec::new();
+    for object in objects {
+        let object = resolve_term(ctx, &object);
+        if !object.is_null() && !next.contains(&object) {
+            next.push(object);
+        }
+    }
+    for port in ports_of(ctx, signal) {
+        let inside = low_conn(ctx, &port) == *signal;
+        let direction = port.get_direction(ctx);
+        // The port drives the signal for inputs seen from inside and
+        // outputs seen from outside.
+        let drives_signal = match direction {
+            Some(Direction::Input) => inside,
+            Some(Direction::Output) => !inside,
+            _ => true,
+        };
+        let driven_by_signal = match direction {
+            Some(Direction::Input) => !inside,
+            Some(Direction::Output) => inside,
+            _ => true,
+        };
+        if ((fan_in && drives_signal) || (!fan_in && driven_by_signal)) && !next.contains(&port) {
+            next.push(port);
+        }
+    }
+    next
+}
+
+/// Returns the signals read by an expression.
+fn signals(ctx: &SimContext, expr: &Handle) -> Vec<Handle> {
+    let Some(typ) = expr.get_raw_property(ctx, crate::Property::Type) else {
+        return Vec::new();
+    };
+    match typ as u32 {
+        vpi_sys::vpiNet
+        | vpi_sys::vpiReg
+        | vpi_sys::vpiIntegerVar
+        | vpi_sys::vpiTimeVar
+        | vpi_sys::vpiRealVar => vec![expr.clone()],
+        vpi_sys::vpiNetBit
+        | vpi_sys::vpiRegBit
+        | vpi_sys::vpiPartSelect
+        | vpi_sys::vpiBitSelect
+        | vpi_sys::vpiMemoryWord => signals(ctx, &expr.get(ctx, ObjectType::Parent)),
+        vpi_sys::vpiOperation => expr
+            .iterator(ctx, ObjectType::Operand)
+            .flat_map(|operand| signals(ctx, &operand))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Maps primitive terminals to their primitive and bit drivers to their
+/// signal.
+fn resolve_term(ctx: &SimContext, object: &Handle) -> Handle {
+    match object.get_raw_property(ctx, crate::Property::Type) {
+        Some(typ) if typ as u32 == vpi_sys::vpiPrimTerm => {
+            let primitive = object.get(ctx, ObjectType::Primitive);
+            if primitive.is_null() {
+                object.get(ctx, ObjectType::Parent)
+            } else {
+                primitive
+            }
+        }
+        Some(typ) if typ as u32 == vpi_sys::vpiNetBit || typ as u32 == vpi_sys::vpiRegBit => {
+            object.get(ctx, ObjectType::Parent)
+        }
+        _ => object.clone(),
+    }
+}
+
+/// Returns the signal inside the module connected to `port`, falling back to
+/// the signal of the same name, as Icarus Verilog does not provide
+/// `vpiLowConn`.
+pub(crate) fn low_conn(ctx: &SimContext, port: &Handle) -> Handle {
+    let inner = port.get(ctx, ObjectType::LowConn);
+    if !inner.is_null() {
+        return inner;
+    }
+    match port.get_name(ctx) {
+        Some(name) => {
+            Handle::handle_by_name_and_scope(ctx, &name, &port.get(ctx, ObjectType::Module))
+        }
+        None => Handle::null(),
+    }
+}
+
+/// Returns the ports connected to `signal`, falling back to the ports of its
+/// module when `vpiPortInst` is not supported.
+fn ports_of(ctx: &SimContext, signal: &Handle) -> Vec<Handle> {
+    let ports: Vec<Handle> = signal.iterator(ctx, ObjectType::PortInst).collect();
+    if !ports.is_empty() {
+        return ports;
+    }
+    signal
+        .get(ctx, ObjectType::Module)
+        .iterator(ctx, ObjectType::Port)
+        .filter(|port| low_conn(ctx, port) == *signal)
+        .collect()
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::{ConnectivityGraph, Trace};
+    use crate::mock::MockSimulator;
+    use crate::model::ObjectKind;
+    use crate::{Direction, Handle, ObjectType, PrimType};
+
+    fn names(graph: &ConnectivityGraph, ids: Vec<super::NodeId>) -> Vec<String> {
+        let mut names: Vec<_> = ids
+            .into_iter()
+            .filter_map(|id| graph.node(id).name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn traces_through_ports_assignments_and_gates() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        let a = sim.add_reg(&tb, "a", 1);
+        let b = sim.add_reg(&tb, "b", 1);
+        let y = sim.add_net(&tb, "y", 1);
+
+        // dut computes out = in_a & in_b through an internal net.
+        let dut = sim.add_module(&tb, "dut", "dut");
+        let in_a = sim.add_net(&dut, "in_a", 1);
+        let in_b = sim.add_net(&dut, "in_b", 1);
+        let and_out = sim.add_net(&dut, "and_out", 1);
+        let out = sim.add_net(&dut, "out", 1);
+        let pa = sim.add_port(&dut, "in_a", Direction::Input, &in_a);
+        let pb = sim.add_port(&dut, "in_b", Direction::Input, &in_b);
+        let po = sim.add_port(&dut, "out", Direction::Output, &out);
+        assert!(sim.connect_port(&pa, &a));
+        assert!(sim.connect_port(&pb, &b));
+        assert!(sim.connect_port(&po, &y));
+        let gate = sim.add_gate(&dut, "g1", PrimType::And, &and_out, &[&in_a, &in_b]);
+        let _ = sim.add_cont_assign(&dut, &out, &[&and_out]);
+
+        let fan_in = ConnectivityGraph::fan_in(ctx, &y);
+        assert_eq!(fan_in.direction(), Trace::FanIn);
+        assert_eq!(names(&fan_in, fan_in.source_registers()), ["tb.a", "tb.b"]);
+        let g1 = fan_in.find(ctx, &gate).unwrap();
+        assert!(matches!(fan_in.node(g1).object, ObjectKind::Primitive(_)));
+        assert_eq!(
+            names(&fan_in, fan_in.drivers(g1).collect()),
+            ["tb.dut.in_a", "tb.dut.in_b"]
+        );
+        assert_eq!(names(&fan_in, fan_in.endpoints()), ["tb.a", "tb.b"]);
+
+        let fan_out = ConnectivityGraph::fan_out(ctx, &a);
+        assert_eq!(
+            names(&fan_out, fan_out.loads(fan_out.root()).collect()),
+            ["tb.dut.in_a"]
+        );
+        assert_eq!(names(&fan_out, fan_out.endpoints()), ["tb.y"]);
+        assert!(fan_out.find(ctx, &b).is_none());
+    }
+
+    #[test]
+    fn drivers_are_followed_through_ports() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        let a = sim.add_reg(&tb, "a", 1);
+        let y = sim.add_net(&tb, "y", 1);
+        let dut = sim.add_module(&tb, "dut", "dut");
+        let out = sim.add_net(&dut, "out", 1);
+        let port = sim.add_port(&dut, "out", Direction::Output, &out);
+        assert!(sim.connect_port(&port, &y));
+        let _ = sim.add_cont_assign(&dut, &out, &[&a]);
+        let _ = sim.add_cont_assign(&tb, &y, &[&a]);
+
+        // `vpiDriver` of the collapsed net also reaches the assignment in
+        // `dut`, which the graph only connects through the port.
+        assert_eq!(y.iterator(ctx, ObjectType::Driver).count(), 2);
+        let graph = ConnectivityGraph::fan_in(ctx, &y);
+        let drivers: Vec<_> = graph.drivers(graph.root()).collect();
+        assert_eq!(drivers.len(), 2);
+        let port = graph.find(ctx, &port).unwrap();
+        assert!(drivers.contains(&port));
+        assert_eq!(graph.drivers(port).count(), 1);
+    }
+}
@@ -0,0 +1,322 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{CbReason, Handle};
+
+/// Closure sent from a worker thread to run on the simulator thread.
+type Job = Box<dyn FnOnce(&SimContext) + Send>;
+
+/// Jobs queued for one simulator thread.
+type JobQueue = Arc<Mutex<VecDeque<Job>>>;
+
+thread_local! {
+    static ON_SIM_THREAD: Cell<bool> = const { Cell::new(false) };
+    static QUEUE: RefCell<Option<JobQueue>> = const { RefCell::new(None) };
+    static PUMP: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Proof that the caller runs on the simulator thread.
+///
+/// VPI routines may only be called from the thread the simulator loaded the
+/// plugin on, and only while the simulator has handed control to the plugin:
+/// in a startup routine, a system task/function routine or a callback. A
+/// `SimContext` is only available in those places, through
+/// [`CbData::context`](crate::CbData::context), [`SimContext::current`] or a
+/// `&SimContext` parameter of a [`macro@crate::systf`] function.
+///
+/// `SimContext` and [`Handle`] are neither `Send` nor `Sync`, so moving them
+/// to another thread is a compile error:
+///
+/// ```compile_fail
+/// let handle = vpi::Handle::null();
+/// std::thread::spawn(move || handle.is_null());
+/// ```
+///
+/// ```compile_fail
+/// fn worker(ctx: &vpi::SimContext) {
+///     std::thread::scope(|s| {
+///         s.spawn(|| ctx.printf("from a worker"));
+///     });
+/// }
+/// ```
+///
+/// Worker threads instead obtain a [`SimRemote`] and send closures back with
+/// [`SimContext::from_worker`].
+///
+/// Every routine that calls into the VPI takes a `&SimContext`, so a worker
+/// thread cannot reach the simulator without one:
+///
+/// ```compile_fail
+/// fn worker(ctx: &vpi::SimContext) {
+///     let ctx = *ctx;
+///     std::thread::spawn(move || vpi::Handle::handle_by_name(&ctx, "tb.dut"));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SimContext {
+    _not_send: PhantomData<*const ()>,
+}
+
+/// Ready-made token handed out by reference.
+const CONTEXT: SimContext = SimContext {
+    _not_send: PhantomData,
+};
+
+/// Marks the current thread as the simulator thread.
+///
+/// Listed first in the table emitted by
+/// [`startup_routines!`](crate::startup_routines) and called on entry of the
+/// crate's callback and system task/function routines.
+#[doc(hidden)]
+pub extern "C" fn enter_simulator_thread() {
+    ON_SIM_THREAD.set(true);
+}
+
+impl SimContext {
+    /// Returns the context if the current thread is the simulator thread.
+    ///
+    /// The thread is recognized once the simulator has entered the plugin,
+    /// through the [`startup_routines!`](crate::startup_routines) table, a
+    /// callback or a [`macro@crate::systf`] routine. Returns `None` on any
+    /// other thread.
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        ON_SIM_THREAD.get().then_some(CONTEXT)
+    }
+
+    /// Creates a context without checking the current thread.
+    ///
+    /// # Safety
+    ///
+    /// The caller must be on the simulator thread, inside a routine called
+    /// by the simulator.
+    #[must_use]
+    pub unsafe fn new_unchecked() -> Self {
+        CONTEXT
+    }
+
+    /// Returns a reference to a context that lives for `'a`.
+    pub(crate) fn borrowed<'a>() -> &'a Self {
+        &CONTEXT
+    }
+
+    /// Looks up a handle by hierarchical name; see [`Handle::handle_by_name`].
+    #[must_use]
+    pub fn handle_by_name(&self, name: &str) -> Handle {
+        Handle::handle_by_name(self, name)
+    }
+
+    /// Returns the current system task/function call handle; see
+    /// [`crate::current_systf_call`].
+    #[must_use]
+    pub fn current_systf_call(&self) -> Handle {
+        crate::current_systf_call(self)
+    }
+
+    /// Prints a message through `vpi_printf`; see [`crate::printf`].
+    pub fn printf(&self, msg: impl AsRef<str>) {
+        crate::printf(self, msg);
+    }
+
+    /// Returns a link that worker threads use to reach this thread.
+    ///
+    /// The first call on a thread registers the `cbNextSimTime` callback that
+    /// runs the closures sent with [`SimContext::from_worker`].
+    #[must_use]
+    pub fn remote(&self) -> SimRemote {
+        let queue = QUEUE.with_borrow_mut(|queue| Arc::clone(queue.get_or_insert_default()));
+        start_pump(self);
+        SimRemote { queue }
+    }
+
+    /// Queues `f` to run on the simulator thread behind `remote`.
+    ///
+    /// This is the way for worker threads to touch the simulation. The
+    /// closure runs at the start of the next simulation time step, from the
+    /// `cbNextSimTime` callback registered by [`SimContext::remote`], or
+    /// when [`SimContext::run_pending`] is called. Other callbacks never run
+    /// it. Its result is delivered through the returned receiver, which
+    /// reports a disconnection if the closure is never run.
+    ///
+    /// ```ignore
+    /// let remote = ctx.remote();
+    /// std::thread::spawn(move || {
+    ///     let answer = expensive_computation();
+    ///     vpi::SimContext::from_worker(&remote, move |ctx| {
+    ///         ctx.printf(format!("worker result: {answer}"));
+    ///     });
+    /// });
+    /// ```
+    pub fn from_worker<F, R>(remote: &SimRemote, f: F) -> Receiver<R>
+    where
+        F: FnOnce(&SimContext) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        remote.push(Box::new(move |ctx| {
+            let _ = sender.send(f(ctx));
+        }));
+        receiver
+    }
+
+    /// Runs the closures queued by worker threads and returns their number.
+    ///
+    /// The callback registered by [`SimContext::remote`] does this once per
+    /// time step; call it directly if worker results are needed earlier. Do
+    /// not call it from a read-only callback, since the closures may write
+    /// values.
+    pub fn run_pending(&self) -> usize {
+        let Some(queue) = QUEUE.with_borrow(Clone::clone) else {
+            return 0;
+        };
+        let mut count = 0;
+        // Jobs are popped one at a time so they can queue further jobs.
+        while let Some(job) = pop(&queue) {
+            let _ = crate::catch_panic(self, "worker closure", || job(self));
+            count += 1;
+        }
+        count
+    }
+}
+
+fn pop(queue: &JobQueue) -> Option<Job> {
+    queue
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .pop_front()
+}
+
+/// Marks the simulator thread on entry of a callback and returns its context.
+pub(crate) fn enter_callback() -> &'static SimContext {
+    enter_simulator_thread();
+    SimContext::borrowed()
+}
+
+/// Keeps one `cbNextSimTime` callback registered that runs the worker jobs.
+///
+/// The callback is one-shot, so it registers its successor before returning.
+fn start_pump(ctx: &SimContext) {
+    if PUMP.get() {
+        return;
+    }
+    let handle = crate::register_cb(ctx, CbReason::NextSimTime, |data| {
+        PUMP.set(false);
+        let ctx = data.context();
+        ctx.run_pending();
+        start_pump(ctx);
+    });
+    PUMP.set(!handle.is_null());
+}
+
+/// Drops the jobs queued for the current thread.
+#[cfg(mock_backend)]
+pub(crate) fn reset_thread() {
+    QUEUE.set(None);
+    PUMP.set(false);
+    ON_SIM_THREAD.set(false);
+}
+
+/// Sendable link from worker threads to a simulator thread.
+///
+/// Obtained with [`SimContext::remote`] and used with
+/// [`SimContext::from_worker`].
+#[derive(Clone)]
+pub struct SimRemote {
+    queue: JobQueue,
+}
+
+impl SimRemote {
+    fn push(&self, job: Job) {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(job);
+    }
+
+    /// Returns the number of closures waiting to run.
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
+
+impl std::fmt::Debug for SimRemote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SimRemote")
+            .field("pending", &self.pending())
+            .finish()
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::SimContext;
+    use crate::mock::MockSimulator;
+    use crate::{register_cb_with_time, CbReason, Handle, Time, Value, ValueType};
+
+    #[test]
+    fn context_is_only_available_on_the_simulator_thread() {
+        let _sim = MockSimulator::new();
+        assert!(SimContext::current().is_some());
+        let other = std::thread::spawn(|| SimContext::current().is_none());
+        assert!(other.join().unwrap());
+    }
+
+    #[test]
+    fn worker_closures_run_at_the_next_time_step() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let _ = sim.add_reg(&top, "q", 8);
+        sim.start();
+
+        let remote = ctx.remote();
+        let result = std::thread::spawn(move || {
+            SimContext::from_worker(&remote, |ctx| {
+                ctx.handle_by_name("tb.q").get_name(ctx).unwrap_or_default()
+            })
+        })
+        .join()
+        .unwrap();
+        assert!(result.try_recv().is_err());
+
+        let _ = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(1), |_| {});
+        sim.run_for(1);
+        assert_eq!(result.try_recv().as_deref(), Ok("q"));
+    }
+
+    #[test]
+    fn worker_closures_do_not_run_in_other_callbacks() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 8);
+        sim.start();
+
+        let remote = ctx.remote();
+        let result = SimContext::from_worker(&remote, |_| 1);
+        let seen = Rc::new(Cell::new(None));
+        let pending = Rc::clone(&seen);
+        let probe = remote.clone();
+        let _ = q.register_value_change_cb(ctx, ValueType::Int, move |_| {
+            pending.set(Some(probe.pending()));
+        });
+        assert!(sim.set_value(&q, &Value::Int(3)));
+        assert_eq!(seen.get(), Some(1));
+        assert_eq!(remote.pending(), 1);
+
+        let _ = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(1), |_| {});
+        sim.run_for(1);
+        assert_eq!(remote.pending(), 0);
+        assert_eq!(result.try_recv(), Ok(1));
+    }
+}
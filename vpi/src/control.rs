@@ -1,3 +1,4 @@
+use crate::SimContext;
 use vpi_sys::PLI_INT32;
 
 /// Simulator control operations for `vpi_control`.
@@ -93,12 +94,22 @@ pub enum AssertionControl {
 }
 
 /// Invokes `vpi_control` with the selected operation.
-pub fn control(control: Control) {
+pub fn control(_ctx: &SimContext, control: Control) {
     unsafe {
         vpi_sys::vpi_control(control as PLI_INT32);
     }
 }
 
+/// Invokes `vpi_control` with the selected operation and one argument.
+///
+/// For [`Control::Stop`] and [`Control::Finish`] the argument is the
+/// diagnostic level that `$stop` and `$finish` take.
+pub fn control_with_arg(_ctx: &SimContext, control: Control, arg: PLI_INT32) {
+    unsafe {
+        vpi_sys::vpi_control(control as PLI_INT32, arg);
+    }
+}
+
 #[cfg(feature = "sv")]
 fn control_sv(code: PLI_INT32) {
     unsafe {
@@ -108,12 +119,12 @@ fn control_sv(code: PLI_INT32) {
 
 /// Invokes `vpi_control` with a SystemVerilog coverage control operation.
 #[cfg(feature = "sv")]
-pub fn coverage_control(control: CoverageControl) {
+pub fn coverage_control(_ctx: &SimContext, control: CoverageControl) {
     control_sv(control as PLI_INT32);
 }
 
 /// Invokes `vpi_control` with a SystemVerilog assertion control operation.
 #[cfg(feature = "sv")]
-pub fn assertion_control(control: AssertionControl) {
+pub fn assertion_control(_ctx: &SimContext, control: AssertionControl) {
     control_sv(control as PLI_INT32);
 }
@@ -1,6 +1,7 @@
 use std::slice;
 
-use crate::{Handle, Time};
+use crate::error::check_last_call;
+use crate::{Error, Handle, SimContext, Time};
 
 /// Time encoding used by VPI delay records.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -111,7 +112,12 @@ impl Handle {
     /// `capacity` controls how many delay entries are allocated for the C API.
     /// Use 1 for simple delays and 3 for min/typ/max delay sets.
     #[must_use]
-    pub fn get_delays(&self, capacity: usize, time_type: DelayTimeType) -> Option<DelayData> {
+    pub fn get_delays(
+        &self,
+        _ctx: &SimContext,
+        capacity: usize,
+        time_type: DelayTimeType,
+    ) -> Option<DelayData> {
         if self.is_null() {
             return None;
         }
@@ -168,12 +174,32 @@ impl Handle {
         })
     }
 
+    /// Fallible variant of [`Handle::get_delays`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles, when the simulator reports an error for the
+    /// read and when the returned delays cannot be decoded.
+    pub fn try_get_delays(
+        &self,
+        ctx: &SimContext,
+        capacity: usize,
+        time_type: DelayTimeType,
+    ) -> Result<DelayData, Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_get_delays", "null handle"));
+        }
+        let delays = self.get_delays(ctx, capacity, time_type);
+        check_last_call(ctx, "vpi_get_delays")?;
+        delays.ok_or_else(|| Error::new("vpi_get_delays", "cannot decode the returned delays"))
+    }
+
     /// Writes delay values to an object using `vpi_put_delays`.
     ///
     /// Returns `false` for null handles or when the delay count does not fit in
     /// the VPI ABI integer type.
     #[must_use]
-    pub fn put_delays(&self, data: &DelayData) -> bool {
+    pub fn put_delays(&self, _ctx: &SimContext, data: &DelayData) -> bool {
         if self.is_null() {
             return false;
         }
@@ -200,6 +226,22 @@ impl Handle {
         unsafe { vpi_sys::vpi_put_delays(self.as_raw(), &raw mut raw_delay) };
         true
     }
+
+    /// Fallible variant of [`Handle::put_delays`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles, for too many delays and when the simulator
+    /// reports an error for the write.
+    pub fn try_put_delays(&self, ctx: &SimContext, data: &DelayData) -> Result<(), Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_put_delays", "null handle"));
+        }
+        if !self.put_delays(ctx, data) {
+            return Err(Error::new("vpi_put_delays", "too many delays"));
+        }
+        check_last_call(ctx, "vpi_put_delays")
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+use crate::SimContext;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
@@ -88,7 +89,7 @@ impl std::fmt::Display for VPIError {
 /// Returns `None` when no error is present, otherwise returns the translated
 /// [`VPIError`] payload.
 #[must_use]
-pub fn chk_error() -> Option<VPIError> {
+pub fn chk_error(_ctx: &SimContext) -> Option<VPIError> {
     let mut error_info = vpi_sys::t_vpi_error_info {
         code: std::ptr::null_mut(),
         message: std::ptr::null_mut(),
@@ -134,6 +135,168 @@ pub fn chk_error() -> Option<VPIError> {
 
 /// Alias of `chk_error` for consistency with rust-vhpi
 #[must_use]
-pub fn check_error() -> Option<VPIError> {
-    chk_error()
+pub fn check_error(ctx: &SimContext) -> Option<VPIError> {
+    chk_error(ctx)
+}
+
+impl std::error::Error for VPIError {}
+
+/// Error returned by the fallible `try_*` functions.
+///
+/// Carries the [`VPIError`] reported by `vpi_chk_error` right after the
+/// failing call, if the simulator reported one, together with a local reason
+/// describing why the call was considered failed.
+#[derive(Debug, Clone)]
+pub struct Error {
+    operation: &'static str,
+    reason: String,
+    vpi: Option<Box<VPIError>>,
+}
+
+impl Error {
+    /// Creates an error for a failure detected without calling the simulator,
+    /// such as a null handle.
+    #[must_use]
+    pub fn new(operation: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            operation,
+            reason: reason.into(),
+            vpi: None,
+        }
+    }
+
+    /// Creates an error for a failed simulator call, attaching the pending
+    /// [`VPIError`] if there is one.
+    ///
+    /// Must be called right after the failing VPI call, since every VPI call
+    /// resets the simulator's error status.
+    #[must_use]
+    pub fn last(ctx: &SimContext, operation: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            vpi: chk_error(ctx).map(Box::new),
+            ..Self::new(operation, reason)
+        }
+    }
+
+    /// Returns the name of the VPI routine that failed, e.g. `vpi_put_value`.
+    #[must_use]
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// Returns the local description of the failure.
+    #[must_use]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Returns the error reported by the simulator, if any.
+    #[must_use]
+    pub fn vpi_error(&self) -> Option<&VPIError> {
+        self.vpi.as_deref()
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.vpi {
+            // Simulators often name the failing routine in their message.
+            Some(vpi) if vpi.message.starts_with(self.operation) => f.write_str(&vpi.message),
+            Some(vpi) => write!(f, "{}: {}", self.operation, vpi.message),
+            None => write!(f, "{}: {}", self.operation, self.reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.vpi.as_deref().map(|vpi| vpi as _)
+    }
+}
+
+/// Fails with the pending simulator error if the last VPI call reported an
+/// error, system or internal failure. Notices and warnings are ignored.
+pub(crate) fn check_last_call(ctx: &SimContext, operation: &'static str) -> Result<(), Error> {
+    match chk_error(ctx) {
+        Some(vpi) if !matches!(vpi.severity, Some(Severity::Notice | Severity::Warning)) => {
+            Err(Error {
+                operation,
+                reason: "the simulator reported an error".to_string(),
+                vpi: Some(Box::new(vpi)),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Turns a null handle returned by `operation` into an [`Error`].
+pub(crate) fn non_null(
+    ctx: &crate::SimContext,
+    handle: crate::Handle,
+    operation: &'static str,
+    reason: impl FnOnce() -> String,
+) -> Result<crate::Handle, Error> {
+    if handle.is_null() {
+        Err(Error::last(ctx, operation, reason()))
+    } else {
+        Ok(handle)
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use crate::mock::MockSimulator;
+    use crate::{try_register_cb_with_time, CbReason, Handle, Severity, Time, Value, ValueType};
+
+    #[test]
+    fn missing_object_has_local_reason() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let _ = sim.add_module(&Handle::null(), "tb", "tb");
+
+        let error = Handle::try_handle_by_name(ctx, "tb.missing").unwrap_err();
+        assert_eq!(error.operation(), "vpi_handle_by_name");
+        assert!(error.vpi_error().is_none());
+        assert_eq!(
+            error.to_string(),
+            "vpi_handle_by_name: no object named `tb.missing`"
+        );
+        assert!(Handle::try_handle_by_name(ctx, "tb").is_ok());
+        assert!(Handle::try_handle_by_name(ctx, "tb\0x").is_err());
+    }
+
+    #[test]
+    fn simulator_error_is_attached() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 4);
+
+        let error = top.try_put_value(ctx, &Value::Int(1)).unwrap_err();
+        let vpi = error.vpi_error().unwrap();
+        assert_eq!(vpi.severity, Some(Severity::Error));
+        assert_eq!(error.to_string(), "vpi_put_value: object cannot be written");
+        assert!(std::error::Error::source(&error).is_some());
+
+        assert!(q.try_put_value(ctx, &Value::Int(5)).is_ok());
+        assert_eq!(q.try_get_value(ctx, ValueType::Int).unwrap(), Value::Int(5));
+        assert!(Handle::null().try_get_value(ctx, ValueType::Int).is_err());
+    }
+
+    #[test]
+    fn rejected_callback_registration_fails() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+
+        let error = top
+            .try_register_value_change_cb(ctx, ValueType::Int, |_| {})
+            .unwrap_err();
+        assert_eq!(error.operation(), "vpi_register_cb");
+        assert!(error.vpi_error().is_some());
+        assert_eq!(sim.active_callbacks(), 0);
+
+        let handle = try_register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(1), |_| {});
+        assert!(handle.is_ok());
+    }
 }
@@ -0,0 +1,606 @@
+//! Export of the elaborated design to JSON and Graphviz DOT.
+//!
+//! [`Design::collect`] walks the instance tree from the top modules, after
+//! parameters and generate blocks were resolved by the simulator, and records
+//! for each instance its definition name, source location, ports, signals,
+//! parameters and child instances. The snapshot can then be written as a JSON
+//! document for tooling, or as a DOT graph of the instance tree with the port
+//! connections between sibling instances.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::export::Design;
+//!
+//! fn export(ctx: &vpi::SimContext) {
+//!     let design = Design::collect(ctx);
+//!     std::fs::write("design.json", design.to_json()).unwrap();
+//!     std::fs::write("design.dot", design.to_dot()).unwrap();
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+
+use crate::connectivity::low_conn;
+use crate::model::{Module, ObjectKind, TypedHandle};
+use crate::query::instance_children;
+use crate::{ConstType, Direction, Handle, ObjectType, Property, SimContext, Value, ValueType};
+
+/// Snapshot of the elaborated design.
+#[derive(Debug, Clone, Default)]
+pub struct Design {
+    /// The top-level instances.
+    pub instances: Vec<Instance>,
+}
+
+/// A module instance of a [`Design`].
+#[derive(Debug, Clone, Default)]
+pub struct Instance {
+    /// Instance name.
+    pub name: String,
+    /// Full hierarchical name.
+    pub full_name: String,
+    /// Name of the module definition.
+    pub def_name: Option<String>,
+    /// Source file of the instance.
+    pub file: Option<String>,
+    /// Source line of the instance.
+    pub line: Option<u32>,
+    /// Ports, in declaration order.
+    pub ports: Vec<PortInfo>,
+    /// Nets, regs and variables declared in the instance.
+    pub signals: Vec<SignalInfo>,
+    /// Parameters with their elaborated values.
+    pub parameters: Vec<ParameterInfo>,
+    /// Child instances.
+    pub instances: Vec<Instance>,
+}
+
+/// A port of an [`Instance`].
+#[derive(Debug, Clone)]
+pub struct PortInfo {
+    /// Port name.
+    pub name: String,
+    /// Port direction.
+    pub direction: Option<Direction>,
+    /// Width in bits.
+    pub width: Option<u32>,
+    /// Full name of the signal connected to the port in the parent instance.
+    ///
+    /// Bit and part selects are reported as the signal they select from.
+    pub connection: Option<String>,
+    /// Full name of the signal connected to the port inside the instance.
+    ///
+    /// Falls back to the signal named like the port when the simulator does
+    /// not provide `vpiLowConn`.
+    pub inner_connection: Option<String>,
+}
+
+/// A net, reg or variable of an [`Instance`].
+#[derive(Debug, Clone)]
+pub struct SignalInfo {
+    /// Signal name.
+    pub name: String,
+    /// Type name as reported by [`Handle::get_type_name`].
+    pub type_name: Option<String>,
+    /// Width in bits.
+    pub width: Option<u32>,
+}
+
+/// A parameter of an [`Instance`].
+#[derive(Debug, Clone)]
+pub struct ParameterInfo {
+    /// Parameter name.
+    pub name: String,
+    /// Elaborated value: [`Value::Real`] and [`Value::String`] for real and
+    /// string parameters, [`Value::Int`] when the value fits and
+    /// [`Value::BinStr`] otherwise.
+    pub value: Option<Value>,
+    /// `true` for `localparam`s.
+    pub local: bool,
+}
+
+impl Design {
+    /// Walks the design from all top modules.
+    #[must_use]
+    pub fn collect(ctx: &SimContext) -> Self {
+        Self {
+            instances: Handle::null()
+                .iterator(ctx, ObjectType::Module)
+                .filter_map(|module| module.downcast::<Module>(ctx))
+                .map(|module| Instance::collect(ctx, &module))
+                .collect(),
+        }
+    }
+
+    /// Walks the part of the design below `scope`, which becomes the only
+    /// top-level instance.
+    ///
+    /// Returns an empty design if `scope` is not a module.
+    #[must_use]
+    pub fn collect_from(ctx: &SimContext, scope: &Handle) -> Self {
+        Self {
+            instances: scope
+                .downcast::<Module>(ctx)
+                .map(|module| Instance::collect(ctx, &module))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Iterates over all instances, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &Instance> {
+        let mut stack: Vec<&Instance> = self.instances.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let instance = stack.pop()?;
+            stack.extend(instance.instances.iter().rev());
+            Some(instance)
+        })
+    }
+
+    /// Renders the design as a JSON document.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        Json::Object(vec![(
+            "instances",
+            Json::Array(self.instances.iter().map(Instance::to_json).collect()),
+        )])
+        .write(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    /// Writes the JSON document of [`to_json`](Self::to_json) to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when writing to `out` fails.
+    pub fn write_json(&self, mut out: impl io::Write) -> io::Result<()> {
+        out.write_all(self.to_json().as_bytes())
+    }
+
+    /// Renders the instance tree and port connections as a Graphviz graph.
+    ///
+    /// Instances are boxes linked to their children by dashed lines. Within
+    /// each parent, an arrow leads from every port driving a signal to every
+    /// port reading it, labelled with the signal name; the parent takes part
+    /// through its own ports.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph design {\n    node [shape=box];\n");
+        for instance in self.iter() {
+            let label = match &instance.def_name {
+                Some(def_name) => format!("{}\n({def_name})", instance.name),
+                None => instance.name.clone(),
+            };
+            let _ = writeln!(
+                out,
+                "    {} [label={}];",
+                dot_id(&instance.full_name),
+                dot_id(&label)
+            );
+        }
+        for instance in self.iter() {
+            for child in &instance.instances {
+                let _ = writeln!(
+                    out,
+                    "    {} -> {} [style=dashed, arrowhead=none];",
+                    dot_id(&instance.full_name),
+                    dot_id(&child.full_name)
+                );
+            }
+            for connection in instance.connections() {
+                let _ = writeln!(
+                    out,
+                    "    {} -> {} [label={}, taillabel={}, headlabel={}];",
+                    dot_id(connection.driver.0),
+                    dot_id(connection.load.0),
+                    dot_id(&connection.signal),
+                    dot_id(connection.driver.1),
+                    dot_id(connection.load.1)
+                );
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Writes the graph of [`to_dot`](Self::to_dot) to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when writing to `out` fails.
+    pub fn write_dot(&self, mut out: impl io::Write) -> io::Result<()> {
+        out.write_all(self.to_dot().as_bytes())
+    }
+}
+
+/// A signal of a parent instance connecting two ports.
+struct Connection<'a> {
+    signal: String,
+    /// Full name of the driving instance and its port.
+    driver: (&'a str, &'a str),
+    /// Full name of the reading instance and its port.
+    load: (&'a str, &'a str),
+}
+
+impl Instance {
+    fn collect(ctx: &SimContext, module: &Module) -> Self {
+        let full_name = module.full_name(ctx).unwrap_or_default();
+        Self {
+            name: module.name(ctx).unwrap_or_default(),
+            def_name: module.def_name(ctx),
+            file: module.file(ctx),
+            line: module.line(ctx),
+            ports: module
+                .ports(ctx)
+                .map(|port| PortInfo {
+                    name: port.name(ctx).unwrap_or_default(),
+                    direction: port.direction(ctx),
+                    width: port.size(ctx),
+                    connection: port
+                        .high_conn(ctx)
+                        .and_then(|object| signal_name(ctx, &object)),
+                    inner_connection: signal_name(ctx, &low_conn(ctx, port.handle()).kind(ctx)),
+                })
+                .collect(),
+            signals: module
+                .handle()
+                .iterators(
+                    ctx,
+                    &[ObjectType::Net, ObjectType::Reg, ObjectType::Variables],
+                )
+                .map(|signal| SignalInfo {
+                    name: signal.get_name(ctx).unwrap_or_default(),
+                    type_name: signal.get_type_name(ctx),
+                    width: signal.get_u32(ctx, Property::Size),
+                })
+                .collect(),
+            parameters: module
+                .parameters(ctx)
+                .map(|parameter| ParameterInfo {
+                    name: parameter.name(ctx).unwrap_or_default(),
+                    value: match parameter.const_type(ctx) {
+                        Some(ConstType::Real) => parameter.value(ctx, ValueType::Real),
+                        Some(ConstType::String) => parameter.value(ctx, ValueType::String),
+                        _ => parameter
+                            .value(ctx, ValueType::Int)
+                            .filter(|_| parameter.handle().get_u32(ctx, Property::Size) <= Some(32))
+                            .or_else(|| parameter.value(ctx, ValueType::BinStr)),
+                    },
+                    local: parameter.is_local(ctx),
+                })
+                .collect(),
+            // Instances in generate blocks are only listed as internal scopes.
+            instances: instance_children(ctx, module.handle())
+                .0
+                .into_iter()
+                .filter_map(|child| child.downcast::<Module>(ctx))
+                .map(|child| Instance::collect(ctx, &child))
+                .collect(),
+            full_name,
+        }
+    }
+
+    /// Returns the port-to-port connections through signals of this instance.
+    fn connections(&self) -> Vec<Connection<'_>> {
+        let mut drivers: BTreeMap<String, Vec<(&str, &str)>> = BTreeMap::new();
+        let mut loads: BTreeMap<String, Vec<(&str, &str)>> = BTreeMap::new();
+        // Own ports drive the signal behind them for inputs and read it for
+        // outputs; child ports the other way around.
+        let own = self.ports.iter().filter_map(|port| {
+            let signal = port.inner_connection.clone()?;
+            Some((signal, &self.full_name, port, true))
+        });
+        let children = self.instances.iter().flat_map(|child| {
+            child.ports.iter().filter_map(move |port| {
+                let signal = port.connection.clone()?;
+                Some((signal, &child.full_name, port, false))
+            })
+        });
+        for (signal, owner, port, own) in own.chain(children) {
+            let endpoint = (owner.as_str(), port.name.as_str());
+            let (drives, reads) = match (&port.direction, own) {
+                (Some(Direction::Input), true) | (Some(Direction::Output), false) => (true, false),
+                (Some(Direction::Output), true) | (Some(Direction::Input), false) => (false, true),
+                _ => (true, true),
+            };
+            if drives {
+                drivers.entry(signal.clone()).or_default().push(endpoint);
+            }
+            if reads {
+                loads.entry(signal).or_default().push(endpoint);
+            }
+        }
+        let prefix = format!("{}.", self.full_name);
+        let mut connections = Vec::new();
+        for (signal, drivers) in drivers {
+            let Some((signal_key, loads)) = loads.get_key_value(&signal) else {
+                continue;
+            };
+            let short = signal_key.strip_prefix(&prefix).unwrap_or(signal_key);
+            for &driver in &drivers {
+                for &load in loads.iter().filter(|&&load| load != driver) {
+                    connections.push(Connection {
+                        signal: short.to_string(),
+                        driver,
+                        load,
+                    });
+                }
+            }
+        }
+        connections
+    }
+
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("name", Json::string(&self.name)),
+            ("full_name", Json::string(&self.full_name)),
+            ("def_name", Json::optional(self.def_name.as_deref())),
+            ("file", Json::optional(self.file.as_deref())),
+            ("line", Json::number(self.line)),
+            (
+                "ports",
+                Json::Array(
+                    self.ports
+                        .iter()
+                        .map(|port| {
+                            Json::Object(vec![
+                                ("name", Json::string(&port.name)),
+                                (
+                                    "direction",
+                                    Json::optional(port.direction.as_ref().map(direction_name)),
+                                ),
+                                ("width", Json::number(port.width)),
+                                ("connection", Json::optional(port.connection.as_deref())),
+                                (
+                                    "inner_connection",
+                                    Json::optional(port.inner_connection.as_deref()),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "signals",
+                Json::Array(
+                    self.signals
+                        .iter()
+                        .map(|signal| {
+                            Json::Object(vec![
+                                ("name", Json::string(&signal.name)),
+                                ("type", Json::optional(signal.type_name.as_deref())),
+                                ("width", Json::number(signal.width)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "parameters",
+                Json::Array(
+                    self.parameters
+                        .iter()
+                        .map(|parameter| {
+                            Json::Object(vec![
+                                ("name", Json::string(&parameter.name)),
+                                ("value", Json::value(parameter.value.as_ref())),
+                                ("local", Json::Raw(parameter.local.to_string())),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "instances",
+                Json::Array(self.instances.iter().map(Instance::to_json).collect()),
+            ),
+        ])
+    }
+}
+
+/// Returns the full name of a connected signal, looking through selects.
+fn signal_name(ctx: &SimContext, object: &ObjectKind) -> Option<String> {
+    match object {
+        ObjectKind::Other(handle) => {
+            let parent = handle.get(ctx, ObjectType::Parent);
+            if parent.is_null() {
+                handle.get_full_name(ctx)
+            } else {
+                parent.get_full_name(ctx)
+            }
+        }
+        _ => object.handle().get_full_name(ctx),
+    }
+}
+
+fn direction_name(direction: &Direction) -> &'static str {
+    match direction {
+        Direction::Input => "input",
+        Direction::Output => "output",
+        Direction::Inout => "inout",
+        Direction::MixedIO => "mixed",
+        Direction::NoDirection => "none",
+    }
+}
+
+/// Quotes `text` as a DOT identifier.
+fn dot_id(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Minimal JSON tree, written with two-space indentation.
+enum Json {
+    Raw(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn string(text: &str) -> Self {
+        let mut out = String::with_capacity(text.len() + 2);
+        out.push('"');
+        for c in text.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if u32::from(c) < 0x20 => {
+                    let _ = write!(out, "\\u{:04x}", u32::from(c));
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+        Json::Raw(out)
+    }
+
+    fn optional(text: Option<&str>) -> Self {
+        text.map_or_else(|| Json::Raw("null".into()), Json::string)
+    }
+
+    fn number(number: Option<u32>) -> Self {
+        Json::Raw(number.map_or_else(|| "null".into(), |number| number.to_string()))
+    }
+
+    fn value(value: Option<&Value>) -> Self {
+        match value {
+            Some(Value::Int(value)) => Json::Raw(value.to_string()),
+            Some(Value::Real(value)) if value.is_finite() => Json::Raw(value.to_string()),
+            Some(Value::String(text)) => Json::string(text),
+            Some(value) => Json::string(&value.to_string()),
+            None => Json::Raw("null".into()),
+        }
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        let pad = |out: &mut String, indent: usize| {
+            out.push('\n');
+            out.extend(std::iter::repeat_n(' ', indent * 2));
+        };
+        match self {
+            Json::Raw(text) => out.push_str(text),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    pad(out, indent + 1);
+                    item.write(out, indent + 1);
+                }
+                pad(out, indent);
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    pad(out, indent + 1);
+                    let _ = write!(out, "\"{key}\": ");
+                    value.write(out, indent + 1);
+                }
+                pad(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::Design;
+    use crate::mock::MockSimulator;
+    use crate::{Direction, Handle, Value};
+
+    #[test]
+    fn exports_instances_ports_and_connections() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        sim.set_location(&tb, "tb.v", 3);
+        let _ = sim.add_parameter(&tb, "WIDTH", &Value::Int(8), false);
+        let _ = sim.add_parameter(&tb, "NAME", &Value::String("a\"b".into()), true);
+        let data = sim.add_net(&tb, "data", 8);
+
+        let src = sim.add_module(&tb, "src", "producer");
+        let src_out = sim.add_reg(&src, "out", 8);
+        let port = sim.add_port(&src, "out", Direction::Output, &src_out);
+        assert!(sim.connect_port(&port, &data));
+        let dst = sim.add_module(&tb, "dst", "consumer");
+        let dst_in = sim.add_net(&dst, "in", 8);
+        let port = sim.add_port(&dst, "in", Direction::Input, &dst_in);
+        assert!(sim.connect_port(&port, &data));
+
+        let design = Design::collect(ctx);
+        let names: Vec<_> = design.iter().map(|i| i.full_name.as_str()).collect();
+        assert_eq!(names, ["tb", "tb.src", "tb.dst"]);
+        let tb = &design.instances[0];
+        assert_eq!(tb.file.as_deref(), Some("tb.v"));
+        assert_eq!(tb.parameters[0].value, Some(Value::Int(8)));
+        assert!(!tb.parameters[0].local);
+        let out = &tb.instances[0].ports[0];
+        assert_eq!(out.direction, Some(Direction::Output));
+        assert_eq!(out.width, Some(8));
+        assert_eq!(out.connection.as_deref(), Some("tb.data"));
+
+        let json = design.to_json();
+        assert!(json.contains("\"def_name\": \"producer\""));
+        assert!(json.contains("\"value\": \"a\\\"b\""));
+        assert!(json.contains("\"direction\": \"input\""));
+        assert!(json.contains("\"line\": 3"));
+
+        let dot = design.to_dot();
+        assert!(dot.contains("\"tb\" -> \"tb.src\" [style=dashed, arrowhead=none];"));
+        assert!(dot.contains(
+            "\"tb.src\" -> \"tb.dst\" [label=\"data\", taillabel=\"out\", headlabel=\"in\"];"
+        ));
+        assert!(Design::collect_from(ctx, &data).instances.is_empty());
+        assert_eq!(Design::collect_from(ctx, &dst).iter().count(), 1);
+    }
+
+    #[test]
+    fn exports_instances_in_generate_scopes() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "top", "wrapper");
+        let req = sim.add_net(&top, "req_q", 1);
+        let _ = sim.add_port(&top, "req", Direction::Input, &req);
+        for name in ["lane[0]", "lane[1]"] {
+            let lane = sim.add_gen_scope(&top, name);
+            let leaf = sim.add_module(&lane, "u", "leaf");
+            let d = sim.add_net(&leaf, "d", 1);
+            let port = sim.add_port(&leaf, "d", Direction::Input, &d);
+            assert!(sim.connect_port(&port, &req));
+        }
+
+        let design = Design::collect(ctx);
+        let names: Vec<_> = design.iter().map(|i| i.full_name.as_str()).collect();
+        assert_eq!(names, ["top", "top.lane[0].u", "top.lane[1].u"]);
+        let req = &design.instances[0].ports[0];
+        assert_eq!(req.inner_connection.as_deref(), Some("top.req_q"));
+        assert!(design.to_dot().contains(
+            "\"top\" -> \"top.lane[1].u\" [label=\"req_q\", taillabel=\"req\", headlabel=\"d\"];"
+        ));
+    }
+}
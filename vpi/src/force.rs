@@ -0,0 +1,313 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::watch::SignalKey;
+use crate::{
+    current_simulation_time, CallbackBuilder, CallbackGuard, CbData, CbEvent, CbReason, Error,
+    Handle, SimContext, Time, Value, ValueType,
+};
+
+/// Who applied a force listed by [`forced_objects`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForceSource {
+    /// Forced with [`Handle::force`].
+    Rust,
+    /// Forced by HDL code or another application, as seen by the callbacks
+    /// of [`track_forces`].
+    Hdl,
+}
+
+/// An object currently forced, as listed by [`forced_objects`].
+#[derive(Debug, Clone)]
+pub struct ForcedObject {
+    /// The forced object.
+    pub object: Handle,
+    /// Full hierarchical name of the object, if available.
+    pub name: Option<String>,
+    /// The forced value. HDL forces report it as [`Value::BinStr`].
+    pub value: Option<Value>,
+    /// Simulation time of the force.
+    pub time: Time,
+    /// Who applied the force.
+    pub source: ForceSource,
+}
+
+/// A [`ForcedObject`] as kept in the registry. The handle is stored as an
+/// address, since handles may only be used on the simulator thread.
+struct Entry {
+    object: usize,
+    name: Option<String>,
+    value: Option<Value>,
+    time: Time,
+    source: ForceSource,
+    /// Thread that recorded the force, so that a new mock session only
+    /// clears its own entries.
+    #[cfg(mock_backend)]
+    thread: std::thread::ThreadId,
+}
+
+/// Objects currently forced, for the whole process.
+static REGISTRY: Mutex<BTreeMap<SignalKey, Entry>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    /// `cbForce` and `cbRelease` callbacks while tracking HDL forces.
+    static TRACKING: RefCell<Vec<CallbackGuard>> = const { RefCell::new(Vec::new()) };
+}
+
+fn registry() -> MutexGuard<'static, BTreeMap<SignalKey, Entry>> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Releases a force applied with [`Handle::force`] when dropped.
+///
+/// Use [`ForceGuard::keep`] to leave the object forced.
+#[must_use = "dropping the guard releases the force immediately"]
+#[derive(Debug)]
+pub struct ForceGuard {
+    object: Handle,
+    active: bool,
+}
+
+impl ForceGuard {
+    /// Returns the forced object.
+    #[must_use]
+    pub fn object(&self) -> &Handle {
+        &self.object
+    }
+
+    /// Releases the force now.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator reports an error for the release.
+    pub fn release(mut self, ctx: &SimContext) -> Result<(), Error> {
+        self.active = false;
+        self.object.release(ctx)
+    }
+
+    /// Leaves the object forced and returns it.
+    ///
+    /// The force stays listed by [`forced_objects`] until the object is
+    /// released.
+    #[must_use]
+    pub fn keep(mut self) -> Handle {
+        self.active = false;
+        self.object.clone()
+    }
+}
+
+impl Drop for ForceGuard {
+    fn drop(&mut self) {
+        if self.active {
+            // The guard holds a handle, so it is dropped on the simulator thread.
+            let _ = self.object.release(SimContext::borrowed());
+        }
+    }
+}
+
+impl Handle {
+    /// Forces this net or variable to `value` with `vpiForceFlag`.
+    ///
+    /// The object stays forced until the returned guard is dropped or
+    /// released, and is listed by [`forced_objects`] meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles and when the simulator reports an error for
+    /// the force, for example because the object cannot be forced.
+    pub fn force(&self, ctx: &SimContext, value: &Value) -> Result<ForceGuard, Error> {
+        self.put_value_with_flags(ctx, value, vpi_sys::vpiForceFlag)?;
+        record(ctx, self, Some(value.clone()), ForceSource::Rust);
+        Ok(ForceGuard {
+            object: self.clone(),
+            active: true,
+        })
+    }
+
+    /// Releases a force on this net or variable with `vpiReleaseFlag`.
+    ///
+    /// Releasing an object that is not forced has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles and when the simulator reports an error for
+    /// the release.
+    pub fn release(&self, ctx: &SimContext) -> Result<(), Error> {
+        // The simulator writes the released value into the record, which is
+        // discarded.
+        self.put_value_with_flags(ctx, &Value::Int(0), vpi_sys::vpiReleaseFlag)?;
+        forget(ctx, self);
+        Ok(())
+    }
+}
+
+/// Returns the objects currently forced, ordered by name.
+///
+/// Lists forces applied with [`Handle::force`], and while [`track_forces`]
+/// is active also those applied by HDL code. The list is shared by the
+/// whole process, whichever thread applied the forces.
+#[must_use]
+pub fn forced_objects() -> Vec<ForcedObject> {
+    registry()
+        .values()
+        .map(|entry| ForcedObject {
+            object: Handle::from_raw(entry.object as vpi_sys::vpiHandle),
+            name: entry.name.clone(),
+            value: entry.value.clone(),
+            time: entry.time.clone(),
+            source: entry.source,
+        })
+        .collect()
+}
+
+/// Registers `cbForce` and `cbRelease` callbacks so that forces applied by
+/// HDL code appear in [`forced_objects`].
+///
+/// Calling it again while tracking has no effect.
+///
+/// # Errors
+///
+/// Fails when the simulator rejects either callback.
+pub fn track_forces(ctx: &SimContext) -> Result<(), Error> {
+    if TRACKING.with_borrow(|tracking| !tracking.is_empty()) {
+        return Ok(());
+    }
+    let force = CallbackBuilder::new(CbReason::Force)
+        .value_format(ValueType::BinStr)
+        .register_guarded(ctx, on_force)?;
+    let release = CallbackBuilder::new(CbReason::Release).register_guarded(ctx, on_release)?;
+    TRACKING.set(vec![force, release]);
+    Ok(())
+}
+
+/// Removes the callbacks registered by [`track_forces`].
+///
+/// Forces already listed stay listed until released.
+pub fn untrack_forces() {
+    let guards = TRACKING.take();
+    // The guards remove their callbacks outside the borrow.
+    drop(guards);
+}
+
+fn on_force(data: &CbData) {
+    if let CbEvent::Force { target, value } = data.event() {
+        record(data.context(), &target, value, ForceSource::Hdl);
+    }
+}
+
+fn on_release(data: &CbData) {
+    if let CbEvent::Release { target, .. } = data.event() {
+        forget(data.context(), &target);
+    }
+}
+
+fn record(ctx: &SimContext, object: &Handle, value: Option<Value>, source: ForceSource) {
+    let name = object.get_full_name(ctx);
+    let entry = Entry {
+        object: object.as_raw() as usize,
+        name: name.clone(),
+        value,
+        time: current_simulation_time(ctx),
+        source,
+        #[cfg(mock_backend)]
+        thread: std::thread::current().id(),
+    };
+    registry().insert(SignalKey::new(object, name), entry);
+}
+
+fn forget(ctx: &SimContext, object: &Handle) {
+    let key = SignalKey::new(object, object.get_full_name(ctx));
+    registry().remove(&key);
+}
+
+/// Removes the tracking callbacks and the forces recorded on the current
+/// thread.
+#[cfg(mock_backend)]
+pub(crate) fn reset_thread() {
+    untrack_forces();
+    let thread = std::thread::current().id();
+    registry().retain(|_, entry| entry.thread != thread);
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use std::sync::{Mutex, PoisonError};
+
+    use super::{forced_objects, track_forces, untrack_forces, ForceSource};
+    use crate::mock::MockSimulator;
+    use crate::{Handle, Time, Value, ValueType};
+
+    /// Serializes the tests, since the registry is shared by all threads.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn guards_release_their_force_on_drop() {
+        let _serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let bus = sim.add_net(&top, "bus", 4);
+        let q = sim.add_reg(&top, "q", 4);
+        assert!(sim.set_value(&bus, &Value::Int(3)));
+        sim.start();
+        sim.run_for(2);
+
+        let guard = bus.force(ctx, &Value::Int(5)).unwrap();
+        let kept = q.force(ctx, &Value::Int(9)).unwrap().keep();
+        assert_eq!(bus.get_value(ctx, ValueType::Int), Some(Value::Int(5)));
+
+        let forced = forced_objects();
+        assert_eq!(forced.len(), 2);
+        assert_eq!(forced[0].name.as_deref(), Some("tb.bus"));
+        assert_eq!(forced[0].value, Some(Value::Int(5)));
+        assert_eq!(forced[0].time, Time::Sim(2));
+        assert_eq!(forced[0].source, ForceSource::Rust);
+
+        drop(guard);
+        assert_eq!(bus.get_value(ctx, ValueType::Int), Some(Value::Int(3)));
+        assert_eq!(forced_objects().len(), 1);
+        assert!(kept.release(ctx).is_ok());
+        assert!(forced_objects().is_empty());
+        assert!(Handle::null().force(ctx, &Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn tracking_lists_forces_applied_by_hdl_code() {
+        let _serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 4);
+        sim.start();
+        track_forces(ctx).unwrap();
+        track_forces(ctx).unwrap();
+        assert_eq!(sim.active_callbacks(), 2);
+
+        // Raw forces stand in for HDL `force` and `release` statements.
+        let raw = |flag: u32| {
+            let mut value = vpi_sys::t_vpi_value {
+                format: vpi_sys::vpiIntVal as i32,
+                value: vpi_sys::t_vpi_value__bindgen_ty_1 { integer: 6 },
+            };
+            unsafe {
+                vpi_sys::vpi_put_value(
+                    q.as_raw(),
+                    &raw mut value,
+                    std::ptr::null_mut(),
+                    flag as i32,
+                );
+            }
+        };
+        raw(vpi_sys::vpiForceFlag);
+        let forced = forced_objects();
+        assert_eq!(forced.len(), 1);
+        assert_eq!(forced[0].source, ForceSource::Hdl);
+        assert_eq!(forced[0].value, Some(Value::BinStr("0110".into())));
+
+        raw(vpi_sys::vpiReleaseFlag);
+        assert!(forced_objects().is_empty());
+        untrack_forces();
+        assert_eq!(sim.active_callbacks(), 0);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::ObjectType;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::error::non_null;
+use crate::{Error, ObjectType, SimContext};
 use vpi_sys::{vpiHandle, PLI_INT32};
 
 /// Wrapper around a raw VPI object handle.
@@ -27,6 +32,7 @@ impl Drop for Handle {
     fn drop(&mut self) {
         #[cfg(feature = "release_handle")]
         if !self.is_null() {
+            crate::user_data::release_user_data(self.handle);
             unsafe {
                 vpi_sys::vpi_release_handle(self.handle);
             }
@@ -73,8 +79,8 @@ impl Handle {
     /// against the root (absolute hierarchical names). Returns a null handle
     /// when the name cannot be resolved.
     #[must_use]
-    pub fn handle_by_name(name: &str) -> Self {
-        Self::handle_by_name_and_scope(name, &Handle::null())
+    pub fn handle_by_name(ctx: &SimContext, name: &str) -> Self {
+        Self::handle_by_name_and_scope(ctx, name, &Handle::null())
     }
 
     /// Returns a handle located by name within a scope.
@@ -83,7 +89,7 @@ impl Handle {
     ///
     /// Returns a null handle when the object is not found.
     #[must_use]
-    pub fn handle_by_name_and_scope(name: &str, scope: &Handle) -> Self {
+    pub fn handle_by_name_and_scope(_ctx: &SimContext, name: &str, scope: &Handle) -> Self {
         let Ok(c_name) = std::ffi::CString::new(name) else {
             return Self::null();
         };
@@ -99,7 +105,7 @@ impl Handle {
 
     /// Returns an iterator handle for objects of `typ` under this handle.
     #[must_use]
-    pub fn iterator(&self, typ: ObjectType) -> HandleIterator {
+    pub fn iterator(&self, _ctx: &SimContext, typ: ObjectType) -> HandleIterator {
         let raw = unsafe { vpi_sys::vpi_iterate(typ as PLI_INT32, self.as_raw()) };
         HandleIterator {
             iter: Handle::from_raw(raw),
@@ -110,7 +116,7 @@ impl Handle {
     ///
     /// Returns a null handle when the relation is unavailable.
     #[must_use]
-    pub fn get(&self, typ: ObjectType) -> Self {
+    pub fn get(&self, _ctx: &SimContext, typ: ObjectType) -> Self {
         let handle = unsafe { vpi_sys::vpi_handle(typ as PLI_INT32, self.as_raw()) };
         Self::from_raw(handle)
     }
@@ -119,14 +125,80 @@ impl Handle {
     ///
     /// Returns a null handle when `index` is out of range.
     #[must_use]
-    pub fn handle_by_index(&self, index: i32) -> Self {
+    pub fn handle_by_index(&self, _ctx: &SimContext, index: i32) -> Self {
         let handle = unsafe { vpi_sys::vpi_handle_by_index(self.as_raw(), index) };
         Self::from_raw(handle)
     }
 
+    /// Fallible variant of [`Handle::handle_by_name`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the name cannot be resolved, with the simulator's
+    /// diagnostic attached when it reported one.
+    pub fn try_handle_by_name(ctx: &SimContext, name: &str) -> Result<Self, Error> {
+        Self::try_handle_by_name_and_scope(ctx, name, &Handle::null())
+    }
+
+    /// Fallible variant of [`Handle::handle_by_name_and_scope`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when `name` contains a NUL byte or cannot be resolved in `scope`.
+    pub fn try_handle_by_name_and_scope(
+        ctx: &SimContext,
+        name: &str,
+        scope: &Handle,
+    ) -> Result<Self, Error> {
+        if name.contains('\0') {
+            return Err(Error::new(
+                "vpi_handle_by_name",
+                format!("name {name:?} contains a NUL byte"),
+            ));
+        }
+        non_null(
+            ctx,
+            Self::handle_by_name_and_scope(ctx, name, scope),
+            "vpi_handle_by_name",
+            || format!("no object named `{name}`"),
+        )
+    }
+
+    /// Fallible variant of [`Handle::get`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the relation is unavailable for this handle.
+    pub fn try_get(&self, ctx: &SimContext, typ: ObjectType) -> Result<Self, Error> {
+        non_null(ctx, self.get(ctx, typ), "vpi_handle", || {
+            format!("no {typ:?} relation for this object")
+        })
+    }
+
+    /// Fallible variant of [`Handle::handle_by_index`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when `index` is out of range.
+    pub fn try_handle_by_index(&self, ctx: &SimContext, index: i32) -> Result<Self, Error> {
+        non_null(
+            ctx,
+            self.handle_by_index(ctx, index),
+            "vpi_handle_by_index",
+            || format!("no object at index {index}"),
+        )
+    }
+
     /// Iterates across multiple object kinds and flattens all resulting handles.
-    pub fn iterators<'a>(&'a self, typ: &'a [ObjectType]) -> impl Iterator<Item = Handle> + 'a {
-        typ.iter().copied().flat_map(move |t| self.iterator(t))
+    pub fn iterators<'a>(
+        &'a self,
+        ctx: &SimContext,
+        typ: &'a [ObjectType],
+    ) -> impl Iterator<Item = Handle> + 'a {
+        let ctx = *ctx;
+        typ.iter()
+            .copied()
+            .flat_map(move |t| self.iterator(&ctx, t))
     }
 
     /// Returns a related object handle selected by `typ` using two reference handles.
@@ -135,7 +207,7 @@ impl Handle {
     /// Returns a null handle when this handle or `other` is null, or when the
     /// relation is unavailable.
     #[must_use]
-    pub fn get_multi(&self, typ: ObjectType, other: &Handle) -> Self {
+    pub fn get_multi(&self, _ctx: &SimContext, typ: ObjectType, other: &Handle) -> Self {
         if self.is_null() || other.is_null() {
             return Self::null();
         }
@@ -152,7 +224,7 @@ impl Handle {
     /// when `indices` is empty, when the index count exceeds VPI limits, or
     /// when no object exists at the requested index tuple.
     #[must_use]
-    pub fn handle_by_multi_index(&self, indices: impl AsRef<[i32]>) -> Self {
+    pub fn handle_by_multi_index(&self, _ctx: &SimContext, indices: impl AsRef<[i32]>) -> Self {
         let indices = indices.as_ref();
         if self.is_null() || indices.is_empty() {
             return Self::null();
@@ -177,12 +249,20 @@ impl Handle {
     /// First traverses to `typ` via `vpi_handle`, then resolves a
     /// multidimensional element with `vpi_handle_by_multi_index`.
     #[must_use]
-    pub fn multi_handle_traversal(&self, typ: ObjectType, indices: impl AsRef<[i32]>) -> Self {
-        self.get(typ).handle_by_multi_index(indices)
+    pub fn multi_handle_traversal(
+        &self,
+        ctx: &SimContext,
+        typ: ObjectType,
+        indices: impl AsRef<[i32]>,
+    ) -> Self {
+        self.get(ctx, typ).handle_by_multi_index(ctx, indices)
     }
 }
 
 /// Iterator over VPI scan results from `vpi_iterate`/`vpi_scan`.
+///
+/// The simulator frees the iterator once it is exhausted. An iterator that
+/// is dropped early is released with the current [`ReleaseMethod`].
 pub struct HandleIterator {
     /// Internal iterator handle consumed by successive `vpi_scan` calls.
     pub(crate) iter: Handle,
@@ -207,3 +287,274 @@ impl Iterator for HandleIterator {
         }
     }
 }
+
+impl Drop for HandleIterator {
+    fn drop(&mut self) {
+        if !self.iter.is_null() {
+            release_object(self.iter.as_raw());
+            self.iter.clear();
+        }
+    }
+}
+
+/// VPI routine used to release handles owned by the plugin.
+///
+/// Used by [`OwnedHandle`] and for iterators dropped before exhaustion. The
+/// method is detected from the simulator product on first use and can be
+/// overridden with [`set_release_method`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReleaseMethod {
+    /// `vpi_release_handle`, introduced by IEEE 1800-2009.
+    ReleaseHandle,
+    /// `vpi_free_object`, the IEEE 1364 routine.
+    FreeObject,
+    /// Never release handles.
+    Keep,
+}
+
+impl ReleaseMethod {
+    /// Picks the release routine for a simulator product name.
+    ///
+    /// Icarus Verilog only guarantees `vpi_free_object`; other simulators
+    /// use `vpi_release_handle`.
+    #[must_use]
+    pub fn for_product(product: &str) -> Self {
+        if product.to_ascii_lowercase().contains("icarus") {
+            ReleaseMethod::FreeObject
+        } else {
+            ReleaseMethod::ReleaseHandle
+        }
+    }
+
+    fn detect(_ctx: &SimContext) -> Self {
+        let mut vlog_info = vpi_sys::t_vpi_vlog_info {
+            argc: 0,
+            argv: std::ptr::null_mut(),
+            version: std::ptr::null_mut(),
+            product: std::ptr::null_mut(),
+        };
+        unsafe { vpi_sys::vpi_get_vlog_info(&raw mut vlog_info) };
+        if vlog_info.product.is_null() {
+            return ReleaseMethod::ReleaseHandle;
+        }
+        let product = unsafe { std::ffi::CStr::from_ptr(vlog_info.product) };
+        Self::for_product(&product.to_string_lossy())
+    }
+}
+
+const RELEASE_UNSET: u8 = 0;
+
+static RELEASE_METHOD: AtomicU8 = AtomicU8::new(RELEASE_UNSET);
+
+/// Returns the routine used to release owned handles, detecting it from the
+/// simulator product on first use.
+#[must_use]
+pub fn release_method(ctx: &SimContext) -> ReleaseMethod {
+    match RELEASE_METHOD.load(Ordering::Relaxed) {
+        1 => ReleaseMethod::ReleaseHandle,
+        2 => ReleaseMethod::FreeObject,
+        3 => ReleaseMethod::Keep,
+        _ => {
+            let method = ReleaseMethod::detect(ctx);
+            set_release_method(method);
+            method
+        }
+    }
+}
+
+/// Overrides the routine used to release owned handles.
+pub fn set_release_method(method: ReleaseMethod) {
+    let raw = match method {
+        ReleaseMethod::ReleaseHandle => 1,
+        ReleaseMethod::FreeObject => 2,
+        ReleaseMethod::Keep => 3,
+    };
+    RELEASE_METHOD.store(raw, Ordering::Relaxed);
+}
+
+fn release_object(handle: vpiHandle) {
+    // Handles are not `Send`, so they are dropped on the simulator thread.
+    match release_method(SimContext::borrowed()) {
+        ReleaseMethod::ReleaseHandle => unsafe {
+            crate::user_data::release_user_data(handle);
+            vpi_sys::vpi_release_handle(handle);
+        },
+        ReleaseMethod::FreeObject => unsafe {
+            crate::user_data::release_user_data(handle);
+            vpi_sys::vpi_free_object(handle);
+        },
+        ReleaseMethod::Keep => {}
+    }
+}
+
+/// A [`Handle`] owned by the plugin, released when dropped.
+///
+/// The handle is released with the current [`ReleaseMethod`], independently
+/// of the `release_handle` feature. Use it for handles obtained by lookups
+/// and traversals that are kept for a while, so long-running simulations do
+/// not accumulate them. It dereferences to [`Handle`], but cannot be cloned.
+#[derive(Debug, Default)]
+pub struct OwnedHandle {
+    handle: Handle,
+}
+
+impl OwnedHandle {
+    /// Takes ownership of `handle`.
+    #[must_use]
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    /// Borrows the handle.
+    #[must_use]
+    pub fn borrow(&self) -> HandleRef<'_> {
+        HandleRef::from(&self.handle)
+    }
+
+    /// Gives up ownership without releasing the handle.
+    #[must_use]
+    pub fn into_inner(mut self) -> Handle {
+        let raw = self.handle.as_raw();
+        self.handle.clear();
+        Handle::from_raw(raw)
+    }
+}
+
+impl From<Handle> for OwnedHandle {
+    fn from(handle: Handle) -> Self {
+        Self::new(handle)
+    }
+}
+
+impl std::ops::Deref for OwnedHandle {
+    type Target = Handle;
+
+    fn deref(&self) -> &Handle {
+        &self.handle
+    }
+}
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        if !self.handle.is_null() {
+            release_object(self.handle.as_raw());
+            self.handle.clear();
+        }
+    }
+}
+
+/// A borrowed [`Handle`] that is valid for `'a` and never released.
+///
+/// Used for objects owned by the simulator, such as [`crate::CbData::obj`],
+/// which are only valid during the callback. It dereferences to [`Handle`],
+/// so `clone()` returns a plain [`Handle`] to keep beyond that.
+pub struct HandleRef<'a> {
+    handle: ManuallyDrop<Handle>,
+    _borrow: PhantomData<&'a Handle>,
+}
+
+impl HandleRef<'_> {
+    /// Borrows a raw handle provided by the simulator.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be null or stay valid for the chosen lifetime.
+    #[must_use]
+    pub unsafe fn from_raw(raw: vpiHandle) -> Self {
+        Self {
+            handle: ManuallyDrop::new(Handle::from_raw(raw)),
+            _borrow: PhantomData,
+        }
+    }
+}
+
+impl<'a> From<&'a Handle> for HandleRef<'a> {
+    fn from(handle: &'a Handle) -> Self {
+        unsafe { Self::from_raw(handle.as_raw()) }
+    }
+}
+
+impl std::ops::Deref for HandleRef<'_> {
+    type Target = Handle;
+
+    fn deref(&self) -> &Handle {
+        &self.handle
+    }
+}
+
+impl std::fmt::Debug for HandleRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("HandleRef").field(&*self.handle).finish()
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::{release_method, OwnedHandle, ReleaseMethod};
+    use crate::mock::MockSimulator;
+    use crate::{chk_error, Handle, ObjectType, SimContext};
+
+    fn is_live_iterator(ctx: &SimContext, raw: vpi_sys::vpiHandle) -> bool {
+        let _ = unsafe { vpi_sys::vpi_scan(raw) };
+        chk_error(ctx).is_none()
+    }
+
+    #[test]
+    fn partially_consumed_iterator_is_released() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let _ = sim.add_reg(&top, "a", 1);
+        let _ = sim.add_reg(&top, "b", 1);
+        let _ = sim.add_reg(&top, "c", 1);
+
+        let mut regs = top.iterator(ctx, ObjectType::Reg);
+        let raw = regs.iter.as_raw();
+        assert_eq!(
+            regs.next().and_then(|r| r.get_name(ctx)).as_deref(),
+            Some("a")
+        );
+        assert!(is_live_iterator(ctx, raw));
+        drop(regs);
+        assert!(!is_live_iterator(ctx, raw));
+        assert_eq!(
+            top.iterator(ctx, ObjectType::Reg)
+                .nth(2)
+                .unwrap()
+                .get_name(ctx)
+                .as_deref(),
+            Some("c")
+        );
+    }
+
+    #[test]
+    fn owned_handle_is_released_on_drop() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let _ = sim.add_reg(&top, "a", 1);
+        assert_eq!(release_method(ctx), ReleaseMethod::ReleaseHandle);
+
+        let raw = unsafe { vpi_sys::vpi_iterate(vpi_sys::vpiReg as i32, top.as_raw()) };
+        let owned = OwnedHandle::new(Handle::from_raw(raw));
+        assert_eq!(owned.borrow().as_raw(), raw);
+        drop(owned);
+        assert!(!is_live_iterator(ctx, raw));
+
+        let raw = unsafe { vpi_sys::vpi_iterate(vpi_sys::vpiReg as i32, top.as_raw()) };
+        let handle = OwnedHandle::from(Handle::from_raw(raw)).into_inner();
+        assert!(is_live_iterator(ctx, handle.as_raw()));
+    }
+
+    #[test]
+    fn release_method_follows_product() {
+        assert_eq!(
+            ReleaseMethod::for_product("Icarus Verilog"),
+            ReleaseMethod::FreeObject
+        );
+        assert_eq!(
+            ReleaseMethod::for_product("Verilator"),
+            ReleaseMethod::ReleaseHandle
+        );
+    }
+}
@@ -7,6 +7,10 @@
 //! | `bigint` | Enables conversion between [`LogicVec`] and arbitrary-precision integers using [`num_bigint::BigInt`] and [`num_bigint::BigUint`]. | No |
 //! | `cb_info` | Uses `vpi_get_cb_info` when removing callbacks. | Yes |
 //! | `dynamic` | Enables runtime VPI symbol lookup via `vpi-shim` on Windows and macOS, allowing plugins to build without directly linking to a simulator library. | No |
+//! | `fst` | Enables [`wave::fst`], a compressed FST waveform writer, using `flate2`. | No |
+//! | `macros` | Enables the [`macro@systf`] attribute for declaring typed system tasks and functions. | No |
+//! | `mock` | Provides [`mock::MockSimulator`], an in-process fake simulator implementing the VPI entry points for unit-testing plugins without a simulator. Not for plugins loaded by a real simulator, and unavailable together with `dynamic` on Windows and macOS. | No |
+//! | `regex` | Enables [`query::Query::regex`] and `/regex/` patterns in query strings, using the `regex` crate. | No |
 //! | `release_handle` | Calls `vpi_release_handle` when dropping a [`Handle`]. | No |
 //! | `sv`     | Enables SystemVerilog VPI extensions (types, callbacks, and properties defined in IEEE 1800). | No |
 //! | `value_array` | Enables support for VPI array values via `vpi_get_value_array` and `vpi_put_value_array`. Otherwise the related functions are still available, but use repeated calls to the scalar `vpi_get_value` and `vpi_put_value` functions. | No |
@@ -16,44 +20,73 @@
 #[cfg(all(feature = "dynamic", any(target_os = "windows", target_os = "macos")))]
 use vpi_shim as _;
 
+// Lets `::vpi` paths emitted by the proc macros resolve in this crate's tests.
+#[cfg(all(test, feature = "macros"))]
+extern crate self as vpi;
+
 #[macro_use]
 mod macros;
 
+mod attribute;
 mod callback;
+mod checkpoint;
+pub mod connectivity;
+mod context;
 mod control;
 mod delays;
 mod error;
+pub mod export;
+mod force;
 mod handle;
+pub mod lint;
 mod logic;
 mod mcd;
+#[cfg(mock_backend)]
+pub mod mock;
+pub mod model;
 mod object;
+mod panic;
 mod property;
+pub mod query;
 mod simulator;
+pub mod stats;
 mod systf;
 mod test_vpi_stubs;
+pub mod testbench;
 mod time;
+mod user_data;
 mod value;
+mod watch;
+pub mod wave;
 
 use std::ffi::CString;
 
+pub use attribute::*;
 pub use callback::*;
+pub use checkpoint::*;
+pub use context::*;
 pub use control::*;
 pub use delays::*;
 pub use error::*;
+pub use force::*;
 pub use handle::*;
 pub use logic::*;
 pub use mcd::*;
 pub use object::*;
+pub use panic::*;
 pub use property::*;
 pub use simulator::*;
 pub use systf::*;
 pub use time::*;
 pub use value::*;
+#[cfg(feature = "macros")]
+pub use vpi_macros::systf;
+pub use watch::*;
 
 /// Prints a message through the simulator's `vpi_printf`.
 ///
 /// A trailing newline is appended automatically.
-pub fn printf(msg: impl AsRef<str>) {
+pub fn printf(_ctx: &SimContext, msg: impl AsRef<str>) {
     static FMT: &[u8] = b"%s\n\0";
     let cstr = string_to_ascii_cstring(msg);
     unsafe {
@@ -67,7 +100,7 @@ pub fn printf(msg: impl AsRef<str>) {
 /// Flushes the simulator's default output streams via `vpi_flush`.
 ///
 /// Returns `Ok(())` on success (`0`) and `Err(code)` otherwise.
-pub fn flush() -> Result<(), i32> {
+pub fn flush(_ctx: &SimContext) -> Result<(), i32> {
     let code = unsafe { vpi_sys::vpi_flush() };
     if code == 0 {
         Ok(())
@@ -79,8 +112,8 @@ pub fn flush() -> Result<(), i32> {
 /// `format!`-style wrapper around [`printf`].
 #[macro_export]
 macro_rules! printf {
-    ($($arg:tt)*) => {{
-        $crate::printf(&format!($($arg)*));
+    ($ctx:expr, $($arg:tt)*) => {{
+        $crate::printf($ctx, &format!($($arg)*));
     }}
 }
 
@@ -0,0 +1,582 @@
+//! Design checks run on the elaborated design.
+//!
+//! A [`Linter`] walks the instance tree and reports [`Finding`]s for the
+//! enabled [`Rule`]s. It can run at any time, but is meant to be registered
+//! for `cbEndOfCompile` or `cbStartOfSimulation` with
+//! [`Linter::register`], which prints the findings with their source
+//! location and hands them over, for example to end the run on violations.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::lint::{Linter, Rule};
+//! use vpi::{CbReason, SimContext};
+//!
+//! fn start(ctx: &SimContext) {
+//!     Linter::new()
+//!         .disable(Rule::DefaultParameter)
+//!         .register(ctx, CbReason::StartOfSimulation, |ctx, findings| {
+//!             if !findings.is_empty() {
+//!                 vpi::control(ctx, vpi::Control::Finish);
+//!             }
+//!         })
+//!         .unwrap()
+//!         .detach();
+//! }
+//! ```
+
+use std::fmt;
+
+use crate::connectivity::{neighbours, Trace};
+use crate::model::{Module, Net, TypedHandle};
+use crate::query::instance_children;
+use crate::{
+    CallbackBuilder, CallbackGuard, CbReason, ConstType, Direction, Error, Handle, NetType,
+    ObjectType, Property, SimContext, ValueType,
+};
+
+/// A check performed by a [`Linter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    /// Input ports of instances that are not connected.
+    UnconnectedInput,
+    /// Nets of a type without wired resolution driven from several places.
+    MultipleDrivers,
+    /// Nets with loads but no driver.
+    UndrivenNet,
+    /// Ports whose connection differs in width on both sides.
+    WidthMismatch,
+    /// Nets declared implicitly.
+    ImplicitNet,
+    /// Parameters of instances left at their declared default.
+    ///
+    /// Relies on the simulator reporting the declaration as `vpiRhs` of the
+    /// `vpiParamAssign`; instances of top modules are not checked.
+    DefaultParameter,
+}
+
+impl Rule {
+    /// All rules, in the order they are checked.
+    pub const ALL: [Rule; 6] = [
+        Rule::UnconnectedInput,
+        Rule::MultipleDrivers,
+        Rule::UndrivenNet,
+        Rule::WidthMismatch,
+        Rule::ImplicitNet,
+        Rule::DefaultParameter,
+    ];
+
+    /// Returns the name used when reporting findings.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnconnectedInput => "unconnected-input",
+            Rule::MultipleDrivers => "multiple-drivers",
+            Rule::UndrivenNet => "undriven-net",
+            Rule::WidthMismatch => "width-mismatch",
+            Rule::ImplicitNet => "implicit-net",
+            Rule::DefaultParameter => "default-parameter",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A violation of a [`Rule`].
+#[derive(Debug, Clone)]
+pub struct Finding {
+    /// The violated rule.
+    pub rule: Rule,
+    /// The offending port, net or parameter.
+    pub object: Handle,
+    /// Full hierarchical name of the object.
+    pub name: String,
+    /// Source file of the object, or of its module.
+    pub file: Option<String>,
+    /// Source line of the object, or of its module.
+    pub line: Option<u32>,
+    /// Description of the violation.
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    /// Formats as `file:line: rule: message`, or starting with the object
+    /// name when the location is unknown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{file}:{line}: ")?,
+            (Some(file), None) => write!(f, "{file}: ")?,
+            _ => write!(f, "{}: ", self.name)?,
+        }
+        write!(f, "{}: {}", self.rule, self.message)
+    }
+}
+
+/// Runs design checks; see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Linter {
+    rules: Vec<Rule>,
+    scope: Option<Handle>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// Creates a linter checking all rules on the whole design.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            rules: Rule::ALL.to_vec(),
+            scope: None,
+        }
+    }
+
+    /// Checks only `rules`.
+    #[must_use]
+    pub fn rules(mut self, rules: &[Rule]) -> Self {
+        self.rules = rules.to_vec();
+        self
+    }
+
+    /// Stops checking `rule`.
+    #[must_use]
+    pub fn disable(mut self, rule: Rule) -> Self {
+        self.rules.retain(|&enabled| enabled != rule);
+        self
+    }
+
+    /// Checks only the instances below and including `scope`.
+    #[must_use]
+    pub fn scope(mut self, scope: &Handle) -> Self {
+        self.scope = Some(scope.clone());
+        self
+    }
+
+    /// Runs the checks and returns the findings, ordered by instance.
+    #[must_use]
+    pub fn run(&self, ctx: &SimContext) -> Vec<Finding> {
+        let tops: Vec<Module> = match &self.scope {
+            Some(scope) => scope.downcast::<Module>(ctx).into_iter().collect(),
+            None => Handle::null()
+                .iterator(ctx, ObjectType::Module)
+                .filter_map(|module| module.downcast::<Module>(ctx))
+                .collect(),
+        };
+        let mut findings = Vec::new();
+        let mut pending = tops;
+        pending.reverse();
+        while let Some(module) = pending.pop() {
+            // Generate scopes and named blocks are checked with their module.
+            let (modules, scopes) = instance_children(ctx, module.handle());
+            self.check(ctx, &module, module.handle(), &mut findings);
+            for scope in &scopes {
+                self.check(ctx, &module, scope, &mut findings);
+            }
+            let mut children: Vec<Module> = modules
+                .into_iter()
+                .filter_map(|child| child.downcast::<Module>(ctx))
+                .collect();
+            children.reverse();
+            pending.extend(children);
+        }
+        findings
+    }
+
+    /// Registers a one-shot callback for `reason` that runs the checks,
+    /// prints the findings with [`report`] and passes them to `on_findings`
+    /// together with the context of the callback.
+    ///
+    /// `reason` is typically [`CbReason::EndOfCompile`] or
+    /// [`CbReason::StartOfSimulation`]. Dropping the guard before the
+    /// callback has run removes it.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator rejects the callback.
+    pub fn register(
+        self,
+        ctx: &SimContext,
+        reason: CbReason,
+        on_findings: impl FnOnce(&SimContext, &[Finding]) + 'static,
+    ) -> Result<CallbackGuard, Error> {
+        CallbackBuilder::new(reason).register_once(ctx, move |data| {
+            let ctx = data.context();
+            let findings = self.run(ctx);
+            report(ctx, &findings);
+            on_findings(ctx, &findings);
+        })
+    }
+
+    fn enabled(&self, rule: Rule) -> bool {
+        self.rules.contains(&rule)
+    }
+
+    /// Checks the objects declared in `scope`, which is `module` itself or
+    /// one of its generate scopes and named blocks.
+    fn check(
+        &self,
+        ctx: &SimContext,
+        module: &Module,
+        scope: &Handle,
+        findings: &mut Vec<Finding>,
+    ) {
+        let top = module.is_top(ctx);
+        let own = scope == module.handle();
+        let mut add = |rule: Rule, object: &Handle, message: String| {
+            let name = object.get_full_name(ctx).unwrap_or_else(|| {
+                format!(
+                    "{}.{}",
+                    scope.get_full_name(ctx).unwrap_or_default(),
+                    object.get_name(ctx).unwrap_or_default()
+                )
+            });
+            let (file, line) = match object.get_str(ctx, Property::File) {
+                Some(file) => (Some(file), object.get_u32(ctx, Property::LineNo)),
+                None => (module.file(ctx), module.line(ctx)),
+            };
+            findings.push(Finding {
+                rule,
+                object: object.clone(),
+                name,
+                file,
+                line,
+                message,
+            });
+        };
+
+        for port in module.ports(ctx).filter(|_| own) {
+            let port = port.handle();
+            let name = port.get_name(ctx).unwrap_or_default();
+            let outer = port.get(ctx, ObjectType::HighConn);
+            if outer.is_null() {
+                if !top
+                    && self.enabled(Rule::UnconnectedInput)
+                    && port.get_direction(ctx) == Some(Direction::Input)
+                {
+                    add(
+                        Rule::UnconnectedInput,
+                        port,
+                        format!("input port `{name}` is not connected"),
+                    );
+                }
+                continue;
+            }
+            if self.enabled(Rule::WidthMismatch) {
+                let inner = port.get(ctx, ObjectType::LowConn);
+                let inner_size = if inner.is_null() {
+                    port.get_u32(ctx, Property::Size)
+                } else {
+                    inner.get_u32(ctx, Property::Size)
+                };
+                if let (Some(outer_size), Some(inner_size)) =
+                    (outer.get_u32(ctx, Property::Size), inner_size)
+                {
+                    if outer_size != inner_size {
+                        add(
+                            Rule::WidthMismatch,
+                            port,
+                            format!(
+                                "port `{name}` is {inner_size} bits wide but connected to {outer_size} bits"
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
+        for typed in scope
+            .iterator(ctx, ObjectType::Net)
+            .filter_map(|net| net.downcast::<Net>(ctx))
+        {
+            let net = typed.handle();
+            let name = net.get_name(ctx).unwrap_or_default();
+            if self.enabled(Rule::ImplicitNet)
+                && net.get_bool(ctx, Property::ImplicitDecl) == Some(true)
+            {
+                add(
+                    Rule::ImplicitNet,
+                    net,
+                    format!("net `{name}` is declared implicitly"),
+                );
+            }
+            if !self.enabled(Rule::MultipleDrivers) && !self.enabled(Rule::UndrivenNet) {
+                continue;
+            }
+            let drivers = neighbours(ctx, net, Trace::FanIn);
+            if self.enabled(Rule::MultipleDrivers) && !is_resolved(ctx, &typed) {
+                let conflicting = conflicting_drivers(ctx, net, &drivers);
+                if conflicting > 1 {
+                    add(
+                        Rule::MultipleDrivers,
+                        net,
+                        format!("net `{name}` has {conflicting} drivers of the same bits"),
+                    );
+                }
+            }
+            if drivers.is_empty()
+                && self.enabled(Rule::UndrivenNet)
+                && !neighbours(ctx, net, Trace::FanOut).is_empty()
+            {
+                add(
+                    Rule::UndrivenNet,
+                    net,
+                    format!("net `{name}` has loads but no driver"),
+                );
+            }
+        }
+
+        if own && !top && self.enabled(Rule::DefaultParameter) {
+            for assign in module.handle().iterator(ctx, ObjectType::ParamAssign) {
+                let parameter = assign.get(ctx, ObjectType::Lhs);
+                if parameter.get_bool(ctx, Property::LocalParam) == Some(true)
+                    || !same_value(ctx, &parameter, &assign.get(ctx, ObjectType::Rhs))
+                {
+                    continue;
+                }
+                let name = parameter.get_name(ctx).unwrap_or_default();
+                add(
+                    Rule::DefaultParameter,
+                    &parameter,
+                    format!("parameter `{name}` is left at its default"),
+                );
+            }
+        }
+    }
+}
+
+/// Prints findings with `vpi_printf`, one per line.
+pub fn report(ctx: &SimContext, findings: &[Finding]) {
+    for finding in findings {
+        crate::printf!(ctx, "{finding}");
+    }
+}
+
+/// Returns `true` for net types that combine several drivers by design.
+fn is_resolved(ctx: &SimContext, net: &Net) -> bool {
+    matches!(
+        net.net_type(ctx),
+        Some(
+            NetType::Wand
+                | NetType::Wor
+                | NetType::TriAnd
+                | NetType::TriOr
+                | NetType::TriReg
+                | NetType::Supply0
+                | NetType::Supply1
+        )
+    )
+}
+
+/// Counts the drivers of `net` that write bits also written by another
+/// driver, so nets assigned bit by bit are not reported.
+fn conflicting_drivers(ctx: &SimContext, net: &Handle, drivers: &[Handle]) -> usize {
+    let bits: Vec<_> = drivers
+        .iter()
+        .map(|driver| driven_bits(ctx, net, driver))
+        .collect();
+    let overlap = |a: Option<(i64, i64)>, b: Option<(i64, i64)>| match (a, b) {
+        (Some((a_low, a_high)), Some((b_low, b_high))) => a_low <= b_high && b_low <= a_high,
+        _ => true,
+    };
+    (0..bits.len())
+        .filter(|&i| (0..bits.len()).any(|j| j != i && overlap(bits[i], bits[j])))
+        .count()
+}
+
+/// Returns the lowest and highest bit of `net` written by `driver`, or
+/// `None` if it writes the whole net or bits that cannot be told.
+fn driven_bits(ctx: &SimContext, net: &Handle, driver: &Handle) -> Option<(i64, i64)> {
+    let target = match driver.get_raw_property(ctx, Property::Type)? as u32 {
+        vpi_sys::vpiContAssign => driver.get(ctx, ObjectType::Lhs),
+        vpi_sys::vpiGate | vpi_sys::vpiSwitch | vpi_sys::vpiUdp => driver
+            .iterator(ctx, ObjectType::PrimTerm)
+            .find(|term| !matches!(term.get_direction(ctx), Some(Direction::Input)))?
+            .get(ctx, ObjectType::Expr),
+        _ => return None,
+    };
+    if target.get(ctx, ObjectType::Parent) != *net {
+        return None;
+    }
+    match target.get_raw_property(ctx, Property::Type)? as u32 {
+        vpi_sys::vpiBitSelect | vpi_sys::vpiNetBit => {
+            let index = constant(ctx, &target.get(ctx, ObjectType::Index))?;
+            Some((index, index))
+        }
+        vpi_sys::vpiPartSelect => {
+            let left = constant(ctx, &target.get(ctx, ObjectType::LeftRange))?;
+            let right = constant(ctx, &target.get(ctx, ObjectType::RightRange))?;
+            Some((left.min(right), left.max(right)))
+        }
+        _ => None,
+    }
+}
+
+fn constant(ctx: &SimContext, expr: &Handle) -> Option<i64> {
+    match expr.get_value(ctx, ValueType::Int)? {
+        crate::Value::Int(value) => Some(i64::from(value)),
+        _ => None,
+    }
+}
+
+/// Compares the values of a parameter and its declared default.
+fn same_value(ctx: &SimContext, parameter: &Handle, default: &Handle) -> bool {
+    let format = match parameter.get_const_type(ctx) {
+        Some(ConstType::Real) => ValueType::Real,
+        Some(ConstType::String) => ValueType::String,
+        _ => ValueType::BinStr,
+    };
+    match (
+        parameter.get_value(ctx, format),
+        default.get_value(ctx, format),
+    ) {
+        (Some(crate::Value::BinStr(a)), Some(crate::Value::BinStr(b))) => {
+            // Sized and unsized constants differ in leading zeros only.
+            a.trim_start_matches('0') == b.trim_start_matches('0')
+        }
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::{Linter, Rule};
+    use crate::mock::MockSimulator;
+    use crate::{CbReason, Direction, Handle, PrimType, Value};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn rules_report_their_violations() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        let _ = sim.add_parameter(&tb, "SEED", &Value::Int(1), false);
+        let clk = sim.add_reg(&tb, "clk", 1);
+        let bus = sim.add_net(&tb, "bus", 4);
+        let floating = sim.add_net(&tb, "floating", 1);
+        let typo = sim.add_net(&tb, "tpyo", 1);
+        sim.set_implicit(&typo, true);
+
+        let dut = sim.add_module(&tb, "dut", "dut");
+        sim.set_location(&dut, "dut.v", 1);
+        let width = sim.add_parameter(&dut, "WIDTH", &Value::Int(8), false);
+        let depth = sim.add_parameter(&dut, "DEPTH", &Value::Int(4), false);
+        assert!(sim.override_parameter(&width, &Value::Int(4)));
+        let _ = sim.add_parameter(&dut, "LAST", &Value::Int(3), true);
+        let d_clk = sim.add_net(&dut, "clk", 1);
+        let d_rst = sim.add_net(&dut, "rst", 1);
+        let d_data = sim.add_net(&dut, "data", 8);
+        let port = sim.add_port(&dut, "clk", Direction::Input, &d_clk);
+        assert!(sim.connect_port(&port, &clk));
+        let rst = sim.add_port(&dut, "rst", Direction::Input, &d_rst);
+        sim.set_location(&rst, "dut.v", 2);
+        let port = sim.add_port(&dut, "data", Direction::Input, &d_data);
+        assert!(sim.connect_port(&port, &bus));
+
+        // Two gates drive `y`; `floating` is read but never driven.
+        let y = sim.add_net(&dut, "y", 1);
+        let _ = sim.add_gate(&dut, "g1", PrimType::And, &y, &[&d_clk, &d_rst]);
+        let _ = sim.add_gate(&dut, "g2", PrimType::Or, &y, &[&d_clk]);
+        let _ = sim.add_cont_assign(&tb, &bus, &[&floating]);
+
+        // `z` is assigned bit by bit, `w` has overlapping part selects.
+        let z = sim.add_net(&dut, "z", 2);
+        for bit in 0..2 {
+            let _ = sim.add_cont_assign(&dut, &sim.add_select(&z, bit, bit), &[&d_clk]);
+        }
+        let w = sim.add_net(&dut, "w", 4);
+        let _ = sim.add_cont_assign(&dut, &sim.add_select(&w, 3, 1), &[&d_clk]);
+        let _ = sim.add_cont_assign(&dut, &sim.add_select(&w, 1, 0), &[&d_rst]);
+
+        let findings = Linter::new().run(ctx);
+        let found: Vec<_> = findings
+            .iter()
+            .map(|finding| (finding.rule, finding.name.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Rule::UndrivenNet, "tb.floating"),
+                (Rule::ImplicitNet, "tb.tpyo"),
+                (Rule::UnconnectedInput, "tb.dut.rst"),
+                (Rule::WidthMismatch, "tb.dut.data"),
+                (Rule::MultipleDrivers, "tb.dut.y"),
+                (Rule::MultipleDrivers, "tb.dut.w"),
+                (Rule::DefaultParameter, "tb.dut.DEPTH"),
+            ]
+        );
+        assert_eq!(
+            findings[2].to_string(),
+            "dut.v:2: unconnected-input: input port `rst` is not connected"
+        );
+        assert_eq!(
+            findings[5].message,
+            "net `w` has 2 drivers of the same bits"
+        );
+        assert_eq!(findings[6].object, depth);
+        assert_eq!(
+            findings[3].message,
+            "port `data` is 8 bits wide but connected to 4 bits"
+        );
+
+        let only = Linter::new()
+            .rules(&[Rule::MultipleDrivers, Rule::ImplicitNet])
+            .disable(Rule::ImplicitNet)
+            .scope(&dut)
+            .run(ctx);
+        assert_eq!(only.len(), 2);
+        assert_eq!(only[0].rule, Rule::MultipleDrivers);
+
+        let reported = Rc::new(Cell::new(0));
+        let count = reported.clone();
+        let _guard = Linter::new()
+            .rules(&[Rule::ImplicitNet])
+            .register(ctx, CbReason::StartOfSimulation, move |_, findings| {
+                count.set(findings.len());
+            })
+            .unwrap();
+        let _ = sim.take_output();
+        sim.start();
+        assert_eq!(reported.get(), 1);
+        assert_eq!(
+            sim.take_output(),
+            "tb.tpyo: implicit-net: net `tpyo` is declared implicitly\n"
+        );
+    }
+
+    #[test]
+    fn generate_scopes_are_checked() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        let lane = sim.add_gen_scope(&tb, "lane[0]");
+        let typo = sim.add_net(&lane, "vlaid", 1);
+        sim.set_implicit(&typo, true);
+        let leaf = sim.add_module(&lane, "u", "leaf");
+        let d = sim.add_net(&leaf, "d", 1);
+        let _ = sim.add_port(&leaf, "d", Direction::Input, &d);
+
+        let found: Vec<_> = Linter::new()
+            .rules(&[Rule::ImplicitNet, Rule::UnconnectedInput])
+            .run(ctx)
+            .into_iter()
+            .map(|finding| (finding.rule, finding.name))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Rule::ImplicitNet, "tb.lane[0].vlaid".to_string()),
+                (Rule::UnconnectedInput, "tb.lane[0].u.d".to_string()),
+            ]
+        );
+    }
+}
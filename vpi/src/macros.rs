@@ -2,6 +2,9 @@
 ///
 /// Provide one or more `extern "C" fn()` routine names. The macro emits a
 /// null-terminated function pointer table as expected by common simulators.
+/// The table starts with a routine that records the simulator thread, so
+/// [`SimContext::current`](crate::SimContext::current) is available in the
+/// listed routines.
 ///
 /// # Example
 /// ```ignore
@@ -15,7 +18,8 @@
 macro_rules! startup_routines {
     ($($func:expr),* $(,)?) => {
         #[unsafe(no_mangle)]
-        pub static vlog_startup_routines: [Option<extern "C" fn()>; $crate::count_idents!($($func),*) + 1] = [
+        pub static vlog_startup_routines: [Option<extern "C" fn()>; $crate::count_idents!($($func),*) + 2] = [
+            Some($crate::enter_simulator_thread),
             $(Some($func),)*
             None,
         ];
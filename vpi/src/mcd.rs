@@ -1,3 +1,4 @@
+use crate::SimContext;
 use std::ffi::CString;
 
 /// Multi-channel descriptor used by VPI for output streams.
@@ -11,14 +12,14 @@ pub static MCD_STDOUT: MCD = MCD { mask: 0x1 };
 
 impl MCD {
     /// Creates a new MCD for the given output file name.
-    pub fn new(filename: impl AsRef<str>) -> Self {
+    pub fn new(_ctx: &SimContext, filename: impl AsRef<str>) -> Self {
         let c_filename = CString::new(filename.as_ref()).unwrap();
         let mask = unsafe { vpi_sys::vpi_mcd_open(c_filename.as_ptr().cast_mut()) };
         Self { mask }
     }
 
     /// Write a message to the MCD.
-    pub fn write(&self, msg: impl AsRef<str>) {
+    pub fn write(&self, _ctx: &SimContext, msg: impl AsRef<str>) {
         let cstr = CString::new(msg.as_ref()).unwrap();
         unsafe {
             vpi_sys::vpi_mcd_printf(self.mask, cstr.as_ptr().cast_mut());
@@ -26,19 +27,19 @@ impl MCD {
     }
 
     /// Write a message with a newline to the MCD.
-    pub fn writeln(&self, msg: impl AsRef<str>) {
-        self.write(format!("{}\n", msg.as_ref()));
+    pub fn writeln(&self, ctx: &SimContext, msg: impl AsRef<str>) {
+        self.write(ctx, format!("{}\n", msg.as_ref()));
     }
 
     /// Closes this MCD stream in the simulator.
-    pub fn close(&self) {
+    pub fn close(&self, _ctx: &SimContext) {
         unsafe {
             vpi_sys::vpi_mcd_close(self.mask);
         }
     }
 
     /// Flushes any buffered MCD output.
-    pub fn flush(&self) {
+    pub fn flush(&self, _ctx: &SimContext) {
         unsafe {
             vpi_sys::vpi_mcd_flush(self.mask);
         }
@@ -46,7 +47,7 @@ impl MCD {
 
     #[must_use]
     /// Get the filename associated with this MCD, if any.
-    pub fn file_name(&self) -> Option<String> {
+    pub fn file_name(&self, _ctx: &SimContext) -> Option<String> {
         let ptr = unsafe { vpi_sys::vpi_mcd_name(self.mask) };
         if ptr.is_null() {
             None
@@ -71,7 +72,7 @@ impl std::ops::BitOr for MCD {
 /// Formats and writes a line to an [`MCD`].
 #[macro_export]
 macro_rules! mcd_println {
-    ($mcd:expr, $($arg:tt)*) => {{
-        $mcd.writeln(&format!($($arg)*));
+    ($ctx:expr, $mcd:expr, $($arg:tt)*) => {{
+        $mcd.writeln($ctx, &format!($($arg)*));
     }}
 }
@@ -0,0 +1,4368 @@
+//! In-process mock simulator for unit-testing VPI plugins.
+//!
+//! Enabled with the `mock` feature. This module implements the `vpi_*` entry
+//! points of `vpi_user.h` over an in-memory design model, so plugin code that
+//! uses [`Handle`], [`register_cb`](crate::register_cb),
+//! [`register_systf`](crate::register_systf),
+//! [`get_systf_args`](crate::get_systf_args) and friends can be exercised from
+//! ordinary `cargo test` runs without a simulator installed.
+//!
+//! The model covers modules, nets, regs, integer and real variables,
+//! memories, ports and parameters. A time wheel orders time-based callbacks
+//! and scheduled value updates within each time step, and value-change,
+//! force and release callbacks fire when stored values change.
+//!
+//! Simulator state is thread-local: every test thread has its own design,
+//! time wheel and callback table, and [`MockSimulator::new`] resets the state
+//! of the current thread.
+//!
+//! Do not enable this feature in plugins loaded by a real simulator, since the
+//! exported symbols would shadow the simulator's own VPI implementation.
+//!
+//! # Example
+//!
+//! ```
+//! use vpi::mock::MockSimulator;
+//! use vpi::{register_cb_with_time, CbReason, Handle, Time, Value, ValueType};
+//!
+//! let sim = MockSimulator::new();
+//! let ctx = sim.context();
+//! let top = sim.add_module(&Handle::null(), "tb", "tb");
+//! let count = sim.add_reg(&top, "count", 8);
+//!
+//! let _cb = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(5), |data| {
+//!     let ctx = data.context();
+//!     let count = Handle::handle_by_name(ctx, "tb.count");
+//!     let _ = count.put_value(ctx, &Value::Int(42));
+//! });
+//! sim.run();
+//!
+//! assert_eq!(sim.time(), 5);
+//! assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(42)));
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ffi::{c_void, CStr, CString};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use num_traits::FromPrimitive;
+use vpi_sys::{vpiHandle, PLI_BYTE8, PLI_INT32, PLI_UINT32};
+
+use crate::{
+    scalar_vector_to_vecval, Direction, Handle, LogicVal, LogicVec, PrimType, Severity, SimContext,
+    Value,
+};
+
+type CbRoutine = unsafe extern "C" fn(*mut vpi_sys::t_cb_data) -> PLI_INT32;
+
+/// Product name reported by `vpi_get_vlog_info` and `vpi_chk_error`.
+const PRODUCT: &CStr = c"vpi-mock";
+/// Version string reported by `vpi_get_vlog_info`.
+const VERSION: &[u8] = concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes();
+/// Error code reported through `vpi_chk_error`.
+const ERROR_CODE: &CStr = c"MOCK";
+/// Time unit and precision (as powers of ten) of modules without a timescale.
+const DEFAULT_TIMESCALE: i32 = -9;
+
+thread_local! {
+    static SIM: RefCell<SimState> = RefCell::new(SimState::default());
+}
+
+/// Object identifiers are shared across threads so raw handle values stay
+/// unique process-wide; some crate registries are keyed by handle address.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+fn with_sim<R>(f: impl FnOnce(&mut SimState) -> R) -> R {
+    SIM.with(|sim| f(&mut sim.borrow_mut()))
+}
+
+/// Runs `f` as a VPI entry point, clearing the error record of the previous call.
+fn api<R>(f: impl FnOnce(&mut SimState) -> R) -> R {
+    with_sim(|s| {
+        s.error = None;
+        f(s)
+    })
+}
+
+fn raw(id: usize) -> vpiHandle {
+    std::ptr::without_provenance_mut(id)
+}
+
+fn raw_or_null(id: Option<usize>) -> vpiHandle {
+    id.map_or(std::ptr::null_mut(), raw)
+}
+
+/// Argument passed to a mocked system task or function call.
+#[derive(Debug, Clone)]
+pub enum MockArg {
+    /// A literal value, exposed to the plugin as a `vpiConstant` object.
+    Value(Value),
+    /// A reference to a design object such as a net, reg or memory word.
+    Object(Handle),
+}
+
+impl From<Value> for MockArg {
+    fn from(value: Value) -> Self {
+        MockArg::Value(value)
+    }
+}
+
+impl From<&Handle> for MockArg {
+    fn from(handle: &Handle) -> Self {
+        MockArg::Object(handle.clone())
+    }
+}
+
+/// Design state and `vpi_put_data` records captured by [`MockSimulator::save`].
+#[derive(Debug, Clone, Default)]
+pub struct MockCheckpoint {
+    time: u64,
+    values: Vec<(usize, Stored, Option<Stored>)>,
+    queue: BTreeMap<u64, Slot>,
+    data: BTreeMap<i32, Vec<u8>>,
+    restart_callbacks: Vec<SavedCallback>,
+}
+
+/// A restart callback registered during a save, and so part of its image.
+#[derive(Debug, Clone)]
+struct SavedCallback {
+    id: usize,
+    reason: PLI_INT32,
+    rtn: CbRoutine,
+    user_data: usize,
+}
+
+impl MockCheckpoint {
+    /// Returns the simulation time at which the checkpoint was taken.
+    #[must_use]
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Returns the bytes written with `vpi_put_data` for a save/restart ID.
+    #[must_use]
+    pub fn data(&self, id: i32) -> Option<&[u8]> {
+        self.data.get(&id).map(Vec::as_slice)
+    }
+}
+
+/// Controller for the mock simulator of the current thread.
+///
+/// The design is built with the `add_*` methods, which return ordinary
+/// [`Handle`]s, and simulation is advanced with [`MockSimulator::run`] or
+/// [`MockSimulator::run_until`]. Plugin code under test talks to the same
+/// design through the regular `vpi` API.
+pub struct MockSimulator {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Default for MockSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockSimulator {
+    /// Creates a controller and resets the mock simulator of the current thread.
+    ///
+    /// All objects, callbacks and registered system tasks from a previous
+    /// mock session on this thread are discarded, as are closures queued with
+    /// [`SimContext::from_worker`](crate::SimContext::from_worker). The
+    /// current thread becomes the simulator thread, and the
+    /// [`PanicPolicy`](crate::PanicPolicy) is reset to its default, and the
+    /// forces recorded on this thread are removed from
+    /// [`forced_objects`](crate::forced_objects).
+    #[must_use]
+    pub fn new() -> Self {
+        crate::force::reset_thread();
+        with_sim(|s| *s = SimState::default());
+        crate::context::reset_thread();
+        crate::context::enter_simulator_thread();
+        crate::set_panic_policy(crate::PanicPolicy::default());
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Returns the context of the simulator thread this controller runs on.
+    #[must_use]
+    pub fn context(&self) -> &'static SimContext {
+        SimContext::borrowed()
+    }
+
+    /// Calls the routines of a `vlog_startup_routines` table until the first `None`.
+    ///
+    /// This mirrors how a simulator loads a plugin built with
+    /// [`startup_routines!`](crate::startup_routines).
+    pub fn run_startup_routines(&self, routines: &[Option<extern "C" fn()>]) {
+        for routine in routines.iter().map_while(|routine| *routine) {
+            routine();
+        }
+    }
+
+    /// Sets the command-line arguments reported by `vpi_get_vlog_info`.
+    pub fn set_arguments(&self, arguments: &[&str]) {
+        with_sim(|s| {
+            s.arguments = arguments
+                .iter()
+                .map(|arg| CString::new(arg.replace('\0', "")).unwrap_or_default())
+                .collect();
+            s.argv = s
+                .arguments
+                .iter()
+                .map(|arg| arg.as_ptr().cast_mut())
+                .collect();
+        });
+    }
+
+    /// Adds a module instance.
+    ///
+    /// Pass `Handle::null()` as `parent` to add a top-level module. Returns a
+    /// null handle when `parent` is not a module.
+    #[must_use]
+    pub fn add_module(&self, parent: &Handle, name: &str, def_name: &str) -> Handle {
+        self.add_scope(parent, name, def_name, vpi_sys::vpiModule)
+    }
+
+    /// Adds a generate scope, such as `gen[0]` of a `for` generate loop, to a
+    /// module or generate scope.
+    ///
+    /// The scope is listed by `vpiInternalScope` rather than `vpiModule`, and
+    /// takes objects and module instances like a module does.
+    #[must_use]
+    pub fn add_gen_scope(&self, parent: &Handle, name: &str) -> Handle {
+        if parent.is_null() {
+            return Handle::null();
+        }
+        self.add_scope(parent, name, "", vpi_sys::vpiGenScope)
+    }
+
+    fn add_scope(&self, parent: &Handle, name: &str, def_name: &str, obj_type: u32) -> Handle {
+        with_sim(|s| {
+            let scope = if parent.is_null() {
+                None
+            } else {
+                Some(s.module_id(parent.as_raw())?)
+            };
+            let (unit, precision) = scope.map_or((DEFAULT_TIMESCALE, DEFAULT_TIMESCALE), |id| {
+                match s.objects[&id].kind {
+                    Kind::Module(ref module) => (module.unit, module.precision),
+                    _ => (DEFAULT_TIMESCALE, DEFAULT_TIMESCALE),
+                }
+            });
+            Some(s.insert(Object::new(
+                name,
+                scope,
+                Kind::Module(Module {
+                    obj_type,
+                    def_name: def_name.to_string(),
+                    unit,
+                    precision,
+                }),
+            )))
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Adds a `wire` net of `width` bits to a module. Nets start at `z`.
+    #[must_use]
+    pub fn add_net(&self, scope: &Handle, name: &str, width: usize) -> Handle {
+        let value = vec![LogicVal::Z; width.max(1)];
+        self.add_signal(scope, name, vpi_sys::vpiNet, Stored::Bits(value), false)
+    }
+
+    /// Adds a `reg` of `width` bits to a module. Regs start at `x`.
+    #[must_use]
+    pub fn add_reg(&self, scope: &Handle, name: &str, width: usize) -> Handle {
+        let value = vec![LogicVal::X; width.max(1)];
+        self.add_signal(scope, name, vpi_sys::vpiReg, Stored::Bits(value), false)
+    }
+
+    /// Adds a signed 32-bit `integer` variable to a module.
+    #[must_use]
+    pub fn add_integer(&self, scope: &Handle, name: &str) -> Handle {
+        let value = vec![LogicVal::X; 32];
+        self.add_signal(
+            scope,
+            name,
+            vpi_sys::vpiIntegerVar,
+            Stored::Bits(value),
+            true,
+        )
+    }
+
+    /// Adds a `real` variable to a module.
+    #[must_use]
+    pub fn add_real(&self, scope: &Handle, name: &str) -> Handle {
+        self.add_signal(scope, name, vpi_sys::vpiRealVar, Stored::Real(0.0), false)
+    }
+
+    fn add_signal(
+        &self,
+        scope: &Handle,
+        name: &str,
+        obj_type: u32,
+        value: Stored,
+        signed: bool,
+    ) -> Handle {
+        with_sim(|s| {
+            let scope = s.module_id(scope.as_raw())?;
+            Some(s.insert(Object::new(
+                name,
+                Some(scope),
+                Kind::Signal(Signal {
+                    obj_type,
+                    signed,
+                    driven: value,
+                    forced: None,
+                    index: None,
+                    implicit: false,
+                }),
+            )))
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Adds a memory (`reg [width-1:0] name [0:depth-1]`) to a module.
+    ///
+    /// The words are reachable with [`Handle::handle_by_index`] and by names
+    /// such as `tb.mem[3]`.
+    #[must_use]
+    pub fn add_memory(&self, scope: &Handle, name: &str, width: usize, depth: usize) -> Handle {
+        with_sim(|s| {
+            let scope = s.module_id(scope.as_raw())?;
+            let width = width.max(1);
+            let array = s.insert(Object::new(
+                name,
+                Some(scope),
+                Kind::Array(Array { words: Vec::new() }),
+            ));
+            let words = (0..depth)
+                .map(|index| {
+                    let index = i32::try_from(index).unwrap_or(i32::MAX);
+                    let mut word = Object::new(
+                        format!("{name}[{index}]"),
+                        Some(scope),
+                        Kind::Signal(Signal {
+                            obj_type: vpi_sys::vpiMemoryWord,
+                            signed: false,
+                            driven: Stored::Bits(vec![LogicVal::X; width]),
+                            forced: None,
+                            index: Some(index),
+                            implicit: false,
+                        }),
+                    );
+                    word.parent = Some(array);
+                    s.insert(word)
+                })
+                .collect();
+            if let Some(Kind::Array(array)) = s.objects.get_mut(&array).map(|o| &mut o.kind) {
+                array.words = words;
+            }
+            Some(array)
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Adds a port to a module, connected inside the module to `low_conn`.
+    ///
+    /// Ports are indexed in the order they are added.
+    #[must_use]
+    pub fn add_port(
+        &self,
+        module: &Handle,
+        name: &str,
+        direction: Direction,
+        low_conn: &Handle,
+    ) -> Handle {
+        with_sim(|s| {
+            let module = s.module_id(module.as_raw())?;
+            let low_conn = s.signal_id(low_conn.as_raw());
+            let index = s
+                .objects
+                .values()
+                .filter(|o| o.scope == Some(module) && matches!(o.kind, Kind::Port(_)))
+                .count();
+            Some(s.insert(Object::new(
+                name,
+                Some(module),
+                Kind::Port(Port {
+                    direction: direction as u32,
+                    index: i32::try_from(index).unwrap_or(i32::MAX),
+                    low_conn,
+                    high_conn: None,
+                }),
+            )))
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Connects a port to a signal in the instantiating module (`vpiHighConn`).
+    ///
+    /// Returns `false` when `port` is not a port or `high_conn` is not a signal.
+    pub fn connect_port(&self, port: &Handle, high_conn: &Handle) -> bool {
+        with_sim(|s| {
+            let Some(high_conn) = s.signal_id(high_conn.as_raw()) else {
+                return false;
+            };
+            match s.object_mut(port.as_raw()).map(|o| &mut o.kind) {
+                Some(Kind::Port(port)) => {
+                    port.high_conn = Some(high_conn);
+                    true
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// Adds a bit select `signal[left]` if `left == right`, otherwise a part
+    /// select `signal[left:right]`.
+    ///
+    /// Selects can be used as the left-hand side of
+    /// [`MockSimulator::add_cont_assign`]; their `vpiParent` is `signal`.
+    #[must_use]
+    pub fn add_select(&self, signal: &Handle, left: i32, right: i32) -> Handle {
+        with_sim(|s| {
+            let signal = s.signal_id(signal.as_raw())?;
+            let scope = s.objects[&signal].scope;
+            let mut object = Object::new("", scope, Kind::Select(Select { left, right }));
+            object.parent = Some(signal);
+            Some(s.insert(object))
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Adds a continuous assignment `assign lhs = rhs` to a module.
+    ///
+    /// `lhs` is a signal or a select from [`MockSimulator::add_select`].
+    /// Several right-hand signals are combined into a concatenation. The
+    /// signals report the assignment through `vpiDriver`/`vpiLocalDriver` and
+    /// `vpiLoad`/`vpiLocalLoad`.
+    #[must_use]
+    pub fn add_cont_assign(&self, scope: &Handle, lhs: &Handle, rhs: &[&Handle]) -> Handle {
+        with_sim(|s| {
+            let scope = s.module_id(scope.as_raw())?;
+            let lhs = s.target_id(lhs.as_raw())?;
+            let operands = rhs
+                .iter()
+                .map(|signal| s.signal_id(signal.as_raw()))
+                .collect::<Option<Vec<_>>>()?;
+            let rhs = match operands[..] {
+                [] => return None,
+                [single] => single,
+                _ => s.insert(Object::new(
+                    "",
+                    Some(scope),
+                    Kind::Operation(Operation {
+                        op_type: vpi_sys::vpiConcatOp,
+                        operands,
+                    }),
+                )),
+            };
+            Some(s.insert(Object::new(
+                "",
+                Some(scope),
+                Kind::ContAssign(ContAssign { lhs, rhs }),
+            )))
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Adds a gate instance such as `and name (output, inputs...)` to a
+    /// module.
+    ///
+    /// The terminals are reachable with `vpiPrimTerm`, and the connected
+    /// signals report them through their driver and load relations.
+    #[must_use]
+    pub fn add_gate(
+        &self,
+        scope: &Handle,
+        name: &str,
+        prim_type: PrimType,
+        output: &Handle,
+        inputs: &[&Handle],
+    ) -> Handle {
+        with_sim(|s| {
+            let scope = s.module_id(scope.as_raw())?;
+            let mut exprs = vec![(vpi_sys::vpiOutput, s.signal_id(output.as_raw())?)];
+            for input in inputs {
+                exprs.push((vpi_sys::vpiInput, s.signal_id(input.as_raw())?));
+            }
+            let def_name = format!("{prim_type:?}").to_lowercase();
+            let primitive = s.insert(Object::new(
+                name,
+                Some(scope),
+                Kind::Primitive(Primitive {
+                    prim_type: prim_type as u32,
+                    def_name,
+                    terms: Vec::new(),
+                }),
+            ));
+            let terms = exprs
+                .into_iter()
+                .enumerate()
+                .map(|(index, (direction, expr))| {
+                    let mut term = Object::new(
+                        "",
+                        Some(scope),
+                        Kind::PrimTerm(PrimTerm {
+                            primitive,
+                            direction,
+                            expr,
+                            index: i32::try_from(index).unwrap_or(i32::MAX),
+                        }),
+                    );
+                    term.parent = Some(primitive);
+                    s.insert(term)
+                })
+                .collect();
+            if let Some(Kind::Primitive(p)) = s.objects.get_mut(&primitive).map(|o| &mut o.kind) {
+                p.terms = terms;
+            }
+            Some(primitive)
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Adds a parameter (or a `localparam` when `local` is set) to a module.
+    ///
+    /// Returns a null handle when `value` has no constant representation.
+    #[must_use]
+    pub fn add_parameter(&self, scope: &Handle, name: &str, value: &Value, local: bool) -> Handle {
+        with_sim(|s| {
+            let scope = s.module_id(scope.as_raw())?;
+            let parameter = s.insert(Object::new(
+                name,
+                Some(scope),
+                Kind::Parameter(Constant::from_value(value)?, local),
+            ));
+            let default = s.insert(Object::new(
+                value.to_string(),
+                None,
+                Kind::Constant(Constant::from_value(value)?),
+            ));
+            s.insert(Object::new(
+                "",
+                Some(scope),
+                Kind::ParamAssign(ParamAssign {
+                    lhs: parameter,
+                    rhs: default,
+                }),
+            ));
+            Some(parameter)
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Adds an `always` block to a module, reachable with `vpiProcess`.
+    #[must_use]
+    pub fn add_always(&self, scope: &Handle) -> Handle {
+        self.add_process(scope, vpi_sys::vpiAlways)
+    }
+
+    /// Adds an `initial` block to a module, reachable with `vpiProcess`.
+    #[must_use]
+    pub fn add_initial(&self, scope: &Handle) -> Handle {
+        self.add_process(scope, vpi_sys::vpiInitial)
+    }
+
+    fn add_process(&self, scope: &Handle, obj_type: u32) -> Handle {
+        with_sim(|s| {
+            let scope = s.module_id(scope.as_raw())?;
+            Some(s.insert(Object::new("", Some(scope), Kind::Process(obj_type))))
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Attaches a `(* name = value *)` attribute to an object, reachable with
+    /// `vpiAttribute`.
+    ///
+    /// `def_attr` sets `vpiDefAttribute`, marking attributes of the module
+    /// definition rather than of the instance.
+    pub fn add_attribute(
+        &self,
+        object: &Handle,
+        name: &str,
+        value: &Value,
+        def_attr: bool,
+    ) -> Handle {
+        with_sim(|s| {
+            let parent = s.id(object.as_raw())?;
+            let mut attribute = Object::new(
+                name,
+                None,
+                Kind::Attribute(Constant::from_value(value)?, def_attr),
+            );
+            attribute.parent = Some(parent);
+            Some(s.insert(attribute))
+        })
+        .map_or_else(Handle::null, |id| Handle::from_raw(raw(id)))
+    }
+
+    /// Overrides the value of a parameter, as a `#(...)` parameter
+    /// assignment of the instance would.
+    ///
+    /// The `vpiParamAssign` of the parameter keeps the declared default as
+    /// its `vpiRhs`. Returns `false` if `parameter` is not a parameter.
+    pub fn override_parameter(&self, parameter: &Handle, value: &Value) -> bool {
+        with_sim(|s| {
+            let constant = Constant::from_value(value)?;
+            match s.object_mut(parameter.as_raw()).map(|o| &mut o.kind) {
+                Some(Kind::Parameter(current, _)) => {
+                    *current = constant;
+                    Some(())
+                }
+                _ => None,
+            }
+        })
+        .is_some()
+    }
+
+    /// Sets the time unit and precision of a module as powers of ten.
+    ///
+    /// For example, `set_timescale(&top, -9, -12)` models `` `timescale 1ns/1ps ``.
+    pub fn set_timescale(&self, module: &Handle, unit: i32, precision: i32) {
+        with_sim(|s| {
+            if let Some(Kind::Module(module)) = s.object_mut(module.as_raw()).map(|o| &mut o.kind) {
+                module.unit = unit;
+                module.precision = precision;
+            }
+        });
+    }
+
+    /// Marks a signal as signed.
+    pub fn set_signed(&self, signal: &Handle, signed: bool) {
+        with_sim(|s| {
+            if let Some(Kind::Signal(signal)) = s.object_mut(signal.as_raw()).map(|o| &mut o.kind) {
+                signal.signed = signed;
+            }
+        });
+    }
+
+    /// Marks a net as implicitly declared, as reported by `vpiImplicitDecl`.
+    pub fn set_implicit(&self, net: &Handle, implicit: bool) {
+        with_sim(|s| {
+            if let Some(Kind::Signal(signal)) = s.object_mut(net.as_raw()).map(|o| &mut o.kind) {
+                signal.implicit = implicit;
+            }
+        });
+    }
+
+    /// Sets the source location reported through `vpiFile` and `vpiLineNo`.
+    pub fn set_location(&self, object: &Handle, file: &str, line: i32) {
+        with_sim(|s| {
+            if let Some(object) = s.object_mut(object.as_raw()) {
+                object.file = CString::new(file.replace('\0', "")).ok();
+                object.line = line;
+            }
+        });
+    }
+
+    /// Writes a value to a signal immediately, as testbench stimulus would.
+    ///
+    /// Value-change callbacks fire before this returns. Returns `false` when
+    /// the object cannot be written or the value cannot be converted.
+    pub fn set_value(&self, object: &Handle, value: &Value) -> bool {
+        let _ = object.put_value(self.context(), value);
+        with_sim(|s| s.error.take().is_none())
+    }
+
+    /// Schedules a value to be written to a signal `delay` ticks from now.
+    ///
+    /// Returns `false` when the object cannot be written or the value cannot
+    /// be converted.
+    pub fn schedule_value(&self, object: &Handle, value: &Value, delay: u64) -> bool {
+        let _ = object.put_value_scheduled(
+            self.context(),
+            value,
+            Some(&crate::Time::Sim(delay)),
+            crate::PutValueDelay::PureTransport,
+            &crate::PutValueFlags::empty(),
+        );
+        with_sim(|s| s.error.take().is_none())
+    }
+
+    /// Adds a call site of a registered system task or function.
+    ///
+    /// `name` is the registered name including the leading `$`. Calls added
+    /// after [`MockSimulator::start`] are compiled immediately. Returns a null
+    /// handle when no system task or function is registered under `name` or
+    /// when an argument cannot be represented.
+    #[must_use]
+    pub fn add_systf_call(&self, scope: &Handle, name: &str, args: &[MockArg]) -> Handle {
+        let call = with_sim(|s| {
+            let scope = if scope.is_null() {
+                None
+            } else {
+                Some(s.module_id(scope.as_raw())?)
+            };
+            let (systf, info) = s.objects.iter().find_map(|(id, o)| match &o.kind {
+                Kind::UserSystf(systf) if systf.name.to_bytes() == name.as_bytes() => {
+                    Some((*id, systf.data))
+                }
+                _ => None,
+            })?;
+            let mut arg_ids = Vec::with_capacity(args.len());
+            for arg in args {
+                let id = match arg {
+                    MockArg::Value(value) => {
+                        let constant = Constant::from_value(value)?;
+                        s.insert(Object::new(
+                            value.to_string(),
+                            None,
+                            Kind::Constant(constant),
+                        ))
+                    }
+                    MockArg::Object(handle) => s.id(handle.as_raw())?,
+                };
+                arg_ids.push(id);
+            }
+            let obj_type = if info.type_ as u32 == vpi_sys::vpiSysFunc {
+                vpi_sys::vpiSysFuncCall
+            } else {
+                vpi_sys::vpiSysTaskCall
+            };
+            let call = s.insert(Object::new(
+                name,
+                scope,
+                Kind::Call(Call {
+                    obj_type,
+                    systf,
+                    args: arg_ids,
+                    compiled: false,
+                    size: None,
+                    ret: None,
+                }),
+            ));
+            Some((call, s.started))
+        });
+        let Some((call, started)) = call else {
+            return Handle::null();
+        };
+        if started {
+            compile_call(call);
+        }
+        Handle::from_raw(raw(call))
+    }
+
+    /// Invokes the `calltf` routine of a call site added with
+    /// [`MockSimulator::add_systf_call`].
+    ///
+    /// The call is compiled first if that has not happened yet. For system
+    /// functions, returns the value written by the routine with `put_value`.
+    pub fn call_systf(&self, call: &Handle) -> Option<Value> {
+        let id = with_sim(|s| match s.object(call.as_raw())?.kind {
+            Kind::Call(ref c) => Some((s.id(call.as_raw())?, c.compiled)),
+            _ => None,
+        });
+        let (id, compiled) = id?;
+        if !compiled {
+            compile_call(id);
+        }
+        let (calltf, user_data) = with_sim(|s| s.systf_routines(id))?;
+        if let Some(calltf) = calltf.calltf {
+            with_sim(|s| s.call_stack.push(id));
+            unsafe { calltf(user_data) };
+            with_sim(|s| s.call_stack.pop());
+        }
+        with_sim(|s| s.return_value(id))
+    }
+
+    /// Adds a call site and invokes it once; see [`MockSimulator::call_systf`].
+    pub fn invoke_systf(&self, name: &str, args: &[MockArg]) -> Option<Value> {
+        let call = self.add_systf_call(&Handle::null(), name, args);
+        if call.is_null() {
+            return None;
+        }
+        self.call_systf(&call)
+    }
+
+    /// Ends elaboration and starts simulation.
+    ///
+    /// Compiles all call sites (running `compiletf` and `sizetf`), then fires
+    /// `cbEndOfCompile` and `cbStartOfSimulation`. Subsequent calls do nothing.
+    pub fn start(&self) {
+        let calls = with_sim(|s| {
+            if s.started {
+                return None;
+            }
+            s.started = true;
+            Some(
+                s.objects
+                    .iter()
+                    .filter(|(_, o)| matches!(o.kind, Kind::Call(ref c) if !c.compiled))
+                    .map(|(id, _)| *id)
+                    .collect::<Vec<_>>(),
+            )
+        });
+        let Some(calls) = calls else {
+            return;
+        };
+        for call in calls {
+            compile_call(call);
+        }
+        fire_reason(vpi_sys::cbEndOfCompile);
+        fire_reason(vpi_sys::cbStartOfSimulation);
+    }
+
+    /// Runs the simulation until no events remain, then finishes it.
+    ///
+    /// Returns early, without finishing, when a callback calls
+    /// [`control`](crate::control) with [`Control::Stop`](crate::Control::Stop);
+    /// calling `run` again resumes the simulation.
+    pub fn run(&self) {
+        if self.is_finished() {
+            return;
+        }
+        self.start();
+        match run_loop(None) {
+            RunEnd::Stopped => {}
+            RunEnd::Idle | RunEnd::Limit | RunEnd::Finished => self.finish(),
+        }
+    }
+
+    /// Processes all events scheduled at or before `time`.
+    ///
+    /// The simulation time is advanced to `time` afterwards unless a callback
+    /// stopped or finished the simulation.
+    pub fn run_until(&self, time: u64) {
+        if self.is_finished() {
+            return;
+        }
+        self.start();
+        match run_loop(Some(time)) {
+            RunEnd::Stopped => {}
+            RunEnd::Finished => self.finish(),
+            RunEnd::Idle | RunEnd::Limit => with_sim(|s| s.now = s.now.max(time)),
+        }
+    }
+
+    /// Processes all events in the next `duration` ticks; see [`MockSimulator::run_until`].
+    pub fn run_for(&self, duration: u64) {
+        self.run_until(self.time().saturating_add(duration));
+    }
+
+    /// Returns the current simulation time in ticks of the simulation precision.
+    #[must_use]
+    pub fn time(&self) -> u64 {
+        with_sim(|s| s.now)
+    }
+
+    /// Finishes the simulation, firing `cbEndOfSimulation` once.
+    pub fn finish(&self) {
+        let first = with_sim(|s| {
+            let first = !s.finished;
+            s.finished = true;
+            s.finish_requested = false;
+            first
+        });
+        if first {
+            fire_reason(vpi_sys::cbEndOfSimulation);
+        }
+    }
+
+    /// Returns `true` once the simulation has finished or a finish was requested.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        with_sim(|s| s.finished || s.finish_requested)
+    }
+
+    /// Returns the text written with `vpi_printf` and to the `stdout` MCD.
+    #[must_use]
+    pub fn output(&self) -> String {
+        with_sim(|s| s.output.clone())
+    }
+
+    /// Returns and clears the text written with `vpi_printf`.
+    pub fn take_output(&self) -> String {
+        with_sim(|s| std::mem::take(&mut s.output))
+    }
+
+    /// Returns the text written to an MCD file opened with `vpi_mcd_open`.
+    ///
+    /// Files stay readable after they are closed, until they are reopened.
+    #[must_use]
+    pub fn file_output(&self, file_name: &str) -> Option<String> {
+        with_sim(|s| s.file_contents.get(file_name).cloned())
+    }
+
+    /// Returns the number of registered callbacks that can still fire.
+    #[must_use]
+    pub fn active_callbacks(&self) -> usize {
+        with_sim(|s| {
+            s.callbacks
+                .iter()
+                .filter(|id| {
+                    matches!(s.objects.get(id).map(|o| &o.kind),
+                        Some(Kind::Callback(cb)) if cb.state == CbState::Active)
+                })
+                .count()
+        })
+    }
+
+    /// Reports a simulation error, as a simulator would for HDL run-time errors.
+    ///
+    /// The error becomes visible through [`chk_error`](crate::chk_error) and
+    /// `cbError` callbacks fire.
+    pub fn report_error(&self, severity: Severity, message: &str) {
+        with_sim(|s| {
+            let state = if s.started {
+                vpi_sys::vpiRun
+            } else {
+                vpi_sys::vpiCompile
+            };
+            s.error = Some(ErrorRecord::new(severity as i32, state as i32, message));
+        });
+        fire_reason(vpi_sys::cbError);
+    }
+
+    /// Saves a checkpoint, firing `cbStartOfSave` and `cbEndOfSave`.
+    ///
+    /// Each save callback gets its own ID from
+    /// `vpi_get(vpiSaveRestartID, NULL)` to use with `vpi_put_data`.
+    /// `cbStartOfRestart` and `cbEndOfRestart` callbacks registered during
+    /// the save are part of the checkpoint.
+    pub fn save(&self) -> MockCheckpoint {
+        let first_callback = with_sim(|s| {
+            s.checkpoint = Some(CheckpointState::default());
+            s.callbacks.len()
+        });
+        for reason in [vpi_sys::cbStartOfSave, vpi_sys::cbEndOfSave] {
+            for cb in with_sim(|s| s.callbacks_for(reason)) {
+                with_sim(|s| {
+                    s.next_save_id += 1;
+                    s.save_id = s.next_save_id;
+                });
+                fire(Notify::plain(cb), false);
+            }
+        }
+        with_sim(|s| {
+            s.save_id = 0;
+            let data = s.checkpoint.take().map(|c| c.data).unwrap_or_default();
+            let restart_callbacks = s.callbacks[first_callback..]
+                .iter()
+                .filter_map(|id| match &s.objects.get(id)?.kind {
+                    Kind::Callback(cb)
+                        if cb.state == CbState::Active
+                            && matches!(
+                                cb.reason as u32,
+                                vpi_sys::cbStartOfRestart | vpi_sys::cbEndOfRestart
+                            ) =>
+                    {
+                        Some(SavedCallback {
+                            id: *id,
+                            reason: cb.reason,
+                            rtn: cb.rtn,
+                            user_data: cb.user_data as usize,
+                        })
+                    }
+                    _ => None,
+                })
+                .collect();
+            let values = s
+                .objects
+                .iter()
+                .filter_map(|(id, o)| match &o.kind {
+                    Kind::Signal(signal) => {
+                        Some((*id, signal.driven.clone(), signal.forced.clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            MockCheckpoint {
+                time: s.now,
+                values,
+                queue: s.queue.clone(),
+                data,
+                restart_callbacks,
+            }
+        })
+    }
+
+    /// Restores a checkpoint, firing `cbStartOfRestart` and `cbEndOfRestart`.
+    ///
+    /// Signal values, the simulation time and pending events are restored;
+    /// the data written during the save is readable with `vpi_get_data`.
+    /// Like a simulator loading the save image, the restart callbacks
+    /// registered during the save are registered again if they have been
+    /// removed since, for example because the plugin state that registered
+    /// them was torn down. `vpiSaveRestartID` is not available.
+    pub fn restart(&self, checkpoint: &MockCheckpoint) {
+        with_sim(|s| {
+            for saved in &checkpoint.restart_callbacks {
+                let active = matches!(
+                    s.objects.get(&saved.id).map(|o| &o.kind),
+                    Some(Kind::Callback(cb)) if cb.state == CbState::Active
+                );
+                if !active {
+                    let data = vpi_sys::t_cb_data {
+                        reason: saved.reason,
+                        cb_rtn: Some(saved.rtn),
+                        obj: std::ptr::null_mut(),
+                        time: std::ptr::null_mut(),
+                        value: std::ptr::null_mut(),
+                        index: 0,
+                        user_data: saved.user_data as *mut PLI_BYTE8,
+                    };
+                    s.register_cb(&data);
+                }
+            }
+            s.now = checkpoint.time;
+            s.queue = checkpoint.queue.clone();
+            for (id, driven, forced) in &checkpoint.values {
+                if let Some(Kind::Signal(signal)) = s.objects.get_mut(id).map(|o| &mut o.kind) {
+                    signal.driven = driven.clone();
+                    signal.forced = forced.clone();
+                }
+            }
+            s.finished = false;
+            s.finish_requested = false;
+            s.checkpoint = Some(CheckpointState {
+                data: checkpoint.data.clone(),
+                cursors: HashMap::new(),
+                restoring: true,
+            });
+        });
+        fire_reason(vpi_sys::cbStartOfRestart);
+        fire_reason(vpi_sys::cbEndOfRestart);
+        with_sim(|s| s.checkpoint = None);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stored {
+    Bits(Vec<LogicVal>),
+    Real(f64),
+}
+
+impl Stored {
+    fn width(&self) -> usize {
+        match self {
+            Stored::Bits(bits) => bits.len(),
+            Stored::Real(_) => 64,
+        }
+    }
+}
+
+/// Shape of a writable object, used to convert incoming values.
+#[derive(Clone, Copy)]
+struct Target {
+    width: usize,
+    real: bool,
+}
+
+struct Module {
+    /// `vpiModule`, or `vpiGenScope` for generate scopes.
+    obj_type: u32,
+    def_name: String,
+    unit: i32,
+    precision: i32,
+}
+
+struct Signal {
+    obj_type: u32,
+    signed: bool,
+    driven: Stored,
+    forced: Option<Stored>,
+    index: Option<i32>,
+    implicit: bool,
+}
+
+impl Signal {
+    fn value(&self) -> &Stored {
+        self.forced.as_ref().unwrap_or(&self.driven)
+    }
+
+    fn native_format(&self) -> u32 {
+        match (self.obj_type, &self.driven) {
+            (_, Stored::Real(_)) => vpi_sys::vpiRealVal,
+            (vpi_sys::vpiIntegerVar, _) => vpi_sys::vpiIntVal,
+            (_, value) if value.width() == 1 => vpi_sys::vpiScalarVal,
+            _ => vpi_sys::vpiVectorVal,
+        }
+    }
+}
+
+struct Array {
+    words: Vec<usize>,
+}
+
+struct Port {
+    direction: u32,
+    index: i32,
+    low_conn: Option<usize>,
+    high_conn: Option<usize>,
+}
+
+struct ContAssign {
+    lhs: usize,
+    /// A signal or an [`Operation`].
+    rhs: usize,
+}
+
+struct Operation {
+    op_type: u32,
+    operands: Vec<usize>,
+}
+
+/// Bit or part select of the signal stored as the object's parent.
+struct Select {
+    left: i32,
+    right: i32,
+}
+
+struct Primitive {
+    prim_type: u32,
+    def_name: String,
+    terms: Vec<usize>,
+}
+
+struct PrimTerm {
+    primitive: usize,
+    direction: u32,
+    expr: usize,
+    index: i32,
+}
+
+/// `parameter lhs = rhs`, with `rhs` the declared default.
+struct ParamAssign {
+    lhs: usize,
+    rhs: usize,
+}
+
+struct Constant {
+    value: Stored,
+    signed: bool,
+    const_type: u32,
+}
+
+impl Constant {
+    fn from_value(value: &Value) -> Option<Self> {
+        let (value, signed, const_type) = match value {
+            Value::Int(v) => (bits_from_i64(i64::from(*v), 32), true, vpi_sys::vpiDecConst),
+            Value::ShortInt(v) => (bits_from_i64(i64::from(*v), 16), true, vpi_sys::vpiDecConst),
+            Value::LongInt(v) => (bits_from_i64(*v, 64), true, vpi_sys::vpiDecConst),
+            Value::Real(v) => (Stored::Real(*v), false, vpi_sys::vpiRealConst),
+            Value::ShortReal(v) => (Stored::Real(f64::from(*v)), false, vpi_sys::vpiRealConst),
+            Value::Scalar(bit) => (
+                Stored::Bits(vec![normalize(*bit)]),
+                false,
+                vpi_sys::vpiBinaryConst,
+            ),
+            Value::Vector(vec) => (
+                Stored::Bits(vec.iter().copied().map(normalize).collect()),
+                false,
+                vpi_sys::vpiBinaryConst,
+            ),
+            Value::BinStr(s) => (
+                Stored::Bits(parse_radix(s, 1)?),
+                false,
+                vpi_sys::vpiBinaryConst,
+            ),
+            Value::OctStr(s) => (
+                Stored::Bits(parse_radix(s, 3)?),
+                false,
+                vpi_sys::vpiOctConst,
+            ),
+            Value::HexStr(s) => (
+                Stored::Bits(parse_radix(s, 4)?),
+                false,
+                vpi_sys::vpiHexConst,
+            ),
+            Value::DecStr(s) => (
+                Stored::Bits(parse_decimal(s, 32)?),
+                true,
+                vpi_sys::vpiDecConst,
+            ),
+            Value::String(s) => (Stored::Bits(string_to_bits(s.as_bytes())), false, {
+                vpi_sys::vpiStringConst
+            }),
+            Value::Time(crate::Time::Sim(t)) => {
+                (bits_from_u64(*t, 64), false, vpi_sys::vpiDecConst)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            value,
+            signed,
+            const_type,
+        })
+    }
+
+    fn native_format(&self) -> u32 {
+        match (&self.value, self.const_type) {
+            (Stored::Real(_), _) => vpi_sys::vpiRealVal,
+            (_, vpi_sys::vpiStringConst) => vpi_sys::vpiStringVal,
+            (value, vpi_sys::vpiDecConst) if self.signed && value.width() == 32 => {
+                vpi_sys::vpiIntVal
+            }
+            (value, _) if value.width() == 1 => vpi_sys::vpiScalarVal,
+            _ => vpi_sys::vpiVectorVal,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CbState {
+    Active,
+    Retired,
+    Removed,
+}
+
+struct Callback {
+    reason: PLI_INT32,
+    rtn: CbRoutine,
+    obj: vpiHandle,
+    time: Option<Box<vpi_sys::t_vpi_time>>,
+    value: Option<Box<vpi_sys::t_vpi_value>>,
+    index: PLI_INT32,
+    user_data: *mut PLI_BYTE8,
+    state: CbState,
+}
+
+struct Systf {
+    data: vpi_sys::t_vpi_systf_data,
+    name: CString,
+}
+
+struct Call {
+    obj_type: u32,
+    systf: usize,
+    args: Vec<usize>,
+    compiled: bool,
+    size: Option<usize>,
+    ret: Option<Stored>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EventState {
+    Pending,
+    Done,
+    Cancelled,
+}
+
+struct Event {
+    target: usize,
+    value: Stored,
+    time: u64,
+    state: EventState,
+}
+
+enum Kind {
+    Module(Module),
+    Signal(Signal),
+    Array(Array),
+    Port(Port),
+    ContAssign(ContAssign),
+    Operation(Operation),
+    Select(Select),
+    Primitive(Primitive),
+    PrimTerm(PrimTerm),
+    Parameter(Constant, bool),
+    ParamAssign(ParamAssign),
+    Process(u32),
+    Attribute(Constant, bool),
+    Constant(Constant),
+    Iterator(VecDeque<usize>),
+    Callback(Callback),
+    UserSystf(Systf),
+    Call(Call),
+    Event(Event),
+    Freed,
+}
+
+struct Object {
+    name: String,
+    scope: Option<usize>,
+    parent: Option<usize>,
+    file: Option<CString>,
+    line: i32,
+    kind: Kind,
+}
+
+impl Object {
+    fn new(name: impl Into<String>, scope: Option<usize>, kind: Kind) -> Self {
+        Self {
+            name: name.into(),
+            scope,
+            parent: None,
+            file: None,
+            line: 0,
+            kind,
+        }
+    }
+
+    fn vpi_type(&self) -> u32 {
+        match &self.kind {
+            Kind::Module(module) => module.obj_type,
+            Kind::Signal(signal) => signal.obj_type,
+            Kind::Array(_) => vpi_sys::vpiMemory,
+            Kind::Port(_) => vpi_sys::vpiPort,
+            Kind::ContAssign(_) => vpi_sys::vpiContAssign,
+            Kind::Operation(_) => vpi_sys::vpiOperation,
+            Kind::Select(select) if select.left == select.right => vpi_sys::vpiBitSelect,
+            Kind::Select(_) => vpi_sys::vpiPartSelect,
+            Kind::Primitive(_) => vpi_sys::vpiGate,
+            Kind::PrimTerm(_) => vpi_sys::vpiPrimTerm,
+            Kind::Parameter(..) => vpi_sys::vpiParameter,
+            Kind::ParamAssign(_) => vpi_sys::vpiParamAssign,
+            Kind::Process(obj_type) => *obj_type,
+            Kind::Attribute(..) => vpi_sys::vpiAttribute,
+            Kind::Constant(_) => vpi_sys::vpiConstant,
+            Kind::Iterator(_) => vpi_sys::vpiIterator,
+            Kind::Callback(_) => vpi_sys::vpiCallback,
+            Kind::UserSystf(_) => vpi_sys::vpiUserSystf,
+            Kind::Call(call) => call.obj_type,
+            Kind::Event(_) => vpi_sys::vpiSchedEvent,
+            Kind::Freed => 0,
+        }
+    }
+
+    /// Returns `true` for objects that can be found with `vpi_handle_by_name`.
+    fn is_named(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::Module(_)
+                | Kind::Signal(_)
+                | Kind::Array(_)
+                | Kind::Parameter(..)
+                | Kind::Primitive(_)
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Item {
+    Callback(usize),
+    Event(usize),
+}
+
+/// Callbacks and events of one time step, in the order they are processed.
+#[derive(Clone, Debug, Default)]
+struct Slot {
+    start: VecDeque<usize>,
+    active: VecDeque<Item>,
+    read_write: VecDeque<usize>,
+    read_only: VecDeque<usize>,
+    end: VecDeque<usize>,
+}
+
+impl Slot {
+    fn is_empty(&self) -> bool {
+        self.start.is_empty()
+            && self.active.is_empty()
+            && self.read_write.is_empty()
+            && self.read_only.is_empty()
+            && self.end.is_empty()
+    }
+
+    fn pop(&mut self) -> Option<Item> {
+        self.start
+            .pop_front()
+            .map(Item::Callback)
+            .or_else(|| self.active.pop_front())
+            .or_else(|| self.read_write.pop_front().map(Item::Callback))
+            .or_else(|| self.read_only.pop_front().map(Item::Callback))
+            .or_else(|| self.end.pop_front().map(Item::Callback))
+    }
+}
+
+struct ErrorRecord {
+    level: PLI_INT32,
+    state: PLI_INT32,
+    message: CString,
+}
+
+impl ErrorRecord {
+    fn new(level: PLI_INT32, state: PLI_INT32, message: &str) -> Self {
+        Self {
+            level,
+            state,
+            message: CString::new(message.replace('\0', "")).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct CheckpointState {
+    data: BTreeMap<i32, Vec<u8>>,
+    cursors: HashMap<i32, usize>,
+    restoring: bool,
+}
+
+/// A callback to invoke, and the object whose value it reports.
+#[derive(Clone, Copy)]
+struct Notify {
+    cb: usize,
+    source: Option<usize>,
+    index: PLI_INT32,
+}
+
+impl Notify {
+    fn plain(cb: usize) -> Self {
+        Self {
+            cb,
+            source: None,
+            index: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct SimState {
+    objects: BTreeMap<usize, Object>,
+    callbacks: Vec<usize>,
+    now: u64,
+    started: bool,
+    finished: bool,
+    stop_requested: bool,
+    finish_requested: bool,
+    queue: BTreeMap<u64, Slot>,
+    next_sim_time: Vec<usize>,
+    call_stack: Vec<usize>,
+    error: Option<ErrorRecord>,
+    output: String,
+    files: BTreeMap<u32, String>,
+    file_contents: HashMap<String, String>,
+    userdata: HashMap<usize, usize>,
+    delays: HashMap<usize, Vec<vpi_sys::t_vpi_time>>,
+    checkpoint: Option<CheckpointState>,
+    save_id: i32,
+    next_save_id: i32,
+    arguments: Vec<CString>,
+    argv: Vec<*mut PLI_BYTE8>,
+    scratch_value: Option<Encoded>,
+    scratch_str: CString,
+}
+
+impl SimState {
+    fn insert(&mut self, object: Object) -> usize {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        self.objects.insert(id, object);
+        id
+    }
+
+    fn id(&self, handle: vpiHandle) -> Option<usize> {
+        let id = handle.addr();
+        match self.objects.get(&id) {
+            Some(object) if !matches!(object.kind, Kind::Freed) => Some(id),
+            _ => None,
+        }
+    }
+
+    fn object(&self, handle: vpiHandle) -> Option<&Object> {
+        self.id(handle).map(|id| &self.objects[&id])
+    }
+
+    fn object_mut(&mut self, handle: vpiHandle) -> Option<&mut Object> {
+        let id = self.id(handle)?;
+        self.objects.get_mut(&id)
+    }
+
+    /// Drops a removed callback from the time queue so it no longer advances time.
+    fn unschedule(&mut self, cb: usize) {
+        self.next_sim_time.retain(|&id| id != cb);
+        self.queue.retain(|_, slot| {
+            for ids in [
+                &mut slot.start,
+                &mut slot.read_write,
+                &mut slot.read_only,
+                &mut slot.end,
+            ] {
+                ids.retain(|&id| id != cb);
+            }
+            slot.active
+                .retain(|item| !matches!(item, Item::Callback(id) if *id == cb));
+            !slot.is_empty()
+        });
+    }
+
+    fn module_id(&self, handle: vpiHandle) -> Option<usize> {
+        let id = self.id(handle)?;
+        matches!(self.objects[&id].kind, Kind::Module(_)).then_some(id)
+    }
+
+    fn signal_id(&self, handle: vpiHandle) -> Option<usize> {
+        let id = self.id(handle)?;
+        matches!(self.objects[&id].kind, Kind::Signal(_)).then_some(id)
+    }
+
+    /// Accepts a signal or a select of one.
+    fn target_id(&self, handle: vpiHandle) -> Option<usize> {
+        let id = self.id(handle)?;
+        matches!(self.objects[&id].kind, Kind::Signal(_) | Kind::Select(_)).then_some(id)
+    }
+
+    /// Returns the signal written through `target`, resolving selects.
+    fn selected(&self, target: usize) -> usize {
+        match &self.objects[&target] {
+            Object {
+                kind: Kind::Select(_),
+                parent: Some(signal),
+                ..
+            } => *signal,
+            _ => target,
+        }
+    }
+
+    fn fail(&mut self, message: impl AsRef<str>) {
+        self.error = Some(ErrorRecord::new(
+            vpi_sys::vpiError as PLI_INT32,
+            vpi_sys::vpiPLI as PLI_INT32,
+            message.as_ref(),
+        ));
+    }
+
+    fn full_name(&self, id: usize) -> String {
+        let object = &self.objects[&id];
+        match object.scope {
+            Some(scope) => format!("{}.{}", self.full_name(scope), object.name),
+            None => object.name.clone(),
+        }
+    }
+
+    /// Returns the module instance containing `id`, skipping generate scopes.
+    fn enclosing_module(&self, id: usize) -> Option<usize> {
+        match &self.objects.get(&id)?.kind {
+            Kind::Module(module) if module.obj_type == vpi_sys::vpiModule => Some(id),
+            _ => self.enclosing_module(self.objects[&id].scope?),
+        }
+    }
+
+    fn module_of(&self, id: usize) -> Option<&Module> {
+        let object = self.objects.get(&id)?;
+        match &object.kind {
+            Kind::Module(module) => Some(module),
+            _ => self.module_of(object.scope?),
+        }
+    }
+
+    /// Simulation precision: the finest precision of all modules.
+    fn precision(&self) -> i32 {
+        self.objects
+            .values()
+            .filter_map(|o| match &o.kind {
+                Kind::Module(module) => Some(module.precision),
+                _ => None,
+            })
+            .min()
+            .unwrap_or(DEFAULT_TIMESCALE)
+    }
+
+    /// Number of simulation ticks per time unit of `id`'s module.
+    fn scale(&self, id: Option<usize>) -> f64 {
+        let precision = self.precision();
+        let unit = id
+            .and_then(|id| self.module_of(id))
+            .map_or(precision, |module| module.unit);
+        10f64.powi(unit - precision)
+    }
+
+    fn ticks(&self, time: &vpi_sys::t_vpi_time, id: Option<usize>) -> Option<u64> {
+        match time.type_ as u32 {
+            vpi_sys::vpiSimTime => Some(u64::from(time.high) << 32 | u64::from(time.low)),
+            vpi_sys::vpiScaledRealTime => {
+                let ticks = (time.real * self.scale(id)).round();
+                (ticks.is_finite() && ticks >= 0.0).then_some(ticks as u64)
+            }
+            _ => None,
+        }
+    }
+
+    fn time_struct(
+        &self,
+        ticks: u64,
+        time_type: PLI_INT32,
+        id: Option<usize>,
+    ) -> vpi_sys::t_vpi_time {
+        let mut time = vpi_sys::t_vpi_time {
+            type_: time_type,
+            high: 0,
+            low: 0,
+            real: 0.0,
+        };
+        match time_type as u32 {
+            vpi_sys::vpiScaledRealTime => time.real = ticks as f64 / self.scale(id),
+            vpi_sys::vpiSuppressTime => {}
+            _ => {
+                time.type_ = vpi_sys::vpiSimTime as PLI_INT32;
+                time.high = (ticks >> 32) as u32;
+                time.low = (ticks & 0xFFFF_FFFF) as u32;
+            }
+        }
+        time
+    }
+
+    fn children(&self, scope: usize, keep: impl Fn(&Object) -> bool) -> Vec<usize> {
+        self.objects
+            .iter()
+            .filter(|(_, o)| o.scope == Some(scope) && o.parent.is_none() && keep(o))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Signals joined to `signal` through port connections, including itself,
+    /// which simulators treat as one net for `vpiDriver` and `vpiLoad`.
+    fn collapsed(&self, signal: usize) -> Vec<usize> {
+        let mut net = vec![signal];
+        let mut i = 0;
+        while let Some(&current) = net.get(i) {
+            for object in self.objects.values() {
+                let Kind::Port(port) = &object.kind else {
+                    continue;
+                };
+                let other = if port.low_conn == Some(current) {
+                    port.high_conn
+                } else if port.high_conn == Some(current) {
+                    port.low_conn
+                } else {
+                    None
+                };
+                if let Some(other) = other.filter(|other| !net.contains(other)) {
+                    net.push(other);
+                }
+            }
+            i += 1;
+        }
+        net
+    }
+
+    /// Continuous assignments and output terminals driving `signal`.
+    fn drivers(&self, signal: usize) -> Vec<usize> {
+        self.objects
+            .iter()
+            .filter(|(_, o)| match &o.kind {
+                Kind::ContAssign(assign) => self.selected(assign.lhs) == signal,
+                Kind::PrimTerm(term) => term.direction != vpi_sys::vpiInput && term.expr == signal,
+                _ => false,
+            })
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Continuous assignments and input terminals reading `signal`.
+    fn loads(&self, signal: usize) -> Vec<usize> {
+        self.objects
+            .iter()
+            .filter(|(_, o)| match &o.kind {
+                Kind::ContAssign(assign) => match &self.objects[&assign.rhs].kind {
+                    Kind::Operation(operation) => operation.operands.contains(&signal),
+                    _ => assign.rhs == signal,
+                },
+                Kind::PrimTerm(term) => term.direction != vpi_sys::vpiOutput && term.expr == signal,
+                _ => false,
+            })
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    fn callbacks_for(&self, reason: u32) -> Vec<usize> {
+        self.callbacks
+            .iter()
+            .copied()
+            .filter(|id| {
+                matches!(self.objects.get(id).map(|o| &o.kind),
+                    Some(Kind::Callback(cb))
+                        if cb.state == CbState::Active && cb.reason as u32 == reason)
+            })
+            .collect()
+    }
+
+    /// Callbacks watching `id` (or any object, when `any` is set) for `reason`.
+    fn watchers(&self, reason: u32, id: usize, any: bool) -> Vec<Notify> {
+        let mut notify = Vec::new();
+        let parent = self.objects.get(&id).and_then(|o| match &o.kind {
+            Kind::Signal(signal) => o.parent.zip(signal.index),
+            _ => None,
+        });
+        for cb in self.callbacks_for(reason) {
+            let Some(Kind::Callback(callback)) = self.objects.get(&cb).map(|o| &o.kind) else {
+                continue;
+            };
+            let obj = callback.obj.addr();
+            if obj == id || (any && callback.obj.is_null()) {
+                notify.push(Notify {
+                    cb,
+                    source: Some(id),
+                    index: 0,
+                });
+            } else if let Some((_, index)) = parent.filter(|(array, _)| *array == obj) {
+                notify.push(Notify {
+                    cb,
+                    source: Some(id),
+                    index,
+                });
+            }
+        }
+        notify
+    }
+
+    fn size(&self, id: usize) -> Option<usize> {
+        match &self.objects.get(&id)?.kind {
+            Kind::Signal(signal) => Some(signal.driven.width()),
+            Kind::Array(array) => Some(array.words.len()),
+            Kind::Select(select) => Some(select.left.abs_diff(select.right) as usize + 1),
+            Kind::Port(port) => port.low_conn.and_then(|id| self.size(id)),
+            Kind::Parameter(constant, _)
+            | Kind::Attribute(constant, _)
+            | Kind::Constant(constant) => Some(constant.value.width()),
+            Kind::Call(call) => self.call_target(call).map(|target| target.width),
+            _ => None,
+        }
+    }
+
+    fn func_type(&self, systf: usize) -> Option<u32> {
+        match &self.objects.get(&systf)?.kind {
+            Kind::UserSystf(systf) if systf.data.type_ as u32 == vpi_sys::vpiSysFunc => {
+                Some(systf.data.sysfunctype as u32)
+            }
+            _ => None,
+        }
+    }
+
+    fn call_target(&self, call: &Call) -> Option<Target> {
+        let (width, real) = match self.func_type(call.systf)? {
+            vpi_sys::vpiRealFunc => (64, true),
+            vpi_sys::vpiTimeFunc => (64, false),
+            vpi_sys::vpiSizedFunc | vpi_sys::vpiSizedSignedFunc => (call.size.unwrap_or(32), false),
+            _ => (32, false),
+        };
+        Some(Target { width, real })
+    }
+
+    fn target(&self, id: usize) -> Option<Target> {
+        match &self.objects.get(&id)?.kind {
+            Kind::Signal(signal) => Some(Target {
+                width: signal.driven.width(),
+                real: matches!(signal.driven, Stored::Real(_)),
+            }),
+            Kind::Call(call) => self.call_target(call),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of `id` with its signedness and native value format.
+    fn readable(&self, id: usize) -> Option<(&Stored, bool, u32)> {
+        match &self.objects.get(&id)?.kind {
+            Kind::Signal(signal) => Some((signal.value(), signal.signed, signal.native_format())),
+            Kind::Parameter(constant, _)
+            | Kind::Attribute(constant, _)
+            | Kind::Constant(constant) => {
+                Some((&constant.value, constant.signed, constant.native_format()))
+            }
+            Kind::Call(call) => {
+                let value = call.ret.as_ref()?;
+                let format = match self.func_type(call.systf)? {
+                    vpi_sys::vpiRealFunc => vpi_sys::vpiRealVal,
+                    vpi_sys::vpiTimeFunc => vpi_sys::vpiTimeVal,
+                    vpi_sys::vpiSizedFunc | vpi_sys::vpiSizedSignedFunc => vpi_sys::vpiVectorVal,
+                    _ => vpi_sys::vpiIntVal,
+                };
+                let signed = self.func_type(call.systf)? != vpi_sys::vpiSizedFunc;
+                Some((value, signed, format))
+            }
+            _ => None,
+        }
+    }
+
+    fn encode_value(&self, id: usize, format: u32, pad_bits: usize) -> Option<Encoded> {
+        let (value, signed, native) = self.readable(id)?;
+        encode(value, format, signed, native, pad_bits)
+    }
+
+    /// Stores a new driven value, returning the value-change callbacks to fire.
+    fn set_driven(&mut self, id: usize, value: Stored) -> Vec<Notify> {
+        let changed = match self.objects.get_mut(&id).map(|o| &mut o.kind) {
+            Some(Kind::Signal(signal)) => {
+                let old = signal.value().clone();
+                signal.driven = value;
+                *signal.value() != old
+            }
+            Some(Kind::Call(call)) => {
+                call.ret = Some(value);
+                false
+            }
+            _ => false,
+        };
+        if changed {
+            self.watchers(vpi_sys::cbValueChange, id, false)
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn force(&mut self, id: usize, value: Stored) -> Vec<Notify> {
+        let Some(Kind::Signal(signal)) = self.objects.get_mut(&id).map(|o| &mut o.kind) else {
+            self.fail("only nets and variables can be forced");
+            return Vec::new();
+        };
+        let old = signal.value().clone();
+        signal.forced = Some(value);
+        let changed = *signal.value() != old;
+        let mut notify = if changed {
+            self.watchers(vpi_sys::cbValueChange, id, false)
+        } else {
+            Vec::new()
+        };
+        notify.extend(self.watchers(vpi_sys::cbForce, id, true));
+        notify
+    }
+
+    fn release(&mut self, id: usize) -> Vec<Notify> {
+        let Some(Kind::Signal(signal)) = self.objects.get_mut(&id).map(|o| &mut o.kind) else {
+            self.fail("only nets and variables can be released");
+            return Vec::new();
+        };
+        let Some(forced) = signal.forced.take() else {
+            return Vec::new();
+        };
+        // Variables keep the forced value until the next assignment; nets
+        // return to the value of their drivers.
+        if signal.obj_type != vpi_sys::vpiNet {
+            signal.driven = forced.clone();
+        }
+        let changed = *signal.value() != forced;
+        let mut notify = if changed {
+            self.watchers(vpi_sys::cbValueChange, id, false)
+        } else {
+            Vec::new()
+        };
+        notify.extend(self.watchers(vpi_sys::cbRelease, id, true));
+        notify
+    }
+
+    fn apply_event(&mut self, event: usize) -> Vec<Notify> {
+        let Some(Kind::Event(event)) = self.objects.get_mut(&event).map(|o| &mut o.kind) else {
+            return Vec::new();
+        };
+        if event.state != EventState::Pending {
+            return Vec::new();
+        }
+        event.state = EventState::Done;
+        let (target, value) = (event.target, event.value.clone());
+        self.set_driven(target, value)
+    }
+
+    /// Cancels pending events on `target` according to the delay mode.
+    fn cancel_pending(&mut self, target: usize, mode: u32, at: u64) {
+        for object in self.objects.values_mut() {
+            if let Kind::Event(event) = &mut object.kind {
+                let cancel = event.target == target
+                    && event.state == EventState::Pending
+                    && match mode {
+                        vpi_sys::vpiInertialDelay => true,
+                        vpi_sys::vpiTransportDelay => event.time > at,
+                        _ => false,
+                    };
+                if cancel {
+                    event.state = EventState::Cancelled;
+                }
+            }
+        }
+    }
+
+    fn put_value(
+        &mut self,
+        object: vpiHandle,
+        value_p: vpi_sys::p_vpi_value,
+        time_p: vpi_sys::p_vpi_time,
+        flags: PLI_INT32,
+    ) -> (Vec<Notify>, Option<usize>) {
+        let Some(id) = self.id(object) else {
+            self.fail("vpi_put_value: invalid handle");
+            return (Vec::new(), None);
+        };
+        let mode = (flags as u32) & 0xFFF;
+        if mode == vpi_sys::vpiCancelEvent {
+            match self.objects.get_mut(&id).map(|o| &mut o.kind) {
+                Some(Kind::Event(event)) if event.state == EventState::Pending => {
+                    event.state = EventState::Cancelled;
+                }
+                Some(Kind::Event(_)) => {}
+                _ => self.fail("vpi_put_value: vpiCancelEvent requires an event handle"),
+            }
+            return (Vec::new(), None);
+        }
+        if mode == vpi_sys::vpiReleaseFlag {
+            return (self.release(id), None);
+        }
+        let Some(target) = self.target(id) else {
+            self.fail("vpi_put_value: object cannot be written");
+            return (Vec::new(), None);
+        };
+        if value_p.is_null() {
+            self.fail("vpi_put_value: null value");
+            return (Vec::new(), None);
+        }
+        let value = match unsafe { decode_put(&*value_p, target) } {
+            Ok(value) => value,
+            Err(message) => {
+                self.fail(format!("vpi_put_value: {message}"));
+                return (Vec::new(), None);
+            }
+        };
+        match mode {
+            0 | vpi_sys::vpiNoDelay => (self.set_driven(id, value), None),
+            vpi_sys::vpiForceFlag => (self.force(id, value), None),
+            vpi_sys::vpiInertialDelay
+            | vpi_sys::vpiTransportDelay
+            | vpi_sys::vpiPureTransportDelay => {
+                let delay = if time_p.is_null() {
+                    Some(0)
+                } else {
+                    self.ticks(unsafe { &*time_p }, Some(id))
+                };
+                let Some(delay) = delay else {
+                    self.fail("vpi_put_value: invalid delay");
+                    return (Vec::new(), None);
+                };
+                let at = self.now.saturating_add(delay);
+                self.cancel_pending(id, mode, at);
+                let event = self.insert(Object::new(
+                    "",
+                    None,
+                    Kind::Event(Event {
+                        target: id,
+                        value,
+                        time: at,
+                        state: EventState::Pending,
+                    }),
+                ));
+                self.queue
+                    .entry(at)
+                    .or_default()
+                    .active
+                    .push_back(Item::Event(event));
+                let return_event = (flags as u32) & vpi_sys::vpiReturnEvent != 0;
+                (Vec::new(), return_event.then_some(event))
+            }
+            _ => {
+                self.fail("vpi_put_value: unsupported delay mode");
+                (Vec::new(), None)
+            }
+        }
+    }
+
+    fn register_cb(&mut self, data: &vpi_sys::t_cb_data) -> Option<usize> {
+        let Some(rtn) = data.cb_rtn else {
+            self.fail("vpi_register_cb: missing cb_rtn");
+            return None;
+        };
+        let reason = data.reason as u32;
+        let obj = if data.obj.is_null() {
+            None
+        } else if let Some(id) = self.id(data.obj) {
+            Some(id)
+        } else {
+            self.fail("vpi_register_cb: invalid object handle");
+            return None;
+        };
+        let time = (!data.time.is_null()).then(|| Box::new(unsafe { *data.time }));
+        let value = (!data.value.is_null()).then(|| Box::new(unsafe { *data.value }));
+
+        let slot_time = match reason {
+            vpi_sys::cbValueChange => {
+                let watchable = obj.is_some_and(|id| {
+                    matches!(self.objects[&id].kind, Kind::Signal(_) | Kind::Array(_))
+                });
+                if !watchable {
+                    self.fail("vpi_register_cb: cbValueChange requires a net, variable or array");
+                    return None;
+                }
+                None
+            }
+            vpi_sys::cbAfterDelay
+            | vpi_sys::cbAtStartOfSimTime
+            | vpi_sys::cbReadWriteSynch
+            | vpi_sys::cbReadOnlySynch
+            | vpi_sys::cbAtEndOfSimTime
+            | vpi_sys::cbNBASynch => {
+                let Some(delay) = time.as_deref().and_then(|time| self.ticks(time, obj)) else {
+                    self.fail("vpi_register_cb: time-based callback requires a valid time");
+                    return None;
+                };
+                Some(self.now.saturating_add(delay))
+            }
+            vpi_sys::cbForce
+            | vpi_sys::cbRelease
+            | vpi_sys::cbAssign
+            | vpi_sys::cbDeassign
+            | vpi_sys::cbNextSimTime
+            | vpi_sys::cbEndOfCompile
+            | vpi_sys::cbStartOfSimulation
+            | vpi_sys::cbEndOfSimulation
+            | vpi_sys::cbError
+            | vpi_sys::cbPLIError
+            | vpi_sys::cbTchkViolation
+            | vpi_sys::cbStartOfSave
+            | vpi_sys::cbEndOfSave
+            | vpi_sys::cbStartOfRestart
+            | vpi_sys::cbEndOfRestart
+            | vpi_sys::cbStartOfReset
+            | vpi_sys::cbEndOfReset
+            | vpi_sys::cbEnterInteractive
+            | vpi_sys::cbExitInteractive
+            | vpi_sys::cbInteractiveScopeChange
+            | vpi_sys::cbUnresolvedSystf
+            | vpi_sys::cbStmt
+            | vpi_sys::cbDisable
+            | vpi_sys::cbSignal => None,
+            _ => {
+                self.fail(format!("vpi_register_cb: unsupported reason {reason}"));
+                return None;
+            }
+        };
+
+        let id = self.insert(Object::new(
+            "",
+            None,
+            Kind::Callback(Callback {
+                reason: data.reason,
+                rtn,
+                obj: raw_or_null(obj),
+                time,
+                value,
+                index: data.index,
+                user_data: data.user_data,
+                state: CbState::Active,
+            }),
+        ));
+        self.callbacks.push(id);
+
+        if let Some(at) = slot_time {
+            let slot = self.queue.entry(at).or_default();
+            match reason {
+                vpi_sys::cbAtStartOfSimTime => slot.start.push_back(id),
+                vpi_sys::cbAfterDelay => slot.active.push_back(Item::Callback(id)),
+                vpi_sys::cbReadOnlySynch => slot.read_only.push_back(id),
+                vpi_sys::cbAtEndOfSimTime => slot.end.push_back(id),
+                _ => slot.read_write.push_back(id),
+            }
+        } else if reason == vpi_sys::cbNextSimTime {
+            self.next_sim_time.push(id);
+        }
+        Some(id)
+    }
+
+    /// Collects the data for one callback invocation, retiring one-shot callbacks.
+    fn prepare(&mut self, notify: Notify, retire: bool) -> Option<Prepared> {
+        let Kind::Callback(cb) = &self.objects.get(&notify.cb)?.kind else {
+            return None;
+        };
+        if cb.state != CbState::Active {
+            return None;
+        }
+        let (reason, rtn, obj, user_data) = (cb.reason, cb.rtn, cb.obj, cb.user_data);
+        let time_type = cb.time.as_ref().map(|time| time.type_);
+        let value_format = cb.value.as_ref().map(|value| value.format as u32);
+        let index = if notify.source.is_some() {
+            notify.index
+        } else {
+            cb.index
+        };
+        let obj_id = self.id(obj);
+        let time = time_type.map(|time_type| self.time_struct(self.now, time_type, obj_id));
+        let value = value_format
+            .zip(notify.source)
+            .and_then(|(format, source)| {
+                let pad_bits = obj_id.and_then(|id| self.size(id)).unwrap_or(0);
+                self.encode_value(source, format, pad_bits)
+            });
+        if retire {
+            if let Some(Kind::Callback(cb)) = self.objects.get_mut(&notify.cb).map(|o| &mut o.kind)
+            {
+                cb.state = CbState::Retired;
+            }
+        }
+        Some(Prepared {
+            reason,
+            rtn,
+            obj,
+            time,
+            value,
+            index,
+            user_data,
+        })
+    }
+
+    fn systf_routines(&self, call: usize) -> Option<(vpi_sys::t_vpi_systf_data, *mut PLI_BYTE8)> {
+        let Kind::Call(call) = &self.objects.get(&call)?.kind else {
+            return None;
+        };
+        match &self.objects.get(&call.systf)?.kind {
+            Kind::UserSystf(systf) => Some((systf.data, systf.data.user_data)),
+            _ => None,
+        }
+    }
+
+    fn return_value(&self, call: usize) -> Option<Value> {
+        let Kind::Call(c) = &self.objects.get(&call)?.kind else {
+            return None;
+        };
+        let value = c.ret.as_ref()?;
+        match (self.func_type(c.systf)?, value) {
+            (_, Stored::Real(real)) => Some(Value::Real(*real)),
+            (vpi_sys::vpiSizedFunc | vpi_sys::vpiSizedSignedFunc, Stored::Bits(bits)) => {
+                Some(Value::Vector(LogicVec::from(bits.clone())))
+            }
+            (vpi_sys::vpiTimeFunc, Stored::Bits(bits)) => {
+                Some(Value::Time(crate::Time::Sim(bits_to_u64(bits))))
+            }
+            (_, Stored::Bits(bits)) => Some(Value::Int(bits_to_i64(bits, true) as i32)),
+        }
+    }
+
+    fn iterate(&self, typ: u32, ref_id: Option<usize>) -> Option<Vec<usize>> {
+        let Some(id) = ref_id else {
+            return match typ {
+                vpi_sys::vpiModule => Some(
+                    self.objects
+                        .iter()
+                        .filter(|(_, o)| o.scope.is_none() && matches!(o.kind, Kind::Module(_)))
+                        .map(|(id, _)| *id)
+                        .collect(),
+                ),
+                vpi_sys::vpiUserSystf => Some(
+                    self.objects
+                        .iter()
+                        .filter(|(_, o)| matches!(o.kind, Kind::UserSystf(_)))
+                        .map(|(id, _)| *id)
+                        .collect(),
+                ),
+                vpi_sys::vpiCallback => Some(
+                    self.callbacks
+                        .iter()
+                        .copied()
+                        .filter(|id| {
+                            matches!(self.objects[id].kind,
+                                Kind::Callback(ref cb) if cb.state == CbState::Active)
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            };
+        };
+        let signal_of = |obj_types: &[u32]| {
+            let obj_types = obj_types.to_vec();
+            move |o: &Object| matches!(&o.kind, Kind::Signal(s) if obj_types.contains(&s.obj_type))
+        };
+        let items = match (&self.objects[&id].kind, typ) {
+            (Kind::Module(_), vpi_sys::vpiModule) => self.children(
+                id,
+                |o| matches!(&o.kind, Kind::Module(m) if m.obj_type == vpi_sys::vpiModule),
+            ),
+            (Kind::Module(_), vpi_sys::vpiInternalScope) => self.children(
+                id,
+                |o| matches!(&o.kind, Kind::Module(m) if m.obj_type != vpi_sys::vpiModule),
+            ),
+            (Kind::Module(_), vpi_sys::vpiNet) => self.children(id, signal_of(&[vpi_sys::vpiNet])),
+            (Kind::Module(_), vpi_sys::vpiReg) => self.children(id, signal_of(&[vpi_sys::vpiReg])),
+            (Kind::Module(_), vpi_sys::vpiIntegerVar) => {
+                self.children(id, signal_of(&[vpi_sys::vpiIntegerVar]))
+            }
+            (Kind::Module(_), vpi_sys::vpiRealVar) => {
+                self.children(id, signal_of(&[vpi_sys::vpiRealVar]))
+            }
+            (Kind::Module(_), vpi_sys::vpiVariables) => self.children(
+                id,
+                signal_of(&[vpi_sys::vpiIntegerVar, vpi_sys::vpiRealVar]),
+            ),
+            (Kind::Module(_), vpi_sys::vpiMemory | vpi_sys::vpiRegArray) => {
+                self.children(id, |o| matches!(o.kind, Kind::Array(_)))
+            }
+            (Kind::Module(_), vpi_sys::vpiPort) => {
+                self.children(id, |o| matches!(o.kind, Kind::Port(_)))
+            }
+            (Kind::Module(_), vpi_sys::vpiParameter) => {
+                self.children(id, |o| matches!(o.kind, Kind::Parameter(..)))
+            }
+            (Kind::Module(_), vpi_sys::vpiProcess) => {
+                self.children(id, |o| matches!(o.kind, Kind::Process(_)))
+            }
+            (Kind::Module(_), vpi_sys::vpiParamAssign) => {
+                self.children(id, |o| matches!(o.kind, Kind::ParamAssign(_)))
+            }
+            (Kind::Module(_), vpi_sys::vpiContAssign) => {
+                self.children(id, |o| matches!(o.kind, Kind::ContAssign(_)))
+            }
+            (Kind::Module(_), vpi_sys::vpiPrimitive | vpi_sys::vpiGate) => {
+                self.children(id, |o| matches!(o.kind, Kind::Primitive(_)))
+            }
+            (Kind::Signal(_), vpi_sys::vpiLocalDriver) => self.drivers(id),
+            (Kind::Signal(_), vpi_sys::vpiLocalLoad) => self.loads(id),
+            (Kind::Signal(_), vpi_sys::vpiDriver) => self
+                .collapsed(id)
+                .into_iter()
+                .flat_map(|signal| self.drivers(signal))
+                .collect(),
+            (Kind::Signal(_), vpi_sys::vpiLoad) => self
+                .collapsed(id)
+                .into_iter()
+                .flat_map(|signal| self.loads(signal))
+                .collect(),
+            (Kind::Signal(_), vpi_sys::vpiPortInst) => self
+                .objects
+                .iter()
+                .filter(|(_, o)| {
+                    matches!(&o.kind, Kind::Port(port)
+                        if port.low_conn == Some(id) || port.high_conn == Some(id))
+                })
+                .map(|(id, _)| *id)
+                .collect(),
+            (Kind::Primitive(primitive), vpi_sys::vpiPrimTerm) => primitive.terms.clone(),
+            (Kind::Operation(operation), vpi_sys::vpiOperand) => operation.operands.clone(),
+            (Kind::Array(array), vpi_sys::vpiMemoryWord | vpi_sys::vpiReg) => array.words.clone(),
+            (Kind::Call(call), vpi_sys::vpiArgument) => call.args.clone(),
+            (_, vpi_sys::vpiAttribute) => self
+                .objects
+                .iter()
+                .filter(|(_, o)| o.parent == Some(id) && matches!(o.kind, Kind::Attribute(..)))
+                .map(|(id, _)| *id)
+                .collect(),
+            _ => Vec::new(),
+        };
+        Some(items)
+    }
+
+    fn relation(&mut self, typ: u32, ref_id: Option<usize>) -> Option<usize> {
+        let Some(id) = ref_id else {
+            return match typ {
+                vpi_sys::vpiSysTfCall => self.call_stack.last().copied(),
+                _ => None,
+            };
+        };
+        let object = &self.objects[&id];
+        match (typ, &object.kind) {
+            (vpi_sys::vpiScope, _) => object.scope,
+            (vpi_sys::vpiModule, _) => self.enclosing_module(object.scope?),
+            (vpi_sys::vpiParent, _) => object.parent,
+            (vpi_sys::vpiLowConn, Kind::Port(port)) => port.low_conn,
+            (vpi_sys::vpiHighConn, Kind::Port(port)) => port.high_conn,
+            (vpi_sys::vpiLhs, Kind::ContAssign(assign)) => Some(assign.lhs),
+            (vpi_sys::vpiRhs, Kind::ContAssign(assign)) => Some(assign.rhs),
+            (vpi_sys::vpiLhs, Kind::ParamAssign(assign)) => Some(assign.lhs),
+            (vpi_sys::vpiRhs, Kind::ParamAssign(assign)) => Some(assign.rhs),
+            (vpi_sys::vpiExpr, Kind::PrimTerm(term)) => Some(term.expr),
+            (vpi_sys::vpiPrimitive, Kind::PrimTerm(term)) => Some(term.primitive),
+            (vpi_sys::vpiUserSystf, Kind::Call(call)) => Some(call.systf),
+            (vpi_sys::vpiIndex, Kind::Signal(signal)) => {
+                let index = signal.index?;
+                Some(self.int_constant(index))
+            }
+            (vpi_sys::vpiIndex, Kind::Select(select)) if select.left == select.right => {
+                let index = select.left;
+                Some(self.int_constant(index))
+            }
+            (vpi_sys::vpiLeftRange, Kind::Select(select)) if select.left != select.right => {
+                let left = select.left;
+                Some(self.int_constant(left))
+            }
+            (vpi_sys::vpiRightRange, Kind::Select(select)) if select.left != select.right => {
+                let right = select.right;
+                Some(self.int_constant(right))
+            }
+            (vpi_sys::vpiLeftRange | vpi_sys::vpiRightRange, Kind::Signal(_) | Kind::Array(_)) => {
+                let size = i32::try_from(self.size(id)?).ok()?;
+                let bound = match (typ, &object.kind) {
+                    (vpi_sys::vpiLeftRange, Kind::Signal(_)) => size - 1,
+                    (vpi_sys::vpiRightRange, Kind::Array(_)) => size - 1,
+                    _ => 0,
+                };
+                Some(self.int_constant(bound))
+            }
+            _ => None,
+        }
+    }
+
+    fn int_constant(&mut self, value: i32) -> usize {
+        let constant = Constant {
+            value: bits_from_i64(i64::from(value), 32),
+            signed: true,
+            const_type: vpi_sys::vpiDecConst,
+        };
+        self.insert(Object::new(
+            value.to_string(),
+            None,
+            Kind::Constant(constant),
+        ))
+    }
+
+    fn by_name(&self, name: &str, scope: Option<usize>) -> Option<usize> {
+        let mut candidates = Vec::with_capacity(2);
+        if let Some(scope) = scope {
+            candidates.push(format!("{}.{name}", self.full_name(scope)));
+        }
+        candidates.push(name.to_string());
+        candidates.iter().find_map(|target| {
+            self.objects
+                .iter()
+                .find(|(id, o)| o.is_named() && self.full_name(**id) == *target)
+                .map(|(id, _)| *id)
+        })
+    }
+
+    fn by_index(&self, id: usize, index: i32) -> Option<usize> {
+        match &self.objects[&id].kind {
+            Kind::Array(array) => usize::try_from(index)
+                .ok()
+                .and_then(|index| array.words.get(index).copied()),
+            _ => None,
+        }
+    }
+
+    fn property(&self, property: PLI_INT32, id: Option<usize>) -> Option<PLI_INT32> {
+        let Ok(property) = u32::try_from(property) else {
+            return None;
+        };
+        let Some(id) = id else {
+            return match property {
+                vpi_sys::vpiTimeUnit | vpi_sys::vpiTimePrecision => Some(self.precision()),
+                vpi_sys::vpiSaveRestartID => (self.save_id > 0).then_some(self.save_id),
+                _ => None,
+            };
+        };
+        let object = &self.objects[&id];
+        let as_int = |value: bool| Some(PLI_INT32::from(value));
+        match (property, &object.kind) {
+            (vpi_sys::vpiType, _) => Some(object.vpi_type() as PLI_INT32),
+            (vpi_sys::vpiSize, _) => self.size(id).and_then(|size| i32::try_from(size).ok()),
+            (vpi_sys::vpiLineNo, _) => Some(object.line),
+            (vpi_sys::vpiTopModule, Kind::Module(module))
+                if module.obj_type == vpi_sys::vpiModule =>
+            {
+                as_int(object.scope.is_none())
+            }
+            (vpi_sys::vpiDefLineNo, Kind::Module(_)) => Some(object.line),
+            (vpi_sys::vpiTimeUnit, _) => self.module_of(id).map(|module| module.unit),
+            (vpi_sys::vpiTimePrecision, _) => self.module_of(id).map(|module| module.precision),
+            (vpi_sys::vpiScalar, Kind::Signal(_) | Kind::Port(_)) => {
+                as_int(self.size(id) == Some(1))
+            }
+            (vpi_sys::vpiVector, Kind::Signal(signal)) => {
+                as_int(matches!(&signal.driven, Stored::Bits(bits) if bits.len() > 1))
+            }
+            (vpi_sys::vpiVector, Kind::Port(_)) => as_int(self.size(id).unwrap_or(0) > 1),
+            (vpi_sys::vpiSigned, Kind::Signal(signal)) => as_int(signal.signed),
+            (vpi_sys::vpiSigned, Kind::Parameter(constant, _) | Kind::Constant(constant)) => {
+                as_int(constant.signed)
+            }
+            (vpi_sys::vpiArray | vpi_sys::vpiIsMemory, Kind::Array(_)) => as_int(true),
+            (vpi_sys::vpiArray | vpi_sys::vpiIsMemory, Kind::Signal(_)) => as_int(false),
+            (vpi_sys::vpiImplicitDecl, Kind::Signal(signal)) => as_int(signal.implicit),
+            (vpi_sys::vpiAutomatic, Kind::Signal(_)) => as_int(false),
+            (vpi_sys::vpiDirection, Kind::Port(port)) => Some(port.direction as PLI_INT32),
+            (vpi_sys::vpiPortIndex, Kind::Port(port)) => Some(port.index),
+            (vpi_sys::vpiDirection, Kind::PrimTerm(term)) => Some(term.direction as PLI_INT32),
+            (vpi_sys::vpiTermIndex, Kind::PrimTerm(term)) => Some(term.index),
+            (vpi_sys::vpiPrimType, Kind::Primitive(primitive)) => {
+                Some(primitive.prim_type as PLI_INT32)
+            }
+            (vpi_sys::vpiOpType, Kind::Operation(operation)) => {
+                Some(operation.op_type as PLI_INT32)
+            }
+            (vpi_sys::vpiNetType, Kind::Signal(signal)) if signal.obj_type == vpi_sys::vpiNet => {
+                Some(vpi_sys::vpiWire as PLI_INT32)
+            }
+            (vpi_sys::vpiLocalParam, Kind::Parameter(_, local)) => as_int(*local),
+            (vpi_sys::vpiDefAttribute, Kind::Attribute(_, def_attr)) => as_int(*def_attr),
+            (vpi_sys::vpiConstType, Kind::Parameter(constant, _) | Kind::Constant(constant)) => {
+                Some(constant.const_type as PLI_INT32)
+            }
+            (vpi_sys::vpiScheduled, Kind::Event(event)) => {
+                as_int(event.state == EventState::Pending)
+            }
+            (vpi_sys::vpiSysFuncType, Kind::UserSystf(systf)) => Some(systf.data.sysfunctype),
+            (vpi_sys::vpiSysFuncType, Kind::Call(call)) => {
+                self.func_type(call.systf).map(|t| t as PLI_INT32)
+            }
+            (vpi_sys::vpiUserDefn, Kind::Call(_)) => as_int(true),
+            _ => None,
+        }
+    }
+
+    fn str_property(&self, property: PLI_INT32, id: Option<usize>) -> Option<String> {
+        let Ok(property) = u32::try_from(property) else {
+            return None;
+        };
+        let Some(id) = id else {
+            return (property == vpi_sys::vpiSaveRestartLocation && self.checkpoint.is_some())
+                .then(|| "vpi-mock.chk".to_string());
+        };
+        let object = &self.objects[&id];
+        match (property, &object.kind) {
+            (vpi_sys::vpiType, _) => type_name(object.vpi_type()).map(str::to_string),
+            (
+                vpi_sys::vpiName,
+                Kind::Callback(_)
+                | Kind::Iterator(_)
+                | Kind::Event(_)
+                | Kind::ContAssign(_)
+                | Kind::ParamAssign(_)
+                | Kind::Process(_)
+                | Kind::Operation(_)
+                | Kind::Select(_)
+                | Kind::PrimTerm(_)
+                | Kind::Freed,
+            ) => None,
+            (vpi_sys::vpiName, Kind::UserSystf(systf)) => {
+                Some(systf.name.to_string_lossy().into_owned())
+            }
+            (vpi_sys::vpiName, _) => Some(object.name.clone()),
+            (vpi_sys::vpiFullName, Kind::Module(_) | Kind::Signal(_) | Kind::Array(_)) => {
+                Some(self.full_name(id))
+            }
+            (vpi_sys::vpiFullName, Kind::Port(_) | Kind::Parameter(..) | Kind::Primitive(_)) => {
+                Some(self.full_name(id))
+            }
+            (vpi_sys::vpiDefName, Kind::Module(module))
+                if module.obj_type == vpi_sys::vpiModule =>
+            {
+                Some(module.def_name.clone())
+            }
+            (vpi_sys::vpiDefName, Kind::Primitive(primitive)) => Some(primitive.def_name.clone()),
+            (vpi_sys::vpiFile, _) => object
+                .file
+                .as_ref()
+                .map(|file| file.to_string_lossy().into_owned()),
+            (vpi_sys::vpiDefFile, Kind::Module(_)) => object
+                .file
+                .as_ref()
+                .map(|file| file.to_string_lossy().into_owned()),
+            _ => None,
+        }
+    }
+}
+
+struct Prepared {
+    reason: PLI_INT32,
+    rtn: CbRoutine,
+    obj: vpiHandle,
+    time: Option<vpi_sys::t_vpi_time>,
+    value: Option<Encoded>,
+    index: PLI_INT32,
+    user_data: *mut PLI_BYTE8,
+}
+
+/// Invokes a callback routine without holding the simulator state borrow.
+fn fire(notify: Notify, retire: bool) {
+    let Some(mut prepared) = with_sim(|s| s.prepare(notify, retire)) else {
+        return;
+    };
+    let mut data = vpi_sys::t_cb_data {
+        reason: prepared.reason,
+        cb_rtn: Some(prepared.rtn),
+        obj: prepared.obj,
+        time: prepared
+            .time
+            .as_mut()
+            .map_or(std::ptr::null_mut(), std::ptr::from_mut),
+        value: prepared
+            .value
+            .as_mut()
+            .map_or(std::ptr::null_mut(), |value| &raw mut value.raw),
+        index: prepared.index,
+        user_data: prepared.user_data,
+    };
+    unsafe { (prepared.rtn)(&raw mut data) };
+}
+
+fn fire_all(notify: Vec<Notify>) {
+    for notify in notify {
+        fire(notify, false);
+    }
+}
+
+/// Fires the action callbacks of `reason`, which stay registered.
+fn fire_reason(reason: u32) {
+    for cb in with_sim(|s| s.callbacks_for(reason)) {
+        fire(Notify::plain(cb), false);
+    }
+}
+
+/// Runs `compiletf` and, for sized functions, `sizetf` of a call site.
+fn compile_call(call: usize) {
+    let routines = with_sim(|s| {
+        if let Some(Kind::Call(c)) = s.objects.get_mut(&call).map(|o| &mut o.kind) {
+            c.compiled = true;
+        }
+        s.systf_routines(call)
+    });
+    let Some((data, user_data)) = routines else {
+        return;
+    };
+    with_sim(|s| s.call_stack.push(call));
+    if let Some(compiletf) = data.compiletf {
+        unsafe { compiletf(user_data) };
+    }
+    if let Some(sizetf) = data.sizetf {
+        if data.type_ as u32 == vpi_sys::vpiSysFunc {
+            let size = unsafe { sizetf(user_data) };
+            with_sim(|s| {
+                if let Some(Kind::Call(c)) = s.objects.get_mut(&call).map(|o| &mut o.kind) {
+                    c.size = usize::try_from(size).ok().filter(|size| *size > 0);
+                }
+            });
+        }
+    }
+    with_sim(|s| s.call_stack.pop());
+}
+
+enum RunEnd {
+    Idle,
+    Limit,
+    Stopped,
+    Finished,
+}
+
+enum Step {
+    End(RunEnd),
+    Advance(Vec<usize>),
+    Item(Item),
+    Continue,
+}
+
+fn run_loop(limit: Option<u64>) -> RunEnd {
+    loop {
+        let step = with_sim(|s| {
+            if s.finish_requested {
+                return Step::End(RunEnd::Finished);
+            }
+            if s.stop_requested {
+                s.stop_requested = false;
+                return Step::End(RunEnd::Stopped);
+            }
+            let Some(&time) = s.queue.keys().next() else {
+                return Step::End(RunEnd::Idle);
+            };
+            if limit.is_some_and(|limit| time > limit) {
+                return Step::End(RunEnd::Limit);
+            }
+            if time > s.now {
+                s.now = time;
+                return Step::Advance(std::mem::take(&mut s.next_sim_time));
+            }
+            match s.queue.get_mut(&time).and_then(Slot::pop) {
+                Some(item) => Step::Item(item),
+                None => {
+                    s.queue.remove(&time);
+                    Step::Continue
+                }
+            }
+        });
+        match step {
+            Step::End(end) => return end,
+            Step::Advance(callbacks) => {
+                for cb in callbacks {
+                    fire(Notify::plain(cb), true);
+                }
+            }
+            Step::Item(Item::Callback(cb)) => fire(Notify::plain(cb), true),
+            Step::Item(Item::Event(event)) => fire_all(with_sim(|s| s.apply_event(event))),
+            Step::Continue => {}
+        }
+    }
+}
+
+fn type_name(obj_type: u32) -> Option<&'static str> {
+    Some(match obj_type {
+        vpi_sys::vpiModule => "vpiModule",
+        vpi_sys::vpiGenScope => "vpiGenScope",
+        vpi_sys::vpiNet => "vpiNet",
+        vpi_sys::vpiReg => "vpiReg",
+        vpi_sys::vpiIntegerVar => "vpiIntegerVar",
+        vpi_sys::vpiRealVar => "vpiRealVar",
+        vpi_sys::vpiMemory => "vpiMemory",
+        vpi_sys::vpiMemoryWord => "vpiMemoryWord",
+        vpi_sys::vpiPort => "vpiPort",
+        vpi_sys::vpiContAssign => "vpiContAssign",
+        vpi_sys::vpiOperation => "vpiOperation",
+        vpi_sys::vpiBitSelect => "vpiBitSelect",
+        vpi_sys::vpiPartSelect => "vpiPartSelect",
+        vpi_sys::vpiGate => "vpiGate",
+        vpi_sys::vpiPrimTerm => "vpiPrimTerm",
+        vpi_sys::vpiParameter => "vpiParameter",
+        vpi_sys::vpiParamAssign => "vpiParamAssign",
+        vpi_sys::vpiAlways => "vpiAlways",
+        vpi_sys::vpiAttribute => "vpiAttribute",
+        vpi_sys::vpiInitial => "vpiInitial",
+        vpi_sys::vpiConstant => "vpiConstant",
+        vpi_sys::vpiIterator => "vpiIterator",
+        vpi_sys::vpiCallback => "vpiCallback",
+        vpi_sys::vpiUserSystf => "vpiUserSystf",
+        vpi_sys::vpiSysTaskCall => "vpiSysTaskCall",
+        vpi_sys::vpiSysFuncCall => "vpiSysFuncCall",
+        vpi_sys::vpiSchedEvent => "vpiSchedEvent",
+        _ => return None,
+    })
+}
+
+/// A `t_vpi_value` together with the storage its pointers refer to.
+struct Encoded {
+    raw: vpi_sys::t_vpi_value,
+    _string: Option<CString>,
+    _vector: Option<Vec<vpi_sys::t_vpi_vecval>>,
+    _time: Option<Box<vpi_sys::t_vpi_time>>,
+    _strength: Option<Box<vpi_sys::t_vpi_strengthval>>,
+}
+
+/// Encodes a stored value in the requested VPI format.
+///
+/// `vpiObjTypeVal` selects `native`. Vector buffers are padded to at least
+/// `pad_bits`, since callers size them from `vpiSize` of the callback object.
+fn encode(
+    value: &Stored,
+    format: u32,
+    signed: bool,
+    native: u32,
+    pad_bits: usize,
+) -> Option<Encoded> {
+    let format = if format == vpi_sys::vpiObjTypeVal {
+        native
+    } else {
+        format
+    };
+    let mut encoded = Encoded {
+        raw: vpi_sys::t_vpi_value {
+            format: format as PLI_INT32,
+            value: vpi_sys::t_vpi_value__bindgen_ty_1 { integer: 0 },
+        },
+        _string: None,
+        _vector: None,
+        _time: None,
+        _strength: None,
+    };
+    let bits = match value {
+        Stored::Bits(bits) => bits.clone(),
+        Stored::Real(real) => match format {
+            vpi_sys::vpiRealVal | vpi_sys::vpiShortRealVal => {
+                encoded.raw.value.real = *real;
+                return Some(encoded);
+            }
+            vpi_sys::vpiStringVal => {
+                let text = CString::new(real.to_string()).ok()?;
+                encoded.raw.value.str_ = text.as_ptr().cast_mut();
+                encoded._string = Some(text);
+                return Some(encoded);
+            }
+            _ => match bits_from_i64(real.round() as i64, 64) {
+                Stored::Bits(bits) => bits,
+                Stored::Real(_) => unreachable!(),
+            },
+        },
+    };
+    let signed = signed || matches!(value, Stored::Real(_));
+    match format {
+        vpi_sys::vpiBinStrVal
+        | vpi_sys::vpiOctStrVal
+        | vpi_sys::vpiHexStrVal
+        | vpi_sys::vpiDecStrVal
+        | vpi_sys::vpiStringVal => {
+            let text = match format {
+                vpi_sys::vpiBinStrVal => bits.iter().map(|bit| bit_char(*bit)).collect(),
+                vpi_sys::vpiOctStrVal => radix_string(&bits, 3),
+                vpi_sys::vpiHexStrVal => radix_string(&bits, 4),
+                vpi_sys::vpiDecStrVal => decimal_string(&bits, signed),
+                _ => bits_to_text(&bits),
+            };
+            let text = CString::new(text).ok()?;
+            encoded.raw.value.str_ = text.as_ptr().cast_mut();
+            encoded._string = Some(text);
+        }
+        vpi_sys::vpiScalarVal => {
+            encoded.raw.value.scalar = bits.last().copied().unwrap_or(LogicVal::X) as PLI_INT32;
+        }
+        vpi_sys::vpiIntVal => encoded.raw.value.integer = bits_to_i64(&bits, signed) as i32,
+        vpi_sys::vpiShortIntVal => {
+            encoded.raw.value.integer = i32::from(bits_to_i64(&bits, signed) as i16);
+        }
+        vpi_sys::vpiRealVal | vpi_sys::vpiShortRealVal => {
+            encoded.raw.value.real = if signed {
+                bits_to_i64(&bits, true) as f64
+            } else {
+                bits_to_u64(&bits) as f64
+            };
+        }
+        vpi_sys::vpiVectorVal => {
+            let mut vector = scalar_vector_to_vecval(&bits);
+            let words = bits.len().max(pad_bits).div_ceil(32).max(1);
+            vector.resize(words, vpi_sys::t_vpi_vecval { aval: 0, bval: 0 });
+            encoded.raw.value.vector = vector.as_mut_ptr();
+            encoded._vector = Some(vector);
+        }
+        vpi_sys::vpiStrengthVal => {
+            let mut strength = Box::new(vpi_sys::t_vpi_strengthval {
+                logic: bits.last().copied().unwrap_or(LogicVal::X) as PLI_INT32,
+                s0: vpi_sys::vpiStrongDrive as PLI_INT32,
+                s1: vpi_sys::vpiStrongDrive as PLI_INT32,
+            });
+            encoded.raw.value.strength = strength.as_mut();
+            encoded._strength = Some(strength);
+        }
+        vpi_sys::vpiTimeVal => {
+            let ticks = bits_to_u64(&bits);
+            let mut time = Box::new(vpi_sys::t_vpi_time {
+                type_: vpi_sys::vpiSimTime as PLI_INT32,
+                high: (ticks >> 32) as u32,
+                low: (ticks & 0xFFFF_FFFF) as u32,
+                real: 0.0,
+            });
+            encoded.raw.value.time = time.as_mut();
+            encoded._time = Some(time);
+        }
+        vpi_sys::vpiSuppressVal => {}
+        _ => return None,
+    }
+    Some(encoded)
+}
+
+/// Converts an incoming `t_vpi_value` to the shape of `target`.
+///
+/// # Safety
+///
+/// Pointer members of `raw` selected by its format must be valid.
+unsafe fn decode_put(raw: &vpi_sys::t_vpi_value, target: Target) -> Result<Stored, String> {
+    let format = raw.format as u32;
+    let read_str = || {
+        let ptr = unsafe { raw.value.str_ };
+        if ptr.is_null() {
+            Err("null string".to_string())
+        } else {
+            Ok(unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned())
+        }
+    };
+    let read_time = || {
+        let ptr = unsafe { raw.value.time };
+        if ptr.is_null() {
+            Err("null time".to_string())
+        } else {
+            let time = unsafe { *ptr };
+            match time.type_ as u32 {
+                vpi_sys::vpiScaledRealTime => Ok(time.real.round() as u64),
+                _ => Ok(u64::from(time.high) << 32 | u64::from(time.low)),
+            }
+        }
+    };
+    let read_scalar = |value: PLI_INT32| LogicVal::from_i32(value).map_or(LogicVal::X, normalize);
+
+    if target.real {
+        let real = match format {
+            vpi_sys::vpiRealVal | vpi_sys::vpiShortRealVal => unsafe { raw.value.real },
+            vpi_sys::vpiIntVal | vpi_sys::vpiShortIntVal => f64::from(unsafe { raw.value.integer }),
+            vpi_sys::vpiScalarVal => {
+                if read_scalar(unsafe { raw.value.scalar }) == LogicVal::One {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            vpi_sys::vpiDecStrVal => read_str()?
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| "invalid real value".to_string())?,
+            vpi_sys::vpiTimeVal => read_time()? as f64,
+            _ => return Err(format!("format {format} cannot be written to a real")),
+        };
+        return Ok(Stored::Real(real));
+    }
+
+    let width = target.width;
+    let bits = match format {
+        vpi_sys::vpiBinStrVal => parse_radix(&read_str()?, 1),
+        vpi_sys::vpiOctStrVal => parse_radix(&read_str()?, 3),
+        vpi_sys::vpiHexStrVal => parse_radix(&read_str()?, 4),
+        vpi_sys::vpiDecStrVal => parse_decimal(&read_str()?, width),
+        vpi_sys::vpiStringVal => Some(string_to_bits(read_str()?.as_bytes())),
+        vpi_sys::vpiScalarVal => Some(vec![read_scalar(unsafe { raw.value.scalar })]),
+        vpi_sys::vpiIntVal | vpi_sys::vpiShortIntVal => {
+            let Stored::Bits(bits) = bits_from_i64(i64::from(unsafe { raw.value.integer }), width)
+            else {
+                unreachable!()
+            };
+            Some(bits)
+        }
+        vpi_sys::vpiRealVal | vpi_sys::vpiShortRealVal => {
+            let Stored::Bits(bits) = bits_from_i64(unsafe { raw.value.real }.round() as i64, width)
+            else {
+                unreachable!()
+            };
+            Some(bits)
+        }
+        vpi_sys::vpiVectorVal => {
+            let ptr = unsafe { raw.value.vector };
+            if ptr.is_null() {
+                return Err("null vector".to_string());
+            }
+            let words = unsafe { std::slice::from_raw_parts(ptr, width.div_ceil(32)) };
+            Some(
+                LogicVec::from_vecval(words, width)
+                    .iter()
+                    .copied()
+                    .map(normalize)
+                    .collect(),
+            )
+        }
+        vpi_sys::vpiStrengthVal => {
+            let ptr = unsafe { raw.value.strength };
+            if ptr.is_null() {
+                return Err("null strength".to_string());
+            }
+            Some(vec![read_scalar(unsafe { (*ptr).logic })])
+        }
+        vpi_sys::vpiTimeVal => {
+            let Stored::Bits(bits) = bits_from_u64(read_time()?, width) else {
+                unreachable!()
+            };
+            Some(bits)
+        }
+        _ => return Err(format!("unsupported value format {format}")),
+    };
+    let bits = bits.ok_or_else(|| "malformed value string".to_string())?;
+    Ok(Stored::Bits(resize(&bits, width)))
+}
+
+fn normalize(bit: LogicVal) -> LogicVal {
+    match bit {
+        LogicVal::H => LogicVal::One,
+        LogicVal::L => LogicVal::Zero,
+        LogicVal::DontCare => LogicVal::X,
+        bit => bit,
+    }
+}
+
+fn bit_char(bit: LogicVal) -> char {
+    match bit {
+        LogicVal::Zero => '0',
+        LogicVal::One => '1',
+        LogicVal::Z => 'z',
+        _ => 'x',
+    }
+}
+
+fn bit(value: bool) -> LogicVal {
+    if value {
+        LogicVal::One
+    } else {
+        LogicVal::Zero
+    }
+}
+
+fn bits_from_i64(value: i64, width: usize) -> Stored {
+    Stored::Bits(
+        (0..width)
+            .rev()
+            .map(|i| {
+                bit(if i >= 64 {
+                    value < 0
+                } else {
+                    (value >> i) & 1 == 1
+                })
+            })
+            .collect(),
+    )
+}
+
+fn bits_from_u64(value: u64, width: usize) -> Stored {
+    Stored::Bits(
+        (0..width)
+            .rev()
+            .map(|i| bit(i < 64 && (value >> i) & 1 == 1))
+            .collect(),
+    )
+}
+
+/// Returns the low 64 bits as an unsigned value; `x` and `z` read as `0`.
+fn bits_to_u64(bits: &[LogicVal]) -> u64 {
+    bits.iter()
+        .rev()
+        .take(64)
+        .enumerate()
+        .fold(0, |acc, (i, b)| acc | (u64::from(*b == LogicVal::One) << i))
+}
+
+fn bits_to_i64(bits: &[LogicVal], signed: bool) -> i64 {
+    let value = bits_to_u64(bits);
+    let len = bits.len();
+    if signed && len > 0 && len < 64 && bits[0] == LogicVal::One {
+        (value | (u64::MAX << len)) as i64
+    } else {
+        value as i64
+    }
+}
+
+/// Truncates or extends `bits` to `width`, extending `x`/`z` like Verilog does.
+fn resize(bits: &[LogicVal], width: usize) -> Vec<LogicVal> {
+    if bits.len() >= width {
+        return bits[bits.len() - width..].to_vec();
+    }
+    let fill = match bits.first() {
+        Some(LogicVal::X) => LogicVal::X,
+        Some(LogicVal::Z) => LogicVal::Z,
+        _ => LogicVal::Zero,
+    };
+    let mut out = vec![fill; width - bits.len()];
+    out.extend_from_slice(bits);
+    out
+}
+
+fn parse_radix(text: &str, bits_per_digit: usize) -> Option<Vec<LogicVal>> {
+    let mut bits = Vec::new();
+    for c in text.trim().chars().filter(|c| *c != '_') {
+        match c.to_ascii_lowercase() {
+            'x' => bits.extend(std::iter::repeat_n(LogicVal::X, bits_per_digit)),
+            'z' | '?' => bits.extend(std::iter::repeat_n(LogicVal::Z, bits_per_digit)),
+            c => {
+                let digit = c.to_digit(1 << bits_per_digit)?;
+                bits.extend(
+                    (0..bits_per_digit)
+                        .rev()
+                        .map(|i| bit((digit >> i) & 1 == 1)),
+                );
+            }
+        }
+    }
+    (!bits.is_empty()).then_some(bits)
+}
+
+fn radix_string(bits: &[LogicVal], bits_per_digit: usize) -> String {
+    let mut digits = Vec::new();
+    let mut end = bits.len();
+    while end > 0 {
+        let start = end.saturating_sub(bits_per_digit);
+        let chunk = &bits[start..end];
+        let digit = if chunk.iter().all(|b| *b == LogicVal::Z) {
+            'z'
+        } else if chunk.iter().any(|b| matches!(b, LogicVal::X | LogicVal::Z)) {
+            'x'
+        } else {
+            let value = chunk
+                .iter()
+                .fold(0, |acc, b| (acc << 1) | u32::from(*b == LogicVal::One));
+            char::from_digit(value, 16).unwrap_or('x')
+        };
+        digits.push(digit);
+        end = start;
+    }
+    digits.iter().rev().collect()
+}
+
+fn decimal_string(bits: &[LogicVal], signed: bool) -> String {
+    if !bits.is_empty() && bits.iter().all(|b| *b == LogicVal::Z) {
+        return "z".to_string();
+    }
+    if bits.iter().any(|b| matches!(b, LogicVal::X | LogicVal::Z)) {
+        return "x".to_string();
+    }
+    let negative = signed && bits.first() == Some(&LogicVal::One);
+    let magnitude = if negative {
+        negate(bits)
+    } else {
+        bits.to_vec()
+    };
+    let mut limbs = vec![0u32; magnitude.len().div_ceil(32).max(1)];
+    for (i, b) in magnitude.iter().rev().enumerate() {
+        if *b == LogicVal::One {
+            limbs[i / 32] |= 1 << (i % 32);
+        }
+    }
+    let mut digits = Vec::new();
+    while limbs.iter().any(|limb| *limb != 0) {
+        let mut rem = 0u64;
+        for limb in limbs.iter_mut().rev() {
+            let cur = (rem << 32) | u64::from(*limb);
+            *limb = (cur / 10) as u32;
+            rem = cur % 10;
+        }
+        digits.push(char::from(b'0' + rem as u8));
+    }
+    if digits.is_empty() {
+        digits.push('0');
+    }
+    if negative {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
+
+fn parse_decimal(text: &str, width: usize) -> Option<Vec<LogicVal>> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if digits.eq_ignore_ascii_case("x") {
+        return Some(vec![LogicVal::X; width]);
+    }
+    if digits.eq_ignore_ascii_case("z") {
+        return Some(vec![LogicVal::Z; width]);
+    }
+    let mut limbs = vec![0u32; width.div_ceil(32) + 1];
+    let mut any = false;
+    for c in digits.chars().filter(|c| *c != '_') {
+        let mut carry = u64::from(c.to_digit(10)?);
+        any = true;
+        for limb in &mut limbs {
+            let cur = u64::from(*limb) * 10 + carry;
+            *limb = (cur & 0xFFFF_FFFF) as u32;
+            carry = cur >> 32;
+        }
+    }
+    if !any {
+        return None;
+    }
+    let bits: Vec<LogicVal> = (0..width)
+        .rev()
+        .map(|i| bit((limbs[i / 32] >> (i % 32)) & 1 == 1))
+        .collect();
+    Some(if negative { negate(&bits) } else { bits })
+}
+
+/// Two's complement negation of a vector of known bits.
+fn negate(bits: &[LogicVal]) -> Vec<LogicVal> {
+    let mut out: Vec<LogicVal> = bits.iter().map(|b| bit(*b != LogicVal::One)).collect();
+    for b in out.iter_mut().rev() {
+        if *b == LogicVal::One {
+            *b = LogicVal::Zero;
+        } else {
+            *b = LogicVal::One;
+            break;
+        }
+    }
+    out
+}
+
+fn string_to_bits(bytes: &[u8]) -> Vec<LogicVal> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| bit((byte >> i) & 1 == 1)))
+        .collect()
+}
+
+fn bits_to_text(bits: &[LogicVal]) -> String {
+    let padded = resize(bits, bits.len().div_ceil(8) * 8);
+    padded
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(0u8, |acc, b| (acc << 1) | u8::from(*b == LogicVal::One))
+        })
+        .filter(|byte| *byte != 0)
+        .map(char::from)
+        .collect()
+}
+
+fn copy_cstring(s: &mut SimState, text: Option<String>) -> *mut PLI_BYTE8 {
+    match text.and_then(|text| CString::new(text).ok()) {
+        Some(text) => {
+            s.scratch_str = text;
+            s.scratch_str.as_ptr().cast_mut()
+        }
+        None => std::ptr::null_mut(),
+    }
+}
+
+fn format_printf(format: *mut PLI_BYTE8, arg: *mut PLI_BYTE8) -> String {
+    if format.is_null() {
+        return String::new();
+    }
+    let format = unsafe { CStr::from_ptr(format) }.to_string_lossy();
+    let mut out = String::new();
+    let mut arg = Some(arg).filter(|arg| !arg.is_null());
+    let mut rest = format.as_ref();
+    while let Some(pos) = rest.find('%') {
+        out.push_str(&rest[..pos]);
+        let spec = &rest[pos + 1..];
+        if let Some(after) = spec.strip_prefix('%') {
+            out.push('%');
+            rest = after;
+        } else if let Some(after) = spec.strip_prefix('s') {
+            if let Some(arg) = arg.take() {
+                out.push_str(&unsafe { CStr::from_ptr(arg) }.to_string_lossy());
+            }
+            rest = after;
+        } else {
+            out.push('%');
+            rest = spec;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn write_mcd(s: &mut SimState, mcd: PLI_UINT32, text: &str) {
+    for bit in 0..32 {
+        if mcd & (1 << bit) == 0 {
+            continue;
+        }
+        if bit == 0 {
+            s.output.push_str(text);
+        } else if let Some(name) = s.files.get(&bit) {
+            s.file_contents
+                .entry(name.clone())
+                .or_default()
+                .push_str(text);
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_register_cb(cb_data_p: vpi_sys::p_cb_data) -> vpiHandle {
+    api(|s| {
+        if cb_data_p.is_null() {
+            s.fail("vpi_register_cb: null callback data");
+            return std::ptr::null_mut();
+        }
+        raw_or_null(s.register_cb(unsafe { &*cb_data_p }))
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_remove_cb(cb_obj: vpiHandle) -> PLI_INT32 {
+    api(|s| {
+        let id = s.id(cb_obj);
+        match id
+            .and_then(|id| s.objects.get_mut(&id))
+            .map(|o| &mut o.kind)
+        {
+            Some(Kind::Callback(cb)) => {
+                let was_active = cb.state == CbState::Active;
+                cb.state = CbState::Removed;
+                if let Some(id) = id {
+                    s.unschedule(id);
+                }
+                PLI_INT32::from(was_active)
+            }
+            _ => {
+                s.fail("vpi_remove_cb: not a callback handle");
+                0
+            }
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_cb_info(object: vpiHandle, cb_data_p: vpi_sys::p_cb_data) {
+    if cb_data_p.is_null() {
+        return;
+    }
+    api(|s| {
+        let info = match s.object_mut(object).map(|o| &mut o.kind) {
+            Some(Kind::Callback(cb)) if cb.state != CbState::Removed => Some(vpi_sys::t_cb_data {
+                reason: cb.reason,
+                cb_rtn: Some(cb.rtn),
+                obj: cb.obj,
+                time: cb
+                    .time
+                    .as_deref_mut()
+                    .map_or(std::ptr::null_mut(), std::ptr::from_mut),
+                value: cb
+                    .value
+                    .as_deref_mut()
+                    .map_or(std::ptr::null_mut(), std::ptr::from_mut),
+                index: cb.index,
+                user_data: cb.user_data,
+            }),
+            _ => None,
+        };
+        match info {
+            Some(info) => unsafe { *cb_data_p = info },
+            None => s.fail("vpi_get_cb_info: not an active callback handle"),
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_register_systf(systf_data_p: vpi_sys::p_vpi_systf_data) -> vpiHandle {
+    api(|s| {
+        if systf_data_p.is_null() {
+            s.fail("vpi_register_systf: null systf data");
+            return std::ptr::null_mut();
+        }
+        let mut data = unsafe { *systf_data_p };
+        if data.tfname.is_null() {
+            s.fail("vpi_register_systf: missing tfname");
+            return std::ptr::null_mut();
+        }
+        let name = unsafe { CStr::from_ptr(data.tfname) }.to_owned();
+        if !name.to_bytes().starts_with(b"$") {
+            s.fail("vpi_register_systf: tfname must start with '$'");
+            return std::ptr::null_mut();
+        }
+        let duplicate = s
+            .objects
+            .values()
+            .any(|o| matches!(&o.kind, Kind::UserSystf(systf) if systf.name == name));
+        if duplicate {
+            s.fail("vpi_register_systf: name already registered");
+            return std::ptr::null_mut();
+        }
+        data.tfname = name.as_ptr().cast_mut();
+        let label = name.to_string_lossy().into_owned();
+        raw(s.insert(Object::new(
+            label,
+            None,
+            Kind::UserSystf(Systf { data, name }),
+        )))
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_systf_info(
+    object: vpiHandle,
+    systf_data_p: vpi_sys::p_vpi_systf_data,
+) {
+    if systf_data_p.is_null() {
+        return;
+    }
+    api(|s| {
+        let systf = match s.object(object).map(|o| &o.kind) {
+            Some(Kind::UserSystf(_)) => s.id(object),
+            Some(Kind::Call(call)) => Some(call.systf),
+            _ => None,
+        };
+        match systf.and_then(|id| match &s.objects[&id].kind {
+            Kind::UserSystf(systf) => Some(systf.data),
+            _ => None,
+        }) {
+            Some(data) => unsafe { *systf_data_p = data },
+            None => s.fail("vpi_get_systf_info: not a systf or systf call handle"),
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_handle_by_name(name: *mut PLI_BYTE8, scope: vpiHandle) -> vpiHandle {
+    api(|s| {
+        if name.is_null() {
+            s.fail("vpi_handle_by_name: null name");
+            return std::ptr::null_mut();
+        }
+        let name = unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned();
+        let scope = if scope.is_null() {
+            None
+        } else if let Some(scope) = s.id(scope) {
+            Some(scope)
+        } else {
+            s.fail("vpi_handle_by_name: invalid scope handle");
+            return std::ptr::null_mut();
+        };
+        raw_or_null(s.by_name(&name, scope))
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_handle_by_index(object: vpiHandle, indx: PLI_INT32) -> vpiHandle {
+    api(|s| match s.id(object) {
+        Some(id) => raw_or_null(s.by_index(id, indx)),
+        None => {
+            s.fail("vpi_handle_by_index: invalid handle");
+            std::ptr::null_mut()
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_handle_by_multi_index(
+    obj: vpiHandle,
+    num_index: PLI_INT32,
+    index_array: *mut PLI_INT32,
+) -> vpiHandle {
+    api(|s| match s.id(obj) {
+        Some(id) if num_index == 1 && !index_array.is_null() => {
+            raw_or_null(s.by_index(id, unsafe { *index_array }))
+        }
+        _ => {
+            s.fail("vpi_handle_by_multi_index: only one-dimensional arrays are modeled");
+            std::ptr::null_mut()
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_handle(type_: PLI_INT32, ref_handle: vpiHandle) -> vpiHandle {
+    api(|s| {
+        let ref_id = if ref_handle.is_null() {
+            None
+        } else if let Some(id) = s.id(ref_handle) {
+            Some(id)
+        } else {
+            s.fail("vpi_handle: invalid reference handle");
+            return std::ptr::null_mut();
+        };
+        raw_or_null(s.relation(type_ as u32, ref_id))
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_handle_multi(
+    _type: PLI_INT32,
+    _ref_handle1: vpiHandle,
+    _ref_handle2: vpiHandle,
+) -> vpiHandle {
+    api(|s| {
+        s.fail("vpi_handle_multi: no multi-handle relations are modeled");
+        std::ptr::null_mut()
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_iterate(type_: PLI_INT32, ref_handle: vpiHandle) -> vpiHandle {
+    api(|s| {
+        let ref_id = if ref_handle.is_null() {
+            None
+        } else if let Some(id) = s.id(ref_handle) {
+            Some(id)
+        } else {
+            s.fail("vpi_iterate: invalid reference handle");
+            return std::ptr::null_mut();
+        };
+        match s.iterate(type_ as u32, ref_id) {
+            Some(items) if items.is_empty() => std::ptr::null_mut(),
+            Some(items) => raw(s.insert(Object::new("", None, Kind::Iterator(items.into())))),
+            None => {
+                s.fail(format!("vpi_iterate: unsupported type {type_}"));
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_scan(iterator: vpiHandle) -> vpiHandle {
+    api(|s| {
+        let Some(object) = s.object_mut(iterator) else {
+            s.fail("vpi_scan: invalid iterator handle");
+            return std::ptr::null_mut();
+        };
+        let Kind::Iterator(items) = &mut object.kind else {
+            s.fail("vpi_scan: not an iterator handle");
+            return std::ptr::null_mut();
+        };
+        match items.pop_front() {
+            Some(id) => raw(id),
+            None => {
+                // Exhausted iterators are freed by the simulator.
+                object.kind = Kind::Freed;
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get(property: PLI_INT32, object: vpiHandle) -> PLI_INT32 {
+    api(|s| {
+        let id = if object.is_null() {
+            None
+        } else if let Some(id) = s.id(object) {
+            Some(id)
+        } else {
+            s.fail("vpi_get: invalid handle");
+            return vpi_sys::vpiUndefined;
+        };
+        match s.property(property, id) {
+            Some(value) => value,
+            None => {
+                s.fail(format!("vpi_get: property {property} is not available"));
+                vpi_sys::vpiUndefined
+            }
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get64(property: PLI_INT32, object: vpiHandle) -> vpi_sys::PLI_INT64 {
+    vpi_sys::PLI_INT64::from(unsafe { vpi_get(property, object) })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_str(property: PLI_INT32, object: vpiHandle) -> *mut PLI_BYTE8 {
+    api(|s| {
+        let id = if object.is_null() {
+            None
+        } else if let Some(id) = s.id(object) {
+            Some(id)
+        } else {
+            s.fail("vpi_get_str: invalid handle");
+            return std::ptr::null_mut();
+        };
+        let text = s.str_property(property, id);
+        if text.is_none() {
+            s.fail(format!("vpi_get_str: property {property} is not available"));
+        }
+        copy_cstring(s, text)
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_delays(object: vpiHandle, delay_p: vpi_sys::p_vpi_delay) {
+    if delay_p.is_null() {
+        return;
+    }
+    api(|s| {
+        let Some(id) = s.id(object) else {
+            s.fail("vpi_get_delays: invalid handle");
+            return;
+        };
+        let delay = unsafe { &mut *delay_p };
+        let stored = s.delays.get(&id).cloned().unwrap_or_default();
+        let capacity = usize::try_from(delay.no_of_delays).unwrap_or(0);
+        let count = stored.len().min(capacity);
+        if !delay.da.is_null() {
+            for (i, time) in stored.iter().take(count).enumerate() {
+                let ticks = s.ticks(time, Some(id)).unwrap_or(0);
+                unsafe { *delay.da.add(i) = s.time_struct(ticks, delay.time_type, Some(id)) };
+            }
+        }
+        delay.no_of_delays = i32::try_from(count).unwrap_or(0);
+    });
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_put_delays(object: vpiHandle, delay_p: vpi_sys::p_vpi_delay) {
+    if delay_p.is_null() {
+        return;
+    }
+    api(|s| {
+        let Some(id) = s.id(object) else {
+            s.fail("vpi_put_delays: invalid handle");
+            return;
+        };
+        let delay = unsafe { &*delay_p };
+        let count = usize::try_from(delay.no_of_delays).unwrap_or(0);
+        let times = if delay.da.is_null() || count == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(delay.da, count) }.to_vec()
+        };
+        let entry = s.delays.entry(id).or_default();
+        if delay.append_flag == 0 {
+            entry.clear();
+        }
+        entry.extend(times);
+    });
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_value(expr: vpiHandle, value_p: vpi_sys::p_vpi_value) {
+    if value_p.is_null() {
+        return;
+    }
+    api(|s| {
+        let format = unsafe { (*value_p).format } as u32;
+        let encoded = s.id(expr).and_then(|id| s.encode_value(id, format, 0));
+        match encoded {
+            Some(encoded) => {
+                unsafe { *value_p = encoded.raw };
+                s.scratch_value = Some(encoded);
+            }
+            None => {
+                // Report the failure in-band so callers decode no value.
+                unsafe { (*value_p).format = vpi_sys::vpiSuppressVal as PLI_INT32 };
+                s.fail(format!("vpi_get_value: format {format} is not available"));
+            }
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_put_value(
+    object: vpiHandle,
+    value_p: vpi_sys::p_vpi_value,
+    time_p: vpi_sys::p_vpi_time,
+    flags: PLI_INT32,
+) -> vpiHandle {
+    let (notify, event) = api(|s| s.put_value(object, value_p, time_p, flags));
+    fire_all(notify);
+    raw_or_null(event)
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_value_array(
+    object: vpiHandle,
+    arrayvalue_p: vpi_sys::p_vpi_arrayvalue,
+    index_p: *mut PLI_INT32,
+    num: PLI_UINT32,
+) {
+    if arrayvalue_p.is_null() {
+        return;
+    }
+    api(|s| {
+        let array = unsafe { &*arrayvalue_p };
+        let Some(words) = array_words(s, object, index_p, num) else {
+            s.fail("vpi_get_value_array: invalid array or index range");
+            return;
+        };
+        for (i, word) in words.into_iter().enumerate() {
+            let Some((value, signed, _)) = s.readable(word) else {
+                continue;
+            };
+            let bits = match value {
+                Stored::Bits(bits) => bits.clone(),
+                Stored::Real(real) => match bits_from_i64(real.round() as i64, 64) {
+                    Stored::Bits(bits) => bits,
+                    Stored::Real(_) => unreachable!(),
+                },
+            };
+            let real = match value {
+                Stored::Real(real) => *real,
+                Stored::Bits(_) => bits_to_i64(&bits, signed) as f64,
+            };
+            unsafe {
+                match array.format {
+                    vpi_sys::vpiIntVal => {
+                        *array.value.integers.add(i) = bits_to_i64(&bits, signed) as i32;
+                    }
+                    vpi_sys::vpiShortIntVal => {
+                        *array.value.shortints.add(i) = bits_to_i64(&bits, signed) as i16;
+                    }
+                    vpi_sys::vpiLongIntVal => {
+                        *array.value.longints.add(i) = bits_to_i64(&bits, signed);
+                    }
+                    vpi_sys::vpiRealVal => *array.value.reals.add(i) = real,
+                    vpi_sys::vpiShortRealVal => *array.value.shortreals.add(i) = real as f32,
+                    vpi_sys::vpiScalarVal => {
+                        *array.value.rawvals.add(i) =
+                            bits.last().copied().unwrap_or(LogicVal::X) as PLI_BYTE8;
+                    }
+                    vpi_sys::vpiTimeVal => {
+                        *array.value.times.add(i) = s.time_struct(
+                            bits_to_u64(&bits),
+                            vpi_sys::vpiSimTime as PLI_INT32,
+                            None,
+                        );
+                    }
+                    _ => {
+                        s.fail("vpi_get_value_array: unsupported format");
+                        return;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_put_value_array(
+    object: vpiHandle,
+    arrayvalue_p: vpi_sys::p_vpi_arrayvalue,
+    index_p: *mut PLI_INT32,
+    num: PLI_UINT32,
+) {
+    if arrayvalue_p.is_null() {
+        return;
+    }
+    let notify = api(|s| {
+        let array = unsafe { &*arrayvalue_p };
+        let Some(words) = array_words(s, object, index_p, num) else {
+            s.fail("vpi_put_value_array: invalid array or index range");
+            return Vec::new();
+        };
+        let mut notify = Vec::new();
+        for (i, word) in words.into_iter().enumerate() {
+            let Some(target) = s.target(word) else {
+                continue;
+            };
+            let mut value = vpi_sys::t_vpi_value {
+                format: 0,
+                value: vpi_sys::t_vpi_value__bindgen_ty_1 { integer: 0 },
+            };
+            unsafe {
+                match array.format {
+                    vpi_sys::vpiIntVal => {
+                        value.format = vpi_sys::vpiIntVal as PLI_INT32;
+                        value.value.integer = *array.value.integers.add(i);
+                    }
+                    vpi_sys::vpiShortIntVal => {
+                        value.format = vpi_sys::vpiIntVal as PLI_INT32;
+                        value.value.integer = i32::from(*array.value.shortints.add(i));
+                    }
+                    vpi_sys::vpiLongIntVal => {
+                        let stored = bits_from_i64(*array.value.longints.add(i), target.width);
+                        notify.extend(s.set_driven(word, stored));
+                        continue;
+                    }
+                    vpi_sys::vpiRealVal => {
+                        value.format = vpi_sys::vpiRealVal as PLI_INT32;
+                        value.value.real = *array.value.reals.add(i);
+                    }
+                    vpi_sys::vpiShortRealVal => {
+                        value.format = vpi_sys::vpiRealVal as PLI_INT32;
+                        value.value.real = f64::from(*array.value.shortreals.add(i));
+                    }
+                    vpi_sys::vpiScalarVal => {
+                        value.format = vpi_sys::vpiScalarVal as PLI_INT32;
+                        value.value.scalar = PLI_INT32::from(*array.value.rawvals.add(i));
+                    }
+                    vpi_sys::vpiTimeVal => {
+                        value.format = vpi_sys::vpiTimeVal as PLI_INT32;
+                        value.value.time = array.value.times.add(i);
+                    }
+                    _ => {
+                        s.fail("vpi_put_value_array: unsupported format");
+                        return notify;
+                    }
+                }
+                if let Ok(stored) = decode_put(&value, target) {
+                    notify.extend(s.set_driven(word, stored));
+                }
+            }
+        }
+        notify
+    });
+    fire_all(notify);
+}
+
+fn array_words(
+    s: &SimState,
+    object: vpiHandle,
+    index_p: *mut PLI_INT32,
+    num: PLI_UINT32,
+) -> Option<Vec<usize>> {
+    let Kind::Array(array) = &s.object(object)?.kind else {
+        return None;
+    };
+    let start = if index_p.is_null() {
+        0
+    } else {
+        usize::try_from(unsafe { *index_p }).ok()?
+    };
+    let end = start.checked_add(usize::try_from(num).ok()?)?;
+    array.words.get(start..end).map(<[usize]>::to_vec)
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_time(object: vpiHandle, time_p: vpi_sys::p_vpi_time) {
+    if time_p.is_null() {
+        return;
+    }
+    api(|s| {
+        let id = s.id(object);
+        let time_type = unsafe { (*time_p).type_ };
+        unsafe { *time_p = s.time_struct(s.now, time_type, id) };
+    });
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_mcd_open(file_name: *mut PLI_BYTE8) -> PLI_UINT32 {
+    api(|s| {
+        if file_name.is_null() {
+            s.fail("vpi_mcd_open: null file name");
+            return 0;
+        }
+        let name = unsafe { CStr::from_ptr(file_name) }
+            .to_string_lossy()
+            .into_owned();
+        if let Some((bit, _)) = s.files.iter().find(|(_, open)| **open == name) {
+            return 1 << bit;
+        }
+        let Some(bit) = (1..31).find(|bit| !s.files.contains_key(bit)) else {
+            s.fail("vpi_mcd_open: no free channels");
+            return 0;
+        };
+        s.file_contents.insert(name.clone(), String::new());
+        s.files.insert(bit, name);
+        1 << bit
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_mcd_close(mcd: PLI_UINT32) -> PLI_UINT32 {
+    api(|s| {
+        let mut failed = 0;
+        for bit in 1..32 {
+            if mcd & (1 << bit) != 0 && s.files.remove(&bit).is_none() {
+                failed |= 1 << bit;
+            }
+        }
+        failed
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_mcd_name(cd: PLI_UINT32) -> *mut PLI_BYTE8 {
+    api(|s| {
+        let name = match cd {
+            1 => Some("stdout".to_string()),
+            cd if cd.is_power_of_two() => s.files.get(&cd.trailing_zeros()).cloned(),
+            _ => None,
+        };
+        // Ownership passes to the caller, which frees the name with `CString::from_raw`.
+        name.and_then(|name| CString::new(name).ok())
+            .map_or(std::ptr::null_mut(), CString::into_raw)
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_mcd_printf(mcd: PLI_UINT32, format: *mut PLI_BYTE8) -> PLI_INT32 {
+    api(|s| {
+        if format.is_null() {
+            return 0;
+        }
+        let text = unsafe { CStr::from_ptr(format) }
+            .to_string_lossy()
+            .into_owned();
+        write_mcd(s, mcd, &text);
+        i32::try_from(text.len()).unwrap_or(i32::MAX)
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_printf(format: *mut PLI_BYTE8, arg: *mut PLI_BYTE8) -> PLI_INT32 {
+    let text = format_printf(format, arg);
+    api(|s| s.output.push_str(&text));
+    i32::try_from(text.len()).unwrap_or(i32::MAX)
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_mcd_flush(_mcd: PLI_UINT32) -> PLI_INT32 {
+    0
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_flush() -> PLI_INT32 {
+    0
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_compare_objects(object1: vpiHandle, object2: vpiHandle) -> PLI_INT32 {
+    PLI_INT32::from(object1 == object2)
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_chk_error(error_info_p: vpi_sys::p_vpi_error_info) -> PLI_INT32 {
+    with_sim(|s| {
+        let Some(error) = &s.error else {
+            return 0;
+        };
+        if !error_info_p.is_null() {
+            unsafe {
+                *error_info_p = vpi_sys::t_vpi_error_info {
+                    state: error.state,
+                    level: error.level,
+                    message: error.message.as_ptr().cast_mut(),
+                    product: PRODUCT.as_ptr().cast_mut(),
+                    code: ERROR_CODE.as_ptr().cast_mut(),
+                    file: std::ptr::null_mut(),
+                    line: 0,
+                };
+            }
+        }
+        error.level
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_free_object(object: vpiHandle) -> PLI_INT32 {
+    unsafe { vpi_release_handle(object) }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_release_handle(object: vpiHandle) -> PLI_INT32 {
+    api(|s| {
+        let Some(object) = s.object_mut(object) else {
+            return 0;
+        };
+        // Design objects outlive their handles; only iterators and events
+        // that are no longer pending are discarded.
+        let discard = match &object.kind {
+            Kind::Iterator(_) => true,
+            Kind::Event(event) => event.state != EventState::Pending,
+            _ => false,
+        };
+        if discard {
+            object.kind = Kind::Freed;
+        }
+        1
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_vlog_info(vlog_info_p: vpi_sys::p_vpi_vlog_info) -> PLI_INT32 {
+    if vlog_info_p.is_null() {
+        return 0;
+    }
+    api(|s| unsafe {
+        *vlog_info_p = vpi_sys::t_vpi_vlog_info {
+            argc: i32::try_from(s.argv.len()).unwrap_or(0),
+            argv: s.argv.as_mut_ptr(),
+            product: PRODUCT.as_ptr().cast_mut(),
+            version: VERSION.as_ptr().cast::<PLI_BYTE8>().cast_mut(),
+        };
+    });
+    1
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_control(operation: PLI_INT32) -> PLI_INT32 {
+    api(|s| match operation as u32 {
+        vpi_sys::vpiStop => {
+            s.stop_requested = true;
+            1
+        }
+        vpi_sys::vpiFinish => {
+            s.finish_requested = true;
+            1
+        }
+        _ => {
+            s.fail(format!("vpi_control: operation {operation} is not modeled"));
+            0
+        }
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_put_data(
+    id: PLI_INT32,
+    data_loc: *mut PLI_BYTE8,
+    num_of_bytes: PLI_INT32,
+) -> PLI_INT32 {
+    api(|s| {
+        let writable = id > 0 && id <= s.next_save_id;
+        let (Some(checkpoint), true, Ok(len)) = (
+            s.checkpoint.as_mut().filter(|c| !c.restoring),
+            writable && !data_loc.is_null(),
+            usize::try_from(num_of_bytes),
+        ) else {
+            s.fail("vpi_put_data: no save in progress for this ID");
+            return 0;
+        };
+        let bytes = unsafe { std::slice::from_raw_parts(data_loc.cast::<u8>(), len) };
+        checkpoint
+            .data
+            .entry(id)
+            .or_default()
+            .extend_from_slice(bytes);
+        num_of_bytes
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_data(
+    id: PLI_INT32,
+    data_loc: *mut PLI_BYTE8,
+    num_of_bytes: PLI_INT32,
+) -> PLI_INT32 {
+    api(|s| {
+        let (Some(checkpoint), false, Ok(len)) = (
+            s.checkpoint.as_mut().filter(|c| c.restoring),
+            data_loc.is_null(),
+            usize::try_from(num_of_bytes),
+        ) else {
+            s.fail("vpi_get_data: no restart in progress");
+            return 0;
+        };
+        let data = checkpoint.data.get(&id).map_or(&[][..], Vec::as_slice);
+        let cursor = checkpoint.cursors.entry(id).or_default();
+        let available = &data[(*cursor).min(data.len())..];
+        let count = available.len().min(len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(available.as_ptr(), data_loc.cast::<u8>(), count);
+        }
+        *cursor += count;
+        i32::try_from(count).unwrap_or(0)
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_userdata(obj: vpiHandle) -> *mut c_void {
+    api(|s| {
+        s.userdata
+            .get(&obj.addr())
+            .map_or(std::ptr::null_mut(), |data| {
+                std::ptr::with_exposed_provenance_mut(*data)
+            })
+    })
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_put_userdata(obj: vpiHandle, userdata: *mut c_void) -> PLI_INT32 {
+    api(|s| {
+        let Some(id) = s.id(obj) else {
+            s.fail("vpi_put_userdata: invalid handle");
+            return 0;
+        };
+        if userdata.is_null() {
+            s.userdata.remove(&id);
+        } else {
+            s.userdata.insert(id, userdata.expose_provenance());
+        }
+        1
+    })
+}
+
+#[cfg(feature = "sv")]
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_register_assertion_cb(
+    _assertion: vpiHandle,
+    _reason: PLI_INT32,
+    _cb_rtn: vpi_sys::vpi_assertion_callback_func,
+    _user_data: *mut PLI_BYTE8,
+) -> vpiHandle {
+    api(|s| {
+        s.fail("vpi_register_assertion_cb: assertions are not modeled");
+        std::ptr::null_mut()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::{MockArg, MockSimulator};
+    use crate::{
+        chk_error, control, get_systf_args, register_cb, register_cb_with_time, register_systf,
+        remove_cb, CbReason, Control, Direction, Handle, LogicVal, ObjectType, Property,
+        PutValueDelay, PutValueFlags, Severity, SimContext, SysFuncType, SystfKind, Time, Value,
+        ValueType,
+    };
+
+    fn design(sim: &MockSimulator) -> (Handle, Handle, Handle) {
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let dut = sim.add_module(&top, "dut", "counter");
+        let count = sim.add_reg(&dut, "count", 8);
+        (top, dut, count)
+    }
+
+    #[test]
+    fn design_is_visible_through_handles() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (top, dut, count) = design(&sim);
+        let clk = sim.add_net(&dut, "clk", 1);
+        let port = sim.add_port(&dut, "clk", Direction::Input, &clk);
+        let _ = sim.add_parameter(&dut, "WIDTH", &Value::Int(8), false);
+
+        let tops: Vec<Handle> = Handle::null().iterator(ctx, ObjectType::Module).collect();
+        assert_eq!(tops, vec![top.clone()]);
+        assert_eq!(Handle::handle_by_name(ctx, "tb.dut.count"), count);
+        assert_eq!(Handle::handle_by_name_and_scope(ctx, "count", &dut), count);
+        assert_eq!(count.get_full_name(ctx).as_deref(), Some("tb.dut.count"));
+        assert_eq!(
+            dut.get_str(ctx, Property::DefName).as_deref(),
+            Some("counter")
+        );
+        assert_eq!(count.get_size(ctx), Some(8));
+        assert_eq!(top.get_bool(ctx, Property::TopModule), Some(true));
+        assert_eq!(dut.get_bool(ctx, Property::TopModule), Some(false));
+        assert_eq!(port.get_direction(ctx), Some(Direction::Input));
+        assert_eq!(port.get(ctx, ObjectType::LowConn), clk);
+        assert_eq!(count.get_left_range(ctx), Some(7));
+        assert_eq!(count.get_right_range(ctx), Some(0));
+
+        let nets: Vec<Handle> = dut.iterator(ctx, ObjectType::Net).collect();
+        assert_eq!(nets, vec![clk]);
+        let params: Vec<Handle> = dut.iterator(ctx, ObjectType::Parameter).collect();
+        assert_eq!(
+            params[0].get_value(ctx, ValueType::Int),
+            Some(Value::Int(8))
+        );
+    }
+
+    #[test]
+    fn values_convert_between_formats() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, dut, count) = design(&sim);
+
+        assert_eq!(
+            count.get_value(ctx, ValueType::BinStr),
+            Some(Value::BinStr("xxxxxxxx".to_string()))
+        );
+        assert!(sim.set_value(&count, &Value::HexStr("a5".to_string())));
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(0xa5)));
+        assert_eq!(
+            count.get_value(ctx, ValueType::OctStr),
+            Some(Value::OctStr("245".to_string()))
+        );
+        assert_eq!(
+            count.get_value(ctx, ValueType::DecStr),
+            Some(Value::DecStr("165".to_string()))
+        );
+
+        let signed = sim.add_reg(&dut, "delta", 8);
+        sim.set_signed(&signed, true);
+        assert!(sim.set_value(&signed, &Value::Int(-3)));
+        assert_eq!(
+            signed.get_value(ctx, ValueType::DecStr),
+            Some(Value::DecStr("-3".to_string()))
+        );
+        assert_eq!(signed.get_value(ctx, ValueType::Int), Some(Value::Int(-3)));
+
+        assert!(sim.set_value(&count, &Value::BinStr("1z0x".to_string())));
+        assert_eq!(
+            count.get_value(ctx, ValueType::BinStr),
+            Some(Value::BinStr("00001z0x".to_string()))
+        );
+        assert_eq!(
+            count.get_value(ctx, ValueType::ObjType),
+            Some(Value::Vector("00001Z0X".into()))
+        );
+
+        let real = sim.add_real(&dut, "ratio");
+        assert!(sim.set_value(&real, &Value::Real(0.5)));
+        assert_eq!(
+            real.get_value(ctx, ValueType::ObjType),
+            Some(Value::Real(0.5))
+        );
+    }
+
+    #[test]
+    fn value_change_callbacks_fire_on_changes_only() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, _, count) = design(&sim);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+
+        let log = Rc::clone(&seen);
+        let cb = count.register_value_change_cb(ctx, ValueType::Int, move |data| {
+            log.borrow_mut()
+                .push((data.value.clone(), data.time.clone()));
+        });
+        assert!(!cb.is_null());
+
+        assert!(sim.schedule_value(&count, &Value::Int(1), 10));
+        assert!(sim.schedule_value(&count, &Value::Int(1), 20));
+        assert!(sim.schedule_value(&count, &Value::Int(2), 30));
+        sim.run_until(25);
+        remove_cb(ctx, &cb);
+        sim.run();
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![(Some(Value::Int(1)), Some(Time::Sim(10)))]
+        );
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(2)));
+    }
+
+    #[test]
+    fn time_callbacks_follow_region_order() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let order = Rc::new(RefCell::new(Vec::new()));
+
+        for reason in [
+            CbReason::ReadOnlySynch,
+            CbReason::ReadWriteSynch,
+            CbReason::AfterDelay,
+            CbReason::AtStartOfSimTime,
+        ] {
+            let order = Rc::clone(&order);
+            let _ = register_cb_with_time(ctx, reason, Time::Sim(3), move |data| {
+                order.borrow_mut().push((data.reason, data.time.clone()));
+            });
+        }
+        let next = Rc::clone(&order);
+        let _ = register_cb(ctx, CbReason::NextSimTime, move |data| {
+            next.borrow_mut().push((data.reason, None));
+        });
+        let ended = Rc::new(Cell::new(false));
+        let flag = Rc::clone(&ended);
+        let _ = register_cb(ctx, CbReason::EndOfSimulation, move |_| flag.set(true));
+
+        sim.run();
+
+        let reasons: Vec<CbReason> = order.borrow().iter().map(|(r, _)| *r).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                CbReason::NextSimTime,
+                CbReason::AtStartOfSimTime,
+                CbReason::AfterDelay,
+                CbReason::ReadWriteSynch,
+                CbReason::ReadOnlySynch,
+            ]
+        );
+        assert_eq!(order.borrow()[1].1, Some(Time::Sim(3)));
+        assert!(ended.get());
+        assert_eq!(sim.time(), 3);
+        assert!(sim.is_finished());
+    }
+
+    #[test]
+    fn systf_calls_see_arguments_and_return_values() {
+        unsafe extern "C" fn calltf(_: *mut std::os::raw::c_char) -> i32 {
+            let ctx = SimContext::borrowed();
+            let args = get_systf_args(ctx, [ValueType::Int, ValueType::Int]);
+            if let [Some(Value::Int(a)), Some(Value::Int(b))] = args.as_slice() {
+                let _ = crate::current_systf_call(ctx).put_value(ctx, &Value::Int(a + b));
+            }
+            0
+        }
+
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, _, count) = design(&sim);
+        let handle = register_systf(
+            ctx,
+            SystfKind::Func,
+            c"$add",
+            Some(calltf),
+            None,
+            None,
+            std::ptr::null_mut(),
+            Some(SysFuncType::Int),
+        );
+        assert!(!handle.is_null());
+        assert!(sim.set_value(&count, &Value::Int(40)));
+
+        let result = sim.invoke_systf("$add", &[MockArg::from(&count), Value::Int(2).into()]);
+        assert_eq!(result, Some(Value::Int(42)));
+
+        let info = crate::get_systf_info(ctx, &handle).unwrap();
+        assert_eq!(info.name.as_deref(), Some("$add"));
+        assert!(sim.invoke_systf("$missing", &[]).is_none());
+    }
+
+    #[test]
+    fn scheduled_events_can_be_cancelled() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, _, count) = design(&sim);
+        assert!(sim.set_value(&count, &Value::Int(0)));
+
+        let event = count
+            .put_value_scheduled(
+                ctx,
+                &Value::Int(7),
+                Some(&Time::Sim(5)),
+                PutValueDelay::Transport,
+                &PutValueFlags::ReturnEvent,
+            )
+            .unwrap()
+            .into_handle();
+        assert_eq!(event.get_bool(ctx, Property::Scheduled), Some(true));
+
+        unsafe {
+            vpi_sys::vpi_put_value(
+                event.as_raw(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                vpi_sys::vpiCancelEvent as i32,
+            );
+        }
+        assert_eq!(event.get_bool(ctx, Property::Scheduled), Some(false));
+
+        sim.run();
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(0)));
+    }
+
+    #[test]
+    fn delayed_writes_return_cancellable_events() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, _, count) = design(&sim);
+        assert!(sim.set_value(&count, &Value::Int(0)));
+
+        let flags = PutValueFlags::empty();
+        let first = count
+            .put_value_delayed(
+                ctx,
+                &Value::Int(1),
+                &Time::Sim(2),
+                PutValueDelay::Transport,
+                &flags,
+            )
+            .unwrap();
+        let second = count
+            .put_value_delayed(
+                ctx,
+                &Value::Int(2),
+                &Time::Sim(4),
+                PutValueDelay::Transport,
+                &flags,
+            )
+            .unwrap();
+        assert!(first.is_pending(ctx) && second.is_pending(ctx));
+        assert!(count
+            .put_value_delayed(
+                ctx,
+                &Value::Int(3),
+                &Time::Sim(1),
+                PutValueDelay::NoDelay,
+                &flags
+            )
+            .is_err());
+
+        assert_eq!(second.cancel(ctx).ok(), Some(true));
+        assert_eq!(second.cancel(ctx).ok(), Some(false));
+        sim.run_for(4);
+        assert!(!first.is_pending(ctx));
+        assert_eq!(first.cancel(ctx).ok(), Some(false));
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(1)));
+
+        // Dropping an event releases its handle without cancelling it.
+        let raw = first.handle().as_raw();
+        drop(first);
+        assert!(Handle::from_raw(raw).get_type(ctx).is_none());
+        let third = count
+            .put_value_delayed(
+                ctx,
+                &Value::Int(3),
+                &Time::Sim(1),
+                PutValueDelay::Transport,
+                &flags,
+            )
+            .unwrap();
+        drop(third);
+        sim.run();
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(3)));
+    }
+
+    #[test]
+    fn force_and_release_follow_net_and_variable_rules() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, dut, count) = design(&sim);
+        let bus = sim.add_net(&dut, "bus", 4);
+        let forces = Rc::new(Cell::new(0));
+
+        let seen = Rc::clone(&forces);
+        let _ = register_cb(ctx, CbReason::Force, move |_| seen.set(seen.get() + 1));
+
+        let force = |handle: &Handle, value: i32, flag: u32| {
+            let mut raw = vpi_sys::t_vpi_value {
+                format: vpi_sys::vpiIntVal as i32,
+                value: vpi_sys::t_vpi_value__bindgen_ty_1 { integer: value },
+            };
+            unsafe {
+                vpi_sys::vpi_put_value(
+                    handle.as_raw(),
+                    &raw mut raw,
+                    std::ptr::null_mut(),
+                    flag as i32,
+                );
+            }
+        };
+
+        assert!(sim.set_value(&count, &Value::Int(1)));
+        force(&count, 9, vpi_sys::vpiForceFlag);
+        assert!(sim.set_value(&count, &Value::Int(2)));
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(9)));
+        force(&count, 0, vpi_sys::vpiReleaseFlag);
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(9)));
+
+        assert!(sim.set_value(&bus, &Value::Int(3)));
+        force(&bus, 5, vpi_sys::vpiForceFlag);
+        assert_eq!(bus.get_value(ctx, ValueType::Int), Some(Value::Int(5)));
+        force(&bus, 0, vpi_sys::vpiReleaseFlag);
+        assert_eq!(bus.get_value(ctx, ValueType::Int), Some(Value::Int(3)));
+        assert_eq!(forces.get(), 2);
+    }
+
+    #[test]
+    fn memory_words_report_their_index_to_array_callbacks() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, dut, _) = design(&sim);
+        let mem = sim.add_memory(&dut, "mem", 8, 4);
+        let indices = Rc::new(RefCell::new(Vec::new()));
+
+        let seen = Rc::clone(&indices);
+        let _ = mem.register_value_change_cb(ctx, ValueType::Int, move |data| {
+            seen.borrow_mut().push((data.index, data.value.clone()));
+        });
+
+        let word = Handle::handle_by_name(ctx, "tb.dut.mem[2]");
+        assert_eq!(word, mem.handle_by_index(ctx, 2));
+        assert_eq!(mem.get_size(ctx), Some(4));
+        assert!(mem.is_array(ctx));
+        assert!(sim.set_value(&word, &Value::Int(17)));
+
+        assert_eq!(*indices.borrow(), vec![(2, Some(Value::Int(17)))]);
+        let values = mem.get_value_array(ctx, ValueType::Int).unwrap();
+        assert_eq!(values[2], Value::Int(17));
+    }
+
+    #[test]
+    fn output_and_errors_are_captured() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        crate::printf(ctx, "hello");
+        let log = crate::MCD::new(ctx, "run.log");
+        log.writeln(ctx, "to file");
+        assert_eq!(log.file_name(ctx).as_deref(), Some("run.log"));
+        log.close(ctx);
+
+        assert_eq!(sim.take_output(), "hello\n");
+        assert_eq!(sim.file_output("run.log").as_deref(), Some("to file\n"));
+
+        assert!(Handle::handle_by_name(ctx, "nope").is_null());
+        assert!(chk_error(ctx).is_none());
+        assert!(Handle::null().get_value(ctx, ValueType::Int).is_none());
+        let _ = unsafe { vpi_sys::vpi_get(vpi_sys::vpiSize as i32, std::ptr::null_mut()) };
+        let error = chk_error(ctx).unwrap();
+        assert_eq!(error.severity, Some(Severity::Error));
+        assert_eq!(error.product, "vpi-mock");
+
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::clone(&messages);
+        let _ = register_cb(ctx, CbReason::Error, move |_| {
+            seen.borrow_mut().push(chk_error(ctx).map(|e| e.message));
+        });
+        sim.report_error(Severity::Warning, "assertion failed");
+        assert_eq!(
+            *messages.borrow(),
+            vec![Some("assertion failed".to_string())]
+        );
+    }
+
+    #[test]
+    fn control_finish_ends_the_run() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let _ = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(4), |_| {
+            control(ctx, Control::Finish);
+        });
+        let late = Rc::new(Cell::new(false));
+        let flag = Rc::clone(&late);
+        let _ = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(8), move |_| {
+            flag.set(true)
+        });
+
+        sim.run();
+        assert_eq!(sim.time(), 4);
+        assert!(sim.is_finished());
+        assert!(!late.get());
+    }
+
+    #[test]
+    fn save_and_restart_round_trip_plugin_data() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let (_, _, count) = design(&sim);
+        assert!(sim.set_value(&count, &Value::Int(5)));
+
+        let _ = register_cb(ctx, CbReason::StartOfSave, |_| unsafe {
+            let id = vpi_sys::vpi_get(vpi_sys::vpiSaveRestartID as i32, std::ptr::null_mut());
+            let mut bytes = *b"state";
+            super::vpi_put_data(id, bytes.as_mut_ptr().cast(), 5);
+        });
+        let restored = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&restored);
+        let _ = register_cb(ctx, CbReason::StartOfRestart, move |_| unsafe {
+            let mut buf = [0u8; 8];
+            let n = super::vpi_get_data(1, buf.as_mut_ptr().cast(), 8);
+            sink.borrow_mut().extend_from_slice(&buf[..n as usize]);
+        });
+
+        let checkpoint = sim.save();
+        assert_eq!(checkpoint.data(1), Some(&b"state"[..]));
+        assert!(sim.set_value(&count, &Value::Int(6)));
+
+        sim.restart(&checkpoint);
+        assert_eq!(*restored.borrow(), b"state".to_vec());
+        assert_eq!(count.get_value(ctx, ValueType::Int), Some(Value::Int(5)));
+        assert_eq!(
+            count.get_value(ctx, ValueType::Scalar),
+            Some(Value::Scalar(LogicVal::One))
+        );
+    }
+}
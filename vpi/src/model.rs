@@ -0,0 +1,749 @@
+//! Typed wrappers over [`Handle`] for the objects of the Verilog object model.
+//!
+//! Every simulator object is a [`Handle`], which offers all relations and
+//! properties whether or not they apply to the object. The types of this
+//! module wrap handles of one kind of object and only offer the relations
+//! and properties the standard defines for it, so traversals are
+//! discoverable and do not issue invalid `vpi_iterate` or `vpi_handle` calls.
+//!
+//! Handles are converted with [`Handle::downcast`], which checks the object
+//! type, or classified with [`Handle::kind`]. Relations that can lead to
+//! objects of several types, such as the drivers of a net, return
+//! [`ObjectKind`]s. The underlying handle remains available through
+//! [`TypedHandle::handle`] for anything not covered here.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::model::{Module, ObjectKind, TypedHandle};
+//! use vpi::{Handle, SimContext};
+//!
+//! fn walk(ctx: &SimContext, module: &Module) {
+//!     for net in module.nets(ctx) {
+//!         for driver in net.drivers(ctx) {
+//!             if let ObjectKind::Port(port) = driver {
+//!                 let (net, port) = (net.full_name(ctx), port.name(ctx));
+//!                 vpi::printf!(ctx, "{net:?} driven by port {port:?}");
+//!             }
+//!         }
+//!     }
+//!     module.modules(ctx).for_each(|child| walk(ctx, &child));
+//! }
+//!
+//! fn start(ctx: &SimContext) {
+//!     if let Some(top) = Handle::handle_by_name(ctx, "tb").downcast::<Module>(ctx) {
+//!         walk(ctx, &top);
+//!     }
+//! }
+//! ```
+
+use num_traits::FromPrimitive;
+
+use crate::{
+    ConstType, Direction, FuncType, Handle, HandleIterator, NetType, ObjectType, PrimType,
+    Property, SimContext, Value, ValueType,
+};
+
+/// A handle known to refer to an object of a particular kind.
+pub trait TypedHandle: Sized {
+    /// The `vpiType` values of the objects this type wraps.
+    const TYPES: &'static [u32];
+
+    /// Wraps `handle` without checking its type.
+    ///
+    /// Prefer [`Handle::downcast`], which checks it.
+    fn from_handle_unchecked(handle: Handle) -> Self;
+
+    /// Returns the underlying handle.
+    fn handle(&self) -> &Handle;
+
+    /// Returns the underlying handle, consuming the wrapper.
+    fn into_handle(self) -> Handle;
+
+    /// Returns the name of the object.
+    fn name(&self, ctx: &SimContext) -> Option<String> {
+        self.handle().get_name(ctx)
+    }
+
+    /// Returns the full hierarchical name of the object.
+    fn full_name(&self, ctx: &SimContext) -> Option<String> {
+        self.handle().get_full_name(ctx)
+    }
+
+    /// Returns the source file declaring the object.
+    fn file(&self, ctx: &SimContext) -> Option<String> {
+        self.handle().get_str(ctx, Property::File)
+    }
+
+    /// Returns the source line declaring the object.
+    fn line(&self, ctx: &SimContext) -> Option<u32> {
+        self.handle().get_u32(ctx, Property::LineNo)
+    }
+}
+
+macro_rules! typed_handles {
+    ($(
+        $(#[$doc:meta])*
+        $name:ident => [$($typ:ident),+ $(,)?];
+    )+) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, PartialEq)]
+            pub struct $name(Handle);
+
+            impl TypedHandle for $name {
+                const TYPES: &'static [u32] = &[$(vpi_sys::$typ),+];
+
+                fn from_handle_unchecked(handle: Handle) -> Self {
+                    Self(handle)
+                }
+
+                fn handle(&self) -> &Handle {
+                    &self.0
+                }
+
+                fn into_handle(self) -> Handle {
+                    self.0
+                }
+            }
+
+            impl From<$name> for Handle {
+                fn from(object: $name) -> Handle {
+                    object.0
+                }
+            }
+
+            impl AsRef<Handle> for $name {
+                fn as_ref(&self) -> &Handle {
+                    &self.0
+                }
+            }
+        )+
+
+        /// An object classified by its type; see [`Handle::kind`].
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum ObjectKind {
+            $(
+                $(#[$doc])*
+                $name($name),
+            )+
+            /// An object without a typed wrapper, such as an expression.
+            Other(Handle),
+        }
+
+        impl ObjectKind {
+            fn classify(ctx: &SimContext, handle: Handle) -> Self {
+                let typ = handle.get_raw_property(ctx, Property::Type).unwrap_or(0) as u32;
+                $(
+                    if $name::TYPES.contains(&typ) {
+                        return Self::$name($name(handle));
+                    }
+                )+
+                Self::Other(handle)
+            }
+
+            /// Returns the underlying handle.
+            #[must_use]
+            pub fn handle(&self) -> &Handle {
+                match self {
+                    $(Self::$name(object) => &object.0,)+
+                    Self::Other(handle) => handle,
+                }
+            }
+        }
+
+        impl From<ObjectKind> for Handle {
+            fn from(kind: ObjectKind) -> Handle {
+                match kind {
+                    $(ObjectKind::$name(object) => object.0,)+
+                    ObjectKind::Other(handle) => handle,
+                }
+            }
+        }
+    };
+}
+
+typed_handles! {
+    /// A module instance.
+    Module => [vpiModule];
+    /// A scalar or vector net.
+    Net => [vpiNet];
+    /// A scalar or vector reg.
+    Reg => [vpiReg];
+    /// An integer, time or real variable.
+    Variable => [vpiIntegerVar, vpiTimeVar, vpiRealVar];
+    /// A module port.
+    Port => [vpiPort];
+    /// A parameter or specparam.
+    Parameter => [vpiParameter, vpiSpecParam];
+    /// A memory, an array of regs.
+    Memory => [vpiMemory];
+    /// A word of a memory.
+    MemoryWord => [vpiMemoryWord];
+    /// An array of nets or regs.
+    Array => [vpiNetArray, vpiRegArray];
+    /// A function declaration.
+    Function => [vpiFunction];
+    /// A task declaration.
+    Task => [vpiTask];
+    /// A continuous assignment.
+    ContAssign => [vpiContAssign];
+    /// A gate, switch or UDP instance.
+    Primitive => [vpiGate, vpiSwitch, vpiUdp];
+}
+
+impl Handle {
+    /// Returns this handle as `T` if the object is of one of its types.
+    #[must_use]
+    pub fn downcast<T: TypedHandle>(&self, ctx: &SimContext) -> Option<T> {
+        let typ = self.get_raw_property(ctx, Property::Type)? as u32;
+        T::TYPES
+            .contains(&typ)
+            .then(|| T::from_handle_unchecked(self.clone()))
+    }
+
+    /// Classifies this handle by its object type.
+    #[must_use]
+    pub fn kind(&self, ctx: &SimContext) -> ObjectKind {
+        ObjectKind::classify(ctx, self.clone())
+    }
+}
+
+fn typed<T: TypedHandle>(iterator: HandleIterator) -> impl Iterator<Item = T> {
+    iterator.map(T::from_handle_unchecked)
+}
+
+fn kinds(ctx: &SimContext, iterator: HandleIterator) -> impl Iterator<Item = ObjectKind> {
+    let ctx = *ctx;
+    iterator.map(move |handle| ObjectKind::classify(&ctx, handle))
+}
+
+fn related<T: TypedHandle>(ctx: &SimContext, handle: &Handle, typ: ObjectType) -> Option<T> {
+    let related = handle.get(ctx, typ);
+    (!related.is_null()).then(|| T::from_handle_unchecked(related))
+}
+
+fn related_kind(ctx: &SimContext, handle: &Handle, typ: ObjectType) -> Option<ObjectKind> {
+    let related = handle.get(ctx, typ);
+    (!related.is_null()).then(|| ObjectKind::classify(ctx, related))
+}
+
+fn size(ctx: &SimContext, handle: &Handle) -> Option<u32> {
+    handle.get_u32(ctx, Property::Size)
+}
+
+impl Module {
+    /// Returns the module definition name.
+    #[must_use]
+    pub fn def_name(&self, ctx: &SimContext) -> Option<String> {
+        self.0.get_str(ctx, Property::DefName)
+    }
+
+    /// Returns `true` for top-level modules.
+    #[must_use]
+    pub fn is_top(&self, ctx: &SimContext) -> bool {
+        self.0.get_bool(ctx, Property::TopModule).unwrap_or(false)
+    }
+
+    /// Returns `true` for cell instances.
+    #[must_use]
+    pub fn is_cell(&self, ctx: &SimContext) -> bool {
+        self.0
+            .get_bool(ctx, Property::CellInstance)
+            .unwrap_or(false)
+    }
+
+    /// Returns the module containing this one; `None` for top-level modules.
+    #[must_use]
+    pub fn parent(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Iterates over the module instances directly inside this one.
+    pub fn modules(&self, ctx: &SimContext) -> impl Iterator<Item = Module> {
+        typed(self.0.iterator(ctx, ObjectType::Module))
+    }
+
+    /// Iterates over the nets declared in this module.
+    pub fn nets(&self, ctx: &SimContext) -> impl Iterator<Item = Net> {
+        typed(self.0.iterator(ctx, ObjectType::Net))
+    }
+
+    /// Iterates over the regs declared in this module.
+    pub fn regs(&self, ctx: &SimContext) -> impl Iterator<Item = Reg> {
+        typed(self.0.iterator(ctx, ObjectType::Reg))
+    }
+
+    /// Iterates over the integer, time and real variables of this module.
+    pub fn variables(&self, ctx: &SimContext) -> impl Iterator<Item = Variable> {
+        typed(self.0.iterator(ctx, ObjectType::Variables))
+    }
+
+    /// Iterates over the memories declared in this module.
+    pub fn memories(&self, ctx: &SimContext) -> impl Iterator<Item = Memory> {
+        typed(self.0.iterator(ctx, ObjectType::Memory))
+    }
+
+    /// Iterates over the net and reg arrays declared in this module.
+    pub fn arrays(&self, ctx: &SimContext) -> impl Iterator<Item = Array> {
+        typed(self.0.iterator(ctx, ObjectType::NetArray))
+            .chain(typed(self.0.iterator(ctx, ObjectType::RegArray)))
+    }
+
+    /// Iterates over the ports of this module, in declaration order.
+    pub fn ports(&self, ctx: &SimContext) -> impl Iterator<Item = Port> {
+        typed(self.0.iterator(ctx, ObjectType::Port))
+    }
+
+    /// Iterates over the parameters of this module.
+    pub fn parameters(&self, ctx: &SimContext) -> impl Iterator<Item = Parameter> {
+        typed(self.0.iterator(ctx, ObjectType::Parameter))
+    }
+
+    /// Iterates over the functions declared in this module.
+    pub fn functions(&self, ctx: &SimContext) -> impl Iterator<Item = Function> {
+        typed(self.0.iterator(ctx, ObjectType::Function))
+    }
+
+    /// Iterates over the tasks declared in this module.
+    pub fn tasks(&self, ctx: &SimContext) -> impl Iterator<Item = Task> {
+        typed(self.0.iterator(ctx, ObjectType::Task))
+    }
+
+    /// Iterates over the continuous assignments of this module.
+    pub fn cont_assigns(&self, ctx: &SimContext) -> impl Iterator<Item = ContAssign> {
+        typed(self.0.iterator(ctx, ObjectType::ContAssign))
+    }
+
+    /// Iterates over the gate, switch and UDP instances of this module.
+    pub fn primitives(&self, ctx: &SimContext) -> impl Iterator<Item = Primitive> {
+        typed(self.0.iterator(ctx, ObjectType::Primitive))
+    }
+}
+
+impl Net {
+    /// Returns the module declaring this net.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the width in bits.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Returns the net type, such as `wire` or `tri`.
+    #[must_use]
+    pub fn net_type(&self, ctx: &SimContext) -> Option<NetType> {
+        NetType::from_i32(self.0.get_raw_property(ctx, Property::NetType)?)
+    }
+
+    /// Returns `true` for signed nets.
+    #[must_use]
+    pub fn is_signed(&self, ctx: &SimContext) -> bool {
+        self.0.get_bool(ctx, Property::Signed).unwrap_or(false)
+    }
+
+    /// Returns the current value in `format`.
+    #[must_use]
+    pub fn value(&self, ctx: &SimContext, format: ValueType) -> Option<Value> {
+        self.0.get_value(ctx, format)
+    }
+
+    /// Iterates over the bits of a vector net.
+    pub fn bits(&self, ctx: &SimContext) -> HandleIterator {
+        self.0.iterator(ctx, ObjectType::Bit)
+    }
+
+    /// Iterates over all drivers of the net, across ports.
+    pub fn drivers(&self, ctx: &SimContext) -> impl Iterator<Item = ObjectKind> {
+        kinds(ctx, self.0.iterator(ctx, ObjectType::Driver))
+    }
+
+    /// Iterates over all loads of the net, across ports.
+    pub fn loads(&self, ctx: &SimContext) -> impl Iterator<Item = ObjectKind> {
+        kinds(ctx, self.0.iterator(ctx, ObjectType::Load))
+    }
+
+    /// Iterates over the drivers inside the module declaring the net.
+    pub fn local_drivers(&self, ctx: &SimContext) -> impl Iterator<Item = ObjectKind> {
+        kinds(ctx, self.0.iterator(ctx, ObjectType::LocalDriver))
+    }
+
+    /// Iterates over the loads inside the module declaring the net.
+    pub fn local_loads(&self, ctx: &SimContext) -> impl Iterator<Item = ObjectKind> {
+        kinds(ctx, self.0.iterator(ctx, ObjectType::LocalLoad))
+    }
+
+    /// Iterates over the ports connected to the net, inside and outside the
+    /// declaring module.
+    pub fn ports(&self, ctx: &SimContext) -> impl Iterator<Item = Port> {
+        typed(self.0.iterator(ctx, ObjectType::PortInst))
+    }
+}
+
+impl Reg {
+    /// Returns the module declaring this reg.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the width in bits.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Returns `true` for signed regs.
+    #[must_use]
+    pub fn is_signed(&self, ctx: &SimContext) -> bool {
+        self.0.get_bool(ctx, Property::Signed).unwrap_or(false)
+    }
+
+    /// Returns the current value in `format`.
+    #[must_use]
+    pub fn value(&self, ctx: &SimContext, format: ValueType) -> Option<Value> {
+        self.0.get_value(ctx, format)
+    }
+
+    /// Iterates over the bits of a vector reg.
+    pub fn bits(&self, ctx: &SimContext) -> HandleIterator {
+        self.0.iterator(ctx, ObjectType::Bit)
+    }
+
+    /// Iterates over the ports connected to the reg.
+    pub fn ports(&self, ctx: &SimContext) -> impl Iterator<Item = Port> {
+        typed(self.0.iterator(ctx, ObjectType::PortInst))
+    }
+}
+
+impl Variable {
+    /// Returns the module declaring this variable.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the width in bits.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Returns the current value in `format`.
+    #[must_use]
+    pub fn value(&self, ctx: &SimContext, format: ValueType) -> Option<Value> {
+        self.0.get_value(ctx, format)
+    }
+}
+
+impl Port {
+    /// Returns the module owning this port.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the port direction.
+    #[must_use]
+    pub fn direction(&self, ctx: &SimContext) -> Option<Direction> {
+        self.0.get_direction(ctx)
+    }
+
+    /// Returns the position of the port in the module port list, from 0.
+    #[must_use]
+    pub fn index(&self, ctx: &SimContext) -> Option<u32> {
+        self.0.get_u32(ctx, Property::PortIndex)
+    }
+
+    /// Returns the width in bits.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Returns the expression connected to the port in the instantiating
+    /// module.
+    #[must_use]
+    pub fn high_conn(&self, ctx: &SimContext) -> Option<ObjectKind> {
+        related_kind(ctx, &self.0, ObjectType::HighConn)
+    }
+
+    /// Returns the expression connected to the port inside the module.
+    #[must_use]
+    pub fn low_conn(&self, ctx: &SimContext) -> Option<ObjectKind> {
+        related_kind(ctx, &self.0, ObjectType::LowConn)
+    }
+
+    /// Iterates over the bits of a vector port.
+    pub fn bits(&self, ctx: &SimContext) -> HandleIterator {
+        self.0.iterator(ctx, ObjectType::Bit)
+    }
+}
+
+impl Parameter {
+    /// Returns the module declaring this parameter.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns `true` for `localparam`s.
+    #[must_use]
+    pub fn is_local(&self, ctx: &SimContext) -> bool {
+        self.0.get_bool(ctx, Property::LocalParam).unwrap_or(false)
+    }
+
+    /// Returns how the value was written in the source.
+    #[must_use]
+    pub fn const_type(&self, ctx: &SimContext) -> Option<ConstType> {
+        self.0.get_const_type(ctx)
+    }
+
+    /// Returns the value in `format`.
+    #[must_use]
+    pub fn value(&self, ctx: &SimContext, format: ValueType) -> Option<Value> {
+        self.0.get_value(ctx, format)
+    }
+}
+
+impl Memory {
+    /// Returns the module declaring this memory.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the number of words.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Iterates over the words of the memory.
+    pub fn words(&self, ctx: &SimContext) -> impl Iterator<Item = MemoryWord> {
+        typed(self.0.iterator(ctx, ObjectType::MemoryWord))
+    }
+
+    /// Returns the word at `index`.
+    ///
+    /// Returns `None` if there is no such word or the simulator does not
+    /// report it as a `vpiMemoryWord`.
+    #[must_use]
+    pub fn word(&self, ctx: &SimContext, index: i32) -> Option<MemoryWord> {
+        self.0.handle_by_index(ctx, index).downcast(ctx)
+    }
+}
+
+impl MemoryWord {
+    /// Returns the memory containing this word.
+    #[must_use]
+    pub fn memory(&self, ctx: &SimContext) -> Option<Memory> {
+        related(ctx, &self.0, ObjectType::Parent)
+    }
+
+    /// Returns the index of the word in the memory.
+    #[must_use]
+    pub fn index(&self, ctx: &SimContext) -> Option<i32> {
+        match self
+            .0
+            .get(ctx, ObjectType::Index)
+            .get_value(ctx, ValueType::Int)?
+        {
+            Value::Int(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Returns the width in bits.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Returns the current value in `format`.
+    #[must_use]
+    pub fn value(&self, ctx: &SimContext, format: ValueType) -> Option<Value> {
+        self.0.get_value(ctx, format)
+    }
+}
+
+impl Array {
+    /// Returns the module declaring this array.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the number of elements.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Iterates over the elements, which are nets or regs.
+    pub fn elements(&self, ctx: &SimContext) -> impl Iterator<Item = ObjectKind> {
+        let is_net =
+            self.0.get_raw_property(ctx, Property::Type) == Some(vpi_sys::vpiNetArray as i32);
+        let typ = if is_net {
+            ObjectType::Net
+        } else {
+            ObjectType::Reg
+        };
+        kinds(ctx, self.0.iterator(ctx, typ))
+    }
+
+    /// Returns the element at `index`.
+    #[must_use]
+    pub fn element(&self, ctx: &SimContext, index: i32) -> Option<ObjectKind> {
+        let element = self.0.handle_by_index(ctx, index);
+        (!element.is_null()).then(|| ObjectKind::classify(ctx, element))
+    }
+
+    /// Iterates over the unpacked ranges of the array.
+    pub fn ranges(&self, ctx: &SimContext) -> HandleIterator {
+        self.0.iterator(ctx, ObjectType::Range)
+    }
+}
+
+impl Function {
+    /// Returns the module declaring this function.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the return type.
+    #[must_use]
+    pub fn func_type(&self, ctx: &SimContext) -> Option<FuncType> {
+        self.0.get_func_type(ctx)
+    }
+
+    /// Returns the width of the return value in bits.
+    #[must_use]
+    pub fn size(&self, ctx: &SimContext) -> Option<u32> {
+        size(ctx, &self.0)
+    }
+
+    /// Iterates over the argument declarations.
+    pub fn io_decls(&self, ctx: &SimContext) -> HandleIterator {
+        self.0.iterator(ctx, ObjectType::IODecl)
+    }
+}
+
+impl Task {
+    /// Returns the module declaring this task.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Iterates over the argument declarations.
+    pub fn io_decls(&self, ctx: &SimContext) -> HandleIterator {
+        self.0.iterator(ctx, ObjectType::IODecl)
+    }
+}
+
+impl ContAssign {
+    /// Returns the module containing this assignment.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the assigned expression.
+    #[must_use]
+    pub fn lhs(&self, ctx: &SimContext) -> Option<ObjectKind> {
+        related_kind(ctx, &self.0, ObjectType::Lhs)
+    }
+
+    /// Returns the assigned value expression.
+    #[must_use]
+    pub fn rhs(&self, ctx: &SimContext) -> Option<ObjectKind> {
+        related_kind(ctx, &self.0, ObjectType::Rhs)
+    }
+}
+
+impl Primitive {
+    /// Returns the module containing this instance.
+    #[must_use]
+    pub fn module(&self, ctx: &SimContext) -> Option<Module> {
+        related(ctx, &self.0, ObjectType::Module)
+    }
+
+    /// Returns the primitive type, such as `and` or `nmos`.
+    #[must_use]
+    pub fn prim_type(&self, ctx: &SimContext) -> Option<PrimType> {
+        self.0.get_prim_type(ctx)
+    }
+
+    /// Returns the definition name, such as `and` or the UDP name.
+    #[must_use]
+    pub fn def_name(&self, ctx: &SimContext) -> Option<String> {
+        self.0.get_str(ctx, Property::DefName)
+    }
+
+    /// Iterates over the terminals, in connection order.
+    pub fn terms(&self, ctx: &SimContext) -> HandleIterator {
+        self.0.iterator(ctx, ObjectType::PrimTerm)
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::{Memory, Module, Net, ObjectKind, Port, TypedHandle};
+    use crate::mock::MockSimulator;
+    use crate::{Direction, Handle, Value, ValueType};
+
+    #[test]
+    fn downcasts_check_the_object_type() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        let dut = sim.add_module(&tb, "dut", "counter");
+        let clk = sim.add_net(&dut, "clk", 1);
+        let _ = sim.add_reg(&dut, "count", 8);
+        let _ = sim.add_memory(&dut, "mem", 8, 4);
+        let _ = sim.add_port(&dut, "clk", Direction::Input, &clk);
+        let _ = sim.add_parameter(&dut, "WIDTH", &Value::Int(8), false);
+
+        let tb = tb.downcast::<Module>(ctx).unwrap();
+        assert!(tb.is_top(ctx));
+        assert!(clk.downcast::<Module>(ctx).is_none());
+        assert!(matches!(clk.kind(ctx), ObjectKind::Net(_)));
+        assert!(matches!(Handle::null().kind(ctx), ObjectKind::Other(_)));
+
+        let dut = tb.modules(ctx).next().unwrap();
+        assert_eq!(dut.def_name(ctx).as_deref(), Some("counter"));
+        assert_eq!(dut.parent(ctx), Some(tb));
+        let net: Net = clk.downcast(ctx).unwrap();
+        assert_eq!(net.module(ctx), Some(dut.clone()));
+        assert_eq!(net.size(ctx), Some(1));
+
+        let count = dut.regs(ctx).next().unwrap();
+        assert_eq!(count.full_name(ctx).as_deref(), Some("tb.dut.count"));
+        assert_eq!(count.value(ctx, ValueType::Int), Some(Value::Int(0)));
+
+        let mem: Memory = dut.memories(ctx).next().unwrap();
+        assert_eq!(mem.words(ctx).count(), 4);
+        let word = mem.word(ctx, 2).unwrap();
+        assert_eq!(word.name(ctx).as_deref(), Some("mem[2]"));
+        assert_eq!(word.index(ctx), Some(2));
+        assert_eq!(word.memory(ctx), Some(mem.clone()));
+        assert!(matches!(word.handle().kind(ctx), ObjectKind::MemoryWord(_)));
+        assert!(mem.word(ctx, 9).is_none());
+
+        let port: Port = dut.ports(ctx).next().unwrap();
+        assert_eq!(port.direction(ctx), Some(Direction::Input));
+        assert!(matches!(port.low_conn(ctx), Some(ObjectKind::Net(low)) if low == net));
+
+        let param = dut.parameters(ctx).next().unwrap();
+        assert_eq!(param.value(ctx, ValueType::Int), Some(Value::Int(8)));
+        assert!(!param.is_local(ctx));
+        assert_eq!(dut.functions(ctx).count(), 0);
+        assert_eq!(Handle::from(param).get_name(ctx).as_deref(), Some("WIDTH"));
+    }
+}
@@ -0,0 +1,166 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::{control, control_with_arg, CbReason, Control, Handle, SimContext};
+
+/// What happens after a panic is caught in a routine called by the simulator.
+///
+/// The panic is always reported through [`crate::printf`] first. Select the
+/// policy with [`set_panic_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Keep simulating.
+    Continue,
+    /// Stop the simulation with `vpiStop`, as `$stop` does.
+    Stop,
+    /// Finish the simulation with `vpiFinish`, as `$finish(n)` does.
+    ///
+    /// The argument is the `$finish` diagnostic level, not an exit status:
+    /// 0 prints nothing, 1 prints the simulation time and location, and 2
+    /// also prints memory and CPU usage. VPI offers no portable way to set
+    /// the exit status of the simulator.
+    Finish(i32),
+}
+
+impl Default for PanicPolicy {
+    /// Finishes with diagnostic level 1, the default of `$finish`.
+    fn default() -> Self {
+        Self::Finish(1)
+    }
+}
+
+thread_local! {
+    static POLICY: Cell<PanicPolicy> = Cell::new(PanicPolicy::default());
+}
+
+/// Sets the policy for panics caught on the simulator thread.
+///
+/// Defaults to [`PanicPolicy::Finish`] with diagnostic level 1.
+pub fn set_panic_policy(policy: PanicPolicy) {
+    POLICY.set(policy);
+}
+
+/// Returns the policy for panics caught on the simulator thread.
+#[must_use]
+pub fn panic_policy() -> PanicPolicy {
+    POLICY.get()
+}
+
+/// Runs `f`, catching a panic before it unwinds into the simulator.
+///
+/// Callbacks and `#[systf]` routines of this crate are run this way. Use it
+/// in hand-written `extern "C"` routines, such as a `calltf`, where unwinding
+/// out of the function would abort the simulator. A caught panic is reported
+/// with `routine` as context and handled according to [`panic_policy`];
+/// `None` is returned in that case.
+///
+/// ```ignore
+/// unsafe extern "C" fn calltf(_: *mut std::ffi::c_char) -> i32 {
+///     // Called by the simulator on its own thread.
+///     let ctx = unsafe { vpi::SimContext::new_unchecked() };
+///     vpi::catch_panic(&ctx, "$my_task calltf", || my_task(&ctx)).unwrap_or(0)
+/// }
+/// ```
+pub fn catch_panic<R>(ctx: &SimContext, routine: &str, f: impl FnOnce() -> R) -> Option<R> {
+    guard(ctx, || routine.to_string(), f)
+}
+
+/// Like [`catch_panic`], describing a callback by reason and object.
+pub(crate) fn guard_callback<R>(
+    ctx: &SimContext,
+    reason: CbReason,
+    obj: &Handle,
+    f: impl FnOnce() -> R,
+) -> Option<R> {
+    guard(
+        ctx,
+        || match obj.get_full_name(ctx) {
+            Some(name) => format!("{reason:?} callback on {name}"),
+            None => format!("{reason:?} callback"),
+        },
+        f,
+    )
+}
+
+fn guard<R>(
+    ctx: &SimContext,
+    describe: impl FnOnce() -> String,
+    f: impl FnOnce() -> R,
+) -> Option<R> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            crate::printf(
+                ctx,
+                format!(
+                    "ERROR: panic in {}: {}",
+                    describe(),
+                    panic_message(payload.as_ref())
+                ),
+            );
+            match panic_policy() {
+                PanicPolicy::Continue => {}
+                PanicPolicy::Stop => control(ctx, Control::Stop),
+                PanicPolicy::Finish(level) => control_with_arg(ctx, Control::Finish, level),
+            }
+            None
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "non-string panic payload"
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::{catch_panic, panic_policy, set_panic_policy, PanicPolicy};
+    use crate::mock::MockSimulator;
+    use crate::{register_cb_with_time, CbReason, Handle, Time};
+
+    #[test]
+    fn callback_panic_finishes_the_simulation_by_default() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+        assert_eq!(panic_policy(), PanicPolicy::Finish(1));
+
+        let _ = q.register_cb(ctx, CbReason::ValueChange, |_| panic!("boom"));
+        let _ = sim.set_value(&q, &crate::Value::Int(1));
+        assert!(sim
+            .take_output()
+            .contains("ERROR: panic in ValueChange callback on tb.q: boom"));
+        assert!(sim.is_finished());
+    }
+
+    #[test]
+    fn continue_policy_keeps_callbacks_running() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        sim.start();
+        set_panic_policy(PanicPolicy::Continue);
+
+        let _ = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(1), |_| {
+            panic!("{}", String::from("first"));
+        });
+        let _ = register_cb_with_time(ctx, CbReason::AfterDelay, Time::Sim(2), |_| {
+            crate::printf(ctx, "second");
+        });
+        sim.run_for(2);
+        assert_eq!(
+            sim.take_output(),
+            "ERROR: panic in AfterDelay callback: first\nsecond\n"
+        );
+        assert!(!sim.is_finished());
+        assert_eq!(catch_panic(ctx, "task", || 3), Some(3));
+    }
+}
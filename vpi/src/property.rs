@@ -2,7 +2,7 @@ use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 use vpi_sys::PLI_INT32;
 
-use crate::{Handle, ObjectType, Value, ValueType};
+use crate::{Handle, ObjectType, SimContext, Value, ValueType};
 
 #[cfg(feature = "sv")]
 use crate::HandleIterator;
@@ -992,7 +992,7 @@ impl Handle {
     ///
     /// Returns `None` for null handles.
     #[must_use]
-    pub fn get_i64(&self, property: Property) -> Option<i64> {
+    pub fn get_i64(&self, _ctx: &SimContext, property: Property) -> Option<i64> {
         if self.is_null() {
             return None;
         }
@@ -1005,8 +1005,8 @@ impl Handle {
     ///
     /// Returns `None` for null handles or negative raw values.
     #[must_use]
-    pub fn get_u64(&self, property: Property) -> Option<u64> {
-        let value = self.get_i64(property)?;
+    pub fn get_u64(&self, ctx: &SimContext, property: Property) -> Option<u64> {
+        let value = self.get_i64(ctx, property)?;
         u64::try_from(value).ok()
     }
 
@@ -1014,7 +1014,7 @@ impl Handle {
     ///
     /// Returns `None` for null handles or unsupported properties.
     #[must_use]
-    pub fn get_u32(&self, property: Property) -> Option<u32> {
+    pub fn get_u32(&self, _ctx: &SimContext, property: Property) -> Option<u32> {
         if self.is_null() {
             return None;
         }
@@ -1042,7 +1042,7 @@ impl Handle {
     ///
     /// Returns `None` for null handles, unsupported properties, or invalid UTF-8.
     #[must_use]
-    pub fn get_str(&self, property: Property) -> Option<String> {
+    pub fn get_str(&self, _ctx: &SimContext, property: Property) -> Option<String> {
         if self.is_null() {
             return None;
         }
@@ -1073,7 +1073,7 @@ impl Handle {
     ///
     /// Returns `None` for null handles or unsupported properties.
     #[must_use]
-    pub fn get_bool(&self, property: Property) -> Option<bool> {
+    pub fn get_bool(&self, _ctx: &SimContext, property: Property) -> Option<bool> {
         if self.is_null() {
             return None;
         }
@@ -1097,6 +1097,7 @@ impl Handle {
             | Property::LocalParam
             | Property::ModPathHasIfNone
             | Property::IsMemory
+            | Property::DefAttribute
             | Property::IsProtected => unsafe {
                 let value = vpi_sys::vpi_get(property as PLI_INT32, self.as_raw());
                 Some(value != 0)
@@ -1112,7 +1113,7 @@ impl Handle {
 
     /// Returns this object's port direction, if available.
     #[must_use]
-    pub fn get_direction(&self) -> Option<Direction> {
+    pub fn get_direction(&self, _ctx: &SimContext) -> Option<Direction> {
         if self.is_null() {
             return None;
         }
@@ -1122,7 +1123,7 @@ impl Handle {
 
     /// Returns this object's operation subtype, if available.
     #[must_use]
-    pub fn get_op_type(&self) -> Option<OpType> {
+    pub fn get_op_type(&self, _ctx: &SimContext) -> Option<OpType> {
         if self.is_null() {
             return None;
         }
@@ -1132,7 +1133,7 @@ impl Handle {
 
     /// Returns this object's primitive subtype, if available.
     #[must_use]
-    pub fn get_prim_type(&self) -> Option<PrimType> {
+    pub fn get_prim_type(&self, _ctx: &SimContext) -> Option<PrimType> {
         if self.is_null() {
             return None;
         }
@@ -1142,7 +1143,7 @@ impl Handle {
 
     /// Returns this object's timing check subtype, if available.
     #[must_use]
-    pub fn get_tchk_type(&self) -> Option<TchkType> {
+    pub fn get_tchk_type(&self, _ctx: &SimContext) -> Option<TchkType> {
         if self.is_null() {
             return None;
         }
@@ -1152,7 +1153,7 @@ impl Handle {
 
     /// Returns this object's constant subtype, if available.
     #[must_use]
-    pub fn get_const_type(&self) -> Option<ConstType> {
+    pub fn get_const_type(&self, _ctx: &SimContext) -> Option<ConstType> {
         if self.is_null() {
             return None;
         }
@@ -1162,25 +1163,25 @@ impl Handle {
 
     #[must_use]
     /// Returns this object's name, if available.
-    pub fn get_name(&self) -> Option<String> {
+    pub fn get_name(&self, ctx: &SimContext) -> Option<String> {
         if self.is_null() {
             return None;
         }
-        self.get_str(Property::Name)
+        self.get_str(ctx, Property::Name)
     }
 
     #[must_use]
     /// Returns this object's full hierarchical name, if available.
-    pub fn get_full_name(&self) -> Option<String> {
+    pub fn get_full_name(&self, ctx: &SimContext) -> Option<String> {
         if self.is_null() {
             return None;
         }
-        self.get_str(Property::FullName)
+        self.get_str(ctx, Property::FullName)
     }
 
     /// Returns this object's function type, if available.
     #[must_use]
-    pub fn get_func_type(&self) -> Option<FuncType> {
+    pub fn get_func_type(&self, _ctx: &SimContext) -> Option<FuncType> {
         if self.is_null() {
             return None;
         }
@@ -1190,7 +1191,7 @@ impl Handle {
 
     /// Returns this object's system function type, if available.
     #[must_use]
-    pub fn get_sys_func_type(&self) -> Option<SysFuncType> {
+    pub fn get_sys_func_type(&self, _ctx: &SimContext) -> Option<SysFuncType> {
         if self.is_null() {
             return None;
         }
@@ -1200,7 +1201,7 @@ impl Handle {
 
     /// Returns this object's edge mask, if available.
     #[must_use]
-    pub fn get_edge(&self) -> Option<Edge> {
+    pub fn get_edge(&self, _ctx: &SimContext) -> Option<Edge> {
         if self.is_null() {
             return None;
         }
@@ -1210,7 +1211,7 @@ impl Handle {
 
     /// Returns this object's VPI object type.
     #[must_use]
-    pub fn get_type(&self) -> Option<ObjectType> {
+    pub fn get_type(&self, _ctx: &SimContext) -> Option<ObjectType> {
         if self.is_null() {
             return None;
         }
@@ -1220,7 +1221,7 @@ impl Handle {
 
     /// Returns this object's index value, if available.
     #[must_use]
-    pub fn get_index(&self) -> Option<i32> {
+    pub fn get_index(&self, _ctx: &SimContext) -> Option<i32> {
         if self.is_null() {
             return None;
         }
@@ -1231,8 +1232,11 @@ impl Handle {
 
     /// Returns this object's left range value, if available.
     #[must_use]
-    pub fn get_left_range(&self) -> Option<i32> {
-        match self.get(ObjectType::LeftRange).get_value(ValueType::Int) {
+    pub fn get_left_range(&self, ctx: &SimContext) -> Option<i32> {
+        match self
+            .get(ctx, ObjectType::LeftRange)
+            .get_value(ctx, ValueType::Int)
+        {
             Some(Value::Int(value)) => Some(value),
             _ => None,
         }
@@ -1240,8 +1244,11 @@ impl Handle {
 
     /// Returns this object's right range value, if available.
     #[must_use]
-    pub fn get_right_range(&self) -> Option<i32> {
-        match self.get(ObjectType::RightRange).get_value(ValueType::Int) {
+    pub fn get_right_range(&self, ctx: &SimContext) -> Option<i32> {
+        match self
+            .get(ctx, ObjectType::RightRange)
+            .get_value(ctx, ValueType::Int)
+        {
             Some(Value::Int(value)) => Some(value),
             _ => None,
         }
@@ -1250,54 +1257,55 @@ impl Handle {
     #[cfg(feature = "sv")]
     /// Iterates packages visible from this scope/root.
     #[must_use]
-    pub fn get_packages(&self) -> Vec<Handle> {
+    pub fn get_packages(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::Package).collect()
+        self.iterator(ctx, ObjectType::Package).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates interface instances visible from this scope/root.
     #[must_use]
-    pub fn get_interfaces(&self) -> Vec<Handle> {
+    pub fn get_interfaces(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::Interface).collect()
+        self.iterator(ctx, ObjectType::Interface).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates program instances visible from this scope/root.
     #[must_use]
-    pub fn get_programs(&self) -> Vec<Handle> {
+    pub fn get_programs(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::Program).collect()
+        self.iterator(ctx, ObjectType::Program).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates virtual interface variables visible from this scope/root.
     #[must_use]
-    pub fn get_virtual_interfaces(&self) -> Vec<Handle> {
+    pub fn get_virtual_interfaces(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::VirtualInterfaceVar).collect()
+        self.iterator(ctx, ObjectType::VirtualInterfaceVar)
+            .collect()
     }
 
     #[cfg(feature = "sv")]
     /// Returns whether this object participates in randomization.
     #[must_use]
-    pub fn is_randomized(&self) -> Option<bool> {
-        self.get_bool(Property::IsRandomized)
+    pub fn is_randomized(&self, ctx: &SimContext) -> Option<bool> {
+        self.get_bool(ctx, Property::IsRandomized)
     }
 
     #[cfg(feature = "sv")]
     /// Returns the SystemVerilog randomization qualifier (`rand`, `randc`, etc.).
     #[must_use]
-    pub fn get_rand_type(&self) -> Option<RandType> {
+    pub fn get_rand_type(&self, _ctx: &SimContext) -> Option<RandType> {
         if self.is_null() {
             return None;
         }
@@ -1308,21 +1316,21 @@ impl Handle {
     #[cfg(feature = "sv")]
     /// Returns whether this constraint object is enabled.
     #[must_use]
-    pub fn is_constraint_enabled(&self) -> Option<bool> {
-        self.get_bool(Property::IsConstraintEnabled)
+    pub fn is_constraint_enabled(&self, ctx: &SimContext) -> Option<bool> {
+        self.get_bool(ctx, Property::IsConstraintEnabled)
     }
 
     #[cfg(feature = "sv")]
     /// Returns whether this constraint object is declared `soft`.
     #[must_use]
-    pub fn is_constraint_soft(&self) -> Option<bool> {
-        self.get_bool(Property::Soft)
+    pub fn is_constraint_soft(&self, ctx: &SimContext) -> Option<bool> {
+        self.get_bool(ctx, Property::Soft)
     }
 
     #[cfg(feature = "sv")]
     /// Returns distribution style metadata for distribution constraints.
     #[must_use]
-    pub fn get_dist_type(&self) -> Option<DistType> {
+    pub fn get_dist_type(&self, _ctx: &SimContext) -> Option<DistType> {
         if self.is_null() {
             return None;
         }
@@ -1333,61 +1341,61 @@ impl Handle {
     #[cfg(feature = "sv")]
     /// Iterates class constraints reachable from this object.
     #[must_use]
-    pub fn get_constraints(&self) -> Vec<Handle> {
+    pub fn get_constraints(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::Constraint).collect()
+        self.iterator(ctx, ObjectType::Constraint).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates constraint-ordering nodes (`solve ... before ...`) on this object.
     #[must_use]
-    pub fn get_constraint_ordering(&self) -> Vec<Handle> {
+    pub fn get_constraint_ordering(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::ConstraintOrdering).collect()
+        self.iterator(ctx, ObjectType::ConstraintOrdering).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates constraint items for this constraint object.
     #[must_use]
-    pub fn get_constraint_items(&self) -> Vec<Handle> {
+    pub fn get_constraint_items(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::ConstraintItem).collect()
+        self.iterator(ctx, ObjectType::ConstraintItem).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates `solve before` edges under a constraint-ordering object.
     #[must_use]
-    pub fn get_solve_before(&self) -> Vec<Handle> {
+    pub fn get_solve_before(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::SolveBefore).collect()
+        self.iterator(ctx, ObjectType::SolveBefore).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates `solve after` edges under a constraint-ordering object.
     #[must_use]
-    pub fn get_solve_after(&self) -> Vec<Handle> {
+    pub fn get_solve_after(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::SolveAfter).collect()
+        self.iterator(ctx, ObjectType::SolveAfter).collect()
     }
 
     #[cfg(feature = "sv")]
     /// Iterates distribution-item nodes (`dist` list entries).
     #[must_use]
-    pub fn get_distribution_items(&self) -> Vec<Handle> {
+    pub fn get_distribution_items(&self, ctx: &SimContext) -> Vec<Handle> {
         if self.is_null() {
             return Vec::new();
         }
-        self.iterator(ObjectType::DistItem).collect()
+        self.iterator(ctx, ObjectType::DistItem).collect()
     }
 
     #[cfg(feature = "sv")]
@@ -1397,11 +1405,11 @@ impl Handle {
     /// then reads its `vpiType` property to determine the concrete typespec
     /// variant.
     #[must_use]
-    pub fn get_typespec(&self) -> Option<Typespec> {
+    pub fn get_typespec(&self, ctx: &SimContext) -> Option<Typespec> {
         if self.is_null() {
             return None;
         }
-        let ts = self.get(ObjectType::Typespec);
+        let ts = self.get(ctx, ObjectType::Typespec);
         if ts.is_null() {
             return None;
         }
@@ -1412,13 +1420,13 @@ impl Handle {
     #[cfg(feature = "sv")]
     /// Iterates class/struct members reachable from this object.
     #[must_use]
-    pub fn member_iterator(&self) -> HandleIterator {
+    pub fn member_iterator(&self, ctx: &SimContext) -> HandleIterator {
         if self.is_null() {
             return HandleIterator {
                 iter: Handle::default(),
             };
         }
-        self.iterator(ObjectType::Member)
+        self.iterator(ctx, ObjectType::Member)
     }
 
     /// Returns a human-readable type name for this object.
@@ -1441,32 +1449,32 @@ impl Handle {
     /// Returns `None` for null handles or when none of the above produce a
     /// valid string.
     #[must_use]
-    pub fn get_type_name(&self) -> Option<String> {
+    pub fn get_type_name(&self, ctx: &SimContext) -> Option<String> {
         if self.is_null() {
             return None;
         }
 
         // Unwrap port handles to their underlying signal before inspecting type.
-        if self.is_port() {
+        if self.is_port(ctx) {
             // 1. Follow the internal connection (works in many simulators).
-            let inner = self.get(ObjectType::LowConn);
+            let inner = self.get(ctx, ObjectType::LowConn);
             let inner = if inner.is_null() {
-                self.get(ObjectType::HighConn)
+                self.get(ctx, ObjectType::HighConn)
             } else {
                 inner
             };
             if !inner.is_null() {
-                return inner.get_type_name();
+                return inner.get_type_name(ctx);
             }
 
             // 2. Look up the net/reg with the same name in the parent scope.
             //    Icarus Verilog doesn't populate LowConn/HighConn, but the
             //    underlying signal always shares the port's name in the module.
-            if let Some(port_name) = self.get_name() {
-                let scope = self.get(ObjectType::Scope);
-                let net = Handle::handle_by_name_and_scope(&port_name, &scope);
+            if let Some(port_name) = self.get_name(ctx) {
+                let scope = self.get(ctx, ObjectType::Scope);
+                let net = Handle::handle_by_name_and_scope(ctx, &port_name, &scope);
                 if !net.is_null() {
-                    return net.get_type_name();
+                    return net.get_type_name(ctx);
                 }
             }
 
@@ -1483,20 +1491,20 @@ impl Handle {
 
         #[cfg(feature = "sv")]
         {
-            let ts = self.get(ObjectType::Typespec);
+            let ts = self.get(ctx, ObjectType::Typespec);
             if !ts.is_null() {
-                if let name @ Some(_) = ts.get_str(Property::DefName) {
+                if let name @ Some(_) = ts.get_str(ctx, Property::DefName) {
                     return name;
                 }
-                if let name @ Some(_) = ts.get_str(Property::Name) {
+                if let name @ Some(_) = ts.get_str(ctx, Property::Name) {
                     return name;
                 }
             }
         }
-        if let Some(var_type) = self.get_var_type() {
+        if let Some(var_type) = self.get_var_type(ctx) {
             return Some(var_type.to_string());
         }
-        self.get_str(Property::Type)
+        self.get_str(ctx, Property::Type)
     }
 
     /// Returns this object's variable kind, if the handle refers to a variable.
@@ -1504,7 +1512,7 @@ impl Handle {
     /// Reads `vpiType` and maps the result to a [`VarType`] variant.
     /// Returns `None` for null handles or non-variable object types.
     #[must_use]
-    pub fn get_var_type(&self) -> Option<VarType> {
+    pub fn get_var_type(&self, _ctx: &SimContext) -> Option<VarType> {
         if self.is_null() {
             return None;
         }
@@ -1514,8 +1522,8 @@ impl Handle {
 
     /// Returns this object's size, i.e., number of elements, if available.
     #[must_use]
-    pub fn get_size(&self) -> Option<u32> {
-        self.get_u32(Property::Size)
+    pub fn get_size(&self, ctx: &SimContext) -> Option<u32> {
+        self.get_u32(ctx, Property::Size)
     }
 
     /// Reads a numeric property using `vpi_get` and returns it as `PLI_INT32`.
@@ -1526,7 +1534,7 @@ impl Handle {
     ///
     /// To obtain other types use the corresponding methods.
     #[must_use]
-    pub fn get_raw_property(&self, property: Property) -> Option<PLI_INT32> {
+    pub fn get_raw_property(&self, _ctx: &SimContext, property: Property) -> Option<PLI_INT32> {
         if self.is_null() {
             return None;
         }
@@ -1536,9 +1544,9 @@ impl Handle {
 
     /// Returns true if this object is a port or port bit.
     #[must_use]
-    pub fn is_port(&self) -> bool {
+    pub fn is_port(&self, ctx: &SimContext) -> bool {
         matches!(
-            self.get_type(),
+            self.get_type(ctx),
             Some(ObjectType::Port | ObjectType::PortBit)
         )
     }
@@ -1547,26 +1555,27 @@ impl Handle {
 #[cfg(all(test, feature = "sv"))]
 mod tests {
     use super::{DistType, RandType};
-    use crate::Handle;
+    use crate::{Handle, SimContext};
 
     #[test]
     fn sv_constraint_helpers_on_null_handle_are_safe() {
+        let ctx = SimContext::borrowed();
         let h = Handle::null();
-        assert!(h.get_packages().is_empty());
-        assert!(h.get_interfaces().is_empty());
-        assert!(h.get_programs().is_empty());
-        assert!(h.get_virtual_interfaces().is_empty());
-        assert_eq!(h.is_randomized(), None);
-        assert_eq!(h.get_rand_type(), None);
-        assert_eq!(h.is_constraint_enabled(), None);
-        assert_eq!(h.is_constraint_soft(), None);
-        assert_eq!(h.get_dist_type(), None);
-        assert!(h.get_constraints().is_empty());
-        assert!(h.get_constraint_ordering().is_empty());
-        assert!(h.get_constraint_items().is_empty());
-        assert!(h.get_solve_before().is_empty());
-        assert!(h.get_solve_after().is_empty());
-        assert!(h.get_distribution_items().is_empty());
+        assert!(h.get_packages(ctx).is_empty());
+        assert!(h.get_interfaces(ctx).is_empty());
+        assert!(h.get_programs(ctx).is_empty());
+        assert!(h.get_virtual_interfaces(ctx).is_empty());
+        assert_eq!(h.is_randomized(ctx), None);
+        assert_eq!(h.get_rand_type(ctx), None);
+        assert_eq!(h.is_constraint_enabled(ctx), None);
+        assert_eq!(h.is_constraint_soft(ctx), None);
+        assert_eq!(h.get_dist_type(ctx), None);
+        assert!(h.get_constraints(ctx).is_empty());
+        assert!(h.get_constraint_ordering(ctx).is_empty());
+        assert!(h.get_constraint_items(ctx).is_empty());
+        assert!(h.get_solve_before(ctx).is_empty());
+        assert!(h.get_solve_after(ctx).is_empty());
+        assert!(h.get_distribution_items(ctx).is_empty());
     }
 
     #[test]
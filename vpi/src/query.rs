@@ -0,0 +1,730 @@
+//! Selection of design objects by hierarchical name patterns.
+//!
+//! A query combines a path pattern with optional kind filters and a depth
+//! limit, and yields the matching [`Handle`]s:
+//!
+//! - Path components are separated by `.` and matched against object names
+//!   with `*` (any run of characters) and `?` (one character).
+//! - A `**` component matches any number of scope levels, including none, so
+//!   `tb.dut.**.fifo*.wr_ptr` finds `wr_ptr` in every FIFO below `tb.dut`. A
+//!   trailing `**` selects everything below.
+//! - Kind filters restrict the selected objects to modules, nets, regs,
+//!   variables, memories, ports or parameters. Ports can further be filtered
+//!   by direction. Without filters all kinds except ports are selected.
+//! - A depth limit bounds the number of name components of the selected
+//!   objects; top-level modules have depth 1.
+//!
+//! With the `regex` feature, a regular expression can be used instead of a
+//! path pattern. It must match the full hierarchical name.
+//!
+//! Queries can be built in code or parsed from text, for example from a
+//! plusarg or a configuration file. The text form is the path pattern, or a
+//! regular expression between slashes, followed by space-separated filters:
+//!
+//! ```text
+//! tb.dut.**.fifo*.wr_ptr
+//! tb.dut.** nets regs depth=4
+//! tb.*.u_core ports(direction=input)
+//! /tb\.dut\..*_(rd|wr)_ptr/ regs
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::query::{Kind, Query};
+//!
+//! fn list(ctx: &vpi::SimContext) {
+//!     let query: Query = "tb.dut.**.fifo*.wr_ptr".parse().unwrap();
+//!     for handle in query.kind(Kind::Reg).select(ctx) {
+//!         vpi::printf!(ctx, "{:?}", handle.get_full_name(ctx));
+//!     }
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::wave::split_hierarchy;
+use crate::{Direction, Handle, ObjectType, SimContext};
+
+/// Kind of object selected by a [`Query`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Module instances (`modules`).
+    Module,
+    /// Nets (`nets`).
+    Net,
+    /// Regs (`regs`).
+    Reg,
+    /// Integer, real and other variables (`vars`).
+    Variable,
+    /// Memories (`memories`).
+    Memory,
+    /// Module ports (`ports`).
+    Port,
+    /// Parameters (`params`).
+    Parameter,
+}
+
+impl Kind {
+    const DEFAULT: [Kind; 6] = [
+        Kind::Module,
+        Kind::Net,
+        Kind::Reg,
+        Kind::Variable,
+        Kind::Memory,
+        Kind::Parameter,
+    ];
+
+    fn object_type(self) -> ObjectType {
+        match self {
+            Kind::Module => ObjectType::Module,
+            Kind::Net => ObjectType::Net,
+            Kind::Reg => ObjectType::Reg,
+            Kind::Variable => ObjectType::Variables,
+            Kind::Memory => ObjectType::Memory,
+            Kind::Port => ObjectType::Port,
+            Kind::Parameter => ObjectType::Parameter,
+        }
+    }
+
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        Some(match keyword {
+            "modules" => Kind::Module,
+            "nets" => Kind::Net,
+            "regs" => Kind::Reg,
+            "vars" | "variables" => Kind::Variable,
+            "memories" => Kind::Memory,
+            "ports" => Kind::Port,
+            "params" | "parameters" => Kind::Parameter,
+            _ => return None,
+        })
+    }
+}
+
+/// Error returned when a query cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    position: usize,
+    message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the byte offset in the query text where the error was found.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns a description of the error.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid query at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `**`: any number of scope levels.
+    AnyDepth,
+    Glob(String),
+}
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Path(Vec<Segment>),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+/// A selection of design objects; see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Query {
+    pattern: Pattern,
+    kinds: Vec<Kind>,
+    direction: Option<Direction>,
+    max_depth: Option<usize>,
+}
+
+impl Query {
+    /// Creates a query from a path pattern such as `tb.dut.**.fifo*.wr_ptr`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for empty patterns and empty path components.
+    pub fn new(pattern: &str) -> Result<Self, QueryError> {
+        Ok(Self::with_pattern(Pattern::Path(parse_path(pattern, 0)?)))
+    }
+
+    /// Creates a query from a regular expression matched against full
+    /// hierarchical names.
+    ///
+    /// # Errors
+    ///
+    /// Fails for invalid regular expressions.
+    #[cfg(feature = "regex")]
+    pub fn regex(pattern: &str) -> Result<Self, QueryError> {
+        regex::Regex::new(&format!("^(?:{pattern})$"))
+            .map(|regex| Self::with_pattern(Pattern::Regex(regex)))
+            .map_err(|err| QueryError::new(0, err.to_string()))
+    }
+
+    /// Parses the text form of a query: a path pattern or `/regex/`,
+    /// followed by filters such as `nets`, `ports(direction=input)` or
+    /// `depth=3`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for invalid patterns, unknown filters and regular expressions
+    /// without the `regex` feature.
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        let start = text.len() - text.trim_start().len();
+        let rest = &text[start..];
+        let (mut query, mut position) = if let Some(body) = rest.strip_prefix('/') {
+            let Some(end) = body.rfind('/') else {
+                return Err(QueryError::new(start, "unterminated regular expression"));
+            };
+            (Self::parse_regex(&body[..end], start)?, start + end + 2)
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            (
+                Self::with_pattern(Pattern::Path(parse_path(&rest[..end], start)?)),
+                start + end,
+            )
+        };
+        while let Some((filter, next)) = next_filter(text, position)? {
+            query.apply_filter(&filter)?;
+            position = next;
+        }
+        Ok(query)
+    }
+
+    #[cfg(feature = "regex")]
+    fn parse_regex(pattern: &str, position: usize) -> Result<Self, QueryError> {
+        Self::regex(pattern).map_err(|err| QueryError::new(position, err.message))
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn parse_regex(_pattern: &str, position: usize) -> Result<Self, QueryError> {
+        Err(QueryError::new(
+            position,
+            "regular expressions require the `regex` feature",
+        ))
+    }
+
+    fn with_pattern(pattern: Pattern) -> Self {
+        Self {
+            pattern,
+            kinds: Vec::new(),
+            direction: None,
+            max_depth: None,
+        }
+    }
+
+    /// Adds `kind` to the selected kinds.
+    ///
+    /// Without any kind, all kinds except [`Kind::Port`] are selected.
+    #[must_use]
+    pub fn kind(mut self, kind: Kind) -> Self {
+        self.add_kind(kind);
+        self
+    }
+
+    /// Selects only ports of `direction`, and adds [`Kind::Port`].
+    #[must_use]
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self.add_kind(Kind::Port);
+        self
+    }
+
+    /// Selects only objects with at most `depth` name components below the
+    /// search root.
+    #[must_use]
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Returns the matching objects of the whole design.
+    #[must_use]
+    pub fn select(&self, ctx: &SimContext) -> std::vec::IntoIter<Handle> {
+        self.select_in(ctx, &Handle::null())
+    }
+
+    /// Returns the matching objects below `scope`, with path patterns
+    /// relative to it. A null scope selects from the top-level modules.
+    ///
+    /// Objects are returned in traversal order, each once.
+    #[must_use]
+    pub fn select_in(&self, ctx: &SimContext, scope: &Handle) -> std::vec::IntoIter<Handle> {
+        let mut found = Vec::new();
+        match &self.pattern {
+            Pattern::Path(segments) => self.walk(ctx, scope, segments, 1, &mut found),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => self.walk_all(ctx, scope, regex, 1, &mut found),
+        }
+        let mut seen = HashSet::new();
+        found.retain(|handle| match handle.get_full_name(ctx) {
+            Some(name) => seen.insert(name),
+            None => true,
+        });
+        found.into_iter()
+    }
+
+    /// Matches `segments` against the children of `scope`, which are at
+    /// `depth`.
+    fn walk(
+        &self,
+        ctx: &SimContext,
+        scope: &Handle,
+        segments: &[Segment],
+        depth: usize,
+        found: &mut Vec<Handle>,
+    ) {
+        if self.max_depth.is_some_and(|max| depth > max) {
+            return;
+        }
+        match segments.split_first() {
+            None => {}
+            Some((Segment::AnyDepth, rest)) => {
+                self.walk(ctx, scope, rest, depth, found);
+                for child in child_scopes(ctx, scope) {
+                    self.walk(ctx, &child, segments, depth + 1, found);
+                }
+            }
+            Some((Segment::Glob(glob), [])) => {
+                found.extend(
+                    self.objects(ctx, scope).filter(|object| {
+                        object.get_name(ctx).is_some_and(|n| glob_match(glob, &n))
+                    }),
+                );
+            }
+            Some((Segment::Glob(glob), rest)) => {
+                for child in child_scopes(ctx, scope) {
+                    if child
+                        .get_name(ctx)
+                        .is_some_and(|name| glob_match(glob, &name))
+                    {
+                        self.walk(ctx, &child, rest, depth + 1, found);
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    fn walk_all(
+        &self,
+        ctx: &SimContext,
+        scope: &Handle,
+        regex: &regex::Regex,
+        depth: usize,
+        found: &mut Vec<Handle>,
+    ) {
+        if self.max_depth.is_some_and(|max| depth > max) {
+            return;
+        }
+        found.extend(self.objects(ctx, scope).filter(|object| {
+            object
+                .get_full_name(ctx)
+                .is_some_and(|name| regex.is_match(&name))
+        }));
+        for child in child_scopes(ctx, scope) {
+            self.walk_all(ctx, &child, regex, depth + 1, found);
+        }
+    }
+
+    /// Returns the objects of the selected kinds directly in `scope`.
+    fn objects<'a>(
+        &'a self,
+        ctx: &SimContext,
+        scope: &'a Handle,
+    ) -> impl Iterator<Item = Handle> + 'a {
+        let ctx = *ctx;
+        let kinds: &[Kind] = if self.kinds.is_empty() {
+            &Kind::DEFAULT
+        } else {
+            &self.kinds
+        };
+        kinds
+            .iter()
+            // Only modules can be iterated from the null handle.
+            .filter(move |kind| !scope.is_null() || **kind == Kind::Module)
+            .flat_map(move |kind| {
+                scope
+                    .iterator(&ctx, kind.object_type())
+                    .filter(move |object| {
+                        *kind != Kind::Port
+                            || self.direction.as_ref().is_none_or(|direction| {
+                                object.get_direction(&ctx).as_ref() == Some(direction)
+                            })
+                    })
+            })
+    }
+
+    fn add_kind(&mut self, kind: Kind) {
+        if !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+    }
+
+    fn apply_filter(&mut self, filter: &Filter<'_>) -> Result<(), QueryError> {
+        if let Some(depth) = filter.name.strip_prefix("depth=") {
+            let depth = depth
+                .parse()
+                .map_err(|_| QueryError::new(filter.position, "depth must be a number"))?;
+            self.max_depth = Some(depth);
+            return Ok(());
+        }
+        let Some(kind) = Kind::from_keyword(filter.name) else {
+            return Err(QueryError::new(
+                filter.position,
+                format!("unknown filter `{}`", filter.name),
+            ));
+        };
+        self.add_kind(kind);
+        for (key, value) in &filter.options {
+            match (kind, *key) {
+                (Kind::Port, "direction") => {
+                    self.direction = Some(parse_direction(value).ok_or_else(|| {
+                        QueryError::new(filter.position, format!("unknown direction `{value}`"))
+                    })?);
+                }
+                _ => {
+                    return Err(QueryError::new(
+                        filter.position,
+                        format!("`{}` has no option `{key}`", filter.name),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+fn parse_path(pattern: &str, position: usize) -> Result<Vec<Segment>, QueryError> {
+    if pattern.is_empty() {
+        return Err(QueryError::new(position, "empty path pattern"));
+    }
+    let mut segments = Vec::new();
+    for component in split_hierarchy(pattern) {
+        match component.as_str() {
+            "" => return Err(QueryError::new(position, "empty path component")),
+            "**" => segments.push(Segment::AnyDepth),
+            _ => segments.push(Segment::Glob(component)),
+        }
+    }
+    if segments.last() == Some(&Segment::AnyDepth) {
+        segments.push(Segment::Glob("*".into()));
+    }
+    Ok(segments)
+}
+
+fn parse_direction(text: &str) -> Option<Direction> {
+    Some(match text {
+        "input" => Direction::Input,
+        "output" => Direction::Output,
+        "inout" => Direction::Inout,
+        "mixed" => Direction::MixedIO,
+        "none" => Direction::NoDirection,
+        _ => return None,
+    })
+}
+
+/// A filter of the text form, such as `ports(direction=input)`.
+struct Filter<'a> {
+    position: usize,
+    name: &'a str,
+    options: Vec<(&'a str, &'a str)>,
+}
+
+/// Reads the filter starting at or after `position`, returning it and the
+/// position after it.
+fn next_filter(text: &str, position: usize) -> Result<Option<(Filter<'_>, usize)>, QueryError> {
+    let rest = &text[position..];
+    let start = position + (rest.len() - rest.trim_start().len());
+    let rest = &text[start..];
+    if rest.is_empty() {
+        return Ok(None);
+    }
+    let name_end = rest
+        .find(|c: char| c.is_whitespace() || c == '(')
+        .unwrap_or(rest.len());
+    let name = &rest[..name_end];
+    let after_name = rest[name_end..].trim_start();
+    let Some(body) = after_name.strip_prefix('(') else {
+        let filter = Filter {
+            position: start,
+            name,
+            options: Vec::new(),
+        };
+        return Ok(Some((filter, start + name_end)));
+    };
+    let Some(close) = body.find(')') else {
+        return Err(QueryError::new(
+            start,
+            format!("unclosed `(` after `{name}`"),
+        ));
+    };
+    let mut options = Vec::new();
+    for option in body[..close].split(',').filter(|o| !o.trim().is_empty()) {
+        let Some((key, value)) = option.split_once('=') else {
+            return Err(QueryError::new(
+                start,
+                format!("expected `key=value` in `{name}`"),
+            ));
+        };
+        options.push((key.trim(), value.trim()));
+    }
+    let end = text.len() - body[close + 1..].len();
+    Ok(Some((
+        Filter {
+            position: start,
+            name,
+            options,
+        },
+        end,
+    )))
+}
+
+/// Returns the module instances and the internal scopes, such as generate
+/// and named blocks, directly below `scope`.
+pub(crate) fn child_scopes(ctx: &SimContext, scope: &Handle) -> Vec<Handle> {
+    let mut children: Vec<Handle> = scope.iterator(ctx, ObjectType::Module).collect();
+    if !scope.is_null() {
+        for inner in scope.iterator(ctx, ObjectType::InternalScope) {
+            if !children.contains(&inner) {
+                children.push(inner);
+            }
+        }
+    }
+    children
+}
+
+/// Returns the module instances below `module`, looking through its
+/// generate scopes and named blocks, together with those scopes.
+///
+/// The scopes belong to `module` itself and are listed before the scopes
+/// nested in them.
+pub(crate) fn instance_children(ctx: &SimContext, module: &Handle) -> (Vec<Handle>, Vec<Handle>) {
+    let mut modules = Vec::new();
+    let mut inner: Vec<Handle> = Vec::new();
+    let mut scope = module.clone();
+    for next in 0.. {
+        for child in child_scopes(ctx, &scope) {
+            if matches!(child.get_type(ctx), Some(ObjectType::Module)) {
+                modules.push(child);
+            } else {
+                inner.push(child);
+            }
+        }
+        match inner.get(next) {
+            Some(nested) => scope = nested.clone(),
+            None => break,
+        }
+    }
+    (modules, inner)
+}
+
+/// Matches `name` against a pattern with `*` and `?` wildcards.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it resumes from.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, from)) => {
+                    p = star;
+                    n = from + 1;
+                    backtrack = Some((star, from + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, Query};
+
+    #[test]
+    fn globs_match_stars_and_question_marks() {
+        assert!(glob_match("fifo*", "fifo_0"));
+        assert!(glob_match("*_ptr", "wr_ptr"));
+        assert!(glob_match("f?fo*x", "fifo_x"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("fifo?", "fifo"));
+        assert!(!glob_match("*_ptr", "wr_ptr_q"));
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        let error = |text: &str| Query::parse(text).unwrap_err();
+        assert_eq!(error("").message(), "empty path pattern");
+        assert_eq!(error("tb..q").message(), "empty path component");
+        assert_eq!(error("tb.* wires").position(), 5);
+        assert_eq!(
+            error("tb.* nets(width=3)").message(),
+            "`nets` has no option `width`"
+        );
+        assert_eq!(
+            error("tb.* ports(direction=up)").message(),
+            "unknown direction `up`"
+        );
+        assert!(error("tb.* depth=x").message().contains("number"));
+        assert!(error("tb.* ports(direction=input")
+            .message()
+            .contains("unclosed"));
+        #[cfg(not(feature = "regex"))]
+        assert!(error("/tb.*/").message().contains("`regex` feature"));
+    }
+
+    #[cfg(mock_backend)]
+    mod simulated {
+        use crate::mock::MockSimulator;
+        use crate::query::{Kind, Query};
+        use crate::{Direction, Handle, SimContext};
+
+        fn names(ctx: &SimContext, query: &str) -> Vec<String> {
+            query
+                .parse::<Query>()
+                .unwrap()
+                .select(ctx)
+                .filter_map(|handle| handle.get_full_name(ctx))
+                .collect()
+        }
+
+        #[test]
+        fn selects_objects_by_pattern_kind_and_depth() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let tb = sim.add_module(&Handle::null(), "tb", "tb");
+            let dut = sim.add_module(&tb, "dut", "dut");
+            let rx = sim.add_module(&dut, "fifo_rx", "fifo");
+            let tx = sim.add_module(&dut, "fifo_tx", "fifo");
+            let inner = sim.add_module(&tx, "core", "core");
+            for scope in [&rx, &tx, &inner] {
+                let _ = sim.add_reg(scope, "wr_ptr", 4);
+                let _ = sim.add_net(scope, "rd_ptr", 4);
+            }
+            let clk = sim.add_net(&dut, "clk", 1);
+            let _ = sim.add_port(&dut, "clk", Direction::Input, &clk);
+
+            assert_eq!(
+                names(ctx, "tb.dut.**.fifo*.wr_ptr"),
+                ["tb.dut.fifo_rx.wr_ptr", "tb.dut.fifo_tx.wr_ptr"]
+            );
+            assert_eq!(
+                names(ctx, "tb.**.*_ptr regs"),
+                [
+                    "tb.dut.fifo_rx.wr_ptr",
+                    "tb.dut.fifo_tx.wr_ptr",
+                    "tb.dut.fifo_tx.core.wr_ptr"
+                ]
+            );
+            assert_eq!(
+                names(ctx, "tb.** nets depth=4"),
+                [
+                    "tb.dut.clk",
+                    "tb.dut.fifo_rx.rd_ptr",
+                    "tb.dut.fifo_tx.rd_ptr"
+                ]
+            );
+            assert_eq!(
+                names(ctx, "tb.dut.fifo_??"),
+                ["tb.dut.fifo_rx", "tb.dut.fifo_tx"]
+            );
+            assert_eq!(names(ctx, "*.dut.* ports(direction=input)"), ["tb.dut.clk"]);
+            assert!(names(ctx, "*.dut.* ports(direction=output)").is_empty());
+
+            let query = Query::new("fifo_tx.**")
+                .unwrap()
+                .kind(Kind::Net)
+                .max_depth(2);
+            let relative: Vec<_> = query
+                .select_in(ctx, &dut)
+                .filter_map(|h| h.get_name(ctx))
+                .collect();
+            assert_eq!(relative, ["rd_ptr"]);
+        }
+
+        #[test]
+        fn descends_into_generate_scopes() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let tb = sim.add_module(&Handle::null(), "tb", "tb");
+            let dut = sim.add_module(&tb, "dut", "dut");
+            for name in ["gen[0]", "gen[1]"] {
+                let scope = sim.add_gen_scope(&dut, name);
+                let fifo = sim.add_module(&scope, "fifo0", "fifo");
+                let _ = sim.add_reg(&fifo, "wr_ptr", 4);
+                let _ = sim.add_net(&scope, "valid", 1);
+            }
+
+            assert_eq!(names(ctx, "tb.dut.gen[0].fifo*"), ["tb.dut.gen[0].fifo0"]);
+            assert_eq!(
+                names(ctx, "tb.**.wr_ptr"),
+                ["tb.dut.gen[0].fifo0.wr_ptr", "tb.dut.gen[1].fifo0.wr_ptr"]
+            );
+            assert_eq!(
+                names(ctx, "tb.dut.gen*.valid"),
+                ["tb.dut.gen[0].valid", "tb.dut.gen[1].valid"]
+            );
+        }
+
+        #[cfg(feature = "regex")]
+        #[test]
+        fn selects_objects_by_regex() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let tb = sim.add_module(&Handle::null(), "tb", "tb");
+            let dut = sim.add_module(&tb, "dut", "dut");
+            let _ = sim.add_reg(&dut, "wr_ptr", 4);
+            let _ = sim.add_reg(&dut, "rd_ptr", 4);
+            let _ = sim.add_reg(&dut, "wr_ptr_q", 4);
+
+            assert_eq!(
+                names(ctx, r"/tb\.dut\.(rd|wr)_ptr/ regs"),
+                ["tb.dut.wr_ptr", "tb.dut.rd_ptr"]
+            );
+            assert!(Query::regex("(").is_err());
+        }
+    }
+}
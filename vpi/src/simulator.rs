@@ -1,4 +1,4 @@
-use crate::Time;
+use crate::{Handle, SimContext, Time};
 use vpi_sys::PLI_INT32;
 
 /// Returns simulator invocation metadata from `vpi_get_vlog_info`.
@@ -6,7 +6,7 @@ use vpi_sys::PLI_INT32;
 /// This includes the simulator product string, version string, and command-line
 /// arguments as reported by the active VPI implementation.
 #[must_use]
-pub fn simulator_info() -> SimulatorInfo {
+pub fn simulator_info(_ctx: &SimContext) -> SimulatorInfo {
     let mut vlog_info = vpi_sys::t_vpi_vlog_info {
         argc: 0,
         argv: std::ptr::null_mut(),
@@ -51,7 +51,7 @@ pub struct SimulatorInfo {
 
 /// Returns the simulator product name.
 #[must_use]
-pub fn simulator_name() -> String {
+pub fn simulator_name(_ctx: &SimContext) -> String {
     let mut vlog_info = vpi_sys::t_vpi_vlog_info {
         argc: 0,
         argv: std::ptr::null_mut(),
@@ -67,7 +67,7 @@ pub fn simulator_name() -> String {
 
 /// Returns the simulator version string.
 #[must_use]
-pub fn simulator_version() -> String {
+pub fn simulator_version(_ctx: &SimContext) -> String {
     let mut vlog_info = vpi_sys::t_vpi_vlog_info {
         argc: 0,
         argv: std::ptr::null_mut(),
@@ -86,7 +86,7 @@ pub fn simulator_version() -> String {
 /// This uses `vpi_get_time` with a null handle, which requests the simulator's
 /// current global simulation time.
 #[must_use]
-pub fn current_simulation_time() -> Time {
+pub fn current_simulation_time(_ctx: &SimContext) -> Time {
     let mut vpi_time = vpi_sys::s_vpi_time {
         type_: vpi_sys::vpiSimTime as i32,
         high: 0,
@@ -97,6 +97,20 @@ pub fn current_simulation_time() -> Time {
     Time::from(vpi_time)
 }
 
+/// Returns the simulation time precision as a power of 10.
+///
+/// This is the unit of [`Time::Sim`] values, i.e., the finest precision of
+/// all modules in the design, read with `vpi_get(vpiTimePrecision, NULL)`.
+#[must_use]
+pub fn simulation_time_precision(_ctx: &SimContext) -> i32 {
+    unsafe {
+        vpi_sys::vpi_get(
+            crate::Property::TimePrecision as PLI_INT32,
+            std::ptr::null_mut(),
+        )
+    }
+}
+
 /// Represents a module's timescale information
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Timescale {
@@ -140,6 +154,20 @@ impl std::fmt::Display for Timescale {
     }
 }
 
+impl Handle {
+    /// Returns the timescale of this module, if available.
+    ///
+    /// Returns `None` for null handles.
+    #[must_use]
+    pub fn get_timescale(&self, _ctx: &SimContext) -> Option<Timescale> {
+        if self.is_null() {
+            return None;
+        }
+        // SAFETY: The handle is non-null.
+        unsafe { Timescale::from_module(self.as_raw()) }
+    }
+}
+
 /// Convert a power of 10 to a time unit string
 fn power_of_10_to_time_str(power: i32) -> String {
     match power {
@@ -170,7 +198,7 @@ fn power_of_10_to_time_str(power: i32) -> String {
 /// Each entry contains `(module_name, timescale)` where `timescale` is `None`
 /// when time unit/precision properties are not available.
 #[must_use]
-pub fn get_top_module_timescales() -> Vec<(String, Option<Timescale>)> {
+pub fn get_top_module_timescales(_ctx: &SimContext) -> Vec<(String, Option<Timescale>)> {
     let mut results = Vec::new();
 
     unsafe {
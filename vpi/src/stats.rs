@@ -0,0 +1,381 @@
+//! Design statistics per module definition and per hierarchy subtree.
+//!
+//! [`DesignStats::collect`] counts, for every instance, the state held in regs
+//! and variables, the nets, memories, primitives and `always`/`initial`
+//! blocks it declares. The counts are aggregated per module definition and
+//! rolled up per subtree, which makes it easy to compare designs between
+//! releases or to spot memories inferred by accident.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::stats::DesignStats;
+//!
+//! fn summary(ctx: &vpi::SimContext) {
+//!     let stats = DesignStats::collect(ctx);
+//!     vpi::printf!(ctx, "{stats}");
+//!     if let Some(ram) = stats.definition("ram") {
+//!         vpi::printf!(ctx, "{} bits of memory in {} rams", ram.memory_bits, ram.instances);
+//!     }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::AddAssign;
+
+use crate::model::{Module, TypedHandle};
+use crate::query::instance_children;
+use crate::{Handle, ObjectType, Property, SimContext};
+
+/// Counts for one instance, a subtree or a module definition.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of instances.
+    pub instances: usize,
+    /// Number of regs and variables, excluding memories.
+    pub regs: usize,
+    /// Total width of the regs and variables.
+    pub state_bits: u64,
+    /// Number of nets.
+    pub nets: usize,
+    /// Total width of the nets.
+    pub net_bits: u64,
+    /// Number of memories and reg arrays.
+    pub memories: usize,
+    /// Total bits stored in the memories.
+    pub memory_bits: u64,
+    /// Number of gate, switch and UDP instances.
+    pub primitives: usize,
+    /// Number of `always` blocks.
+    pub always_blocks: usize,
+    /// Number of `initial` blocks.
+    pub initial_blocks: usize,
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.instances += other.instances;
+        self.regs += other.regs;
+        self.state_bits += other.state_bits;
+        self.nets += other.nets;
+        self.net_bits += other.net_bits;
+        self.memories += other.memories;
+        self.memory_bits += other.memory_bits;
+        self.primitives += other.primitives;
+        self.always_blocks += other.always_blocks;
+        self.initial_blocks += other.initial_blocks;
+    }
+}
+
+impl Stats {
+    /// Counts the objects declared directly in `scope`, a module or one of
+    /// its generate scopes and named blocks, except for instances.
+    fn of(ctx: &SimContext, scope: &Handle) -> Self {
+        let mut stats = Stats::default();
+        for signal in scope.iterators(ctx, &[ObjectType::Reg, ObjectType::Variables]) {
+            stats.regs += 1;
+            stats.state_bits += bits(ctx, &signal);
+        }
+        for net in scope.iterator(ctx, ObjectType::Net) {
+            stats.nets += 1;
+            stats.net_bits += bits(ctx, &net);
+        }
+        // Simulators may list a memory both as `vpiMemory` and `vpiRegArray`.
+        let mut memories: Vec<String> = Vec::new();
+        let arrays = scope.iterator(ctx, ObjectType::Memory).chain(
+            scope.iterator(ctx, ObjectType::RegArray).filter(|array| {
+                array.get_raw_property(ctx, Property::Type) == Some(vpi_sys::vpiRegArray as i32)
+            }),
+        );
+        for array in arrays {
+            let name = array.get_full_name(ctx).unwrap_or_default();
+            if memories.contains(&name) {
+                continue;
+            }
+            memories.push(name);
+            let word = array
+                .iterators(ctx, &[ObjectType::MemoryWord, ObjectType::Reg])
+                .next()
+                .map_or(0, |word| bits(ctx, &word));
+            stats.memories += 1;
+            stats.memory_bits += bits(ctx, &array) * word;
+        }
+        stats.primitives = scope.iterator(ctx, ObjectType::Primitive).count();
+        for process in scope.iterator(ctx, ObjectType::Process) {
+            match process
+                .get_raw_property(ctx, Property::Type)
+                .map(|typ| typ as u32)
+            {
+                Some(vpi_sys::vpiAlways) => stats.always_blocks += 1,
+                Some(vpi_sys::vpiInitial) => stats.initial_blocks += 1,
+                _ => {}
+            }
+        }
+        stats
+    }
+}
+
+/// Statistics of one instance and its subtree.
+#[derive(Debug, Clone)]
+pub struct InstanceStats {
+    /// Full hierarchical name of the instance.
+    pub full_name: String,
+    /// Name of the module definition.
+    pub def_name: String,
+    /// Counts of the instance itself.
+    pub own: Stats,
+    /// Counts of the instance and everything below it.
+    pub total: Stats,
+    /// Statistics of the child instances.
+    pub children: Vec<InstanceStats>,
+}
+
+impl InstanceStats {
+    fn collect(
+        ctx: &SimContext,
+        module: &Module,
+        definitions: &mut BTreeMap<String, Stats>,
+    ) -> Self {
+        // Generate scopes and named blocks count towards their instance.
+        let (modules, scopes) = instance_children(ctx, module.handle());
+        let mut own = Stats {
+            instances: 1,
+            ..Stats::of(ctx, module.handle())
+        };
+        for scope in &scopes {
+            own += Stats::of(ctx, scope);
+        }
+        let def_name = module.def_name(ctx).unwrap_or_default();
+        *definitions.entry(def_name.clone()).or_default() += own;
+        let children: Vec<InstanceStats> = modules
+            .into_iter()
+            .filter_map(|child| child.downcast::<Module>(ctx))
+            .map(|child| InstanceStats::collect(ctx, &child, definitions))
+            .collect();
+        let mut total = own;
+        for child in &children {
+            total += child.total;
+        }
+        Self {
+            full_name: module.full_name(ctx).unwrap_or_default(),
+            def_name,
+            own,
+            total,
+            children,
+        }
+    }
+}
+
+/// Statistics of a design; see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct DesignStats {
+    definitions: BTreeMap<String, Stats>,
+    instances: Vec<InstanceStats>,
+}
+
+impl DesignStats {
+    /// Collects statistics for the whole design.
+    #[must_use]
+    pub fn collect(ctx: &SimContext) -> Self {
+        Self::from_modules(ctx, Handle::null().iterator(ctx, ObjectType::Module))
+    }
+
+    /// Collects statistics for the subtree below and including `scope`.
+    ///
+    /// Returns empty statistics if `scope` is not a module.
+    #[must_use]
+    pub fn collect_from(ctx: &SimContext, scope: &Handle) -> Self {
+        Self::from_modules(ctx, std::iter::once(scope.clone()))
+    }
+
+    fn from_modules(ctx: &SimContext, modules: impl Iterator<Item = Handle>) -> Self {
+        let mut definitions = BTreeMap::new();
+        let instances = modules
+            .filter_map(|module| module.downcast::<Module>(ctx))
+            .map(|module| InstanceStats::collect(ctx, &module, &mut definitions))
+            .collect();
+        Self {
+            definitions,
+            instances,
+        }
+    }
+
+    /// Returns the counts per module definition, summed over its instances.
+    ///
+    /// Each entry covers what the instances declare themselves, without
+    /// their children.
+    #[must_use]
+    pub fn definitions(&self) -> &BTreeMap<String, Stats> {
+        &self.definitions
+    }
+
+    /// Returns the counts of a module definition.
+    #[must_use]
+    pub fn definition(&self, def_name: &str) -> Option<&Stats> {
+        self.definitions.get(def_name)
+    }
+
+    /// Returns the statistics of the top-level instances.
+    #[must_use]
+    pub fn instances(&self) -> &[InstanceStats] {
+        &self.instances
+    }
+
+    /// Returns the statistics of the instance named `full_name`.
+    #[must_use]
+    pub fn instance(&self, full_name: &str) -> Option<&InstanceStats> {
+        let mut pending: Vec<&InstanceStats> = self.instances.iter().collect();
+        while let Some(instance) = pending.pop() {
+            if instance.full_name == full_name {
+                return Some(instance);
+            }
+            pending.extend(&instance.children);
+        }
+        None
+    }
+
+    /// Returns the counts of the whole design.
+    #[must_use]
+    pub fn total(&self) -> Stats {
+        let mut total = Stats::default();
+        for instance in &self.instances {
+            total += instance.total;
+        }
+        total
+    }
+}
+
+impl fmt::Display for DesignStats {
+    /// Formats a table with one row per module definition and the totals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .definitions
+            .keys()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max("definition".len());
+        writeln!(
+            f,
+            "{:<width$} {:>9} {:>10} {:>10} {:>10} {:>11} {:>10} {:>7} {:>7}",
+            "definition",
+            "instances",
+            "state bits",
+            "nets",
+            "net bits",
+            "memory bits",
+            "primitives",
+            "always",
+            "initial"
+        )?;
+        let total = self.total();
+        let rows = self
+            .definitions
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+            .chain(std::iter::once(("total", &total)));
+        for (name, stats) in rows {
+            writeln!(
+                f,
+                "{name:<width$} {:>9} {:>10} {:>10} {:>10} {:>11} {:>10} {:>7} {:>7}",
+                stats.instances,
+                stats.state_bits,
+                stats.nets,
+                stats.net_bits,
+                stats.memory_bits,
+                stats.primitives,
+                stats.always_blocks,
+                stats.initial_blocks
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn bits(ctx: &SimContext, object: &Handle) -> u64 {
+    object.get_u32(ctx, Property::Size).map_or(0, u64::from)
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use super::{DesignStats, Stats};
+    use crate::mock::MockSimulator;
+    use crate::{Handle, PrimType};
+
+    #[test]
+    fn counts_are_aggregated_per_definition_and_subtree() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        let _ = sim.add_initial(&tb);
+        let clk = sim.add_reg(&tb, "clk", 1);
+        for name in ["ram0", "ram1"] {
+            let ram = sim.add_module(&tb, name, "ram");
+            let _ = sim.add_memory(&ram, "mem", 8, 16);
+            let _ = sim.add_reg(&ram, "q", 8);
+            let _ = sim.add_integer(&ram, "i");
+            let we = sim.add_net(&ram, "we", 1);
+            let _ = sim.add_net(&ram, "addr", 4);
+            let _ = sim.add_gate(&ram, "g", PrimType::Buf, &we, &[&clk]);
+            let _ = sim.add_always(&ram);
+        }
+
+        let stats = DesignStats::collect(ctx);
+        let ram = *stats.definition("ram").unwrap();
+        assert_eq!(
+            ram,
+            Stats {
+                instances: 2,
+                regs: 4,
+                state_bits: 80,
+                nets: 4,
+                net_bits: 10,
+                memories: 2,
+                memory_bits: 256,
+                primitives: 2,
+                always_blocks: 2,
+                initial_blocks: 0,
+            }
+        );
+        let tb_stats = stats.instance("tb").unwrap();
+        assert_eq!(tb_stats.own.state_bits, 1);
+        assert_eq!(tb_stats.total.instances, 3);
+        assert_eq!(tb_stats.total.memory_bits, 256);
+        assert_eq!(tb_stats.total.initial_blocks, 1);
+        assert_eq!(stats.total(), tb_stats.total);
+        assert_eq!(stats.instance("tb.ram1").unwrap().total.instances, 1);
+
+        let table = stats.to_string();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("ram "));
+        assert!(lines[3].starts_with("total"));
+        assert!(lines[3].ends_with("4         10         256          2       2       1"));
+
+        let subtree = DesignStats::collect_from(ctx, &sim.add_module(&tb, "empty", "empty"));
+        assert_eq!(subtree.total().instances, 1);
+        assert!(DesignStats::collect_from(ctx, &clk).instances().is_empty());
+    }
+
+    #[test]
+    fn generate_scopes_count_towards_their_instance() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let tb = sim.add_module(&Handle::null(), "tb", "tb");
+        for name in ["bank[0]", "bank[1]"] {
+            let bank = sim.add_gen_scope(&tb, name);
+            let _ = sim.add_net(&bank, "sel", 2);
+            let ram = sim.add_module(&bank, "ram", "ram");
+            let _ = sim.add_memory(&ram, "mem", 8, 4);
+        }
+
+        let stats = DesignStats::collect(ctx);
+        let tb = stats.instance("tb").unwrap();
+        assert_eq!((tb.own.instances, tb.own.nets, tb.own.net_bits), (1, 2, 4));
+        assert_eq!(tb.total.instances, 3);
+        assert_eq!(tb.total.memory_bits, 64);
+        assert_eq!(stats.definition("ram").unwrap().instances, 2);
+        assert!(stats.instance("tb.bank[1].ram").is_some());
+    }
+}
@@ -10,8 +10,8 @@
 //! use std::ffi::CStr;
 //! use std::os::raw::c_char;
 //! use vpi::{
-//!     current_systf_call, get_systf_arg, register_systf, SysFuncType, SystfKind, Value,
-//!     ValueType,
+//!     current_systf_call, get_systf_arg, register_systf, SimContext, SysFuncType, SystfKind,
+//!     Value, ValueType,
 //! };
 //!
 //! static FUNC_NAME: &CStr = c"$add_one";
@@ -21,26 +21,31 @@
 //! }
 //!
 //! unsafe extern "C" fn calltf_add_one(_user_data: *mut c_char) -> i32 {
-//!     let arg = match get_systf_arg(0, ValueType::Int) {
+//!     // Called by the simulator on its own thread.
+//!     let ctx = unsafe { SimContext::new_unchecked() };
+//!     let arg = match get_systf_arg(&ctx, 0, ValueType::Int) {
 //!         Some(Value::Int(v)) => v,
 //!         _ => 0,
 //!     };
 //!     let result = arg + 1;
 //!
-//!     let call = current_systf_call();
-//!     let _ = call.put_value(&Value::Int(result));
+//!     let call = current_systf_call(&ctx);
+//!     let _ = call.put_value(&ctx, &Value::Int(result));
 //!     0
 //! }
 //!
-//! let _func_handle = register_systf(
-//!     SystfKind::Func,
-//!     FUNC_NAME,
-//!     Some(calltf_add_one),
-//!     Some(compiletf),
-//!     None,
-//!     std::ptr::null_mut(),
-//!     Some(SysFuncType::Sized),
-//! );
+//! fn register(ctx: &SimContext) {
+//!     let _func_handle = register_systf(
+//!         ctx,
+//!         SystfKind::Func,
+//!         FUNC_NAME,
+//!         Some(calltf_add_one),
+//!         Some(compiletf),
+//!         None,
+//!         std::ptr::null_mut(),
+//!         Some(SysFuncType::Sized),
+//!     );
+//! }
 //! ```
 //!
 //! In Verilog/SystemVerilog, the registered function is called using its `$`
@@ -59,7 +64,10 @@
 use std::ffi::CStr;
 use std::os::raw::c_char;
 
-use crate::{Handle, ObjectType, SysFuncType, Value, ValueType};
+use crate::{
+    ConstType, Handle, LogicVal, LogicVec, ObjectType, Property, SimContext, SysFuncType, Time,
+    Value, ValueType,
+};
 
 /// Raw VPI registration record type.
 pub type RawSystfData = vpi_sys::s_vpi_systf_data;
@@ -100,7 +108,7 @@ pub enum SystfKind {
 ///
 /// This is a thin wrapper around `vpi_register_systf`.
 #[must_use]
-pub fn register_systf_raw(data: &mut RawSystfData) -> Handle {
+pub fn register_systf_raw(_ctx: &SimContext, data: &mut RawSystfData) -> Handle {
     let handle = unsafe { vpi_sys::vpi_register_systf(data) };
     Handle::from_raw(handle)
 }
@@ -112,7 +120,9 @@ pub fn register_systf_raw(data: &mut RawSystfData) -> Handle {
 /// For [`SystfKind::Task`], `sys_func_type` is ignored and set to `vpiSysTask`.
 /// For [`SystfKind::Func`], `sys_func_type` defaults to `vpiIntFunc` when omitted.
 #[must_use]
+#[allow(clippy::too_many_arguments)]
 pub fn register_systf(
+    ctx: &SimContext,
     kind: SystfKind,
     name: &'static CStr,
     calltf: Option<SystfCallback>,
@@ -136,7 +146,7 @@ pub fn register_systf(
         user_data,
     };
 
-    register_systf_raw(&mut data)
+    register_systf_raw(ctx, &mut data)
 }
 
 /// Returns the current system task/function call handle.
@@ -144,8 +154,8 @@ pub fn register_systf(
 /// This is typically used inside `calltf` callbacks to access arguments or
 /// assign a return value for system functions.
 #[must_use]
-pub fn current_systf_call() -> Handle {
-    Handle::null().get(ObjectType::SysTfCall)
+pub fn current_systf_call(ctx: &SimContext) -> Handle {
+    Handle::null().get(ctx, ObjectType::SysTfCall)
 }
 
 /// Retrieves raw system task/function metadata for a VPI object.
@@ -154,7 +164,7 @@ pub fn current_systf_call() -> Handle {
 ///
 /// Returns `None` when `object` is null.
 #[must_use]
-pub fn get_systf_info_raw(object: &Handle) -> Option<RawSystfData> {
+pub fn get_systf_info_raw(_ctx: &SimContext, object: &Handle) -> Option<RawSystfData> {
     if object.is_null() {
         return None;
     }
@@ -183,8 +193,8 @@ pub fn get_systf_info_raw(object: &Handle) -> Option<RawSystfData> {
 ///
 /// Returns `None` when `object` is null.
 #[must_use]
-pub fn get_systf_info(object: &Handle) -> Option<SystfInfo> {
-    let data = get_systf_info_raw(object)?;
+pub fn get_systf_info(ctx: &SimContext, object: &Handle) -> Option<SystfInfo> {
+    let data = get_systf_info_raw(ctx, object)?;
     let name = if data.tfname.is_null() {
         None
     } else {
@@ -210,9 +220,9 @@ pub fn get_systf_info(object: &Handle) -> Option<SystfInfo> {
 ///
 /// Returns `None` when called outside a `calltf` context.
 #[must_use]
-pub fn current_systf_info() -> Option<SystfInfo> {
-    let call = current_systf_call();
-    get_systf_info(&call)
+pub fn current_systf_info(ctx: &SimContext) -> Option<SystfInfo> {
+    let call = current_systf_call(ctx);
+    get_systf_info(ctx, &call)
 }
 
 /// Returns one system task/function argument by index in a requested format.
@@ -222,15 +232,15 @@ pub fn current_systf_info() -> Option<SystfInfo> {
 /// out of range, or when the simulator cannot provide the requested value
 /// format.
 #[must_use]
-pub fn get_systf_arg(index: u32, format: ValueType) -> Option<Value> {
-    let call = current_systf_call();
+pub fn get_systf_arg(ctx: &SimContext, index: u32, format: ValueType) -> Option<Value> {
+    let call = current_systf_call(ctx);
     if call.is_null() {
         return None;
     }
 
-    call.iterator(ObjectType::Argument)
+    call.iterator(ctx, ObjectType::Argument)
         .nth(index as usize)
-        .and_then(|arg| arg.get_value(format))
+        .and_then(|arg| arg.get_value(ctx, format))
 }
 
 /// Returns system task/function arguments using per-argument value formats.
@@ -245,31 +255,469 @@ pub fn get_systf_arg(index: u32, format: ValueType) -> Option<Value> {
 /// - `None` when called outside a `calltf` context, when the argument is
 ///   missing, or when value retrieval fails.
 #[must_use]
-pub fn get_systf_args(formats: impl AsRef<[ValueType]>) -> Vec<Option<Value>> {
-    let call = current_systf_call();
+pub fn get_systf_args(ctx: &SimContext, formats: impl AsRef<[ValueType]>) -> Vec<Option<Value>> {
+    let call = current_systf_call(ctx);
     if call.is_null() {
         return std::iter::repeat_with(|| None)
             .take(formats.as_ref().len())
             .collect();
     }
 
-    let mut args = call.iterator(ObjectType::Argument);
+    let mut args = call.iterator(ctx, ObjectType::Argument);
     formats
         .as_ref()
         .iter()
-        .map(|format| args.next().and_then(|arg| arg.get_value(*format)))
+        .map(|format| args.next().and_then(|arg| arg.get_value(ctx, *format)))
         .collect()
 }
 
+/// Conversion from a system task/function argument to a Rust value.
+///
+/// This is implemented for the parameter types accepted by functions
+/// annotated with `#[vpi::systf]` (see the `macros` feature), and can be
+/// implemented for other types to extend the set.
+pub trait SystfArg: Sized {
+    /// Returns `true` if `arg` is an acceptable argument for this type.
+    ///
+    /// This is evaluated by the generated `compiletf` routine. The default
+    /// accepts any argument that carries a value, rejecting scopes such as
+    /// module instances. The implementations of this crate also check the
+    /// type and width: integers take integral values of at most 32 bits, or
+    /// 64 bits for `i64` and `u64`, and `f64` takes real values.
+    fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+        is_value_argument(ctx, arg)
+    }
+
+    /// Reads the current value of `arg`.
+    ///
+    /// Returns `None` when the value cannot be represented by this type.
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self>;
+}
+
+/// Conversion from a Rust value to a system function return value.
+///
+/// This is implemented for the return types accepted by functions annotated
+/// with `#[vpi::systf]`. The unit type registers a system task.
+pub trait SystfReturn {
+    /// Return kind registered with the simulator, or `None` for a system task.
+    const FUNC_TYPE: Option<SysFuncType>;
+
+    /// Result width in bits reported by `sizetf` for sized functions.
+    const SIZE: Option<u32> = None;
+
+    /// Converts the result to the value written to the call handle.
+    fn into_value(self) -> Option<Value>;
+}
+
+fn is_value_argument(ctx: &SimContext, arg: &Handle) -> bool {
+    !arg.is_null()
+        && !matches!(
+            arg.get_type(ctx),
+            Some(
+                ObjectType::Module
+                    | ObjectType::Task
+                    | ObjectType::Function
+                    | ObjectType::NamedBegin
+                    | ObjectType::NamedFork
+                    | ObjectType::NamedEvent
+            )
+        )
+}
+
+/// Returns `true` for real variables, parameters and constants.
+fn is_real_argument(ctx: &SimContext, arg: &Handle) -> bool {
+    match arg.get_type(ctx) {
+        Some(ObjectType::RealVar) => true,
+        #[cfg(feature = "sv")]
+        Some(ObjectType::ShortRealVar) => true,
+        Some(ObjectType::Constant | ObjectType::Parameter) => {
+            matches!(arg.get_const_type(ctx), Some(ConstType::Real))
+        }
+        _ => false,
+    }
+}
+
+/// Returns `true` for integral values of at most `bits` bits, rejecting
+/// reals and string literals.
+fn is_integral_argument(ctx: &SimContext, arg: &Handle, bits: u32) -> bool {
+    is_value_argument(ctx, arg)
+        && !is_real_argument(ctx, arg)
+        && !matches!(arg.get_const_type(ctx), Some(ConstType::String))
+        && arg
+            .get_u32(ctx, Property::Size)
+            .is_none_or(|size| size <= bits)
+}
+
+/// Reads an argument as a vector and converts its known bits to an integer.
+///
+/// Vectors wider than 64 bits are truncated to their low 64 bits. Returns
+/// `None` when any of those bits is `x` or `z`. Signed arguments narrower
+/// than 64 bits are sign-extended.
+fn arg_to_u64(ctx: &SimContext, arg: &Handle) -> Option<u64> {
+    let Some(Value::Vector(vec)) = arg.get_value(ctx, ValueType::Vector) else {
+        return None;
+    };
+    let bits = vec.raw_data();
+    let mut value = 0u64;
+    for (i, bit) in bits.iter().rev().take(64).enumerate() {
+        match bit {
+            LogicVal::One | LogicVal::H => value |= 1 << i,
+            LogicVal::Zero | LogicVal::L => {}
+            _ => return None,
+        }
+    }
+    let width = bits.len();
+    if width > 0
+        && width < 64
+        && arg.get_bool(ctx, Property::Signed) == Some(true)
+        && value >> (width - 1) & 1 == 1
+    {
+        value |= u64::MAX << width;
+    }
+    Some(value)
+}
+
+impl SystfArg for Handle {
+    fn accepts(_ctx: &SimContext, arg: &Handle) -> bool {
+        !arg.is_null()
+    }
+
+    fn from_arg(_ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        Some(arg.clone())
+    }
+}
+
+impl SystfArg for Value {
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        arg.get_value(ctx, ValueType::ObjType)
+    }
+}
+
+impl SystfArg for i32 {
+    fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+        is_integral_argument(ctx, arg, 32)
+    }
+
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        match arg.get_value(ctx, ValueType::Int)? {
+            Value::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SystfArg for f64 {
+    fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+        is_value_argument(ctx, arg) && is_real_argument(ctx, arg)
+    }
+
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        match arg.get_value(ctx, ValueType::Real)? {
+            Value::Real(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SystfArg for bool {
+    fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+        is_integral_argument(ctx, arg, 64)
+    }
+
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        arg_to_u64(ctx, arg).map(|value| value != 0)
+    }
+}
+
+macro_rules! impl_systf_arg_int {
+    ($($ty:ty => $bits:literal),*) => {
+        $(
+            impl SystfArg for $ty {
+                fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+                    is_integral_argument(ctx, arg, $bits)
+                }
+
+                fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+                    // Truncate like a Verilog assignment to a narrower variable.
+                    arg_to_u64(ctx, arg).map(|value| value as $ty)
+                }
+            }
+        )*
+    };
+}
+
+// Narrower types also take 32 bits, the width of unsized literals.
+impl_systf_arg_int!(u8 => 32, u16 => 32, u32 => 32, u64 => 64, i8 => 32, i16 => 32, i64 => 64);
+
+impl SystfArg for String {
+    fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+        is_value_argument(ctx, arg) && !is_real_argument(ctx, arg)
+    }
+
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        match arg.get_value(ctx, ValueType::String)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SystfArg for LogicVec {
+    fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+        is_value_argument(ctx, arg) && !is_real_argument(ctx, arg)
+    }
+
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        match arg.get_value(ctx, ValueType::Vector)? {
+            Value::Vector(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SystfArg for LogicVal {
+    fn accepts(ctx: &SimContext, arg: &Handle) -> bool {
+        is_integral_argument(ctx, arg, 32)
+    }
+
+    fn from_arg(ctx: &SimContext, arg: &Handle) -> Option<Self> {
+        match arg.get_value(ctx, ValueType::Scalar)? {
+            Value::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl SystfReturn for () {
+    const FUNC_TYPE: Option<SysFuncType> = None;
+
+    fn into_value(self) -> Option<Value> {
+        None
+    }
+}
+
+impl SystfReturn for i32 {
+    const FUNC_TYPE: Option<SysFuncType> = Some(SysFuncType::Int);
+
+    fn into_value(self) -> Option<Value> {
+        Some(Value::Int(self))
+    }
+}
+
+impl SystfReturn for f64 {
+    const FUNC_TYPE: Option<SysFuncType> = Some(SysFuncType::Real);
+
+    fn into_value(self) -> Option<Value> {
+        Some(Value::Real(self))
+    }
+}
+
+impl SystfReturn for Time {
+    const FUNC_TYPE: Option<SysFuncType> = Some(SysFuncType::Time);
+
+    fn into_value(self) -> Option<Value> {
+        Some(Value::Time(self))
+    }
+}
+
+impl SystfReturn for bool {
+    const FUNC_TYPE: Option<SysFuncType> = Some(SysFuncType::Sized);
+    const SIZE: Option<u32> = Some(1);
+
+    fn into_value(self) -> Option<Value> {
+        Some(Value::Scalar(if self {
+            LogicVal::One
+        } else {
+            LogicVal::Zero
+        }))
+    }
+}
+
+impl SystfReturn for LogicVal {
+    const FUNC_TYPE: Option<SysFuncType> = Some(SysFuncType::Sized);
+    const SIZE: Option<u32> = Some(1);
+
+    fn into_value(self) -> Option<Value> {
+        Some(Value::Scalar(self))
+    }
+}
+
+macro_rules! impl_systf_return_int {
+    ($($ty:ty => $from:ident),*) => {
+        $(
+            impl SystfReturn for $ty {
+                const FUNC_TYPE: Option<SysFuncType> = Some(SysFuncType::Sized);
+                const SIZE: Option<u32> = Some(<$ty>::BITS);
+
+                fn into_value(self) -> Option<Value> {
+                    Some(LogicVec::$from(self, <$ty>::BITS as usize).as_vector_value())
+                }
+            }
+        )*
+    };
+}
+
+impl_systf_return_int!(
+    u8 => from_uint,
+    u16 => from_uint,
+    u32 => from_uint,
+    u64 => from_uint,
+    i8 => from_int,
+    i16 => from_int,
+    i64 => from_int
+);
+
+/// Prints an error for a system task/function through `vpi_printf`.
+///
+/// The message is prefixed with `ERROR:` and the task/function name.
+pub fn report_systf_error(ctx: &SimContext, name: &CStr, message: &str) {
+    crate::printf(ctx, format!("ERROR: {}: {message}", name.to_string_lossy()));
+}
+
+/// Checks the arguments of the current system task/function call.
+///
+/// `checks` holds one predicate per expected argument, such as
+/// [`SystfArg::accepts`]. On a count mismatch or a rejected argument, an
+/// error is reported with [`report_systf_error`] and the simulation is
+/// finished, as is customary in `compiletf` routines.
+///
+/// Returns `true` when all arguments are acceptable.
+pub fn check_systf_args(
+    ctx: &SimContext,
+    name: &CStr,
+    checks: &[fn(&SimContext, &Handle) -> bool],
+) -> bool {
+    let call = current_systf_call(ctx);
+    if call.is_null() {
+        return false;
+    }
+
+    let args: Vec<Handle> = call.iterator(ctx, ObjectType::Argument).collect();
+    let error = if args.len() == checks.len() {
+        checks
+            .iter()
+            .zip(&args)
+            .position(|(check, arg)| !check(ctx, arg))
+            .map(|index| format!("argument {} has an unsupported type", index + 1))
+    } else {
+        Some(format!(
+            "expected {} argument(s), found {}",
+            checks.len(),
+            args.len()
+        ))
+    };
+
+    match error {
+        Some(message) => {
+            report_systf_error(ctx, name, &message);
+            crate::control(ctx, crate::Control::Finish);
+            false
+        }
+        None => true,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{get_systf_info, get_systf_info_raw};
-    use crate::Handle;
+    use crate::{Handle, SimContext};
 
     #[test]
     fn get_systf_info_on_null_handle_returns_none() {
+        let ctx = SimContext::borrowed();
         let h = Handle::null();
-        assert!(get_systf_info_raw(&h).is_none());
-        assert!(get_systf_info(&h).is_none());
+        assert!(get_systf_info_raw(ctx, &h).is_none());
+        assert!(get_systf_info(ctx, &h).is_none());
+    }
+
+    #[cfg(all(feature = "macros", mock_backend))]
+    mod generated {
+        use crate::mock::MockSimulator;
+        use crate::{get_systf_info, Handle, LogicVec, SysFuncType, Value};
+
+        #[crate::systf]
+        fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+
+        #[crate::systf(name = "$low_byte")]
+        fn low_byte(value: u64) -> u8 {
+            value as u8
+        }
+
+        #[crate::systf]
+        fn scale(value: f64, factor: f64) -> f64 {
+            value * factor
+        }
+
+        #[crate::systf]
+        fn greet(ctx: &crate::SimContext, name: String) {
+            ctx.printf(format!("hello {name}"));
+        }
+
+        #[test]
+        fn generated_routines_register_and_run() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            sim.run_startup_routines(&[
+                Some(add::register),
+                Some(low_byte::register),
+                Some(greet::register),
+            ]);
+            sim.start();
+
+            let result = sim.invoke_systf("$add", &[Value::Int(40).into(), Value::Int(2).into()]);
+            assert_eq!(result, Some(Value::Int(42)));
+
+            let result = sim.invoke_systf("$low_byte", &[Value::HexStr("1234".into()).into()]);
+            assert_eq!(result, Some(Value::Vector(LogicVec::from_uint(0x34u8, 8))));
+
+            let call = sim.add_systf_call(&Handle::null(), "$low_byte", &[Value::Int(1).into()]);
+            let info = get_systf_info(ctx, &call).unwrap();
+            assert_eq!(info.sys_func_type, SysFuncType::Sized as i32);
+            assert_eq!(call.get_size(ctx), Some(8));
+
+            assert_eq!(
+                sim.invoke_systf("$greet", &[Value::String("sim".into()).into()]),
+                None
+            );
+            assert_eq!(sim.take_output(), "hello sim\n");
+        }
+
+        #[test]
+        fn compiletf_rejects_wrong_argument_count() {
+            let sim = MockSimulator::new();
+            sim.run_startup_routines(&[Some(add::register)]);
+
+            let _ = sim.add_systf_call(&Handle::null(), "$add", &[Value::Int(1).into()]);
+            sim.start();
+
+            assert_eq!(
+                sim.take_output(),
+                "ERROR: $add: expected 2 argument(s), found 1\n"
+            );
+            assert!(sim.is_finished());
+        }
+
+        #[test]
+        fn compiletf_rejects_wrong_argument_types() {
+            let sim = MockSimulator::new();
+            sim.run_startup_routines(&[Some(add::register), Some(scale::register)]);
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            let wide = sim.add_reg(&top, "wide", 40);
+            let gain = sim.add_real(&top, "gain");
+
+            let _ = sim.add_systf_call(&top, "$add", &[(&wide).into(), Value::Int(1).into()]);
+            let _ = sim.add_systf_call(&top, "$scale", &[(&gain).into(), Value::Real(2.0).into()]);
+            let _ = sim.add_systf_call(&top, "$scale", &[Value::Int(2).into(), (&gain).into()]);
+            sim.start();
+
+            assert_eq!(
+                sim.take_output(),
+                "ERROR: $add: argument 1 has an unsupported type\n\
+                 ERROR: $scale: argument 1 has an unsupported type\n"
+            );
+            assert!(sim.is_finished());
+        }
     }
 }
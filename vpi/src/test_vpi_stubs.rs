@@ -1,6 +1,7 @@
 #![allow(clippy::missing_const_for_fn)]
 #![cfg(all(
     test,
+    not(feature = "mock"),
     any(
         not(any(target_os = "windows", target_os = "macos")),
         not(feature = "dynamic")
@@ -128,6 +129,11 @@ unsafe extern "C" fn vpi_chk_error(_error_info_p: vpi_sys::p_vpi_error_info) ->
     0
 }
 
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_free_object(_object: vpi_sys::vpiHandle) -> vpi_sys::PLI_INT32 {
+    0
+}
+
 #[unsafe(no_mangle)]
 unsafe extern "C" fn vpi_release_handle(_object: vpi_sys::vpiHandle) -> vpi_sys::PLI_INT32 {
     0
@@ -138,6 +144,37 @@ unsafe extern "C" fn vpi_flush() -> vpi_sys::PLI_INT32 {
     0
 }
 
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_data(
+    _id: vpi_sys::PLI_INT32,
+    _data_loc: *mut vpi_sys::PLI_BYTE8,
+    _num_of_bytes: vpi_sys::PLI_INT32,
+) -> vpi_sys::PLI_INT32 {
+    0
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_put_data(
+    _id: vpi_sys::PLI_INT32,
+    _data_loc: *mut vpi_sys::PLI_BYTE8,
+    _num_of_bytes: vpi_sys::PLI_INT32,
+) -> vpi_sys::PLI_INT32 {
+    0
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_get_userdata(_obj: vpi_sys::vpiHandle) -> *mut std::os::raw::c_void {
+    std::ptr::null_mut()
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn vpi_put_userdata(
+    _obj: vpi_sys::vpiHandle,
+    _userdata: *mut std::os::raw::c_void,
+) -> vpi_sys::PLI_INT32 {
+    0
+}
+
 #[unsafe(no_mangle)]
 unsafe extern "C" fn vpi_put_value(
     _object: vpi_sys::vpiHandle,
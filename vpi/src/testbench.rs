@@ -0,0 +1,888 @@
+//! Async/await testbench runtime driven by VPI callbacks.
+//!
+//! This module provides a small single-threaded executor for writing
+//! testbenches as `async` code. Tasks are spawned with [`spawn`] and await
+//! simulation events such as [`Timer`], [`RisingEdge`], [`FallingEdge`],
+//! [`ValueChanged`], [`ReadWrite`] and [`ReadOnly`]. Each awaitable registers
+//! the matching VPI callback when it is first polled and holds it in a
+//! [`CallbackGuard`], which removes it when the awaitable is dropped before
+//! firing, so tasks can be cancelled or raced with [`select`] without leaving
+//! callbacks behind.
+//!
+//! The executor runs whenever a task is spawned or one of its callbacks
+//! fires, and polls ready tasks until all of them are waiting again. Tasks
+//! are not `Send` and must be spawned from the simulator thread, typically in
+//! a `cbStartOfSimulation` callback.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::testbench::{spawn, RisingEdge, Timer, Unit};
+//! use vpi::{register_cb, CbReason, Handle, SimContext, Value};
+//!
+//! fn start(ctx: &SimContext) {
+//!     let _ = register_cb(ctx, CbReason::StartOfSimulation, |data| {
+//!         let ctx = *data.context();
+//!         let clk = Handle::handle_by_name(&ctx, "tb.clk");
+//!         let count = Handle::handle_by_name(&ctx, "tb.count");
+//!
+//!         let clock_clk = clk.clone();
+//!         let clock = spawn(async move {
+//!             loop {
+//!                 let _ = clock_clk.put_value(&ctx, &Value::Int(0));
+//!                 Timer::new(&ctx, 5, Unit::Ns).await;
+//!                 let _ = clock_clk.put_value(&ctx, &Value::Int(1));
+//!                 Timer::new(&ctx, 5, Unit::Ns).await;
+//!             }
+//!         });
+//!
+//!         spawn(async move {
+//!             for _ in 0..10 {
+//!                 RisingEdge::new(&ctx, &clk).await;
+//!             }
+//!             let count = count.get_value(&ctx, vpi::ValueType::Int);
+//!             vpi::printf!(&ctx, "count = {count:?}");
+//!             clock.cancel();
+//!         })
+//!         .detach();
+//!     });
+//! }
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::{
+    register_cb_with_time_guarded, simulation_time_precision, CallbackGuard, CbData, CbReason,
+    Handle, LogicVal, SimContext, Time, Value, ValueType,
+};
+
+type LocalTask = Pin<Box<dyn Future<Output = ()>>>;
+
+thread_local! {
+    static EXECUTOR: Executor = Executor::default();
+    static READY: Arc<Mutex<VecDeque<usize>>> = Arc::default();
+}
+
+#[derive(Default)]
+struct Executor {
+    tasks: RefCell<HashMap<usize, LocalTask>>,
+    /// Tasks cancelled while they were being polled.
+    cancelled: RefCell<HashSet<usize>>,
+    next_task: Cell<usize>,
+    running: Cell<bool>,
+}
+
+struct TaskWaker {
+    task: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready
+            .lock()
+            .expect("testbench ready queue poisoned")
+            .push_back(self.task);
+    }
+}
+
+fn schedule(task: usize) {
+    READY.with(|ready| {
+        ready
+            .lock()
+            .expect("testbench ready queue poisoned")
+            .push_back(task);
+    });
+}
+
+/// Polls ready tasks until all of them are waiting.
+///
+/// Calls made while the executor is already running (for example from a
+/// callback fired synchronously inside a task) return immediately; the outer
+/// call picks up the newly ready tasks.
+fn run() {
+    EXECUTOR.with(|executor| {
+        if executor.running.replace(true) {
+            return;
+        }
+        let ready = READY.with(Arc::clone);
+        loop {
+            let next = ready
+                .lock()
+                .expect("testbench ready queue poisoned")
+                .pop_front();
+            let Some(id) = next else {
+                break;
+            };
+            // Take the task out so it can spawn or cancel tasks while polled.
+            let Some(mut task) = executor.tasks.borrow_mut().remove(&id) else {
+                continue;
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                task: id,
+                ready: Arc::clone(&ready),
+            }));
+            let pending = task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending();
+            let cancelled = executor.cancelled.borrow_mut().remove(&id);
+            if pending && !cancelled {
+                executor.tasks.borrow_mut().insert(id, task);
+            }
+        }
+        executor.running.set(false);
+    });
+}
+
+/// Handle to a spawned task.
+///
+/// Awaiting the handle yields the task's output, or `None` if the task was
+/// cancelled. Dropping the handle, or calling [`JoinHandle::detach`], lets the
+/// task keep running in the background.
+pub struct JoinHandle<T> {
+    task: usize,
+    state: Rc<RefCell<JoinState<T>>>,
+}
+
+struct JoinState<T> {
+    output: Option<T>,
+    finished: bool,
+    waker: Option<Waker>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Returns `true` once the task has completed or been cancelled.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.state.borrow().finished
+    }
+
+    /// Detaches the task, letting it run to completion in the background.
+    pub fn detach(self) {}
+
+    /// Cancels the task.
+    ///
+    /// The task's future is dropped at its current await point, which
+    /// removes the VPI callbacks it was waiting on. Cancelling a finished
+    /// task has no effect.
+    pub fn cancel(&self) {
+        let waker = {
+            let mut state = self.state.borrow_mut();
+            if state.finished {
+                return;
+            }
+            state.finished = true;
+            state.waker.take()
+        };
+        let task = EXECUTOR.with(|executor| {
+            let task = executor.tasks.borrow_mut().remove(&self.task);
+            if task.is_none() {
+                // The task is being polled; drop it when the poll returns.
+                executor.cancelled.borrow_mut().insert(self.task);
+            }
+            task
+        });
+        drop(task);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        if state.finished {
+            Poll::Ready(state.output.take())
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// Spawns a task on the testbench executor of the current thread.
+///
+/// The task starts running immediately and continues until its first await
+/// point that is not ready, unless the executor is already running, in which
+/// case it runs as soon as the current task yields.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let state = Rc::new(RefCell::new(JoinState {
+        output: None,
+        finished: false,
+        waker: None,
+    }));
+    let task_state = Rc::clone(&state);
+    let task: LocalTask = Box::pin(async move {
+        let output = future.await;
+        let waker = {
+            let mut state = task_state.borrow_mut();
+            state.output = Some(output);
+            state.finished = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+    let id = EXECUTOR.with(|executor| {
+        let id = executor.next_task.get();
+        executor.next_task.set(id + 1);
+        executor.tasks.borrow_mut().insert(id, task);
+        id
+    });
+    schedule(id);
+    run();
+    JoinHandle { task: id, state }
+}
+
+/// Time unit for [`Timer`] durations.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Unit {
+    /// Simulator time steps, at the simulation precision.
+    Step,
+    /// Femtoseconds.
+    Fs,
+    /// Picoseconds.
+    Ps,
+    /// Nanoseconds.
+    Ns,
+    /// Microseconds.
+    Us,
+    /// Milliseconds.
+    Ms,
+    /// Seconds.
+    S,
+}
+
+impl Unit {
+    /// Returns the unit as a power of ten of seconds, or `None` for [`Unit::Step`].
+    #[must_use]
+    pub fn exponent(self) -> Option<i32> {
+        match self {
+            Unit::Step => None,
+            Unit::Fs => Some(-15),
+            Unit::Ps => Some(-12),
+            Unit::Ns => Some(-9),
+            Unit::Us => Some(-6),
+            Unit::Ms => Some(-3),
+            Unit::S => Some(0),
+        }
+    }
+}
+
+/// Converts a duration to simulator steps at the given precision.
+///
+/// Durations finer than the precision are rounded to the nearest step.
+fn to_steps(amount: u64, unit: Unit, precision: i32) -> u64 {
+    let Some(exponent) = unit.exponent() else {
+        return amount;
+    };
+    let shift = exponent - precision;
+    if shift >= 0 {
+        10u64
+            .checked_pow(shift.unsigned_abs())
+            .and_then(|scale| amount.checked_mul(scale))
+            .unwrap_or(u64::MAX)
+    } else {
+        let Some(scale) = 10u64.checked_pow(shift.unsigned_abs()) else {
+            return 0;
+        };
+        amount / scale + u64::from(amount % scale >= scale.div_ceil(2))
+    }
+}
+
+/// Condition a value-change callback must meet to complete its trigger.
+#[derive(Clone, Copy)]
+enum Edge {
+    Rising,
+    Falling,
+    Any,
+}
+
+impl Edge {
+    fn matches(self, value: Option<&Value>) -> bool {
+        matches!(
+            (self, value),
+            (Edge::Any, _)
+                | (
+                    Edge::Rising,
+                    Some(Value::Scalar(LogicVal::One | LogicVal::H))
+                )
+                | (
+                    Edge::Falling,
+                    Some(Value::Scalar(LogicVal::Zero | LogicVal::L))
+                )
+        )
+    }
+}
+
+enum Kind {
+    /// One-shot callback `steps` simulator steps from now.
+    Time { reason: CbReason, steps: Steps },
+    /// Persistent value-change callback on an object.
+    Change { object: Handle, edge: Edge },
+}
+
+#[derive(Clone, Copy)]
+enum Steps {
+    Exact(u64),
+    Scaled(u64, Unit),
+}
+
+#[derive(Default)]
+struct Slot {
+    fired: bool,
+    value: Option<Value>,
+    waker: Option<Waker>,
+}
+
+/// Future completing when a VPI callback fires.
+///
+/// The callback is registered on the first poll and removed when the future
+/// is dropped before it fires.
+struct Trigger {
+    kind: Kind,
+    slot: Rc<RefCell<Slot>>,
+    callback: Option<CallbackGuard>,
+}
+
+impl Trigger {
+    /// The context is only needed as proof: a `Trigger` is not `Send`, so it
+    /// is polled on the simulator thread it was created on.
+    fn new(_ctx: &SimContext, kind: Kind) -> Self {
+        Self {
+            kind,
+            slot: Rc::default(),
+            callback: None,
+        }
+    }
+
+    fn register(&self) -> CallbackGuard {
+        // Created with a context, see `Trigger::new`.
+        let ctx = SimContext::borrowed();
+        let slot: Weak<RefCell<Slot>> = Rc::downgrade(&self.slot);
+        match &self.kind {
+            Kind::Time { reason, steps } => {
+                let steps = match *steps {
+                    Steps::Exact(steps) => steps,
+                    Steps::Scaled(amount, unit) => {
+                        to_steps(amount, unit, simulation_time_precision(ctx))
+                    }
+                };
+                register_cb_with_time_guarded(ctx, *reason, Time::Sim(steps), move |_| {
+                    if fire(&slot, None) {
+                        run();
+                    }
+                })
+            }
+            Kind::Change { object, edge } => {
+                let edge = *edge;
+                let format = match edge {
+                    Edge::Any => ValueType::ObjType,
+                    Edge::Rising | Edge::Falling => ValueType::Scalar,
+                };
+                object.register_value_change_cb_guarded(ctx, format, move |data: &CbData| {
+                    if edge.matches(data.value.as_ref()) && fire(&slot, data.value.clone()) {
+                        run();
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Marks a trigger slot as fired and wakes its task.
+///
+/// Returns `false` when the trigger is gone or has already fired.
+fn fire(slot: &Weak<RefCell<Slot>>, value: Option<Value>) -> bool {
+    let Some(slot) = slot.upgrade() else {
+        return false;
+    };
+    let waker = {
+        let mut slot = slot.borrow_mut();
+        if slot.fired {
+            return false;
+        }
+        slot.fired = true;
+        slot.value = value;
+        slot.waker.take()
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+    true
+}
+
+impl Future for Trigger {
+    type Output = Option<Value>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        {
+            let mut slot = self.slot.borrow_mut();
+            if slot.fired {
+                return Poll::Ready(slot.value.take());
+            }
+            slot.waker = Some(cx.waker().clone());
+        }
+        if self.callback.is_none() {
+            let callback = self.register();
+            self.callback = Some(callback);
+        }
+        Poll::Pending
+    }
+}
+
+macro_rules! unit_trigger {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        pub struct $name(Trigger);
+
+        impl Future for $name {
+            type Output = ();
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+                Pin::new(&mut self.0).poll(cx).map(|_| ())
+            }
+        }
+    };
+}
+
+unit_trigger!(
+    /// Completes after a simulation time delay (`cbAfterDelay`).
+    Timer
+);
+
+impl Timer {
+    /// Creates a timer for `amount` in `unit`.
+    ///
+    /// The delay is converted to simulator steps using the simulation
+    /// precision when the timer is first polled, rounding to the nearest step.
+    #[must_use]
+    pub fn new(ctx: &SimContext, amount: u64, unit: Unit) -> Self {
+        let steps = match unit {
+            Unit::Step => Steps::Exact(amount),
+            unit => Steps::Scaled(amount, unit),
+        };
+        Self(Trigger::new(
+            ctx,
+            Kind::Time {
+                reason: CbReason::AfterDelay,
+                steps,
+            },
+        ))
+    }
+}
+
+unit_trigger!(
+    /// Completes in the read-write synchronization region of the current time
+    /// step (`cbReadWriteSynch`).
+    ReadWrite
+);
+
+impl ReadWrite {
+    /// Creates a read-write synchronization trigger.
+    #[must_use]
+    pub fn new(ctx: &SimContext) -> Self {
+        Self(Trigger::new(
+            ctx,
+            Kind::Time {
+                reason: CbReason::ReadWriteSynch,
+                steps: Steps::Exact(0),
+            },
+        ))
+    }
+}
+
+unit_trigger!(
+    /// Completes in the read-only synchronization region of the current time
+    /// step (`cbReadOnlySynch`).
+    ///
+    /// Values must not be written after this trigger until time advances.
+    ReadOnly
+);
+
+impl ReadOnly {
+    /// Creates a read-only synchronization trigger.
+    #[must_use]
+    pub fn new(ctx: &SimContext) -> Self {
+        Self(Trigger::new(
+            ctx,
+            Kind::Time {
+                reason: CbReason::ReadOnlySynch,
+                steps: Steps::Exact(0),
+            },
+        ))
+    }
+}
+
+unit_trigger!(
+    /// Completes when simulation time next advances (`cbNextSimTime`).
+    NextTimeStep
+);
+
+impl NextTimeStep {
+    /// Creates a next-time-step trigger.
+    #[must_use]
+    pub fn new(ctx: &SimContext) -> Self {
+        Self(Trigger::new(
+            ctx,
+            Kind::Time {
+                reason: CbReason::NextSimTime,
+                steps: Steps::Exact(0),
+            },
+        ))
+    }
+}
+
+unit_trigger!(
+    /// Completes when a single-bit signal changes to `1`.
+    RisingEdge
+);
+
+impl RisingEdge {
+    /// Creates a rising-edge trigger for `signal`.
+    #[must_use]
+    pub fn new(ctx: &SimContext, signal: &Handle) -> Self {
+        Self(Trigger::new(
+            ctx,
+            Kind::Change {
+                object: signal.clone(),
+                edge: Edge::Rising,
+            },
+        ))
+    }
+}
+
+unit_trigger!(
+    /// Completes when a single-bit signal changes to `0`.
+    FallingEdge
+);
+
+impl FallingEdge {
+    /// Creates a falling-edge trigger for `signal`.
+    #[must_use]
+    pub fn new(ctx: &SimContext, signal: &Handle) -> Self {
+        Self(Trigger::new(
+            ctx,
+            Kind::Change {
+                object: signal.clone(),
+                edge: Edge::Falling,
+            },
+        ))
+    }
+}
+
+/// Completes when an object's value changes, yielding the new value.
+///
+/// The value is reported in the object's native format (`vpiObjTypeVal`),
+/// or `None` if the simulator did not provide one.
+pub struct ValueChanged(Trigger);
+
+impl ValueChanged {
+    /// Creates a value-change trigger for `object`.
+    #[must_use]
+    pub fn new(ctx: &SimContext, object: &Handle) -> Self {
+        Self(Trigger::new(
+            ctx,
+            Kind::Change {
+                object: object.clone(),
+                edge: Edge::Any,
+            },
+        ))
+    }
+}
+
+impl Future for ValueChanged {
+    type Output = Option<Value>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+/// Output of [`select`], identifying which future completed first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Either<A, B> {
+    /// The first future completed first.
+    Left(A),
+    /// The second future completed first.
+    Right(B),
+}
+
+/// Waits for both futures to complete and returns both outputs.
+pub async fn join<A, B>(a: A, b: B) -> (A::Output, B::Output)
+where
+    A: Future,
+    B: Future,
+{
+    let mut a = Box::pin(a);
+    let mut b = Box::pin(b);
+    let mut a_out = None;
+    let mut b_out = None;
+    std::future::poll_fn(move |cx| {
+        if a_out.is_none() {
+            if let Poll::Ready(out) = a.as_mut().poll(cx) {
+                a_out = Some(out);
+            }
+        }
+        if b_out.is_none() {
+            if let Poll::Ready(out) = b.as_mut().poll(cx) {
+                b_out = Some(out);
+            }
+        }
+        if a_out.is_some() && b_out.is_some() {
+            Poll::Ready((a_out.take().unwrap(), b_out.take().unwrap()))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Waits for all futures to complete and returns their outputs in order.
+pub async fn join_all<F>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output>
+where
+    F: Future,
+{
+    let mut futures: Vec<_> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+    std::future::poll_fn(move |cx| {
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                if let Poll::Ready(out) = future.as_mut().poll(cx) {
+                    *output = Some(out);
+                }
+            }
+        }
+        if outputs.iter().all(Option::is_some) {
+            Poll::Ready(outputs.iter_mut().map(|out| out.take().unwrap()).collect())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Waits for the first of two futures to complete.
+///
+/// The other future is dropped, which cancels its pending triggers. When both
+/// are ready at the same poll, `a` wins.
+pub async fn select<A, B>(a: A, b: B) -> Either<A::Output, B::Output>
+where
+    A: Future,
+    B: Future,
+{
+    let mut a = Box::pin(a);
+    let mut b = Box::pin(b);
+    std::future::poll_fn(move |cx| {
+        if let Poll::Ready(out) = a.as_mut().poll(cx) {
+            return Poll::Ready(Either::Left(out));
+        }
+        if let Poll::Ready(out) = b.as_mut().poll(cx) {
+            return Poll::Ready(Either::Right(out));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_steps, Unit};
+
+    #[test]
+    fn durations_convert_to_precision_steps() {
+        assert_eq!(to_steps(10, Unit::Ns, -9), 10);
+        assert_eq!(to_steps(10, Unit::Ns, -12), 10_000);
+        assert_eq!(to_steps(7, Unit::Step, -12), 7);
+        assert_eq!(to_steps(1_400, Unit::Ps, -9), 1);
+        assert_eq!(to_steps(1_500, Unit::Ps, -9), 2);
+        assert_eq!(to_steps(1, Unit::S, -15), 1_000_000_000_000_000);
+        assert_eq!(to_steps(u64::MAX, Unit::S, -15), u64::MAX);
+    }
+
+    #[cfg(mock_backend)]
+    mod simulated {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        use crate::mock::MockSimulator;
+        use crate::testbench::{
+            join, select, spawn, Either, FallingEdge, ReadOnly, RisingEdge, Timer, Unit,
+            ValueChanged,
+        };
+        use crate::{current_simulation_time, Handle, SimContext, Time, Value, ValueType};
+
+        fn now(ctx: &SimContext) -> u64 {
+            current_simulation_time(ctx).to_u64().unwrap()
+        }
+
+        fn clock(
+            ctx: &'static SimContext,
+            clk: &Handle,
+            half_period: u64,
+        ) -> crate::testbench::JoinHandle<()> {
+            let clk = clk.clone();
+            spawn(async move {
+                loop {
+                    let _ = clk.put_value(ctx, &Value::Int(0));
+                    Timer::new(ctx, half_period, Unit::Step).await;
+                    let _ = clk.put_value(ctx, &Value::Int(1));
+                    Timer::new(ctx, half_period, Unit::Step).await;
+                }
+            })
+        }
+
+        #[test]
+        fn tasks_await_timers_and_edges() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            let clk = sim.add_reg(&top, "clk", 1);
+            let log = Rc::new(RefCell::new(Vec::new()));
+
+            let clock = clock(ctx, &clk, 5);
+            let task_log = Rc::clone(&log);
+            let task_clk = clk.clone();
+            let checker = spawn(async move {
+                for _ in 0..3 {
+                    RisingEdge::new(ctx, &task_clk).await;
+                    task_log.borrow_mut().push(("rise", now(ctx)));
+                    FallingEdge::new(ctx, &task_clk).await;
+                    task_log.borrow_mut().push(("fall", now(ctx)));
+                }
+                clock.cancel();
+                42
+            });
+
+            sim.run();
+
+            assert_eq!(
+                *log.borrow(),
+                vec![
+                    ("rise", 5),
+                    ("fall", 10),
+                    ("rise", 15),
+                    ("fall", 20),
+                    ("rise", 25),
+                    ("fall", 30),
+                ]
+            );
+            assert!(checker.is_finished());
+            assert_eq!(sim.active_callbacks(), 0);
+        }
+
+        #[test]
+        fn timers_scale_with_the_simulation_precision() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            sim.set_timescale(&top, -9, -12);
+
+            let done = spawn(async {
+                Timer::new(ctx, 2, Unit::Ns).await;
+                now(ctx)
+            });
+            sim.run();
+
+            let waiter = spawn(done);
+            sim.run();
+            assert!(waiter.is_finished());
+            assert_eq!(sim.time(), 2_000);
+        }
+
+        #[test]
+        fn select_cancels_the_losing_trigger() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            let data = sim.add_reg(&top, "data", 8);
+            let result = Rc::new(RefCell::new(None));
+
+            let watched = data.clone();
+            let out = Rc::clone(&result);
+            spawn(async move {
+                let winner = select(
+                    ValueChanged::new(ctx, &watched),
+                    Timer::new(ctx, 100, Unit::Step),
+                )
+                .await;
+                *out.borrow_mut() = Some((winner, now(ctx)));
+                ReadOnly::new(ctx).await;
+            })
+            .detach();
+            assert!(sim.schedule_value(&data, &Value::Int(9), 7));
+            sim.run();
+
+            assert_eq!(
+                *result.borrow(),
+                Some((Either::Left(Some(Value::Vector("00001001".into()))), 7))
+            );
+            assert_eq!(sim.active_callbacks(), 0);
+            assert_eq!(sim.time(), 7);
+        }
+
+        #[test]
+        fn join_waits_for_both_and_cancel_removes_callbacks() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            let flag = sim.add_reg(&top, "flag", 1);
+
+            let joined = Rc::new(RefCell::new(None));
+            let out = Rc::clone(&joined);
+            spawn(async move {
+                let pair = join(
+                    async {
+                        Timer::new(ctx, 3, Unit::Step).await;
+                        now(ctx)
+                    },
+                    async {
+                        Timer::new(ctx, 8, Unit::Step).await;
+                        now(ctx)
+                    },
+                )
+                .await;
+                *out.borrow_mut() = Some(pair);
+            })
+            .detach();
+
+            let watched = flag.clone();
+            let stuck = spawn(async move {
+                RisingEdge::new(ctx, &watched).await;
+            });
+            assert_eq!(sim.active_callbacks(), 3);
+            stuck.cancel();
+            assert_eq!(sim.active_callbacks(), 2);
+
+            let awaited = spawn(stuck);
+            sim.run();
+
+            assert_eq!(*joined.borrow(), Some((3, 8)));
+            assert!(awaited.is_finished());
+            assert_eq!(
+                flag.get_value(ctx, ValueType::BinStr),
+                Some(Value::BinStr("x".into()))
+            );
+            assert_eq!(current_simulation_time(ctx), Time::Sim(8));
+        }
+    }
+}
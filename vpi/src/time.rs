@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use crate::Handle;
+use crate::SimContext;
 
 /// Time value representation used by VPI APIs.
 #[derive(Debug, Clone, PartialEq)]
@@ -13,6 +14,12 @@ pub enum Time {
     Suppress,
 }
 
+/// Time format requested from the simulator, as in `s_vpi_time.type`.
+///
+/// Used for callback times with [`crate::CallbackBuilder::time_format`]; it
+/// is the same encoding as delay records use.
+pub type TimeFormat = crate::DelayTimeType;
+
 impl Display for Time {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -95,7 +102,7 @@ impl Handle {
     ///
     /// Returns `None` when called on a null handle.
     #[must_use]
-    pub fn get_time(&self) -> Option<Time> {
+    pub fn get_time(&self, _ctx: &SimContext) -> Option<Time> {
         if self.is_null() {
             return None;
         }
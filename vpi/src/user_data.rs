@@ -0,0 +1,239 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::error::check_last_call;
+use crate::{register_cb_guarded, CbReason, Error, Handle, SimContext};
+
+/// Values attached with [`Handle::set_user_data`], keyed by the address
+/// handed to `vpi_put_userdata`.
+#[derive(Default)]
+struct UserDataStore {
+    values: HashMap<usize, Rc<dyn Any>>,
+    objects: HashSet<vpi_sys::vpiHandle>,
+    cleanup_registered: bool,
+}
+
+thread_local! {
+    static STORE: RefCell<UserDataStore> = RefCell::default();
+}
+
+/// Detaches and frees all user data once the simulation ends.
+fn free_user_data() {
+    let (values, objects) = STORE.with_borrow_mut(|store| {
+        store.cleanup_registered = false;
+        (
+            std::mem::take(&mut store.values),
+            std::mem::take(&mut store.objects),
+        )
+    });
+    for object in objects {
+        unsafe {
+            vpi_sys::vpi_put_userdata(object, std::ptr::null_mut());
+        }
+    }
+    // Dropped outside the borrow, since destructors may use the VPI.
+    drop(values);
+}
+
+/// Detaches and frees the user data of `object` before its handle is
+/// released with `vpi_release_handle` or `vpi_free_object`.
+///
+/// System task and function calls keep their data, since it is stored with
+/// the call instance, which outlives the handles to it.
+pub(crate) fn release_user_data(object: vpi_sys::vpiHandle) {
+    // The store may already be gone when handles are dropped at thread exit.
+    let attached = STORE
+        .try_with(|store| store.borrow().objects.contains(&object))
+        .unwrap_or(false);
+    if !attached {
+        return;
+    }
+    let typ =
+        unsafe { vpi_sys::vpi_get(crate::Property::Type as vpi_sys::PLI_INT32, object) } as u32;
+    if matches!(typ, vpi_sys::vpiSysTaskCall | vpi_sys::vpiSysFuncCall) {
+        return;
+    }
+    STORE.with_borrow_mut(|store| store.objects.remove(&object));
+    let ptr = unsafe { vpi_sys::vpi_get_userdata(object) };
+    unsafe {
+        vpi_sys::vpi_put_userdata(object, std::ptr::null_mut());
+    }
+    let value = STORE.with_borrow_mut(|store| store.values.remove(&(ptr as usize)));
+    // Dropped outside the borrow, since destructors may use the VPI.
+    drop(value);
+}
+
+impl Handle {
+    /// Attaches `value` to this object using `vpi_put_userdata`.
+    ///
+    /// This is mainly meant for system task/function call handles from
+    /// [`crate::current_systf_call`], so each call site can keep its own
+    /// state across invocations. Read it back with [`Handle::user_data`].
+    ///
+    /// A previous value is replaced and dropped once no [`Rc`] returned by
+    /// [`Handle::user_data`] refers to it anymore. The value is detached and
+    /// dropped when this crate releases the handle, that is when an
+    /// [`OwnedHandle`](crate::OwnedHandle) is dropped or, with the
+    /// `release_handle` feature, when any [`Handle`] to the object is
+    /// dropped. System task and function calls are the exception: their data
+    /// belongs to the call instance and survives its handles. All remaining
+    /// values are detached and dropped when the simulation ends.
+    ///
+    /// Failures are ignored; use [`Handle::try_set_user_data`] to detect them.
+    pub fn set_user_data<T: 'static>(&self, ctx: &SimContext, value: T) {
+        let _ = self.try_set_user_data(ctx, value);
+    }
+
+    /// Fallible variant of [`Handle::set_user_data`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles and when the simulator does not accept user
+    /// data for this object.
+    pub fn try_set_user_data<T: 'static>(&self, ctx: &SimContext, value: T) -> Result<(), Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_put_userdata", "null handle"));
+        }
+        let value: Rc<dyn Any> = Rc::new(value);
+        let ptr = Rc::as_ptr(&value).cast::<()>().cast_mut();
+        let previous = unsafe { vpi_sys::vpi_get_userdata(self.as_raw()) };
+        let accepted = unsafe { vpi_sys::vpi_put_userdata(self.as_raw(), ptr.cast()) };
+        if accepted == 0 {
+            return Err(Error::last(
+                ctx,
+                "vpi_put_userdata",
+                "user data not accepted for this object",
+            ));
+        }
+        check_last_call(ctx, "vpi_put_userdata")?;
+
+        let (replaced, register_cleanup) = STORE.with_borrow_mut(|store| {
+            store.values.insert(ptr as usize, value);
+            store.objects.insert(self.as_raw());
+            (
+                store.values.remove(&(previous as usize)),
+                !std::mem::replace(&mut store.cleanup_registered, true),
+            )
+        });
+        // Dropped outside the borrow, since destructors may use the VPI.
+        drop(replaced);
+        if register_cleanup {
+            register_cb_guarded(ctx, CbReason::EndOfSimulation, |_| free_user_data()).detach();
+        }
+        Ok(())
+    }
+
+    /// Returns the value of type `T` attached with [`Handle::set_user_data`].
+    ///
+    /// Returns `None` if no value is attached, if it was attached by other
+    /// code than [`Handle::set_user_data`], or if it has a different type,
+    /// as checked through its `TypeId`. Use a `Cell` or `RefCell` as `T` to
+    /// update the value in place.
+    ///
+    /// The returned [`Rc`] keeps the value alive even if it is replaced or
+    /// the simulation ends in the meantime.
+    #[must_use]
+    pub fn user_data<T: 'static>(&self, _ctx: &SimContext) -> Option<Rc<T>> {
+        if self.is_null() {
+            return None;
+        }
+        let ptr = unsafe { vpi_sys::vpi_get_userdata(self.as_raw()) };
+        if ptr.is_null() {
+            return None;
+        }
+        let value = STORE.with_borrow(|store| store.values.get(&(ptr as usize)).cloned())?;
+        value.downcast::<T>().ok()
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::mock::MockSimulator;
+    use crate::{current_systf_call, register_systf, Handle, OwnedHandle, SimContext, SystfKind};
+
+    #[test]
+    fn each_call_site_keeps_its_own_state() {
+        unsafe extern "C" fn calltf(_: *mut std::os::raw::c_char) -> i32 {
+            let ctx = SimContext::borrowed();
+            let call = current_systf_call(ctx);
+            match call.user_data::<Cell<u32>>(ctx) {
+                Some(count) => count.set(count.get() + 1),
+                None => call.set_user_data(ctx, Cell::new(1_u32)),
+            }
+            0
+        }
+
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let _ = register_systf(
+            ctx,
+            SystfKind::Task,
+            c"$count",
+            Some(calltf),
+            None,
+            None,
+            std::ptr::null_mut(),
+            None,
+        );
+        let first = sim.add_systf_call(&top, "$count", &[]);
+        let second = sim.add_systf_call(&top, "$count", &[]);
+        sim.start();
+
+        for _ in 0..3 {
+            let _ = sim.call_systf(&first);
+        }
+        let _ = sim.call_systf(&second);
+        assert_eq!(first.user_data::<Cell<u32>>(ctx).map(|n| n.get()), Some(3));
+        assert_eq!(second.user_data::<Cell<u32>>(ctx).map(|n| n.get()), Some(1));
+        assert!(first.user_data::<u32>(ctx).is_none());
+    }
+
+    #[test]
+    fn user_data_is_freed_at_end_of_simulation() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let token = Rc::new(());
+        q.set_user_data(ctx, Rc::clone(&token));
+        let held = q.user_data::<Rc<()>>(ctx).unwrap();
+        q.set_user_data(ctx, Rc::clone(&token));
+        assert_eq!(Rc::strong_count(&token), 3);
+        drop(held);
+        assert_eq!(Rc::strong_count(&token), 2);
+        assert!(Rc::ptr_eq(&*q.user_data::<Rc<()>>(ctx).unwrap(), &token));
+        assert!(Handle::null().try_set_user_data(ctx, 1).is_err());
+
+        let held = q.user_data::<Rc<()>>(ctx).unwrap();
+        sim.finish();
+        assert!(q.user_data::<Rc<()>>(ctx).is_none());
+        assert_eq!(Rc::strong_count(&token), 2);
+        drop(held);
+        assert_eq!(Rc::strong_count(&token), 1);
+    }
+
+    #[test]
+    fn user_data_is_freed_with_its_handle() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        sim.start();
+
+        let token = Rc::new(());
+        let owned = OwnedHandle::new(Handle::from_raw(q.as_raw()));
+        owned.set_user_data(ctx, Rc::clone(&token));
+        assert_eq!(Rc::strong_count(&token), 2);
+        drop(owned);
+        assert_eq!(Rc::strong_count(&token), 1);
+        assert!(q.user_data::<Rc<()>>(ctx).is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use std::ffi::CString;
 use std::fmt::Display;
 
+use crate::SimContext;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 use vpi_sys::PLI_INT32;
@@ -8,7 +9,8 @@ use vpi_sys::PLI_INT32;
 #[cfg(feature = "verilator")]
 use crate::scalar_vector_to_vecval;
 
-use crate::{Handle, LogicVal, LogicVec, Property, Time};
+use crate::error::check_last_call;
+use crate::{Error, Handle, LogicVal, LogicVec, OwnedHandle, Property, Time};
 
 /// High-level value representation returned from or written to VPI objects.
 #[derive(Debug, Clone, PartialEq)]
@@ -252,6 +254,79 @@ pub enum PutValueDelay {
     PureTransport = vpi_sys::vpiPureTransportDelay as i32,
 }
 
+/// A value update scheduled by [`Handle::put_value_delayed`] or
+/// [`Handle::put_value_scheduled`].
+///
+/// The update stays pending until the simulator applies it at its scheduled
+/// time or it is cancelled with [`ScheduledEvent::cancel`]. The event handle
+/// is released when the `ScheduledEvent` is dropped, which does not cancel
+/// a pending update.
+#[derive(Debug)]
+pub struct ScheduledEvent {
+    handle: OwnedHandle,
+}
+
+impl PartialEq for ScheduledEvent {
+    fn eq(&self, other: &Self) -> bool {
+        *self.handle == *other.handle
+    }
+}
+
+impl ScheduledEvent {
+    /// Takes ownership of an event handle returned by `vpi_put_value` with
+    /// [`PutValueFlags::ReturnEvent`]. Returns `None` for null handles.
+    #[must_use]
+    pub fn from_handle(handle: Handle) -> Option<Self> {
+        (!handle.is_null()).then(|| Self {
+            handle: OwnedHandle::new(handle),
+        })
+    }
+
+    /// Returns the event handle.
+    #[must_use]
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Returns the event handle, consuming the event without releasing the
+    /// handle.
+    #[must_use]
+    pub fn into_handle(self) -> Handle {
+        self.handle.into_inner()
+    }
+
+    /// Returns `true` while the update has neither been applied nor
+    /// cancelled, as reported by `vpiScheduled`.
+    #[must_use]
+    pub fn is_pending(&self, ctx: &SimContext) -> bool {
+        self.handle.get_bool(ctx, Property::Scheduled) == Some(true)
+    }
+
+    /// Cancels the update with `vpiCancelEvent`.
+    ///
+    /// Returns `true` if the update was still pending. Cancelling an applied
+    /// or already cancelled update has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the simulator reports an error for the cancellation.
+    pub fn cancel(&self, ctx: &SimContext) -> Result<bool, Error> {
+        if !self.is_pending(ctx) {
+            return Ok(false);
+        }
+        unsafe {
+            vpi_sys::vpi_put_value(
+                self.handle.as_raw(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                vpi_sys::vpiCancelEvent as i32,
+            );
+        }
+        check_last_call(ctx, "vpi_put_value")?;
+        Ok(true)
+    }
+}
+
 struct PutValuePayload {
     /// Raw VPI value record passed to `vpi_put_value`.
     raw: vpi_sys::t_vpi_value,
@@ -1252,27 +1327,47 @@ impl Handle {
     /// Returns a null handle when this handle is null. Otherwise returns the
     /// event handle returned by the simulator (which may also be null).
     #[must_use]
-    pub fn put_value(&self, value: &Value) -> Handle {
-        self.put_value_scheduled(value, None, PutValueDelay::NoDelay, &PutValueFlags::empty())
+    pub fn put_value(&self, _ctx: &SimContext, value: &Value) -> Handle {
+        if self.is_null() {
+            return Handle::null();
+        }
+        Handle::from_raw(self.put_value_raw(
+            value,
+            None,
+            PutValueDelay::NoDelay,
+            &PutValueFlags::empty(),
+        ))
     }
 
     /// Writes a value to this handle using `vpi_put_value` with optional scheduling.
     ///
     /// `time` is ignored when `delay` is [`PutValueDelay::NoDelay`].
-    /// Returns a null handle when this handle is null. Otherwise returns the
-    /// event handle returned by the simulator (which may also be null).
-    #[must_use]
+    /// Returns the scheduled event if the simulator returns one, as it does
+    /// for delayed writes with [`PutValueFlags::ReturnEvent`], and `None`
+    /// when this handle is null.
     pub fn put_value_scheduled(
         &self,
+        _ctx: &SimContext,
         value: &Value,
         time: Option<&Time>,
         delay: PutValueDelay,
         flags: &PutValueFlags,
-    ) -> Handle {
+    ) -> Option<ScheduledEvent> {
         if self.is_null() {
-            return Handle::null();
+            return None;
         }
+        ScheduledEvent::from_handle(Handle::from_raw(
+            self.put_value_raw(value, time, delay, flags),
+        ))
+    }
 
+    fn put_value_raw(
+        &self,
+        value: &Value,
+        time: Option<&Time>,
+        delay: PutValueDelay,
+        flags: &PutValueFlags,
+    ) -> vpi_sys::vpiHandle {
         let mut payload = encode_value_for_put(value);
 
         let mut raw_time_storage;
@@ -1285,11 +1380,96 @@ impl Handle {
 
         let raw_flags = (delay as i32) | (flags.bits() as i32);
 
-        let event = unsafe {
+        unsafe {
             vpi_sys::vpi_put_value(self.as_raw(), &raw mut payload.raw, raw_time_ptr, raw_flags)
-        };
+        }
+    }
+
+    /// Fallible variant of [`Handle::put_value`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles and when the simulator reports an error for
+    /// the write, for example because the object cannot be written.
+    pub fn try_put_value(&self, ctx: &SimContext, value: &Value) -> Result<Handle, Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_put_value", "null handle"));
+        }
+        let event = self.put_value(ctx, value);
+        check_last_call(ctx, "vpi_put_value")?;
+        Ok(event)
+    }
+
+    /// Fallible variant of [`Handle::put_value_scheduled`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles and when the simulator reports an error for
+    /// the write.
+    pub fn try_put_value_scheduled(
+        &self,
+        ctx: &SimContext,
+        value: &Value,
+        time: Option<&Time>,
+        delay: PutValueDelay,
+        flags: &PutValueFlags,
+    ) -> Result<Option<ScheduledEvent>, Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_put_value", "null handle"));
+        }
+        let event = self.put_value_scheduled(ctx, value, time, delay, flags);
+        check_last_call(ctx, "vpi_put_value")?;
+        Ok(event)
+    }
+
+    /// Calls `vpi_put_value` without a time, passing `flags` unchanged.
+    pub(crate) fn put_value_with_flags(
+        &self,
+        ctx: &SimContext,
+        value: &Value,
+        flags: u32,
+    ) -> Result<(), Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_put_value", "null handle"));
+        }
+        let mut payload = encode_value_for_put(value);
+        unsafe {
+            vpi_sys::vpi_put_value(
+                self.as_raw(),
+                &raw mut payload.raw,
+                std::ptr::null_mut(),
+                flags as i32,
+            );
+        }
+        check_last_call(ctx, "vpi_put_value")
+    }
 
-        Handle::from_raw(event)
+    /// Schedules a write of `value` after `time` with a delayed `delay`
+    /// mode, returning the event so it can be inspected or cancelled.
+    ///
+    /// [`PutValueFlags::ReturnEvent`] is added to `flags`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles, for [`PutValueDelay::NoDelay`], when the
+    /// simulator reports an error for the write and when it returns no event.
+    pub fn put_value_delayed(
+        &self,
+        ctx: &SimContext,
+        value: &Value,
+        time: &Time,
+        delay: PutValueDelay,
+        flags: &PutValueFlags,
+    ) -> Result<ScheduledEvent, Error> {
+        if delay == PutValueDelay::NoDelay {
+            return Err(Error::new(
+                "vpi_put_value",
+                "immediate writes do not schedule an event",
+            ));
+        }
+        let flags = PutValueFlags::from_bits_retain(flags.bits() | vpi_sys::vpiReturnEvent);
+        self.try_put_value_scheduled(ctx, value, Some(time), delay, &flags)?
+            .ok_or_else(|| Error::new("vpi_put_value", "no event handle returned"))
     }
 
     /// Writes an integer value to this handle using `vpi_put_value` with no delay.
@@ -1297,8 +1477,8 @@ impl Handle {
     /// Returns a null handle when this handle is null. Otherwise returns the
     /// event handle returned by the simulator (which may also be null).
     #[must_use]
-    pub fn put_int_value(&self, value: i32) -> Handle {
-        self.put_value(&Value::Int(value))
+    pub fn put_int_value(&self, ctx: &SimContext, value: i32) -> Handle {
+        self.put_value(ctx, &Value::Int(value))
     }
 
     /// Writes an array of values to this handle using `vpi_put_value_array`.
@@ -1308,8 +1488,8 @@ impl Handle {
     /// Returns `false` for null handles or unsupported/mixed value slices.
     #[must_use]
     #[cfg(feature = "value_array")]
-    pub fn put_value_array(&self, values: impl AsRef<[Value]>) -> bool {
-        self.put_value_array_with_flags(values, 0, PutValueArrayFlags::empty())
+    pub fn put_value_array(&self, ctx: &SimContext, values: impl AsRef<[Value]>) -> bool {
+        self.put_value_array_with_flags(ctx, values, 0, PutValueArrayFlags::empty())
     }
 
     /// Writes an array of values to this handle using `vpi_put_value_array`.
@@ -1320,6 +1500,7 @@ impl Handle {
     #[cfg(feature = "value_array")]
     pub fn put_value_array_with_flags(
         &self,
+        _ctx: &SimContext,
         values: impl AsRef<[Value]>,
         start_index: i32,
         flags: PutValueArrayFlags,
@@ -1356,8 +1537,8 @@ impl Handle {
     /// unavailable.
     #[must_use]
     #[cfg(not(feature = "value_array"))]
-    pub fn put_value_array(&self, values: impl AsRef<[Value]>) -> bool {
-        self.put_value_array_with_flags(values, 0, &PutValueArrayFlags::empty())
+    pub fn put_value_array(&self, ctx: &SimContext, values: impl AsRef<[Value]>) -> bool {
+        self.put_value_array_with_flags(ctx, values, 0, &PutValueArrayFlags::empty())
     }
 
     /// Writes an array of values to this handle.
@@ -1377,6 +1558,7 @@ impl Handle {
     #[cfg(not(feature = "value_array"))]
     pub fn put_value_array_with_flags(
         &self,
+        ctx: &SimContext,
         values: impl AsRef<[Value]>,
         start_index: i32,
         flags: &PutValueArrayFlags,
@@ -1395,17 +1577,40 @@ impl Handle {
                 return false;
             };
 
-            let element = self.handle_by_index(index);
+            let element = self.handle_by_index(ctx, index);
             if element.is_null() {
                 return false;
             }
 
-            let _ = element.put_value(value);
+            let _ = element.put_value(ctx, value);
         }
 
         true
     }
 
+    /// Fallible variant of [`Handle::put_value_array`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles, for values that cannot be written as an array
+    /// and when the simulator reports an error for the write.
+    pub fn try_put_value_array(
+        &self,
+        ctx: &SimContext,
+        values: impl AsRef<[Value]>,
+    ) -> Result<(), Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_put_value_array", "null handle"));
+        }
+        if !self.put_value_array(ctx, values) {
+            return Err(Error::new(
+                "vpi_put_value_array",
+                "unsupported values or index out of range",
+            ));
+        }
+        check_last_call(ctx, "vpi_put_value_array")
+    }
+
     /// Reads a value from this handle in the requested format.
     ///
     /// If `format` is [`ValueType::ObjType`], the simulator may override the
@@ -1415,7 +1620,7 @@ impl Handle {
     ///
     /// Returns `None` for null handles or unsupported formats.
     #[must_use]
-    pub fn get_value(&self, format: ValueType) -> Option<Value> {
+    pub fn get_value(&self, _ctx: &SimContext, format: ValueType) -> Option<Value> {
         if self.is_null() {
             return None;
         }
@@ -1427,6 +1632,30 @@ impl Handle {
         decode_vpi_value(value, self.as_raw())
     }
 
+    /// Fallible variant of [`Handle::get_value`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles, when the simulator reports an error for the
+    /// read and when the returned value cannot be decoded.
+    pub fn try_get_value(&self, ctx: &SimContext, format: ValueType) -> Result<Value, Error> {
+        if self.is_null() {
+            return Err(Error::new("vpi_get_value", "null handle"));
+        }
+        let mut value = vpi_sys::t_vpi_value {
+            format: format as i32,
+            value: vpi_sys::t_vpi_value__bindgen_ty_1 { integer: 0 },
+        };
+        unsafe { vpi_sys::vpi_get_value(self.as_raw(), &raw mut value) };
+        check_last_call(ctx, "vpi_get_value")?;
+        decode_vpi_value(value, self.as_raw()).ok_or_else(|| {
+            Error::new(
+                "vpi_get_value",
+                format!("cannot decode value in format {format:?}"),
+            )
+        })
+    }
+
     /// Retrieve an array of values from a Verilog object (e.g., memory array, packet array).
     ///
     /// This function calls `vpi_get_value_array` to fetch multiple values at once.
@@ -1442,7 +1671,7 @@ impl Handle {
     /// # Example
     /// ```ignore
     /// let mem = root.scan(vpi_sys::vpiMem)?;
-    /// if let Some(values) = mem.get_value_array(ValueType::Int) {
+    /// if let Some(values) = mem.get_value_array(ctx, ValueType::Int) {
     ///     for (i, val) in values.iter().enumerate() {
     ///         println!("Memory[{}] = {}", i, val);
     ///     }
@@ -1450,7 +1679,7 @@ impl Handle {
     /// ```
     #[must_use]
     #[cfg(feature = "value_array")]
-    pub fn get_value_array(&self, format: ValueType) -> Option<Vec<Value>> {
+    pub fn get_value_array(&self, ctx: &SimContext, format: ValueType) -> Option<Vec<Value>> {
         if self.is_null() {
             return None;
         }
@@ -1636,7 +1865,7 @@ impl Handle {
                 // as the size calculation is different (bits per element vs. total bits)
                 let mut values = Vec::with_capacity(size);
                 for _ in 0..size {
-                    if let Some(val) = self.get_value(ValueType::Vector) {
+                    if let Some(val) = self.get_value(ctx, ValueType::Vector) {
                         values.push(val);
                     }
                 }
@@ -1687,7 +1916,7 @@ impl Handle {
     /// available, or if any element cannot be resolved/read.
     #[must_use]
     #[cfg(not(feature = "value_array"))]
-    pub fn get_value_array(&self, format: ValueType) -> Option<Vec<Value>> {
+    pub fn get_value_array(&self, ctx: &SimContext, format: ValueType) -> Option<Vec<Value>> {
         if self.is_null() {
             return None;
         }
@@ -1701,12 +1930,12 @@ impl Handle {
                 return None;
             };
 
-            let element = self.handle_by_index(index);
+            let element = self.handle_by_index(ctx, index);
             if element.is_null() {
                 return None;
             }
 
-            let value = element.get_value(format)?;
+            let value = element.get_value(ctx, format)?;
             values.push(value);
         }
 
@@ -1715,11 +1944,11 @@ impl Handle {
 
     /// Returns whether this handle represents an array object.
     #[must_use]
-    pub fn is_array(&self) -> bool {
+    pub fn is_array(&self, ctx: &SimContext) -> bool {
         if self.is_null() {
             return false;
         }
-        self.get_bool(Property::Array).unwrap_or(false)
+        self.get_bool(ctx, Property::Array).unwrap_or(false)
     }
 }
 
@@ -1732,7 +1961,7 @@ mod tests {
         value_array_to_time_array, LogicVal, LogicVec, PutValueArrayFlags, PutValueDelay,
         PutValueFlags, Value, ValueType,
     };
-    use crate::{Handle, Strength, StrengthValue, Time};
+    use crate::{Handle, SimContext, Strength, StrengthValue, Time};
 
     #[test]
     fn cstring_lossy_no_nul_strips_interior_nuls() {
@@ -1764,27 +1993,31 @@ mod tests {
 
     #[test]
     fn put_value_on_null_handle_returns_null() {
+        let ctx = SimContext::borrowed();
         let h = Handle::null();
-        let event = h.put_value(&Value::Int(7));
+        let event = h.put_value(ctx, &Value::Int(7));
         assert!(event.is_null());
     }
 
     #[test]
-    fn put_value_scheduled_on_null_handle_returns_null() {
+    fn put_value_scheduled_on_null_handle_returns_none() {
+        let ctx = SimContext::borrowed();
         let h = Handle::null();
         let event = h.put_value_scheduled(
+            ctx,
             &Value::Int(7),
             Some(&Time::Sim(5)),
             PutValueDelay::Inertial,
             &PutValueFlags::ReturnEvent,
         );
-        assert!(event.is_null());
+        assert!(event.is_none());
     }
 
     #[test]
     fn put_int_value_on_null_handle_returns_null() {
+        let ctx = SimContext::borrowed();
         let h = Handle::null();
-        let event = h.put_int_value(7);
+        let event = h.put_int_value(ctx, 7);
         assert!(event.is_null());
     }
 
@@ -1796,8 +2029,9 @@ mod tests {
     #[test]
     #[cfg(not(feature = "value_array"))]
     fn get_value_array_on_null_handle_returns_none() {
+        let ctx = SimContext::borrowed();
         let h = Handle::null();
-        assert!(h.get_value_array(ValueType::Int).is_none());
+        assert!(h.get_value_array(ctx, ValueType::Int).is_none());
     }
 
     #[test]
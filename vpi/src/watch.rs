@@ -0,0 +1,397 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::rc::{Rc, Weak};
+
+use crate::{CallbackGuard, CbData, Error, Handle, SimContext, ValueType};
+
+/// Identifies a listener added with [`SignalWatchers::watch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
+/// Activity of one watched signal, as reported by [`SignalWatchers::stats`].
+#[derive(Debug, Clone)]
+pub struct SignalStats {
+    /// The watched object.
+    pub signal: Handle,
+    /// Full hierarchical name of the object, if available.
+    pub name: Option<String>,
+    /// Number of listeners.
+    pub listeners: usize,
+    /// Number of value-change callbacks received from the simulator.
+    pub dispatches: u64,
+    /// Number of listener calls made for those callbacks.
+    pub listener_calls: u64,
+}
+
+/// Identifies a watched object; names are stable across handle lookups,
+/// while addresses are the fallback for unnamed objects.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum SignalKey {
+    Name(String),
+    Object(usize),
+}
+
+impl SignalKey {
+    pub(crate) fn new(signal: &Handle, name: Option<String>) -> Self {
+        match name {
+            Some(name) => Self::Name(name),
+            None => Self::Object(signal.as_raw() as usize),
+        }
+    }
+}
+
+struct Listener {
+    id: ListenerId,
+    priority: i32,
+    active: Cell<bool>,
+    callback: Box<dyn Fn(&CbData)>,
+}
+
+struct Signal {
+    handle: Handle,
+    name: Option<String>,
+    listeners: Vec<Rc<Listener>>,
+    dispatches: u64,
+    listener_calls: u64,
+    /// Removes the value-change callback when the signal is dropped.
+    _guard: CallbackGuard,
+}
+
+#[derive(Default)]
+struct Watchers {
+    signals: BTreeMap<SignalKey, Signal>,
+    owners: HashMap<ListenerId, SignalKey>,
+    next_id: u64,
+}
+
+/// Fans out value changes of objects to any number of Rust listeners.
+///
+/// Each watched object gets a single [`CbReason::ValueChange`](crate::CbReason::ValueChange)
+/// callback, however many listeners it has. Listeners are added and removed
+/// without calling into the simulator, except for the first listener of an
+/// object, which registers the callback, and the last one, whose removal
+/// removes it again. Listeners run in order of decreasing priority, and in
+/// the order they were added for equal priorities.
+///
+/// Clones share the same listeners. All callbacks are removed once the last
+/// clone is dropped.
+///
+/// ```ignore
+/// let watchers = SignalWatchers::new(ValueType::Int);
+/// let clk = Handle::handle_by_name(ctx, "tb.clk");
+/// watchers.watch(ctx, &clk, 10, |data| sample(data))?;
+/// watchers.watch(ctx, &clk, 0, |data| log(data))?;
+/// ```
+#[derive(Clone)]
+pub struct SignalWatchers {
+    inner: Rc<RefCell<Watchers>>,
+    value_type: ValueType,
+}
+
+impl SignalWatchers {
+    /// Creates an empty set whose listeners receive values in `value_type`.
+    #[must_use]
+    pub fn new(value_type: ValueType) -> Self {
+        Self {
+            inner: Rc::default(),
+            value_type,
+        }
+    }
+
+    /// Adds `listener` for value changes of `signal` with `priority`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for null handles, and when the simulator rejects the
+    /// value-change callback for the first listener of `signal`.
+    pub fn watch<F>(
+        &self,
+        ctx: &SimContext,
+        signal: &Handle,
+        priority: i32,
+        listener: F,
+    ) -> Result<ListenerId, Error>
+    where
+        F: Fn(&CbData) + 'static,
+    {
+        if signal.is_null() {
+            return Err(Error::new("vpi_register_cb", "null handle"));
+        }
+        let name = signal.get_full_name(ctx);
+        let key = SignalKey::new(signal, name.clone());
+
+        if !self.inner.borrow().signals.contains_key(&key) {
+            let watchers = Rc::downgrade(&self.inner);
+            let dispatch_key = key.clone();
+            let guard =
+                signal.register_value_change_cb_guarded(ctx, self.value_type, move |data| {
+                    dispatch(&watchers, &dispatch_key, data);
+                });
+            if !guard.is_active() {
+                return Err(Error::last(
+                    ctx,
+                    "vpi_register_cb",
+                    "value-change callback not registered",
+                ));
+            }
+            self.inner.borrow_mut().signals.insert(
+                key.clone(),
+                Signal {
+                    handle: signal.clone(),
+                    name,
+                    listeners: Vec::new(),
+                    dispatches: 0,
+                    listener_calls: 0,
+                    _guard: guard,
+                },
+            );
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        let id = ListenerId(inner.next_id);
+        inner.next_id += 1;
+        inner.owners.insert(id, key.clone());
+        let listeners = &mut inner
+            .signals
+            .get_mut(&key)
+            .expect("signal registered above")
+            .listeners;
+        let position = listeners
+            .iter()
+            .position(|other| other.priority < priority)
+            .unwrap_or(listeners.len());
+        listeners.insert(
+            position,
+            Rc::new(Listener {
+                id,
+                priority,
+                active: Cell::new(true),
+                callback: Box::new(listener),
+            }),
+        );
+        Ok(id)
+    }
+
+    /// Removes a listener and returns `true` if it was present.
+    ///
+    /// Removing the last listener of an object removes its value-change
+    /// callback. A listener removed while its object is dispatching is not
+    /// called anymore.
+    pub fn unwatch(&self, id: ListenerId) -> bool {
+        let removed = {
+            let mut inner = self.inner.borrow_mut();
+            let Some(key) = inner.owners.remove(&id) else {
+                return false;
+            };
+            let Some(signal) = inner.signals.get_mut(&key) else {
+                return false;
+            };
+            if let Some(position) = signal.listeners.iter().position(|l| l.id == id) {
+                signal.listeners.remove(position).active.set(false);
+            }
+            if signal.listeners.is_empty() {
+                inner.signals.remove(&key)
+            } else {
+                None
+            }
+        };
+        // The guard removes the callback outside the borrow.
+        drop(removed);
+        true
+    }
+
+    /// Removes all listeners of `signal` and its value-change callback.
+    ///
+    /// Returns the number of listeners removed.
+    pub fn unwatch_signal(&self, ctx: &SimContext, signal: &Handle) -> usize {
+        let removed = {
+            let mut inner = self.inner.borrow_mut();
+            let key = SignalKey::new(signal, signal.get_full_name(ctx));
+            let Some(signal) = inner.signals.remove(&key) else {
+                return 0;
+            };
+            for listener in &signal.listeners {
+                listener.active.set(false);
+                inner.owners.remove(&listener.id);
+            }
+            signal
+        };
+        removed.listeners.len()
+    }
+
+    /// Returns the number of listeners of `signal`.
+    #[must_use]
+    pub fn listener_count(&self, ctx: &SimContext, signal: &Handle) -> usize {
+        self.with_signal(ctx, signal, |watched| watched.listeners.len())
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of value-change callbacks registered with the
+    /// simulator, which is the number of watched objects.
+    #[must_use]
+    pub fn registrations(&self) -> usize {
+        self.inner.borrow().signals.len()
+    }
+
+    /// Returns the number of listeners across all objects.
+    #[must_use]
+    pub fn listeners(&self) -> usize {
+        self.inner.borrow().owners.len()
+    }
+
+    /// Returns the activity of each watched object, ordered by name.
+    #[must_use]
+    pub fn stats(&self) -> Vec<SignalStats> {
+        self.inner
+            .borrow()
+            .signals
+            .values()
+            .map(|watched| SignalStats {
+                signal: watched.handle.clone(),
+                name: watched.name.clone(),
+                listeners: watched.listeners.len(),
+                dispatches: watched.dispatches,
+                listener_calls: watched.listener_calls,
+            })
+            .collect()
+    }
+
+    fn with_signal<R>(
+        &self,
+        ctx: &SimContext,
+        signal: &Handle,
+        f: impl FnOnce(&Signal) -> R,
+    ) -> Option<R> {
+        let key = SignalKey::new(signal, signal.get_full_name(ctx));
+        self.inner.borrow().signals.get(&key).map(f)
+    }
+}
+
+impl std::fmt::Debug for SignalWatchers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SignalWatchers")
+            .field("registrations", &self.registrations())
+            .field("listeners", &self.listeners())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Calls the listeners of `key` for one value-change callback.
+fn dispatch(watchers: &Weak<RefCell<Watchers>>, key: &SignalKey, data: &CbData) {
+    let Some(watchers) = watchers.upgrade() else {
+        return;
+    };
+    // Listeners run outside the borrow, so they may add or remove listeners.
+    let listeners = {
+        let mut inner = watchers.borrow_mut();
+        let Some(signal) = inner.signals.get_mut(key) else {
+            return;
+        };
+        signal.dispatches += 1;
+        signal.listeners.clone()
+    };
+    let mut calls = 0;
+    for listener in listeners {
+        if listener.active.get() {
+            (listener.callback)(data);
+            calls += 1;
+        }
+    }
+    let mut inner = watchers.borrow_mut();
+    if let Some(signal) = inner.signals.get_mut(key) {
+        signal.listener_calls += calls;
+    }
+}
+
+#[cfg(all(test, mock_backend))]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::SignalWatchers;
+    use crate::mock::MockSimulator;
+    use crate::{Handle, Value, ValueType};
+
+    #[test]
+    fn listeners_share_one_callback_and_run_by_priority() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 8);
+        sim.start();
+
+        let watchers = SignalWatchers::new(ValueType::Int);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut ids = Vec::new();
+        for (label, priority) in [("low", 0), ("high", 10), ("low2", 0)] {
+            let log = Rc::clone(&log);
+            let id = watchers.watch(ctx, &q, priority, move |data| {
+                log.borrow_mut().push(format!("{label}={:?}", data.value));
+            });
+            ids.push(id.unwrap());
+        }
+        assert_eq!(sim.active_callbacks(), 1);
+        assert_eq!(watchers.listener_count(ctx, &q), 3);
+
+        let _ = sim.set_value(&q, &Value::Int(5));
+        assert_eq!(
+            *log.borrow(),
+            ["high=Some(Int(5))", "low=Some(Int(5))", "low2=Some(Int(5))"]
+        );
+
+        assert!(watchers.unwatch(ids[1]));
+        assert!(!watchers.unwatch(ids[1]));
+        log.borrow_mut().clear();
+        let _ = sim.set_value(&q, &Value::Int(6));
+        assert_eq!(log.borrow().len(), 2);
+
+        let stats = watchers.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].name.as_deref(), Some("tb.q"));
+        assert_eq!(stats[0].listeners, 2);
+        assert_eq!(stats[0].dispatches, 2);
+        assert_eq!(stats[0].listener_calls, 5);
+
+        watchers.unwatch(ids[0]);
+        watchers.unwatch(ids[2]);
+        assert_eq!(watchers.registrations(), 0);
+        assert_eq!(sim.active_callbacks(), 0);
+    }
+
+    #[test]
+    fn listeners_can_remove_themselves_while_dispatching() {
+        let sim = MockSimulator::new();
+        let ctx = sim.context();
+        let top = sim.add_module(&Handle::null(), "tb", "tb");
+        let q = sim.add_reg(&top, "q", 1);
+        let r = sim.add_reg(&top, "r", 1);
+        sim.start();
+
+        let watchers = SignalWatchers::new(ValueType::Int);
+        let calls = Rc::new(RefCell::new(0));
+        let own_id = Rc::new(RefCell::new(None));
+        let handle = watchers.clone();
+        let (count, id) = (Rc::clone(&calls), Rc::clone(&own_id));
+        let first = watchers
+            .watch(ctx, &q, 1, move |_| {
+                *count.borrow_mut() += 1;
+                if let Some(id) = id.borrow_mut().take() {
+                    handle.unwatch(id);
+                }
+            })
+            .unwrap();
+        *own_id.borrow_mut() = Some(first);
+        let _ = watchers.watch(ctx, &r, 0, |_| {}).unwrap();
+
+        let _ = sim.set_value(&q, &Value::Int(1));
+        let _ = sim.set_value(&q, &Value::Int(0));
+        assert_eq!(*calls.borrow(), 1);
+        assert_eq!(watchers.registrations(), 1);
+        assert_eq!(sim.active_callbacks(), 1);
+
+        assert_eq!(watchers.unwatch_signal(ctx, &r), 1);
+        assert!(watchers.watch(ctx, &Handle::null(), 0, |_| {}).is_err());
+        drop(watchers);
+        assert_eq!(sim.active_callbacks(), 0);
+    }
+}
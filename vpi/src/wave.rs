@@ -0,0 +1,492 @@
+//! Waveform writers fed by value-change callbacks.
+//!
+//! - [`vcd`] writes Value Change Dump (IEEE 1364) files that standard
+//!   waveform viewers can open.
+//! - [`fst`] writes compressed, indexed Fast Signal Trace files as used by
+//!   GTKWave, for long simulations of large designs (requires the `fst`
+//!   feature).
+//!
+//! Writers select signals either by scope and depth, like `$dumpvars`, or
+//! from an explicit list of handles, and only record changes while enabled,
+//! so dumping can be started from Rust-side trigger logic.
+
+#[cfg(feature = "fst")]
+pub mod fst;
+pub mod vcd;
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::{Rc, Weak};
+
+use crate::{
+    current_simulation_time, CallbackGuard, CbData, Handle, LogicVal, LogicVec, ObjectType,
+    Property, SimContext, Time, Value, ValueType,
+};
+
+/// Kind of a dumped variable, derived from [`Handle::get_type_name`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VarKind {
+    Wire,
+    Tri,
+    Wand,
+    Wor,
+    Supply0,
+    Supply1,
+    Reg,
+    Integer,
+    Real,
+    Time,
+}
+
+impl VarKind {
+    fn from_type_name(type_name: &str) -> Self {
+        match type_name {
+            "net" | "wire" | "uwire" | "vpiNet" | "none" => VarKind::Wire,
+            "tri" | "tri0" | "tri1" | "triand" | "trior" | "trireg" => VarKind::Tri,
+            "wand" => VarKind::Wand,
+            "wor" => VarKind::Wor,
+            "supply0" => VarKind::Supply0,
+            "supply1" => VarKind::Supply1,
+            "integer" | "int" | "shortint" | "longint" | "byte" | "vpiIntegerVar" => {
+                VarKind::Integer
+            }
+            "real" | "shortreal" | "vpiRealVar" | "vpiShortRealVar" => VarKind::Real,
+            "time" | "vpiTimeVar" => VarKind::Time,
+            _ => VarKind::Reg,
+        }
+    }
+
+    /// Returns the VCD `$var` type keyword.
+    pub(crate) fn keyword(self) -> &'static str {
+        match self {
+            VarKind::Wire => "wire",
+            VarKind::Tri => "tri",
+            VarKind::Wand => "wand",
+            VarKind::Wor => "wor",
+            VarKind::Supply0 => "supply0",
+            VarKind::Supply1 => "supply1",
+            VarKind::Reg => "reg",
+            VarKind::Integer => "integer",
+            VarKind::Real => "real",
+            VarKind::Time => "time",
+        }
+    }
+}
+
+/// A scope enclosing a dumped signal.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ScopeName {
+    pub(crate) name: String,
+    /// Module definition name, empty if unknown.
+    pub(crate) def_name: String,
+}
+
+/// A signal selected for dumping.
+pub(crate) struct Signal {
+    pub(crate) handle: Handle,
+    /// Enclosing scopes, outermost first.
+    pub(crate) scope: Vec<ScopeName>,
+    pub(crate) name: String,
+    pub(crate) size: u32,
+    pub(crate) kind: VarKind,
+}
+
+impl Signal {
+    /// Describes `handle` from its enclosing scopes, size and type name.
+    ///
+    /// The scopes are found by following `vpiScope` up to the top-level
+    /// module. Simulators that do not provide the relation fall back to
+    /// splitting the full name.
+    pub(crate) fn new(ctx: &SimContext, handle: &Handle) -> Option<Self> {
+        let name = handle.get_name(ctx)?;
+        let scope = scope_path(ctx, handle).unwrap_or_else(|| {
+            let mut path = handle
+                .get_full_name(ctx)
+                .map(|full_name| split_hierarchy(&full_name))
+                .unwrap_or_default();
+            path.pop();
+            path.into_iter()
+                .map(|name| ScopeName {
+                    name,
+                    def_name: String::new(),
+                })
+                .collect()
+        });
+        let kind = handle.get_type_name(ctx).map_or(VarKind::Reg, |type_name| {
+            VarKind::from_type_name(&type_name)
+        });
+        let size = match kind {
+            VarKind::Real => 64,
+            _ => handle.get_size(ctx).unwrap_or(1).max(1),
+        };
+        Some(Self {
+            handle: handle.clone(),
+            scope,
+            name,
+            size,
+            kind,
+        })
+    }
+
+    /// Returns the format used to read and watch this signal's value.
+    pub(crate) fn value_type(&self) -> ValueType {
+        match (self.kind, self.size) {
+            (VarKind::Real, _) => ValueType::Real,
+            (_, 1) => ValueType::Scalar,
+            _ => ValueType::Vector,
+        }
+    }
+}
+
+/// Returns the scopes enclosing `handle`, outermost first.
+fn scope_path(ctx: &SimContext, handle: &Handle) -> Option<Vec<ScopeName>> {
+    /// Bounds the walk in case a simulator returns a cyclic relation.
+    const MAX_DEPTH: usize = 1024;
+
+    let mut path = Vec::new();
+    let mut scope = handle.get(ctx, ObjectType::Scope);
+    while !scope.is_null() && path.len() < MAX_DEPTH {
+        path.push(ScopeName {
+            name: scope.get_name(ctx)?,
+            def_name: scope.get_str(ctx, Property::DefName).unwrap_or_default(),
+        });
+        scope = scope.get(ctx, ObjectType::Scope);
+    }
+    if path.is_empty() {
+        return None;
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Collects the nets and variables in `scope` and, up to `depth` levels, its
+/// sub-scopes.
+///
+/// A depth of `0` descends into all levels and `1` only includes `scope`
+/// itself, matching `$dumpvars`. A null scope selects all top-level modules.
+fn collect_scope(ctx: &SimContext, scope: &Handle, depth: usize, signals: &mut Vec<Signal>) {
+    if scope.is_null() {
+        for top in scope.iterator(ctx, ObjectType::Module) {
+            collect_scope(ctx, &top, depth, signals);
+        }
+        return;
+    }
+    for signal in scope.iterators(
+        ctx,
+        &[ObjectType::Net, ObjectType::Reg, ObjectType::Variables],
+    ) {
+        signals.extend(Signal::new(ctx, &signal));
+    }
+    if depth != 1 {
+        for child in scope.iterator(ctx, ObjectType::Module) {
+            collect_scope(ctx, &child, depth.saturating_sub(1), signals);
+        }
+    }
+}
+
+/// Sorts signals by scope and removes duplicates selected more than once.
+fn sort_signals(signals: &mut Vec<Signal>) {
+    signals.sort_by(|a, b| a.scope.cmp(&b.scope).then_with(|| a.name.cmp(&b.name)));
+    signals.dedup_by(|a, b| a.scope == b.scope && a.name == b.name);
+}
+
+/// Splits a hierarchical name into its components.
+///
+/// Escaped identifiers (`\a.b `) are kept whole, including the backslash
+/// and without the terminating space.
+pub(crate) fn split_hierarchy(full_name: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in full_name.chars() {
+        match c {
+            '\\' if current.is_empty() => {
+                escaped = true;
+                current.push(c);
+            }
+            ' ' if escaped => escaped = false,
+            '.' if !escaped => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+/// Signals selected by a writer builder.
+#[derive(Default)]
+pub(crate) struct Selection {
+    pub(crate) scopes: Vec<(Handle, usize)>,
+    pub(crate) signals: Vec<Handle>,
+}
+
+impl Selection {
+    /// Collects, sorts and deduplicates the selected signals.
+    pub(crate) fn collect(&self, ctx: &SimContext) -> Vec<Signal> {
+        let mut signals = Vec::new();
+        for (scope, depth) in &self.scopes {
+            collect_scope(ctx, scope, *depth, &mut signals);
+        }
+        signals.extend(
+            self.signals
+                .iter()
+                .filter_map(|signal| Signal::new(ctx, signal)),
+        );
+        sort_signals(&mut signals);
+        signals
+    }
+}
+
+/// A value read for dumping.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Sample {
+    /// Bits, most significant first. May be shorter than the signal.
+    Bits(Vec<LogicVal>),
+    Real(f64),
+}
+
+impl Sample {
+    /// Converts `value` for dumping `signal`, if the formats match.
+    pub(crate) fn new(signal: &Signal, value: &Value) -> Option<Self> {
+        let bits = match value {
+            Value::Real(real) => return Some(Sample::Real(*real)),
+            Value::Scalar(bit) => vec![*bit],
+            Value::Vector(vector) => vector.raw_data().to_vec(),
+            Value::Int(int) => LogicVec::from_int(*int, signal.size as usize)
+                .raw_data()
+                .to_vec(),
+            _ => return None,
+        };
+        (signal.kind != VarKind::Real).then_some(Sample::Bits(bits))
+    }
+}
+
+/// Returns the waveform character for a bit, mapping weak strengths to
+/// `0` and `1`.
+pub(crate) fn bit_char(bit: LogicVal) -> char {
+    match bit {
+        LogicVal::Zero | LogicVal::L => '0',
+        LogicVal::One | LogicVal::H => '1',
+        LogicVal::Z => 'z',
+        LogicVal::X | LogicVal::DontCare => 'x',
+    }
+}
+
+/// Output format of a waveform writer.
+///
+/// Times are in dump time steps and never decrease between calls.
+pub(crate) trait Sink {
+    /// Starts dumping, or resumes it when `first` is `false`, with the
+    /// current values of all signals.
+    fn dump_on(
+        &mut self,
+        signals: &[Signal],
+        time: u64,
+        values: &[Option<Sample>],
+        first: bool,
+    ) -> io::Result<()>;
+
+    /// Suspends dumping, marking all values unknown.
+    fn dump_off(&mut self, signals: &[Signal], time: u64) -> io::Result<()>;
+
+    /// Records a change of the signal at `index`.
+    fn change(
+        &mut self,
+        signal: &Signal,
+        index: usize,
+        time: u64,
+        value: &Sample,
+    ) -> io::Result<()>;
+
+    /// Writes buffered output.
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Finishes the output. `end` is the final time if dumping was enabled.
+    fn close(&mut self, end: Option<u64>) -> io::Result<()>;
+}
+
+/// Feeds a [`Sink`] from value-change callbacks on the selected signals.
+///
+/// Shared by the writers of all formats, which wrap it in an `Rc` so clones
+/// can be moved into trigger and end-of-simulation callbacks.
+pub(crate) struct Recorder<S: Sink> {
+    /// Output, `None` once closed.
+    sink: Option<S>,
+    signals: Vec<Signal>,
+    callbacks: Vec<CallbackGuard>,
+    /// Whether dumping has been started once.
+    dumped: bool,
+    enabled: bool,
+    error: Option<io::Error>,
+}
+
+impl<S: Sink + 'static> Recorder<S> {
+    pub(crate) fn new(sink: S, signals: Vec<Signal>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            sink: Some(sink),
+            signals,
+            callbacks: Vec::new(),
+            dumped: false,
+            enabled: false,
+            error: None,
+        }))
+    }
+
+    /// Starts or resumes dumping at the current simulation time, registering
+    /// the value-change callbacks the first time.
+    pub(crate) fn dump_on(ctx: &SimContext, this: &Rc<RefCell<Self>>) -> io::Result<()> {
+        let mut recorder = this.borrow_mut();
+        if recorder.enabled || recorder.sink.is_none() {
+            return recorder.status();
+        }
+        let time = now(ctx);
+        let values: Vec<_> = recorder
+            .signals
+            .iter()
+            .map(|signal| {
+                signal
+                    .handle
+                    .get_value(ctx, signal.value_type())
+                    .and_then(|value| Sample::new(signal, &value))
+            })
+            .collect();
+        let first = !recorder.dumped;
+        recorder.record(|sink, signals| sink.dump_on(signals, time, &values, first));
+        if first {
+            recorder.dumped = true;
+            recorder.callbacks = register_callbacks(ctx, &Rc::downgrade(this), &recorder.signals);
+        }
+        recorder.enabled = true;
+        recorder.status()
+    }
+
+    /// Suspends dumping at the current simulation time.
+    pub(crate) fn dump_off(&mut self, ctx: &SimContext) -> io::Result<()> {
+        if !self.enabled {
+            return self.status();
+        }
+        let time = now(ctx);
+        self.record(|sink, signals| sink.dump_off(signals, time));
+        self.enabled = false;
+        self.status()
+    }
+
+    pub(crate) fn is_dumping(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.record(|sink, _| sink.flush());
+        self.status()
+    }
+
+    /// Removes the callbacks and closes the sink, ending at the current
+    /// simulation time if dumping is enabled.
+    pub(crate) fn close(&mut self, ctx: &SimContext) -> io::Result<()> {
+        let end = self.enabled.then(|| now(ctx));
+        self.finish(end);
+        self.status()
+    }
+
+    fn change(&mut self, ctx: &SimContext, index: usize, data: &CbData) {
+        if !self.enabled {
+            return;
+        }
+        let time = match data.time {
+            Some(Time::Sim(ticks)) => ticks,
+            _ => now(ctx),
+        };
+        let signal = &self.signals[index];
+        let value = match &data.value {
+            Some(value) => Sample::new(signal, value),
+            None => signal
+                .handle
+                .get_value(ctx, signal.value_type())
+                .and_then(|value| Sample::new(signal, &value)),
+        };
+        if let Some(value) = value {
+            self.record(|sink, signals| sink.change(&signals[index], index, time, &value));
+        }
+    }
+}
+
+impl<S: Sink> Recorder<S> {
+    /// Runs `op` on the sink unless it is closed or has failed before.
+    fn record(&mut self, op: impl FnOnce(&mut S, &[Signal]) -> io::Result<()>) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Some(sink) = self.sink.as_mut() {
+            if let Err(error) = op(sink, &self.signals) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    pub(crate) fn status(&self) -> io::Result<()> {
+        match &self.error {
+            Some(error) => Err(io::Error::new(error.kind(), error.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(&mut self, end: Option<u64>) {
+        self.callbacks.clear();
+        self.enabled = false;
+        self.record(|sink, _| sink.close(end));
+        self.sink = None;
+    }
+}
+
+impl<S: Sink> Drop for Recorder<S> {
+    fn drop(&mut self) {
+        self.finish(None);
+    }
+}
+
+fn register_callbacks<S: Sink + 'static>(
+    ctx: &SimContext,
+    recorder: &Weak<RefCell<Recorder<S>>>,
+    signals: &[Signal],
+) -> Vec<CallbackGuard> {
+    signals
+        .iter()
+        .enumerate()
+        .map(|(index, signal)| {
+            let recorder = Weak::clone(recorder);
+            signal
+                .handle
+                .register_value_change_cb_guarded(ctx, signal.value_type(), move |data| {
+                    if let Some(recorder) = recorder.upgrade() {
+                        recorder.borrow_mut().change(data.context(), index, data);
+                    }
+                })
+        })
+        .filter(CallbackGuard::is_active)
+        .collect()
+}
+
+fn now(ctx: &SimContext) -> u64 {
+    current_simulation_time(ctx).to_u64().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_hierarchy, VarKind};
+
+    #[test]
+    fn splits_plain_and_escaped_names() {
+        assert_eq!(split_hierarchy("tb.dut.q"), ["tb", "dut", "q"]);
+        assert_eq!(split_hierarchy("tb.\\a.b .q"), ["tb", "\\a.b", "q"]);
+        assert_eq!(split_hierarchy("top"), ["top"]);
+    }
+
+    #[test]
+    fn maps_type_names_to_var_kinds() {
+        assert_eq!(VarKind::from_type_name("net"), VarKind::Wire);
+        assert_eq!(VarKind::from_type_name("logic"), VarKind::Reg);
+        assert_eq!(VarKind::from_type_name("integer"), VarKind::Integer);
+        assert_eq!(VarKind::from_type_name("real"), VarKind::Real);
+        assert_eq!(VarKind::from_type_name("my_struct_t"), VarKind::Reg);
+    }
+}
@@ -0,0 +1,1187 @@
+//! Fast Signal Trace (FST) writer.
+//!
+//! FST is the compressed waveform format of GTKWave. Changes are buffered per
+//! signal and written in blocks, each holding the values at its start, a
+//! zlib-compressed change stream per signal, an index of where each stream
+//! starts and a table of the block's times. The hierarchy and signal sizes are
+//! written when the writer is closed, and the header is then updated in place,
+//! so the output must be seekable.
+//!
+//! The API mirrors [`vcd`](super::vcd): an [`FstBuilder`] selects signals and
+//! the resulting [`FstWriter`] records changes while dumping is enabled.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpi::wave::fst::FstBuilder;
+//! use vpi::{register_cb, CbReason, Handle, SimContext};
+//!
+//! fn start(ctx: &SimContext) {
+//!     let _ = register_cb(ctx, CbReason::StartOfSimulation, |data| {
+//!         let ctx = data.context();
+//!         let top = Handle::handle_by_name(ctx, "tb");
+//!         let Ok(fst) = FstBuilder::new().scope(&top, 0).create(ctx, "dump.fst") else {
+//!             return;
+//!         };
+//!         let _ = fst.dump_on(ctx);
+//!         let _ = register_cb(ctx, CbReason::EndOfSimulation, move |data| {
+//!             let _ = fst.close(data.context());
+//!         });
+//!     });
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+
+use super::{bit_char, Recorder, Sample, Selection, Signal, Sink, VarKind};
+use crate::{simulation_time_precision, Handle, LogicVal, SimContext};
+
+/// Default amount of buffered change data that starts a new block.
+const DEFAULT_BLOCK_SIZE: usize = 8 << 20;
+
+/// Limit on the times in a block, keeping time deltas within 32-bit fields.
+const MAX_BLOCK_TIMES: usize = 1 << 24;
+
+/// Change streams shorter than this are stored uncompressed.
+const MIN_COMPRESSED_LEN: usize = 32;
+
+const BLOCK_HEADER: u8 = 0;
+const BLOCK_VALUE_CHANGES: u8 = 1;
+const BLOCK_BLACKOUT: u8 = 2;
+const BLOCK_GEOMETRY: u8 = 3;
+const BLOCK_HIERARCHY: u8 = 4;
+
+/// Length of the header block after its type byte.
+const HEADER_LEN: u64 = 329;
+const SCOPE_START: u8 = 254;
+const SCOPE_END: u8 = 255;
+const SCOPE_MODULE: u8 = 0;
+const PACK_ZLIB: u8 = b'Z';
+
+/// Non-binary scalar values, in the order of their FST encoding.
+const SCALAR_STATES: &[u8] = b"xzhuwl-?";
+
+/// Selects signals and sets up an FST file.
+pub struct FstBuilder {
+    selection: Selection,
+    block_size: usize,
+}
+
+impl Default for FstBuilder {
+    fn default() -> Self {
+        Self {
+            selection: Selection::default(),
+            block_size: DEFAULT_BLOCK_SIZE,
+        }
+    }
+}
+
+impl FstBuilder {
+    /// Creates a builder with no signals selected.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects the nets and variables of `scope` and its sub-scopes.
+    ///
+    /// As with `$dumpvars`, a `depth` of `0` includes all levels below
+    /// `scope` and `1` only the scope itself. A null scope selects all
+    /// top-level modules.
+    #[must_use]
+    pub fn scope(mut self, scope: &Handle, depth: usize) -> Self {
+        self.selection.scopes.push((scope.clone(), depth));
+        self
+    }
+
+    /// Selects a single signal.
+    #[must_use]
+    pub fn signal(mut self, signal: &Handle) -> Self {
+        self.selection.signals.push(signal.clone());
+        self
+    }
+
+    /// Selects several signals.
+    #[must_use]
+    pub fn signals<'a>(mut self, signals: impl IntoIterator<Item = &'a Handle>) -> Self {
+        self.selection.signals.extend(signals.into_iter().cloned());
+        self
+    }
+
+    /// Sets how many bytes of changes are buffered before a block is
+    /// written, 8 MiB by default.
+    ///
+    /// Larger blocks compress better but use more memory. Blocks end at time
+    /// steps, so a block can exceed the size by the changes of one step.
+    #[must_use]
+    pub fn block_size(mut self, bytes: usize) -> Self {
+        self.block_size = bytes;
+        self
+    }
+
+    /// Creates the file at `path` and returns the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created or written.
+    pub fn create(
+        self,
+        ctx: &SimContext,
+        path: impl AsRef<Path>,
+    ) -> io::Result<FstWriter<BufWriter<File>>> {
+        self.build(ctx, BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes a placeholder header to `out` and returns the writer.
+    ///
+    /// The timescale is the simulation precision, the finest precision of
+    /// all modules, so no value change is rounded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails.
+    pub fn build<W: Write + Seek + 'static>(
+        self,
+        ctx: &SimContext,
+        mut out: W,
+    ) -> io::Result<FstWriter<W>> {
+        let precision = simulation_time_precision(ctx);
+        let signals = self.selection.collect(ctx);
+        let (hierarchy, scope_count) = hierarchy(&signals);
+        let header_pos = out.stream_position()?;
+        let mut sink = FstSink {
+            out,
+            header_pos,
+            timescale: i8::try_from(precision).unwrap_or(i8::MIN),
+            hierarchy,
+            scope_count,
+            lengths: signals.iter().map(frame_len).collect(),
+            values: signals.iter().map(initial_value).collect(),
+            block: None,
+            block_size: self.block_size,
+            blocks: 0,
+            start_time: None,
+            end_time: 0,
+            blackouts: Vec::new(),
+        };
+        sink.write_header()?;
+        Ok(FstWriter {
+            recorder: Recorder::new(sink, signals),
+        })
+    }
+}
+
+/// Streams value changes of the selected signals to an FST file.
+///
+/// Clones share the same file, so one clone can be moved into a trigger
+/// callback and another into a `cbEndOfSimulation` callback that closes it.
+/// The file is only complete once the writer is closed, which also happens
+/// when the last clone is dropped.
+pub struct FstWriter<W: Write + Seek + 'static> {
+    recorder: Rc<RefCell<Recorder<FstSink<W>>>>,
+}
+
+impl<W: Write + Seek + 'static> Clone for FstWriter<W> {
+    fn clone(&self) -> Self {
+        Self {
+            recorder: Rc::clone(&self.recorder),
+        }
+    }
+}
+
+impl<W: Write + Seek + 'static> FstWriter<W> {
+    /// Starts or resumes dumping at the current simulation time.
+    ///
+    /// Records the current values of all signals; the first call also
+    /// registers the value-change callbacks. Does nothing if dumping is
+    /// already enabled or the writer is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing.
+    pub fn dump_on(&self, ctx: &SimContext) -> io::Result<()> {
+        Recorder::dump_on(ctx, &self.recorder)
+    }
+
+    /// Suspends dumping, recording all values unknown and a blackout period
+    /// until dumping resumes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing.
+    pub fn dump_off(&self, ctx: &SimContext) -> io::Result<()> {
+        self.recorder.borrow_mut().dump_off(ctx)
+    }
+
+    /// Returns `true` while changes are being recorded.
+    #[must_use]
+    pub fn is_dumping(&self) -> bool {
+        self.recorder.borrow().is_dumping()
+    }
+
+    /// Flushes the blocks written so far to the output.
+    ///
+    /// Changes of the current block stay buffered until it is full or the
+    /// writer is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing or flushing.
+    pub fn flush(&self) -> io::Result<()> {
+        self.recorder.borrow_mut().flush()
+    }
+
+    /// Stops dumping, removes the callbacks, writes the remaining changes,
+    /// the hierarchy and the final header, and closes the file.
+    ///
+    /// Later calls on any clone do nothing. Must not be called from one of
+    /// this writer's own value-change callbacks.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing or flushing.
+    pub fn close(&self, ctx: &SimContext) -> io::Result<()> {
+        self.recorder.borrow_mut().close(ctx)
+    }
+}
+
+struct FstSink<W: Write + Seek> {
+    out: W,
+    /// Stream position of the header block, rewritten on close.
+    header_pos: u64,
+    timescale: i8,
+    /// Uncompressed hierarchy section.
+    hierarchy: Vec<u8>,
+    scope_count: u64,
+    /// Frame length of each signal: bits, or 0 for reals.
+    lengths: Vec<u32>,
+    /// Latest value of each signal in frame format.
+    values: Vec<Vec<u8>>,
+    block: Option<Block>,
+    block_size: usize,
+    blocks: u64,
+    start_time: Option<u64>,
+    end_time: u64,
+    /// Times at which dumping was switched on (`true`) or off.
+    blackouts: Vec<(u64, bool)>,
+}
+
+/// Changes buffered for the next value-change block.
+struct Block {
+    start: u64,
+    /// Values of all signals at `start`.
+    frame: Vec<u8>,
+    times: Vec<u64>,
+    /// Encoded changes of each signal.
+    changes: Vec<Vec<u8>>,
+    /// Index into `times` of each signal's latest change.
+    last_index: Vec<usize>,
+    size: usize,
+}
+
+impl<W: Write + Seek> FstSink<W> {
+    fn write_header(&mut self) -> io::Result<()> {
+        let var_count = self.lengths.len() as u64;
+        let mut header = Vec::with_capacity(HEADER_LEN as usize + 1);
+        header.push(BLOCK_HEADER);
+        put_u64(&mut header, HEADER_LEN);
+        put_u64(&mut header, self.start_time.unwrap_or(0));
+        put_u64(&mut header, self.end_time);
+        // Endianness check: readers compare this against e.
+        header.extend_from_slice(&std::f64::consts::E.to_le_bytes());
+        put_u64(&mut header, self.block_size as u64);
+        put_u64(&mut header, self.scope_count);
+        put_u64(&mut header, var_count);
+        put_u64(&mut header, var_count);
+        put_u64(&mut header, self.blocks);
+        header.push(self.timescale as u8);
+        put_padded(
+            &mut header,
+            &format!("rust-vpi {}", env!("CARGO_PKG_VERSION")),
+            128,
+        );
+        put_padded(&mut header, "", 119);
+        // File type Verilog.
+        header.push(0);
+        // Time zero.
+        put_u64(&mut header, 0);
+        self.out.write_all(&header)
+    }
+
+    /// Records a change, starting a new block when the current one is full
+    /// and `time` starts a new time step.
+    fn record(&mut self, index: usize, time: u64, value: Vec<u8>) -> io::Result<()> {
+        let block_full = self.block.as_ref().is_some_and(|block| {
+            block.times.last() != Some(&time)
+                && (block.size >= self.block_size || block.times.len() >= MAX_BLOCK_TIMES)
+        });
+        if block_full {
+            self.write_block()?;
+        }
+        let block = self.block.get_or_insert_with(|| Block {
+            start: time,
+            frame: self.values.concat(),
+            times: Vec::new(),
+            changes: vec![Vec::new(); self.values.len()],
+            last_index: vec![0; self.values.len()],
+            size: 0,
+        });
+        if block.times.last() != Some(&time) {
+            block.times.push(time);
+        }
+        let time_index = block.times.len() - 1;
+        let delta = (time_index - block.last_index[index]) as u64;
+        block.last_index[index] = time_index;
+
+        let changes = &mut block.changes[index];
+        let before = changes.len();
+        match self.lengths[index] {
+            0 => {
+                put_varint(changes, (delta << 1) | 1);
+                changes.extend_from_slice(&value);
+            }
+            1 => match value[0] {
+                bit @ (b'0' | b'1') => {
+                    put_varint(changes, (delta << 2) | u64::from(bit - b'0') << 1)
+                }
+                state => {
+                    let code = SCALAR_STATES.iter().position(|&s| s == state).unwrap_or(0);
+                    put_varint(changes, (delta << 4) | (code as u64) << 1 | 1);
+                }
+            },
+            _ if value.iter().all(|bit| matches!(bit, b'0' | b'1')) => {
+                put_varint(changes, delta << 1);
+                changes.extend(value.chunks(8).map(|bits| {
+                    bits.iter()
+                        .enumerate()
+                        .fold(0u8, |byte, (i, bit)| byte | (bit - b'0') << (7 - i))
+                }));
+            }
+            _ => {
+                put_varint(changes, (delta << 1) | 1);
+                changes.extend_from_slice(&value);
+            }
+        }
+        block.size += changes.len() - before;
+        self.values[index] = value;
+        self.start_time.get_or_insert(time);
+        self.end_time = self.end_time.max(time);
+        Ok(())
+    }
+
+    /// Records the values of all signals, skipping those without a value.
+    fn record_all(&mut self, time: u64, values: &[Option<Sample>]) -> io::Result<()> {
+        for (index, value) in values.iter().enumerate() {
+            if let Some(value) = value {
+                let value = frame_value(self.lengths[index], value);
+                self.record(index, time, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered changes as a value-change block.
+    fn write_block(&mut self) -> io::Result<()> {
+        let Some(block) = self.block.take() else {
+            return Ok(());
+        };
+        let max_handle = self.lengths.len() as u64;
+        let mut section = Vec::with_capacity(block.size + block.frame.len() + 64);
+        // Section length, patched below.
+        put_u64(&mut section, 0);
+        put_u64(&mut section, block.start);
+        put_u64(
+            &mut section,
+            block.times.last().copied().unwrap_or(block.start),
+        );
+        put_u64(&mut section, (block.size + block.frame.len()) as u64);
+
+        let frame = compress(&block.frame)?;
+        put_varint(&mut section, block.frame.len() as u64);
+        put_varint(&mut section, frame.len() as u64);
+        put_varint(&mut section, max_handle);
+        section.extend_from_slice(&frame);
+
+        put_varint(&mut section, max_handle);
+        let changes_start = section.len();
+        section.push(PACK_ZLIB);
+        let mut offsets = Vec::with_capacity(block.changes.len());
+        for changes in &block.changes {
+            if changes.is_empty() {
+                offsets.push(None);
+                continue;
+            }
+            offsets.push(Some((section.len() - changes_start) as u64));
+            let packed = if changes.len() >= MIN_COMPRESSED_LEN {
+                compress(changes)?
+            } else {
+                Vec::new()
+            };
+            if !packed.is_empty() && packed.len() < changes.len() {
+                put_varint(&mut section, changes.len() as u64);
+                section.extend_from_slice(&packed);
+            } else {
+                put_varint(&mut section, 0);
+                section.extend_from_slice(changes);
+            }
+        }
+
+        // Index of the change streams: offset deltas for signals with
+        // changes, and run lengths of signals without.
+        let index_start = section.len();
+        let mut previous = 0;
+        let mut unchanged = 0u64;
+        for offset in offsets {
+            match offset {
+                Some(offset) => {
+                    if unchanged > 0 {
+                        put_varint(&mut section, unchanged << 1);
+                        unchanged = 0;
+                    }
+                    put_varint(&mut section, ((offset - previous) << 1) | 1);
+                    previous = offset;
+                }
+                None => unchanged += 1,
+            }
+        }
+        if unchanged > 0 {
+            put_varint(&mut section, unchanged << 1);
+        }
+        let index_len = (section.len() - index_start) as u64;
+        put_u64(&mut section, index_len);
+
+        let mut times = Vec::with_capacity(block.times.len());
+        let mut previous = 0;
+        for &time in &block.times {
+            put_varint(&mut times, time - previous);
+            previous = time;
+        }
+        let packed = compress(&times)?;
+        section.extend_from_slice(&packed);
+        put_u64(&mut section, times.len() as u64);
+        put_u64(&mut section, packed.len() as u64);
+        put_u64(&mut section, block.times.len() as u64);
+
+        let len = section.len() as u64;
+        section[..8].copy_from_slice(&len.to_be_bytes());
+        self.out.write_all(&[BLOCK_VALUE_CHANGES])?;
+        self.out.write_all(&section)?;
+        self.blocks += 1;
+        Ok(())
+    }
+
+    fn write_blackouts(&mut self) -> io::Result<()> {
+        if self.blackouts.is_empty() {
+            return Ok(());
+        }
+        let mut section = Vec::new();
+        put_u64(&mut section, 0);
+        put_varint(&mut section, self.blackouts.len() as u64);
+        let mut previous = 0;
+        for &(time, on) in &self.blackouts {
+            section.push(u8::from(on));
+            put_varint(&mut section, time - previous);
+            previous = time;
+        }
+        let len = section.len() as u64;
+        section[..8].copy_from_slice(&len.to_be_bytes());
+        self.out.write_all(&[BLOCK_BLACKOUT])?;
+        self.out.write_all(&section)
+    }
+
+    fn write_geometry(&mut self) -> io::Result<()> {
+        let mut geometry = Vec::new();
+        for &len in &self.lengths {
+            put_varint(&mut geometry, u64::from(len));
+        }
+        let packed = compress(&geometry)?;
+        self.out.write_all(&[BLOCK_GEOMETRY])?;
+        self.out
+            .write_all(&(packed.len() as u64 + 24).to_be_bytes())?;
+        self.out.write_all(&(geometry.len() as u64).to_be_bytes())?;
+        self.out
+            .write_all(&(self.lengths.len() as u64).to_be_bytes())?;
+        self.out.write_all(&packed)
+    }
+
+    fn write_hierarchy(&mut self) -> io::Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.hierarchy)?;
+        let packed = encoder.finish()?;
+        self.out.write_all(&[BLOCK_HIERARCHY])?;
+        self.out
+            .write_all(&(packed.len() as u64 + 16).to_be_bytes())?;
+        self.out
+            .write_all(&(self.hierarchy.len() as u64).to_be_bytes())?;
+        self.out.write_all(&packed)
+    }
+}
+
+impl<W: Write + Seek> Sink for FstSink<W> {
+    fn dump_on(
+        &mut self,
+        _signals: &[Signal],
+        time: u64,
+        values: &[Option<Sample>],
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            // Start the first block with the initial values in its frame.
+            for (index, value) in values.iter().enumerate() {
+                if let Some(value) = value {
+                    self.values[index] = frame_value(self.lengths[index], value);
+                }
+            }
+        } else {
+            self.blackouts.push((time, true));
+        }
+        self.record_all(time, values)
+    }
+
+    fn dump_off(&mut self, signals: &[Signal], time: u64) -> io::Result<()> {
+        self.blackouts.push((time, false));
+        let unknown: Vec<_> = signals
+            .iter()
+            .map(|signal| (signal.kind != VarKind::Real).then(|| Sample::Bits(vec![LogicVal::X])))
+            .collect();
+        self.record_all(time, &unknown)
+    }
+
+    fn change(
+        &mut self,
+        _signal: &Signal,
+        index: usize,
+        time: u64,
+        value: &Sample,
+    ) -> io::Result<()> {
+        let value = frame_value(self.lengths[index], value);
+        self.record(index, time, value)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    fn close(&mut self, end: Option<u64>) -> io::Result<()> {
+        if let Some(end) = end {
+            self.end_time = self.end_time.max(end);
+        }
+        self.write_block()?;
+        self.write_blackouts()?;
+        self.write_geometry()?;
+        self.write_hierarchy()?;
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(self.header_pos))?;
+        self.write_header()?;
+        self.out.seek(SeekFrom::Start(end))?;
+        self.out.flush()
+    }
+}
+
+/// Encodes the scopes and variables of `signals`, returning the section and
+/// the number of scopes.
+fn hierarchy(signals: &[Signal]) -> (Vec<u8>, u64) {
+    let mut out = Vec::new();
+    let mut scope_count = 0;
+    let mut open: &[_] = &[];
+    for signal in signals {
+        let common = open
+            .iter()
+            .zip(&signal.scope)
+            .take_while(|(open, scope)| open == scope)
+            .count();
+        out.resize(out.len() + open.len() - common, SCOPE_END);
+        for scope in &signal.scope[common..] {
+            out.extend_from_slice(&[SCOPE_START, SCOPE_MODULE]);
+            put_str(&mut out, &scope.name);
+            put_str(&mut out, &scope.def_name);
+            scope_count += 1;
+        }
+        open = &signal.scope;
+        out.push(var_type(signal.kind));
+        // Implicit direction.
+        out.push(0);
+        put_str(&mut out, &signal.name);
+        put_varint(&mut out, u64::from(signal.size));
+        // Not an alias of an earlier variable.
+        put_varint(&mut out, 0);
+    }
+    out.resize(out.len() + open.len(), SCOPE_END);
+    (out, scope_count)
+}
+
+/// Returns the FST variable type code.
+fn var_type(kind: VarKind) -> u8 {
+    match kind {
+        VarKind::Integer => 1,
+        VarKind::Real => 3,
+        VarKind::Reg => 5,
+        VarKind::Supply0 => 6,
+        VarKind::Supply1 => 7,
+        VarKind::Time => 8,
+        VarKind::Tri => 9,
+        VarKind::Wand => 15,
+        VarKind::Wire => 16,
+        VarKind::Wor => 17,
+    }
+}
+
+fn frame_len(signal: &Signal) -> u32 {
+    match signal.kind {
+        VarKind::Real => 0,
+        _ => signal.size,
+    }
+}
+
+fn initial_value(signal: &Signal) -> Vec<u8> {
+    match frame_len(signal) {
+        0 => 0f64.to_le_bytes().to_vec(),
+        len => vec![b'x'; len as usize],
+    }
+}
+
+/// Converts a sample to frame format: `len` ASCII bits, or eight bytes for
+/// reals.
+///
+/// Short values are extended as in VCD: with `x` or `z` if that is the
+/// leftmost bit and with `0` otherwise.
+fn frame_value(len: u32, value: &Sample) -> Vec<u8> {
+    let bits = match (len, value) {
+        (0, Sample::Real(real)) => return real.to_le_bytes().to_vec(),
+        (0, Sample::Bits(_)) => return 0f64.to_le_bytes().to_vec(),
+        (_, Sample::Real(_)) => return vec![b'x'; len as usize],
+        (_, Sample::Bits(bits)) => bits,
+    };
+    let len = len as usize;
+    let mut out: Vec<u8> = bits.iter().map(|&bit| bit_char(bit) as u8).collect();
+    if out.len() > len {
+        out.drain(..out.len() - len);
+    } else if out.len() < len {
+        let fill = match out.first() {
+            Some(&bit @ (b'x' | b'z')) => bit,
+            _ => b'0',
+        };
+        out.splice(..0, std::iter::repeat_n(fill, len - out.len()));
+    }
+    out
+}
+
+/// Compresses `data` with zlib, returning it unchanged if that does not make
+/// it smaller, as readers expect for equal lengths.
+fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let packed = encoder.finish()?;
+    Ok(if packed.len() < data.len() {
+        packed
+    } else {
+        data.to_vec()
+    })
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Appends `value` as an unsigned LEB128 varint.
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_str(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(value.as_bytes());
+    out.push(0);
+}
+
+/// Appends `value` truncated or zero-padded to `len` bytes.
+fn put_padded(out: &mut Vec<u8>, value: &str, len: usize) {
+    let bytes = &value.as_bytes()[..value.len().min(len)];
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + len - bytes.len(), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    use super::{frame_value, Sample, SCALAR_STATES};
+    use crate::LogicVal;
+
+    /// Contents of an FST file, decoded by [`read`].
+    #[derive(Debug, Default)]
+    struct Trace {
+        timescale: i8,
+        start: u64,
+        end: u64,
+        blocks: u64,
+        /// Full name and length of each variable, by handle.
+        vars: Vec<(String, u32)>,
+        /// Changes as time, handle and value, ordered by time and handle.
+        changes: Vec<(u64, usize, String)>,
+        blackouts: Vec<(u64, bool)>,
+    }
+
+    struct Input<'a>(&'a [u8]);
+
+    impl<'a> Input<'a> {
+        fn bytes(&mut self, len: usize) -> &'a [u8] {
+            let (head, tail) = self.0.split_at(len);
+            self.0 = tail;
+            head
+        }
+
+        fn u8(&mut self) -> u8 {
+            self.bytes(1)[0]
+        }
+
+        fn u64(&mut self) -> u64 {
+            u64::from_be_bytes(self.bytes(8).try_into().unwrap())
+        }
+
+        fn varint(&mut self) -> u64 {
+            let mut value = 0;
+            for shift in (0..).step_by(7) {
+                let byte = self.u8();
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            value
+        }
+
+        fn str(&mut self) -> String {
+            let len = self.0.iter().position(|&byte| byte == 0).unwrap();
+            let value = String::from_utf8(self.bytes(len).to_vec()).unwrap();
+            self.u8();
+            value
+        }
+    }
+
+    /// Returns `data` inflated to `len` bytes, or as is if already that long.
+    fn inflate(data: &[u8], len: usize) -> Vec<u8> {
+        if data.len() == len {
+            return data.to_vec();
+        }
+        let mut out = Vec::new();
+        ZlibDecoder::new(data).read_to_end(&mut out).unwrap();
+        assert_eq!(out.len(), len);
+        out
+    }
+
+    /// A minimal reader for the subset of FST produced by the writer.
+    fn read(bytes: &[u8]) -> Trace {
+        let mut input = Input(bytes);
+        let mut trace = Trace::default();
+        let mut lengths = Vec::new();
+        let mut blocks = Vec::new();
+        while !input.0.is_empty() {
+            let kind = input.u8();
+            let len = Input(input.0).u64() as usize;
+            let mut section = Input(&input.bytes(len)[8..]);
+            match kind {
+                0 => {
+                    trace.start = section.u64();
+                    trace.end = section.u64();
+                    let e = f64::from_le_bytes(section.bytes(8).try_into().unwrap());
+                    assert_eq!(e, std::f64::consts::E);
+                    section.bytes(8 * 4);
+                    trace.blocks = section.u64();
+                    trace.timescale = section.u8() as i8;
+                    assert!(section.str().starts_with("rust-vpi "));
+                }
+                1 => blocks.push(section.0),
+                2 => {
+                    let mut time = 0;
+                    for _ in 0..section.varint() {
+                        let on = section.u8() != 0;
+                        time += section.varint();
+                        trace.blackouts.push((time, on));
+                    }
+                }
+                3 => {
+                    let len = section.u64() as usize;
+                    let count = section.u64();
+                    let geometry = inflate(section.0, len);
+                    let mut geometry = Input(&geometry);
+                    lengths = (0..count).map(|_| geometry.varint() as u32).collect();
+                }
+                4 => {
+                    section.u64();
+                    let mut hierarchy = Vec::new();
+                    GzDecoder::new(section.0)
+                        .read_to_end(&mut hierarchy)
+                        .unwrap();
+                    let mut hierarchy = Input(&hierarchy);
+                    let mut scopes = Vec::new();
+                    while !hierarchy.0.is_empty() {
+                        match hierarchy.u8() {
+                            254 => {
+                                hierarchy.u8();
+                                scopes.push(hierarchy.str());
+                                hierarchy.str();
+                            }
+                            255 => {
+                                scopes.pop();
+                            }
+                            _ => {
+                                hierarchy.u8();
+                                let name = hierarchy.str();
+                                let len = hierarchy.varint() as u32;
+                                assert_eq!(hierarchy.varint(), 0);
+                                trace
+                                    .vars
+                                    .push((format!("{}.{name}", scopes.join(".")), len));
+                            }
+                        }
+                    }
+                }
+                kind => panic!("unexpected block type {kind}"),
+            }
+        }
+        assert_eq!(blocks.len() as u64, trace.blocks);
+        for block in blocks {
+            read_block(block, &lengths, &mut trace.changes);
+        }
+        trace
+            .changes
+            .sort_by_key(|&(time, handle, _)| (time, handle));
+        trace
+    }
+
+    fn read_block(block: &[u8], lengths: &[u32], changes: &mut Vec<(u64, usize, String)>) {
+        let mut input = Input(block);
+        let start = input.u64();
+        input.u64();
+        input.u64();
+        let frame_len = input.varint() as usize;
+        let packed_len = input.varint() as usize;
+        assert_eq!(input.varint(), lengths.len() as u64);
+        inflate(input.bytes(packed_len), frame_len);
+        assert_eq!(input.varint(), lengths.len() as u64);
+        let data = input.0;
+        assert_eq!(data[0], b'Z');
+
+        // The time table and its sizes end the block, preceded by the index
+        // and its length.
+        let mut tail = Input(&data[data.len() - 24..]);
+        let (times_len, packed_len, count) = (tail.u64(), tail.u64(), tail.u64());
+        let times_start = data.len() - 24 - packed_len as usize;
+        let times = inflate(&data[times_start..data.len() - 24], times_len as usize);
+        let mut times = Input(&times);
+        let mut time = 0;
+        let times: Vec<u64> = (0..count)
+            .map(|_| {
+                time += times.varint();
+                time
+            })
+            .collect();
+        assert_eq!(times[0], start);
+        let index_len = Input(&data[times_start - 8..]).u64() as usize;
+        let index_start = times_start - 8 - index_len;
+
+        let mut index = Input(&data[index_start..times_start - 8]);
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        while !index.0.is_empty() {
+            let value = index.varint();
+            if value & 1 == 1 {
+                offset += value >> 1;
+                offsets.push(Some(offset as usize));
+            } else {
+                offsets.extend(std::iter::repeat_n(None, (value >> 1) as usize));
+            }
+        }
+        assert_eq!(offsets.len(), lengths.len());
+
+        for (handle, offset) in offsets.iter().enumerate() {
+            let Some(offset) = *offset else { continue };
+            let end = offsets[handle + 1..]
+                .iter()
+                .find_map(|offset| *offset)
+                .unwrap_or(index_start);
+            let mut stream = Input(&data[offset..end]);
+            let stream = match stream.varint() as usize {
+                0 => stream.0.to_vec(),
+                len => inflate(stream.0, len),
+            };
+            let mut stream = Input(&stream);
+            let len = lengths[handle] as usize;
+            let mut time_index = 0;
+            while !stream.0.is_empty() {
+                let code = stream.varint();
+                let (delta, value) = match len {
+                    0 => {
+                        let real = f64::from_le_bytes(stream.bytes(8).try_into().unwrap());
+                        (code >> 1, real.to_string())
+                    }
+                    1 if code & 1 == 0 => (code >> 2, ((code >> 1) & 1).to_string()),
+                    1 => (
+                        code >> 4,
+                        char::from(SCALAR_STATES[((code >> 1) & 7) as usize]).to_string(),
+                    ),
+                    _ if code & 1 == 0 => {
+                        let packed = stream.bytes(len.div_ceil(8));
+                        let bits = (0..len)
+                            .map(|i| {
+                                if packed[i / 8] & (0x80 >> (i % 8)) == 0 {
+                                    '0'
+                                } else {
+                                    '1'
+                                }
+                            })
+                            .collect();
+                        (code >> 1, bits)
+                    }
+                    _ => (
+                        code >> 1,
+                        String::from_utf8(stream.bytes(len).to_vec()).unwrap(),
+                    ),
+                };
+                time_index += delta as usize;
+                changes.push((times[time_index], handle, value));
+            }
+        }
+    }
+
+    #[test]
+    fn extends_short_values_like_vcd() {
+        let bits = |bits: &[LogicVal]| Sample::Bits(bits.to_vec());
+        assert_eq!(frame_value(4, &bits(&[LogicVal::One])), b"0001");
+        assert_eq!(frame_value(3, &bits(&[LogicVal::X])), b"xxx");
+        assert_eq!(frame_value(2, &bits(&[LogicVal::Z, LogicVal::H])), b"z1");
+        assert_eq!(
+            frame_value(2, &bits(&[LogicVal::One, LogicVal::Zero, LogicVal::L])),
+            b"00"
+        );
+        assert_eq!(frame_value(0, &Sample::Real(0.5)), 0.5f64.to_le_bytes());
+    }
+
+    #[cfg(mock_backend)]
+    mod simulated {
+        use std::cell::RefCell;
+        use std::io::{self, Cursor, Seek, SeekFrom, Write};
+        use std::rc::Rc;
+
+        use fst_reader::{FstFilter, FstHierarchyEntry, FstReader, FstSignalValue};
+
+        use super::read;
+        use crate::mock::MockSimulator;
+        use crate::wave::fst::FstBuilder;
+        use crate::{Handle, LogicVec, Value};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Rc<RefCell<Cursor<Vec<u8>>>>);
+
+        impl Write for Buffer {
+            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().write(data)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Seek for Buffer {
+            fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+                self.0.borrow_mut().seek(pos)
+            }
+        }
+
+        impl Buffer {
+            fn bytes(&self) -> Vec<u8> {
+                self.0.borrow().get_ref().clone()
+            }
+        }
+
+        type Change = (u64, usize, String);
+
+        fn change(time: u64, handle: usize, value: &str) -> Change {
+            (time, handle, value.to_string())
+        }
+
+        #[test]
+        fn round_trips_hierarchy_changes_and_blackouts() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            sim.set_timescale(&top, -9, -9);
+            let clk = sim.add_reg(&top, "clk", 1);
+            let count = sim.add_reg(&top, "count", 4);
+            let dut = sim.add_module(&top, "dut", "counter");
+            let gain = sim.add_real(&dut, "gain");
+            sim.start();
+
+            let buffer = Buffer::default();
+            let fst = FstBuilder::new()
+                .scope(&top, 0)
+                .build(ctx, buffer.clone())
+                .unwrap();
+            assert!(sim.schedule_value(&clk, &Value::Int(1), 5));
+            assert!(sim.schedule_value(&count, &Value::Int(3), 5));
+            assert!(sim.schedule_value(&gain, &Value::Real(0.5), 7));
+            assert!(sim.schedule_value(&clk, &Value::Int(0), 10));
+
+            sim.run_until(4);
+            fst.dump_on(ctx).unwrap();
+            sim.run_until(8);
+            fst.dump_off(ctx).unwrap();
+            sim.run_until(9);
+            fst.dump_on(ctx).unwrap();
+            sim.run_until(12);
+            fst.close(ctx).unwrap();
+            assert_eq!(sim.active_callbacks(), 0);
+
+            let trace = read(&buffer.bytes());
+            assert_eq!(trace.timescale, -9);
+            assert_eq!((trace.start, trace.end, trace.blocks), (4, 12, 1));
+            assert_eq!(
+                trace.vars,
+                [
+                    ("tb.clk".to_string(), 1),
+                    ("tb.count".to_string(), 4),
+                    ("tb.dut.gain".to_string(), 64),
+                ]
+            );
+            assert_eq!(trace.blackouts, [(8, false), (9, true)]);
+            assert_eq!(
+                trace.changes,
+                [
+                    change(4, 0, "x"),
+                    change(4, 1, "xxxx"),
+                    change(4, 2, "0"),
+                    change(5, 0, "1"),
+                    change(5, 1, "0011"),
+                    change(7, 2, "0.5"),
+                    change(8, 0, "x"),
+                    change(8, 1, "xxxx"),
+                    change(9, 0, "1"),
+                    change(9, 1, "0011"),
+                    change(9, 2, "0.5"),
+                    change(10, 0, "0"),
+                ]
+            );
+        }
+
+        /// Reads a trace with the independent `fst-reader` crate, returning
+        /// the variables and the changes as in [`read`].
+        fn read_with_fst_reader(bytes: Vec<u8>) -> (i8, Vec<(String, u32)>, Vec<Change>) {
+            let mut reader = FstReader::open(Cursor::new(bytes)).unwrap();
+            let timescale = reader.get_header().timescale_exponent;
+            let mut scopes = Vec::new();
+            let mut vars = Vec::new();
+            reader
+                .read_hierarchy(|entry| match entry {
+                    FstHierarchyEntry::Scope { name, .. } => scopes.push(name),
+                    FstHierarchyEntry::UpScope => {
+                        scopes.pop();
+                    }
+                    FstHierarchyEntry::Var { name, length, .. } => {
+                        vars.push((format!("{}.{name}", scopes.join(".")), length));
+                    }
+                    _ => {}
+                })
+                .unwrap();
+            let mut changes = Vec::new();
+            reader
+                .read_signals(&FstFilter::all(), |time, handle, value| {
+                    let value = match value {
+                        FstSignalValue::String(bits) => String::from_utf8(bits.to_vec()).unwrap(),
+                        FstSignalValue::Real(real) => real.to_string(),
+                    };
+                    changes.push((time, handle.get_index(), value));
+                })
+                .unwrap();
+            changes.sort_by_key(|&(time, handle, _)| (time, handle));
+            (timescale, vars, changes)
+        }
+
+        #[test]
+        fn traces_are_readable_by_fst_reader() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            sim.set_timescale(&top, -9, -12);
+            let clk = sim.add_reg(&top, "clk", 1);
+            let bus = sim.add_reg(&top, "bus", 12);
+            let dut = sim.add_module(&top, "dut", "counter");
+            let gain = sim.add_real(&dut, "gain");
+            sim.start();
+            for step in 1..=60u64 {
+                assert!(sim.schedule_value(&clk, &Value::Int((step % 2) as i32), step * 10));
+                let value = LogicVec::from_int(step as i64 * 37, 12);
+                assert!(sim.schedule_value(&bus, &Value::Vector(value), step * 10));
+            }
+            assert!(sim.schedule_value(&gain, &Value::Real(-1.25), 15));
+
+            let buffer = Buffer::default();
+            let fst = FstBuilder::new()
+                .scope(&top, 0)
+                .block_size(256)
+                .build(ctx, buffer.clone())
+                .unwrap();
+            fst.dump_on(ctx).unwrap();
+            sim.run();
+            fst.close(ctx).unwrap();
+
+            let ours = read(&buffer.bytes());
+            assert!(ours.blocks > 1);
+            let (timescale, vars, changes) = read_with_fst_reader(buffer.bytes());
+            assert_eq!(timescale, -12);
+            assert_eq!(vars, ours.vars);
+            assert_eq!(changes, ours.changes);
+            assert_eq!(changes.len(), 1 + 1 + 1 + 60 + 60 + 1);
+            let handle = |name: &str| vars.iter().position(|(var, _)| var == name).unwrap();
+            assert!(changes.contains(&change(15, handle("tb.dut.gain"), "-1.25")));
+            let last = format!("{:012b}", 60 * 37);
+            assert!(changes.contains(&change(600, handle("tb.bus"), &last)));
+        }
+
+        #[test]
+        fn splits_long_dumps_into_blocks() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            let bus = sim.add_reg(&top, "bus", 40);
+            sim.start();
+            for step in 1..=200u64 {
+                let value = LogicVec::from_int(step as i64 * 0x0101, 40);
+                assert!(sim.schedule_value(&bus, &Value::Vector(value), step));
+            }
+
+            let buffer = Buffer::default();
+            let fst = FstBuilder::new()
+                .signal(&bus)
+                .block_size(256)
+                .build(ctx, buffer.clone())
+                .unwrap();
+            fst.dump_on(ctx).unwrap();
+            sim.run();
+            drop(fst);
+
+            let trace = read(&buffer.bytes());
+            assert!(trace.blocks > 1);
+            assert_eq!(trace.changes.len(), 201);
+            assert_eq!(trace.changes[0], change(0, 0, &"x".repeat(40)));
+            for (step, (time, _, value)) in trace.changes[1..].iter().enumerate() {
+                let step = step as u64 + 1;
+                assert_eq!(*time, step);
+                assert_eq!(*value, format!("{:040b}", step * 0x0101));
+            }
+        }
+    }
+}
@@ -0,0 +1,484 @@
+//! Value Change Dump (VCD) writer.
+//!
+//! A [`VcdBuilder`] selects the signals to dump and writes the VCD header;
+//! the resulting [`VcdWriter`] records changes through value-change callbacks
+//! while dumping is enabled with [`VcdWriter::dump_on`].
+//!
+//! # Example
+//!
+//! Start dumping `tb` and everything below it once `tb.trigger` goes high:
+//!
+//! ```no_run
+//! use vpi::wave::vcd::VcdBuilder;
+//! use vpi::{register_cb, CbReason, Handle, LogicVal, SimContext, Value, ValueType};
+//!
+//! fn start(ctx: &SimContext) {
+//!     let _ = register_cb(ctx, CbReason::StartOfSimulation, |data| {
+//!         let ctx = data.context();
+//!         let top = Handle::handle_by_name(ctx, "tb");
+//!         let Ok(vcd) = VcdBuilder::new().scope(&top, 0).create(ctx, "dump.vcd") else {
+//!             return;
+//!         };
+//!
+//!         let end = vcd.clone();
+//!         let _ = register_cb(ctx, CbReason::EndOfSimulation, move |data| {
+//!             let _ = end.close(data.context());
+//!         });
+//!
+//!         let trigger = Handle::handle_by_name(ctx, "tb.trigger");
+//!         let _ = trigger.register_value_change_cb(ctx, ValueType::Scalar, move |data| {
+//!             if data.value == Some(Value::Scalar(LogicVal::One)) {
+//!                 let _ = vcd.dump_on(data.context());
+//!             }
+//!         });
+//!     });
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use super::{bit_char, Recorder, Sample, Selection, Signal, Sink, VarKind};
+use crate::{simulation_time_precision, Handle, LogicVal, SimContext, Timescale};
+
+/// Selects signals and writes the header of a VCD file.
+#[derive(Default)]
+pub struct VcdBuilder {
+    selection: Selection,
+}
+
+impl VcdBuilder {
+    /// Creates a builder with no signals selected.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects the nets and variables of `scope` and its sub-scopes.
+    ///
+    /// As with `$dumpvars`, a `depth` of `0` includes all levels below
+    /// `scope` and `1` only the scope itself. A null scope selects all
+    /// top-level modules.
+    #[must_use]
+    pub fn scope(mut self, scope: &Handle, depth: usize) -> Self {
+        self.selection.scopes.push((scope.clone(), depth));
+        self
+    }
+
+    /// Selects a single signal.
+    #[must_use]
+    pub fn signal(mut self, signal: &Handle) -> Self {
+        self.selection.signals.push(signal.clone());
+        self
+    }
+
+    /// Selects several signals.
+    #[must_use]
+    pub fn signals<'a>(mut self, signals: impl IntoIterator<Item = &'a Handle>) -> Self {
+        self.selection.signals.extend(signals.into_iter().cloned());
+        self
+    }
+
+    /// Creates the file at `path` and writes the VCD header to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created or written.
+    pub fn create(
+        self,
+        ctx: &SimContext,
+        path: impl AsRef<Path>,
+    ) -> io::Result<VcdWriter<BufWriter<File>>> {
+        self.build(ctx, BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes the VCD header to `out` and returns the writer.
+    ///
+    /// The `$timescale` is the simulation precision, the finest precision of
+    /// all modules, so no value change is rounded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing the header fails.
+    pub fn build<W: Write + 'static>(self, ctx: &SimContext, out: W) -> io::Result<VcdWriter<W>> {
+        let precision = simulation_time_precision(ctx);
+        let signals = self.selection.collect(ctx);
+        let mut sink = VcdSink {
+            out,
+            codes: (0..signals.len()).map(identifier_code).collect(),
+            last_time: None,
+        };
+        sink.write_header(&signals, precision)?;
+        Ok(VcdWriter {
+            recorder: Recorder::new(sink, signals),
+        })
+    }
+}
+
+/// Streams value changes of the selected signals to a VCD file.
+///
+/// Clones share the same file, so one clone can be moved into a trigger
+/// callback and another into a `cbEndOfSimulation` callback that closes it.
+/// The writer is closed when the last clone is dropped.
+pub struct VcdWriter<W: Write + 'static> {
+    recorder: Rc<RefCell<Recorder<VcdSink<W>>>>,
+}
+
+impl<W: Write + 'static> Clone for VcdWriter<W> {
+    fn clone(&self) -> Self {
+        Self {
+            recorder: Rc::clone(&self.recorder),
+        }
+    }
+}
+
+impl<W: Write + 'static> VcdWriter<W> {
+    /// Starts or resumes dumping at the current simulation time.
+    ///
+    /// The first call writes the initial values in a `$dumpvars` section and
+    /// registers the value-change callbacks; later calls write `$dumpon`.
+    /// Does nothing if dumping is already enabled or the writer is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing.
+    pub fn dump_on(&self, ctx: &SimContext) -> io::Result<()> {
+        Recorder::dump_on(ctx, &self.recorder)
+    }
+
+    /// Suspends dumping, writing `$dumpoff` with all values unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing.
+    pub fn dump_off(&self, ctx: &SimContext) -> io::Result<()> {
+        self.recorder.borrow_mut().dump_off(ctx)
+    }
+
+    /// Returns `true` while changes are being recorded.
+    #[must_use]
+    pub fn is_dumping(&self) -> bool {
+        self.recorder.borrow().is_dumping()
+    }
+
+    /// Flushes buffered output.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing or flushing.
+    pub fn flush(&self) -> io::Result<()> {
+        self.recorder.borrow_mut().flush()
+    }
+
+    /// Stops dumping, removes the callbacks and flushes and closes the file.
+    ///
+    /// The final simulation time is written so viewers show the last values
+    /// up to it. Later calls on any clone do nothing. Must not be called from
+    /// one of this writer's own value-change callbacks.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while writing or flushing.
+    pub fn close(&self, ctx: &SimContext) -> io::Result<()> {
+        self.recorder.borrow_mut().close(ctx)
+    }
+}
+
+struct VcdSink<W: Write> {
+    out: W,
+    codes: Vec<String>,
+    last_time: Option<u64>,
+}
+
+impl<W: Write> VcdSink<W> {
+    fn write_header(&mut self, signals: &[Signal], precision: i32) -> io::Result<()> {
+        let timescale = Timescale {
+            unit: precision,
+            precision,
+        };
+        write!(
+            self.out,
+            "$version\n    rust-vpi {}\n$end\n",
+            env!("CARGO_PKG_VERSION")
+        )?;
+        write!(
+            self.out,
+            "$timescale\n    {}\n$end\n",
+            timescale.precision_str()
+        )?;
+        let mut open: &[_] = &[];
+        for (signal, code) in signals.iter().zip(&self.codes) {
+            let common = open
+                .iter()
+                .zip(&signal.scope)
+                .take_while(|(open, scope)| open == scope)
+                .count();
+            for _ in common..open.len() {
+                writeln!(self.out, "$upscope $end")?;
+            }
+            for scope in &signal.scope[common..] {
+                writeln!(self.out, "$scope module {} $end", scope.name)?;
+            }
+            open = &signal.scope;
+            writeln!(
+                self.out,
+                "$var {} {} {code} {} $end",
+                signal.kind.keyword(),
+                signal.size,
+                signal.name
+            )?;
+        }
+        for _ in 0..open.len() {
+            writeln!(self.out, "$upscope $end")?;
+        }
+        writeln!(self.out, "$enddefinitions $end")
+    }
+
+    /// Writes a `#time` line if `time` starts a new dump time.
+    fn timestamp(&mut self, time: u64) -> io::Result<()> {
+        if self.last_time.is_some_and(|last| time <= last) {
+            return Ok(());
+        }
+        self.last_time = Some(time);
+        writeln!(self.out, "#{time}")
+    }
+
+    fn write_value(&mut self, signal: &Signal, index: usize, value: &Sample) -> io::Result<()> {
+        let code = &self.codes[index];
+        match value {
+            Sample::Real(real) => writeln!(self.out, "r{} {code}", real_text(*real)),
+            Sample::Bits(bits) if signal.size == 1 && bits.len() == 1 => {
+                writeln!(self.out, "{}{code}", bit_char(bits[0]))
+            }
+            Sample::Bits(bits) => {
+                let bits: String = bits.iter().copied().map(bit_char).collect();
+                writeln!(self.out, "b{bits} {code}")
+            }
+        }
+    }
+}
+
+impl<W: Write> Sink for VcdSink<W> {
+    fn dump_on(
+        &mut self,
+        signals: &[Signal],
+        time: u64,
+        values: &[Option<Sample>],
+        first: bool,
+    ) -> io::Result<()> {
+        self.timestamp(time)?;
+        writeln!(self.out, "{}", if first { "$dumpvars" } else { "$dumpon" })?;
+        for (index, (signal, value)) in signals.iter().zip(values).enumerate() {
+            if let Some(value) = value {
+                self.write_value(signal, index, value)?;
+            }
+        }
+        writeln!(self.out, "$end")
+    }
+
+    fn dump_off(&mut self, signals: &[Signal], time: u64) -> io::Result<()> {
+        self.timestamp(time)?;
+        writeln!(self.out, "$dumpoff")?;
+        let unknown = Sample::Bits(vec![LogicVal::X]);
+        for (index, signal) in signals.iter().enumerate() {
+            if signal.kind != VarKind::Real {
+                self.write_value(signal, index, &unknown)?;
+            }
+        }
+        writeln!(self.out, "$end")
+    }
+
+    fn change(
+        &mut self,
+        signal: &Signal,
+        index: usize,
+        time: u64,
+        value: &Sample,
+    ) -> io::Result<()> {
+        self.timestamp(time)?;
+        self.write_value(signal, index, value)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    fn close(&mut self, end: Option<u64>) -> io::Result<()> {
+        if let Some(end) = end {
+            self.timestamp(end)?;
+        }
+        self.out.flush()
+    }
+}
+
+/// Formats `real` for a VCD `r` value change.
+///
+/// The VCD grammar only covers finite numbers, so NaN and infinities are
+/// spelled `nan`, `inf` and `-inf`, as accepted by `strtod`-based readers
+/// such as GTKWave, instead of Rust's `NaN`.
+fn real_text(real: f64) -> String {
+    if real.is_nan() {
+        "nan".to_owned()
+    } else if real.is_infinite() {
+        if real > 0.0 { "inf" } else { "-inf" }.to_owned()
+    } else {
+        real.to_string()
+    }
+}
+
+/// Returns the VCD identifier code for the signal at `index`.
+///
+/// Codes use the printable ASCII characters `!` to `~`, shortest first.
+fn identifier_code(index: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - b'!' + 1) as usize;
+    let mut code = String::new();
+    let mut rest = index;
+    loop {
+        code.push(char::from(FIRST + (rest % COUNT) as u8));
+        rest /= COUNT;
+        if rest == 0 {
+            break;
+        }
+        rest -= 1;
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{identifier_code, real_text};
+
+    #[test]
+    fn identifier_codes_are_unique_printable_ascii() {
+        assert_eq!(identifier_code(0), "!");
+        assert_eq!(identifier_code(93), "~");
+        assert_eq!(identifier_code(94), "!!");
+        assert_eq!(identifier_code(95), "\"!");
+        let codes: std::collections::HashSet<_> = (0..20_000).map(identifier_code).collect();
+        assert_eq!(codes.len(), 20_000);
+    }
+
+    #[test]
+    fn non_finite_reals_use_strtod_spellings() {
+        assert_eq!(real_text(0.5), "0.5");
+        assert_eq!(real_text(f64::NAN), "nan");
+        assert_eq!(real_text(f64::INFINITY), "inf");
+        assert_eq!(real_text(f64::NEG_INFINITY), "-inf");
+    }
+
+    #[cfg(mock_backend)]
+    mod simulated {
+        use std::cell::RefCell;
+        use std::io::{self, Write};
+        use std::rc::Rc;
+
+        use crate::mock::MockSimulator;
+        use crate::wave::vcd::VcdBuilder;
+        use crate::{Handle, Value};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+        impl Write for Buffer {
+            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(data);
+                Ok(data.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Buffer {
+            fn text(&self) -> String {
+                String::from_utf8(self.0.borrow().clone()).unwrap()
+            }
+        }
+
+        #[test]
+        fn dumps_scope_hierarchy_and_changes() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            sim.set_timescale(&top, -9, -9);
+            let clk = sim.add_reg(&top, "clk", 1);
+            let count = sim.add_reg(&top, "count", 4);
+            let dut = sim.add_module(&top, "dut", "counter");
+            let gain = sim.add_real(&dut, "gain");
+            sim.start();
+
+            let buffer = Buffer::default();
+            let vcd = VcdBuilder::new()
+                .scope(&top, 0)
+                .build(ctx, buffer.clone())
+                .unwrap();
+            assert!(sim.schedule_value(&clk, &Value::Int(1), 5));
+            assert!(sim.schedule_value(&count, &Value::Int(3), 5));
+            assert!(sim.schedule_value(&gain, &Value::Real(0.5), 7));
+            assert!(sim.schedule_value(&clk, &Value::Int(0), 10));
+
+            sim.run_until(4);
+            vcd.dump_on(ctx).unwrap();
+            sim.run_until(8);
+            vcd.dump_off(ctx).unwrap();
+            sim.run_until(12);
+            vcd.close(ctx).unwrap();
+
+            assert_eq!(
+                buffer.text(),
+                format!(
+                    "$version\n    rust-vpi {}\n$end\n\
+                     $timescale\n    1ns\n$end\n\
+                     $scope module tb $end\n\
+                     $var reg 1 ! clk $end\n\
+                     $var reg 4 \" count $end\n\
+                     $scope module dut $end\n\
+                     $var real 64 # gain $end\n\
+                     $upscope $end\n\
+                     $upscope $end\n\
+                     $enddefinitions $end\n\
+                     #4\n$dumpvars\nx!\nbxxxx \"\nr0 #\n$end\n\
+                     #5\n1!\nb0011 \"\n\
+                     #7\nr0.5 #\n\
+                     #8\n$dumpoff\nx!\nbx \"\n$end\n",
+                    env!("CARGO_PKG_VERSION")
+                )
+            );
+            assert_eq!(sim.active_callbacks(), 0);
+        }
+
+        #[test]
+        fn dumps_selected_signals_in_simulation_precision() {
+            let sim = MockSimulator::new();
+            let ctx = sim.context();
+            let top = sim.add_module(&Handle::null(), "tb", "tb");
+            sim.set_timescale(&top, -9, -9);
+            let fast = sim.add_module(&Handle::null(), "fast", "fast");
+            sim.set_timescale(&fast, -12, -12);
+            let flag = sim.add_reg(&top, "flag", 1);
+            sim.start();
+
+            let buffer = Buffer::default();
+            let vcd = VcdBuilder::new()
+                .signal(&flag)
+                .signal(&flag)
+                .build(ctx, buffer.clone())
+                .unwrap();
+            vcd.dump_on(ctx).unwrap();
+            assert!(sim.schedule_value(&flag, &Value::Int(1), 2_500));
+            sim.run();
+            drop(vcd);
+
+            let text = buffer.text();
+            assert!(text.contains("$timescale\n    1ps\n$end\n"));
+            assert_eq!(text.matches("$var").count(), 1);
+            assert!(text.ends_with("#0\n$dumpvars\nx!\n$end\n#2500\n1!\n"));
+            assert_eq!(sim.active_callbacks(), 0);
+        }
+    }
+}